@@ -0,0 +1,67 @@
+#!/usr/bin/env python3
+#
+# Generates src/unicode/tables.rs, the data behind `--normalize nfc|nfd`, from Python's
+# unicodedata module. The Unicode version is whichever the Python running this ships with.
+#
+# Usage: scripts/gen-unicode-tables.py [OUTPUT]
+
+import sys
+import unicodedata
+
+out = sys.argv[1] if len(sys.argv) > 1 else "src/unicode/tables.rs"
+
+# Hangul syllables are decomposed and composed arithmetically, not through the tables
+HANGUL = range(0xAC00, 0xAC00 + 11172)
+
+decompositions = []
+compositions = []
+for cp in range(0x110000):
+    if cp in HANGUL or 0xD800 <= cp <= 0xDFFF:
+        continue
+    fields = unicodedata.decomposition(chr(cp)).split()
+    if not fields or fields[0].startswith("<"):
+        continue
+    parts = [int(f, 16) for f in fields]
+    decompositions.append((cp, parts[0], parts[1] if len(parts) > 1 else 0))
+    # A two-character decomposition composes back unless it is a composition exclusion, in which
+    # case NFC leaves the character decomposed
+    if len(parts) == 2 and unicodedata.normalize("NFC", chr(cp)) == chr(cp):
+        compositions.append((parts[0], parts[1], cp))
+compositions.sort()
+
+classes = []
+for cp in range(0x110000):
+    ccc = unicodedata.combining(chr(cp))
+    if ccc == 0:
+        continue
+    if classes and classes[-1][1] == cp - 1 and classes[-1][2] == ccc:
+        classes[-1][1] = cp
+    else:
+        classes.append([cp, cp, ccc])
+
+def table (rows, per_line):
+    lines = []
+    for i in range(0, len(rows), per_line):
+        lines.append("    " + " ".join(rows[i:i + per_line]))
+    return "\n".join(lines)
+
+with open(out, "w") as f:
+    f.write("// Generated by scripts/gen-unicode-tables.py from Unicode %s; do not edit.\n\n"
+            % unicodedata.unidata_version)
+
+    f.write("// (code point, first, second): canonical decompositions, with second 0 for a single\n"
+            "// character. Sorted by code point.\n")
+    f.write("pub(super) const DECOMPOSITIONS: &[(u32, u32, u32)] = &[\n")
+    f.write(table(["(0x%x, 0x%x, 0x%x)," % d for d in decompositions], 4))
+    f.write("\n];\n\n")
+
+    f.write("// (first, second, composite): the pairs NFC composes. Sorted by first, then second.\n")
+    f.write("pub(super) const COMPOSITIONS: &[(u32, u32, u32)] = &[\n")
+    f.write(table(["(0x%x, 0x%x, 0x%x)," % c for c in compositions], 4))
+    f.write("\n];\n\n")
+
+    f.write("// (first, last, class): runs of code points with a nonzero canonical combining class.\n"
+            "// Sorted by code point.\n")
+    f.write("pub(super) const COMBINING_CLASSES: &[(u32, u32, u8)] = &[\n")
+    f.write(table(["(0x%x, 0x%x, %d)," % tuple(c) for c in classes], 5))
+    f.write("\n];\n")
@@ -0,0 +1,99 @@
+/*
+ * APT repository metadata, in the deb822 format: stanzas of "Field: value" lines separated by
+ * blank lines, with continuation lines starting with whitespace. See
+ * https://wiki.debian.org/DebianRepository/Format
+ *
+ * A Release (or clearsigned InRelease) file lists the indices of a suite under its SHA256 field,
+ * one " <hex digest> <size> <path>" line each, with paths relative to the Release file. A
+ * Packages index has one stanza per package, whose Filename is relative to the archive root.
+ */
+
+use crate::pgp;
+
+/**
+ * A file listed in a Release or Packages file.
+ */
+#[derive(PartialEq, Debug)]
+pub struct FileEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Kind {
+    Release,
+    Packages
+}
+
+/**
+ * Splits deb822 text into stanzas of (field, value) pairs. Continuation lines are kept as
+ * separate lines of the value.
+ */
+pub fn
+stanzas (text: &str) -> Vec<Vec<(String, String)>> {
+    let mut stanzas: Vec<Vec<(String, String)>> = Vec::new();
+    let mut current: Vec<(String, String)> = Vec::new();
+
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                stanzas.push(std::mem::take(&mut current));
+            }
+        } else if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some((_, value)) = current.last_mut() {
+                value.push('\n');
+                value.push_str(line.trim());
+            }
+        } else if let Some((field, value)) = line.split_once(':') {
+            current.push((field.to_string(), value.trim().to_string()));
+        }
+    }
+
+    if !current.is_empty() {
+        stanzas.push(current);
+    }
+    stanzas
+}
+
+fn
+field<'a> (stanza: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    stanza.iter().find(|(f, _)| f.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+}
+
+fn
+valid_sha256 (hex: &str) -> bool {
+    hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/**
+ * Parses a Release, InRelease or Packages file, telling which it was from its fields.
+ */
+pub fn
+parse (text: &str) -> (Kind, Vec<FileEntry>) {
+    let text = pgp::strip_clearsign(text);
+    let stanzas = stanzas(&text);
+
+    // A Release file is a single stanza with the checksums as a multi-line field
+    if let Some(listing) = stanzas.first().and_then(|s| field(s, "SHA256")).filter(|v| v.starts_with('\n')) {
+        let entries = listing.lines().filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let (sha256, size, path) = (parts.next()?, parts.next()?, parts.next()?);
+            if !valid_sha256(sha256) {
+                return None;
+            }
+            Some(FileEntry { path: path.to_string(), size: size.parse().ok()?, sha256: sha256.to_ascii_lowercase() })
+        }).collect();
+        return (Kind::Release, entries);
+    }
+
+    let entries = stanzas.iter().filter_map(|stanza| {
+        let sha256 = field(stanza, "SHA256").filter(|h| valid_sha256(h))?;
+        Some(FileEntry {
+            path: field(stanza, "Filename")?.to_string(),
+            size: field(stanza, "Size")?.parse().ok()?,
+            sha256: sha256.to_ascii_lowercase()
+        })
+    }).collect();
+    (Kind::Packages, entries)
+}
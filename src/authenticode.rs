@@ -0,0 +1,138 @@
+/*
+ * The Authenticode digest of a PE image (a Windows .exe, .dll, .sys or .efi), the value a code
+ * signature signs and `signtool verify /v` prints. It covers the whole file except the three
+ * places signing writes to: the optional header's CheckSum, the certificate table's entry in the
+ * data directories, and the certificate table itself. See "Windows Authenticode Portable
+ * Executable Signature Format", "Calculating the PE Image Hash".
+ *
+ * The specification hashes the headers, then each section in file order, then whatever follows
+ * the last section. For an image whose sections sit end to end after the headers, as every
+ * linker lays them out, that is the same as hashing the file in order, which is what this does.
+ */
+
+use std::{io::{self, Read, Seek, SeekFrom}, ops::Range};
+
+use crate::{Algo, Digest, Hasher};
+
+// From the PE format specification: the optional header magic for 32- and 64-bit images
+const PE32: u16 = 0x10b;
+const PE32_PLUS: u16 = 0x20b;
+
+// The certificate table is the fifth data directory
+const CERTIFICATE_TABLE: u32 = 4;
+
+/**
+ * Where the fields Authenticode skips are in a particular file.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Layout {
+    // File offset of the optional header's 4-byte CheckSum
+    pub checksum: u64,
+    // File offset of the certificate table's 8-byte data directory entry
+    pub certificate_entry: u64,
+    // File offsets of the certificate table, if the image is signed
+    pub certificate_table: Option<Range<u64>>
+}
+
+fn
+invalid (msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn
+read_at<R: Read + Seek> (reader: &mut R, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => invalid("truncated PE headers"),
+        _ => e
+    })
+}
+
+/**
+ * Reads the DOS, COFF and optional headers of a `file_len`-byte PE image to find the fields
+ * Authenticode skips.
+ */
+pub fn
+layout<R: Read + Seek> (reader: &mut R, file_len: u64) -> io::Result<Layout> {
+    let mut dos = [0u8; 64];
+    read_at(reader, 0, &mut dos)?;
+    if &dos[..2] != b"MZ" {
+        return Err(invalid("not a PE image: no MZ header"));
+    }
+    let pe = u32::from_le_bytes(dos[0x3c..0x40].try_into().unwrap()) as u64;
+
+    // The "PE\0\0" signature, the 20-byte COFF header and the start of the optional header
+    let mut headers = [0u8; 24 + 112];
+    read_at(reader, pe, &mut headers)?;
+    if &headers[..4] != b"PE\0\0" {
+        return Err(invalid("not a PE image: no PE signature"));
+    }
+    let optional_len = u16::from_le_bytes([headers[20], headers[21]]) as u64;
+    let optional = pe + 24;
+
+    let directories = match u16::from_le_bytes([headers[24], headers[25]]) {
+        PE32 => 96,
+        PE32_PLUS => 112,
+        _ => return Err(invalid("unknown optional header magic"))
+    };
+    let count = u32::from_le_bytes(headers[24 + directories - 4..24 + directories].try_into().unwrap());
+    let certificate_entry = directories as u64 + CERTIFICATE_TABLE as u64 * 8;
+    if count <= CERTIFICATE_TABLE || certificate_entry + 8 > optional_len {
+        return Err(invalid("the optional header has no certificate table entry"));
+    }
+
+    let mut entry = [0u8; 8];
+    read_at(reader, optional + certificate_entry, &mut entry)?;
+    // Unlike the other directories, the certificate table's address is a file offset
+    let address = u32::from_le_bytes(entry[..4].try_into().unwrap()) as u64;
+    let size = u32::from_le_bytes(entry[4..].try_into().unwrap()) as u64;
+    let certificate_table = match size {
+        0 => None,
+        _ if address + size > file_len => return Err(invalid("the certificate table runs past the end of the file")),
+        _ => Some(address..address + size)
+    };
+
+    Ok(Layout { checksum: optional + 64, certificate_entry: optional + certificate_entry, certificate_table })
+}
+
+/**
+ * The byte ranges of the file that are hashed, in order.
+ */
+pub fn
+hashed_ranges (layout: &Layout, file_len: u64) -> Vec<Range<u64>> {
+    let mut skipped = vec![layout.checksum..layout.checksum + 4, layout.certificate_entry..layout.certificate_entry + 8];
+    skipped.extend(layout.certificate_table.clone());
+    skipped.sort_by_key(|r| r.start);
+
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for skip in skipped {
+        if skip.start > start {
+            ranges.push(start..skip.start);
+        }
+        start = start.max(skip.end);
+    }
+    if start < file_len {
+        ranges.push(start..file_len);
+    }
+    ranges
+}
+
+/**
+ * Computes the Authenticode digest of a `file_len`-byte PE image.
+ */
+pub fn
+digest<R: Read + Seek> (mut reader: R, file_len: u64, algo: Algo) -> io::Result<Digest> {
+    let layout = layout(&mut reader, file_len)?;
+    let mut hasher = Hasher::new(algo);
+
+    for range in hashed_ranges(&layout, file_len) {
+        reader.seek(SeekFrom::Start(range.start))?;
+        let len = range.end - range.start;
+        if hasher.update_reader(io::BufReader::new(Read::by_ref(&mut reader).take(len)))? != len {
+            return Err(invalid("the file changed while it was being read"));
+        }
+    }
+
+    Ok(hasher.finalize_digest())
+}
@@ -0,0 +1,91 @@
+/*
+ * Bitcoin's uses of SHA-256: block and transaction ids are SHA-256d, i.e. SHA-256 applied twice,
+ * and a block commits to its transactions through a Merkle tree of SHA-256d hashes.
+ *
+ * Bitcoin displays these 32-byte hashes with their bytes reversed, so the hashes printed by block
+ * explorers are the reverse of the raw digest.
+ */
+
+use crate::{digest, Algo};
+
+pub const HEADER_LEN: usize = 80;
+
+/**
+ * SHA-256d(x) = SHA-256(SHA-256(x))
+ */
+pub fn
+sha256d (data: &[u8]) -> Vec<u8> {
+    let first = digest(data, Algo::Sha256);
+    digest(&first, Algo::Sha256)
+}
+
+/**
+ * Converts between internal byte order and Bitcoin's reversed display order.
+ */
+pub fn
+reversed (hash: &[u8]) -> Vec<u8> {
+    hash.iter().rev().copied().collect()
+}
+
+/**
+ * The fields of an 80-byte block header. All integers are little-endian on the wire; the hashes
+ * are kept in internal byte order.
+ */
+pub struct BlockHeader {
+    pub version: i32,
+    pub prev_block: Vec<u8>,
+    pub merkle_root: Vec<u8>,
+    pub time: u32,
+    pub bits: u32,
+    pub nonce: u32
+}
+
+pub fn
+parse_header (header: &[u8]) -> Option<BlockHeader> {
+    if header.len() != HEADER_LEN {
+        return None;
+    }
+
+    let le = |offset: usize| u32::from_le_bytes([header[offset], header[offset + 1], header[offset + 2], header[offset + 3]]);
+
+    Some(BlockHeader {
+        version: le(0) as i32,
+        prev_block: header[4..36].to_vec(),
+        merkle_root: header[36..68].to_vec(),
+        time: le(68),
+        bits: le(72),
+        nonce: le(76)
+    })
+}
+
+/**
+ * The block hash, SHA-256d of the serialized header, in display order.
+ */
+pub fn
+block_hash (header: &[u8]) -> Vec<u8> {
+    reversed(&sha256d(header))
+}
+
+/**
+ * Computes the Merkle root from transaction ids given in display order, returning it in display
+ * order. Each level hashes adjacent pairs with SHA-256d, duplicating the last hash when a level
+ * has an odd number of entries.
+ */
+pub fn
+merkle_root (txids: &[Vec<u8>]) -> Option<Vec<u8>> {
+    if txids.is_empty() {
+        return None;
+    }
+
+    let mut level: Vec<Vec<u8>> = txids.iter().map(|t| reversed(t)).collect();
+
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            level.push(level[level.len() - 1].clone());
+        }
+
+        level = level.chunks(2).map(|pair| sha256d(&[pair[0].as_slice(), pair[1].as_slice()].concat())).collect();
+    }
+
+    Some(reversed(&level[0]))
+}
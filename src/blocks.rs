@@ -0,0 +1,74 @@
+use std::io::{self, Read};
+
+use crate::{Algo, Hasher};
+
+/**
+ * The digest of one fixed-size block of a larger input.
+ */
+pub struct BlockDigest {
+    pub offset: u64,
+    pub len: u64,
+    pub digest: Vec<u8>
+}
+
+/**
+ * Hashes the input once while also producing an independent digest for every `block_size`
+ * bytes of it, so corruption in a large file can later be localized to a block. The final block
+ * may be shorter. Returns the per-block digests and the digest of the whole input.
+ */
+pub fn
+block_digests<R: Read> (reader: R, algo: Algo, block_size: u64) -> io::Result<(Vec<BlockDigest>, Vec<u8>)> {
+    block_digests_with(reader, algo, block_size, |_, _| Ok(()))
+}
+
+/**
+ * As `block_digests`, also handing each piece of data to `sink` along with the zero-based index
+ * of the block it belongs to, e.g. to write every block to its own file in the same pass.
+ */
+pub fn
+block_digests_with<R: Read, F: FnMut(usize, &[u8]) -> io::Result<()>> (
+    mut reader: R,
+    algo: Algo,
+    block_size: u64,
+    mut sink: F
+) -> io::Result<(Vec<BlockDigest>, Vec<u8>)> {
+    let mut whole = Hasher::new(algo);
+    let mut block = Hasher::new(algo);
+    let mut block_len: u64 = 0;
+    let mut offset: u64 = 0;
+    let mut blocks: Vec<BlockDigest> = Vec::new();
+    let mut buffer = vec![0u8; 1 << 16];
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        };
+
+        whole.update(&buffer[..read]);
+
+        let mut data = &buffer[..read];
+        while !data.is_empty() {
+            let take = std::cmp::min((block_size - block_len) as usize, data.len());
+            block.update(&data[..take]);
+            sink(blocks.len(), &data[..take])?;
+            block_len += take as u64;
+            data = &data[take..];
+
+            if block_len == block_size {
+                let finished = std::mem::replace(&mut block, Hasher::new(algo));
+                blocks.push(BlockDigest { offset, len: block_len, digest: finished.finalize() });
+                offset += block_len;
+                block_len = 0;
+            }
+        }
+    }
+
+    if block_len > 0 {
+        blocks.push(BlockDigest { offset, len: block_len, digest: block.finalize() });
+    }
+
+    Ok((blocks, whole.finalize()))
+}
@@ -0,0 +1,102 @@
+use std::{fs, io};
+
+const MAGIC: &str = "sha2-bloom 1";
+
+/**
+ * A Bloom filter keyed by digests. Rather than k independent hash functions, the k bit positions
+ * are derived from the item's digest by double hashing, g_i = h1 + i * h2 (mod m), taking h1 and
+ * h2 from its first 16 bytes (Kirsch and Mitzenmacher, "Less Hashing, Same Performance").
+ *
+ * Saved as a one-line text header followed by the raw bit array.
+ */
+pub struct BloomFilter {
+    pub bits: Vec<u8>,
+    pub m: u64,
+    pub k: u32,
+    pub count: u64
+}
+
+fn
+invalid (msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+impl BloomFilter {
+    pub fn new (m: u64, k: u32) -> BloomFilter {
+        let m = m.max(8);
+        BloomFilter { bits: vec![0u8; m.div_ceil(8) as usize], m, k: k.max(1), count: 0 }
+    }
+
+    /**
+     * Sizes a filter for `expected` items at the given false-positive rate:
+     * m = -n ln p / (ln 2)^2 and k = (m / n) ln 2.
+     */
+    pub fn with_rate (expected: u64, rate: f64) -> BloomFilter {
+        let n = expected.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let m = (-n * rate.ln() / (ln2 * ln2)).ceil();
+        let k = (m / n * ln2).round();
+
+        BloomFilter::new(m as u64, k as u32)
+    }
+
+    fn positions (&self, digest: &[u8]) -> impl Iterator<Item = u64> + '_ {
+        let h1 = u64::from_be_bytes(digest[0..8].try_into().unwrap());
+        let h2 = u64::from_be_bytes(digest[8..16].try_into().unwrap());
+        (0..self.k as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % self.m)
+    }
+
+    /**
+     * Adds an item by its digest, which must be at least 16 bytes.
+     */
+    pub fn insert (&mut self, digest: &[u8]) {
+        let positions: Vec<u64> = self.positions(digest).collect();
+        for p in positions {
+            self.bits[(p / 8) as usize] |= 1 << (p % 8);
+        }
+        self.count += 1;
+    }
+
+    /**
+     * False means the item was definitely never added; true means it probably was.
+     */
+    pub fn contains (&self, digest: &[u8]) -> bool {
+        self.positions(digest).all(|p| self.bits[(p / 8) as usize] & (1 << (p % 8)) != 0)
+    }
+
+    pub fn to_bytes (&self) -> Vec<u8> {
+        let mut out = format!("{} k={} bits={} count={}\n", MAGIC, self.k, self.m, self.count).into_bytes();
+        out.extend_from_slice(&self.bits);
+        out
+    }
+
+    pub fn from_bytes (bytes: &[u8]) -> io::Result<BloomFilter> {
+        let newline = bytes.iter().position(|b| *b == b'\n').ok_or_else(|| invalid("not a bloom filter file"))?;
+        let header = std::str::from_utf8(&bytes[..newline]).map_err(|_| invalid("not a bloom filter file"))?;
+        let fields = header.strip_prefix(MAGIC).ok_or_else(|| invalid("not a bloom filter file"))?;
+
+        let field = |key: &str| -> io::Result<u64> {
+            fields.split_whitespace()
+                .find_map(|f| f.strip_prefix(key).and_then(|v| v.strip_prefix('=')))
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| invalid(&format!("bloom filter header is missing {}", key)))
+        };
+
+        let m = field("bits")?;
+        let k = field("k")? as u32;
+        let bits = bytes[newline + 1..].to_vec();
+        if m == 0 || k == 0 || bits.len() as u64 != m.div_ceil(8) {
+            return Err(invalid("bloom filter is truncated or corrupt"));
+        }
+
+        Ok(BloomFilter { bits, m, k, count: field("count")? })
+    }
+
+    pub fn save (&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn load (path: &str) -> io::Result<BloomFilter> {
+        BloomFilter::from_bytes(&fs::read(path)?)
+    }
+}
@@ -0,0 +1,259 @@
+/*
+ * The GNU build-id of an ELF binary: a note (.note.gnu.build-id) the linker fills with a hash of
+ * the output, which debuggers and symbol servers use to pair a stripped binary with its debug
+ * symbols. Recomputing it shows whether the binary is still the one the linker wrote, so a
+ * symbol server isn't handed a patched or corrupted file under a trusted id.
+ *
+ * What exactly is hashed depends on the linker; see Style. A 20-byte id is SHA-1 and a 32-byte
+ * one SHA-256. MD5, UUID and hand-chosen ids can be read but not recomputed, and neither can
+ * ids from recent versions of lld, which hash with BLAKE3 whatever style is asked for. Stripping
+ * a binary after linking also changes what the linker hashed, so a stripped binary keeps its
+ * id but no longer reproduces it.
+ */
+
+use std::ops::Range;
+
+use crate::{Algo, Hasher};
+
+// From the ELF specification
+const SHT_NULL: u32 = 0;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHT_NOTE: u32 = 7;
+const SHT_NOBITS: u32 = 8;
+const SHF_ALLOC: u64 = 2;
+const NT_GNU_BUILD_ID: u32 = 3;
+
+/**
+ * How a linker computes the build-id. Each hashes the output with the id's bytes zeroed.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Style {
+    // GNU ld (BFD): the ELF header without its e_phoff and e_shoff, the program headers, then each
+    // section header without its sh_offset followed by the section's contents. The build-id
+    // note is zeroed entirely, and the linker-generated .symtab, .strtab and .shstrtab
+    // contribute only their headers.
+    GnuLd,
+    // gold: the whole file
+    Gold
+}
+
+impl Style {
+    pub const ALL: [Style; 2] = [Style::GnuLd, Style::Gold];
+
+    pub fn name (&self) -> &'static str {
+        match self {
+            Style::GnuLd => "GNU ld",
+            Style::Gold => "gold"
+        }
+    }
+}
+
+/**
+ * The build-id note found in a binary.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct BuildId {
+    pub id: Vec<u8>,
+    // File offsets of the whole note, and of the id within it
+    pub note: Range<usize>,
+    pub desc: Range<usize>
+}
+
+impl BuildId {
+    /**
+     * The hash the id's length implies, or None for an id that can't be recomputed.
+     */
+    pub fn algorithm (&self) -> Option<&'static str> {
+        match self.id.len() {
+            20 => Some("SHA-1"),
+            32 => Some("SHA-256"),
+            _ => None
+        }
+    }
+}
+
+struct Section {
+    header: Range<usize>,
+    kind: u32,
+    flags: u64,
+    contents: Range<usize>
+}
+
+/**
+ * The parts of an ELF file the build-id computations need.
+ */
+struct Elf<'a> {
+    bytes: &'a [u8],
+    is_64: bool,
+    big_endian: bool
+}
+
+impl<'a> Elf<'a> {
+    fn parse (bytes: &'a [u8]) -> Result<Elf<'a>, String> {
+        if bytes.len() < 52 || &bytes[..4] != b"\x7fELF" {
+            return Err("not an ELF file".to_string());
+        }
+        let is_64 = match bytes[4] {
+            1 => false,
+            2 => true,
+            _ => return Err("unknown ELF class".to_string())
+        };
+        let big_endian = match bytes[5] {
+            1 => false,
+            2 => true,
+            _ => return Err("unknown ELF byte order".to_string())
+        };
+        if is_64 && bytes.len() < 64 {
+            return Err("truncated ELF header".to_string());
+        }
+        Ok(Elf { bytes, is_64, big_endian })
+    }
+
+    fn uint (&self, offset: usize, len: usize) -> Result<u64, String> {
+        let field = self.bytes.get(offset..offset + len).ok_or("ELF structure runs past the end of the file")?;
+        let mut value = 0u64;
+        for i in 0..len {
+            let byte = if self.big_endian { field[i] } else { field[len - 1 - i] };
+            value = value << 8 | byte as u64;
+        }
+        Ok(value)
+    }
+
+    // A field that is 4 bytes in 32-bit ELF and 8 in 64-bit, at the offset for each
+    fn word (&self, offset_32: usize, offset_64: usize) -> Result<u64, String> {
+        if self.is_64 { self.uint(offset_64, 8) } else { self.uint(offset_32, 4) }
+    }
+
+    fn header_len (&self) -> usize {
+        if self.is_64 { 64 } else { 52 }
+    }
+
+    fn range (&self, start: u64, len: u64) -> Result<Range<usize>, String> {
+        match start.checked_add(len) {
+            Some(end) if end <= self.bytes.len() as u64 => Ok(start as usize..end as usize),
+            _ => Err("ELF structure runs past the end of the file".to_string())
+        }
+    }
+
+    // e_phoff and e_shoff, for zeroing
+    fn table_offsets (&self) -> Range<usize> {
+        if self.is_64 { 32..48 } else { 28..36 }
+    }
+
+    fn program_headers (&self) -> Result<Range<usize>, String> {
+        let (size, count) = if self.is_64 { (self.uint(54, 2)?, self.uint(56, 2)?) } else { (self.uint(42, 2)?, self.uint(44, 2)?) };
+        self.range(self.word(28, 32)?, size * count)
+    }
+
+    fn sections (&self) -> Result<Vec<Section>, String> {
+        let (size, count) = if self.is_64 { (self.uint(58, 2)?, self.uint(60, 2)?) } else { (self.uint(46, 2)?, self.uint(48, 2)?) };
+        let table = self.range(self.word(32, 40)?, size * count)?;
+        if count > 0 && size < if self.is_64 { 64 } else { 40 } {
+            return Err("section headers are too small".to_string());
+        }
+
+        let mut sections = Vec::new();
+        for i in 0..count as usize {
+            let start = table.start + i * size as usize;
+            let kind = self.uint(start + 4, 4)? as u32;
+            let flags = self.word(start + 8, start + 8)?;
+            let offset = self.word(start + 16, start + 24)?;
+            let len = self.word(start + 20, start + 32)?;
+            let contents = if kind == SHT_NOBITS { 0..0 } else { self.range(offset, len)? };
+            sections.push(Section { header: start..start + size as usize, kind, flags, contents });
+        }
+        Ok(sections)
+    }
+
+    // sh_offset within a section header, for zeroing
+    fn section_offset_field (&self) -> Range<usize> {
+        if self.is_64 { 24..32 } else { 16..20 }
+    }
+}
+
+/**
+ * Finds the build-id note in an ELF file, if it has one.
+ */
+pub fn
+find (elf: &[u8]) -> Result<Option<BuildId>, String> {
+    let parsed = Elf::parse(elf)?;
+
+    for section in parsed.sections()?.iter().filter(|s| s.kind == SHT_NOTE) {
+        let mut at = section.contents.start;
+        while at + 12 <= section.contents.end {
+            let name_len = parsed.uint(at, 4)? as usize;
+            let desc_len = parsed.uint(at + 4, 4)? as usize;
+            let kind = parsed.uint(at + 8, 4)? as u32;
+            let name = at + 12;
+            let desc = name + name_len.div_ceil(4) * 4;
+            let end = desc + desc_len.div_ceil(4) * 4;
+            if desc + desc_len > section.contents.end {
+                return Err("malformed note section".to_string());
+            }
+
+            if kind == NT_GNU_BUILD_ID && &elf[name..name + name_len] == b"GNU\0" {
+                return Ok(Some(BuildId { id: elf[desc..desc + desc_len].to_vec(), note: at..end.min(section.contents.end), desc: desc..desc + desc_len }));
+            }
+            at = end;
+        }
+    }
+
+    Ok(None)
+}
+
+/**
+ * Recomputes the build-id the given linker would have written. Returns None if the id isn't
+ * SHA-1 or SHA-256 sized, so can't be recomputed.
+ */
+pub fn
+compute (elf: &[u8], build_id: &BuildId, style: Style) -> Result<Option<Vec<u8>>, String> {
+    let parsed = Elf::parse(elf)?;
+    let mut hasher = match build_id.id.len() {
+        20 => Hasher::new(Algo::Sha1),
+        32 => Hasher::new(Algo::Sha256),
+        _ => return Ok(None)
+    };
+
+    let mut zeroed = elf.to_vec();
+    match style {
+        Style::GnuLd => {
+            zeroed[build_id.note.clone()].fill(0);
+            let mut header = zeroed[..parsed.header_len()].to_vec();
+            header[parsed.table_offsets()].fill(0);
+            hasher.update(&header);
+            hasher.update(&zeroed[parsed.program_headers()?]);
+
+            for section in parsed.sections()? {
+                let mut header = zeroed[section.header.clone()].to_vec();
+                header[parsed.section_offset_field()].fill(0);
+                hasher.update(&header);
+
+                let generated = matches!(section.kind, SHT_SYMTAB | SHT_STRTAB) && section.flags & SHF_ALLOC == 0;
+                if section.kind != SHT_NULL && !generated {
+                    hasher.update(&zeroed[section.contents]);
+                }
+            }
+        },
+        Style::Gold => {
+            zeroed[build_id.desc.clone()].fill(0);
+            hasher.update(&zeroed);
+        }
+    }
+
+    Ok(Some(hasher.finalize()))
+}
+
+/**
+ * Which linker's computation reproduces the embedded id, if any does. Always None if the id
+ * has no algorithm().
+ */
+pub fn
+verify (elf: &[u8], build_id: &BuildId) -> Result<Option<Style>, String> {
+    for style in Style::ALL {
+        if compute(elf, build_id, style)?.as_deref() == Some(&build_id.id[..]) {
+            return Ok(Some(style));
+        }
+    }
+    Ok(None)
+}
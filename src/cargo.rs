@@ -0,0 +1,107 @@
+use std::{fs, path::{Path, PathBuf}};
+
+/**
+ * A `[[package]]` entry from Cargo.lock. Only registry packages carry a checksum; path and git
+ * dependencies have none.
+ */
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    pub source: Option<String>,
+    pub checksum: Option<String>
+}
+
+impl LockedPackage {
+    /**
+     * The file name cargo uses for the downloaded package in its registry cache.
+     */
+    pub fn crate_file_name (&self) -> String {
+        format!("{}-{}.crate", self.name, self.version)
+    }
+}
+
+fn
+quoted (value: &str) -> Option<String> {
+    let value = value.trim();
+    value.strip_prefix('"')?.strip_suffix('"').map(|v| v.to_string())
+}
+
+/**
+ * Reads the packages out of a Cargo.lock. This handles only the flat `key = "value"` subset of
+ * TOML which cargo writes, including the `[metadata]` checksum table of version 1 lockfiles.
+ */
+pub fn
+parse_lock (contents: &str) -> Vec<LockedPackage> {
+    let mut packages: Vec<LockedPackage> = Vec::new();
+    let mut in_package = false;
+    let mut in_metadata = false;
+
+    for line in contents.lines() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            in_package = line == "[[package]]";
+            in_metadata = line == "[metadata]";
+            if in_package {
+                packages.push(LockedPackage {
+                    name: String::new(), version: String::new(), source: None, checksum: None
+                });
+            }
+            continue;
+        }
+
+        let (key, value) = match line.split_once(" = ") {
+            Some(kv) => kv,
+            None => continue
+        };
+
+        if in_package {
+            let package = packages.last_mut().unwrap();
+            match key {
+                "name" => package.name = quoted(value).unwrap_or_default(),
+                "version" => package.version = quoted(value).unwrap_or_default(),
+                "source" => package.source = quoted(value),
+                "checksum" => package.checksum = quoted(value),
+                _ => {}
+            }
+        } else if in_metadata {
+            // "checksum <name> <version> (<source>)" = "<hex>"
+            let key = quoted(key).unwrap_or_default();
+            let mut fields = key.split(' ');
+            if let (Some("checksum"), Some(name), Some(version)) = (fields.next(), fields.next(), fields.next()) {
+                let checksum = quoted(value);
+                if let Some(p) = packages.iter_mut().find(|p| p.name == name && p.version == version) {
+                    p.checksum = checksum;
+                }
+            }
+        }
+    }
+
+    packages
+}
+
+/**
+ * The registry cache directory, $CARGO_HOME/registry/cache, with CARGO_HOME defaulting to
+ * ~/.cargo.
+ */
+pub fn
+default_cache_dir () -> Option<PathBuf> {
+    let home = std::env::var_os("CARGO_HOME").map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".cargo")))?;
+    Some(home.join("registry").join("cache"))
+}
+
+/**
+ * Looks for a downloaded .crate file in each registry's directory beneath the cache directory
+ * (e.g. `index.crates.io-6f17d22bba15001f/`).
+ */
+pub fn
+find_cached (cache_dir: &Path, file_name: &str) -> Option<PathBuf> {
+    let mut registries: Vec<PathBuf> = fs::read_dir(cache_dir).ok()?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .collect();
+    registries.sort();
+
+    registries.into_iter().map(|r| r.join(file_name)).find(|p| p.is_file())
+}
@@ -0,0 +1,342 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    sync::{atomic::{AtomicUsize, Ordering}, mpsc},
+    thread,
+    time::Instant
+};
+
+use crate::{csv, encoding::hex, format, report::{Record, Status}, Algo, Hasher};
+
+/**
+ * A single file to verify, as listed in a checksum file.
+ */
+pub struct Entry {
+    pub expected: String,
+    pub path: String,
+    pub algo: Algo
+}
+
+/**
+ * Parses a line of a sha224sum/sha256sum-style checksum file, "<hex digest> <space|*><path>",
+ * returning the lowercased digest and the path.
+ */
+pub fn
+parse_line (line: &str) -> Option<(String, String)> {
+    let (digest, rest) = line.split_once(' ')?;
+
+    if digest.is_empty() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    // The second separator character marks text (' ') or binary ('*') mode
+    let path = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+    if path.is_empty() {
+        return None;
+    }
+
+    Some((digest.to_ascii_lowercase(), path.to_string()))
+}
+
+/**
+ * Parses a BSD-style tagged line, "<ALGORITHM> (<path>) = <hex digest>", as written by
+ * `sha256sum --tag` and used in the CHECKSUM files published with Fedora and CentOS images.
+ * Returns the algorithm name, the lowercased digest and the path.
+ */
+pub fn
+parse_tagged_line (line: &str) -> Option<(String, String, String)> {
+    let (algorithm, rest) = line.split_once(" (")?;
+    // The path may itself contain ") = ", so split at the last one
+    let (path, digest) = rest.rsplit_once(") = ")?;
+
+    if algorithm.is_empty() || !algorithm.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') || path.is_empty() || digest.is_empty() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    Some((algorithm.to_string(), digest.to_ascii_lowercase(), path.to_string()))
+}
+
+/**
+ * Parses saved `certutil -hashfile` output: "<ALGORITHM> hash of <path>:" followed by the digest
+ * on the next line, in hex with or without spaces between the bytes. Returns the algorithm name,
+ * lowercased digest and path of each entry, with a Windows path reduced to its file name.
+ */
+pub fn
+parse_certutil (text: &str) -> Vec<(String, String, String)> {
+    let lines: Vec<&str> = text.lines().map(|l| l.trim()).collect();
+
+    lines.windows(2).filter_map(|pair| {
+        let (algorithm, path) = pair[0].strip_suffix(':')?.split_once(" hash of ")?;
+        let digest: String = pair[1].chars().filter(|c| *c != ' ').collect();
+        if digest.is_empty() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        Some((algorithm.to_string(), digest.to_ascii_lowercase(), local_name(path)))
+    }).collect()
+}
+
+/**
+ * Parses PowerShell `Get-FileHash | Export-Csv` output, with or without the "#TYPE" line that
+ * Windows PowerShell 5.1 writes first. The Algorithm, Hash and Path columns may come in any order.
+ * Returns the algorithm name, lowercased digest and path of each row; Windows paths are reduced
+ * to file names as for certutil.
+ */
+pub fn
+parse_get_filehash_csv (text: &str) -> Vec<(String, String, String)> {
+    let text = match text.strip_prefix("#TYPE") {
+        Some(rest) => rest.split_once('\n').map(|(_, r)| r).unwrap_or(""),
+        None => text
+    };
+    let mut reader = io::Cursor::new(text.as_bytes());
+
+    // The text is UTF-8 and the delimiter ASCII, so every field is UTF-8 too
+    let read_record = |reader: &mut io::Cursor<&[u8]>| csv::read_record(reader, b',')
+        .map(|record| record.map(|fields| fields.iter().map(|f| String::from_utf8_lossy(f).into_owned()).collect::<Vec<_>>()));
+
+    let header = match read_record(&mut reader) {
+        Ok(Some(h)) => h,
+        _ => return Vec::new()
+    };
+    let column = |name: &str| header.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+    let (Some(algorithm), Some(hash), Some(path)) = (column("Algorithm"), column("Hash"), column("Path")) else {
+        return Vec::new();
+    };
+
+    let mut rows = Vec::new();
+    while let Ok(Some(record)) = read_record(&mut reader) {
+        if let (Some(a), Some(h), Some(p)) = (record.get(algorithm), record.get(hash), record.get(path)) {
+            if !h.is_empty() && h.chars().all(|c| c.is_ascii_hexdigit()) {
+                rows.push((a.clone(), h.to_ascii_lowercase(), local_name(p)));
+            }
+        }
+    }
+    rows
+}
+
+/**
+ * Reduces a Windows path to its file name, since the drive and directories won't exist where
+ * the check is run. Other paths are kept as they are.
+ */
+fn
+local_name (path: &str) -> String {
+    path.rsplit('\\').next().unwrap_or(path).to_string()
+}
+
+/**
+ * The algorithm for a BSD-style tag, e.g. "SHA256".
+ */
+pub fn
+algorithm_for_tag (tag: &str) -> Option<Algo> {
+    Algo::ALL.iter().copied().find(|algo| tag.eq_ignore_ascii_case(algo.tag()))
+}
+
+/**
+ * Infers the algorithm from the length of a hex digest.
+ */
+pub fn
+algorithm_for_hex_len (len: usize) -> Option<Algo> {
+    match len {
+        32 => Some(Algo::Md5),
+        40 => Some(Algo::Sha1),
+        56 => Some(Algo::Sha224),
+        64 => Some(Algo::Sha256),
+        128 => Some(Algo::Sha512),
+        _ => None
+    }
+}
+
+/**
+ * The algorithm's name with its indefinite article, e.g. "an MD5" or "a SHA-256".
+ */
+pub fn
+with_article (algo: Algo) -> String {
+    let article = if algo == Algo::Md5 { "an" } else { "a" };
+    format!("{} {}", article, algo)
+}
+
+/**
+ * Reads an expected digest as copied from a web page or release notes: hex in either case,
+ * possibly split by spaces or colons, possibly labelled "sha256:", "SHA-256=" or "sha3-256:", or
+ * an SRI value such as "sha256-<base64>". A whole line of a checksum file or a hash:// or ni: URI
+ * works too. Returns the algorithm, if the text names one, and the digest bytes. Unlabelled hex
+ * must have the length of an MD5, SHA-1 or SHA-2 digest.
+ */
+pub fn
+parse_pasted_digest (text: &str) -> Option<(Option<Algo>, Vec<u8>)> {
+    let text = text.trim();
+    let fits = |algo: Option<Algo>, bytes: Vec<u8>| match algo {
+        Some(algo) => (bytes.len() == algo.digest_len()).then_some((Some(algo), bytes)),
+        None => algorithm_for_hex_len(bytes.len() * 2).map(|_| (None, bytes))
+    };
+
+    if let Ok((algo, digest)) = format::parse_digest_uri(text) {
+        return Some((Some(algo), digest));
+    }
+
+    let line = parse_line(text).map(|(digest, _)| (None, digest))
+        .or_else(|| parse_tagged_line(text).map(|(tag, digest, _)| (algorithm_for_tag(&tag), digest)));
+    if let Some(pasted) = line.and_then(|(algo, digest)| fits(algo, hex::decode(digest).ok()?)) {
+        return Some(pasted);
+    }
+
+    match text.split_once('-') {
+        Some((tag, b64)) if algorithm_for_tag(tag).is_some() => fits(algorithm_for_tag(tag), base64::decode(b64).ok()?),
+        _ => {
+            // "SHA-256" labels SHA256, but "sha3-256" is SHA3-256's tag as it is
+            let label = text.split_once([':', '='])
+                .and_then(|(label, rest)| algorithm_for_tag(label).or_else(|| algorithm_for_tag(&label.replace('-', ""))).map(|algo| (algo, rest)));
+            let (algo, text) = match label {
+                Some((algo, rest)) => (Some(algo), rest),
+                None => (None, text)
+            };
+            let digits: String = text.chars().filter(|c| !c.is_whitespace() && *c != ':').collect();
+            fits(algo, hex::decode(digits).ok()?)
+        }
+    }
+}
+
+/**
+ * Explains why an expected digest, as the user gave it, doesn't match the actual hex digest of
+ * `algo`:
+ * stray case or whitespace, characters which aren't hex, a truncated digest, a length which
+ * belongs to another algorithm, or where two digests of the same length diverge. Each hint is a
+ * line of text; the last three of a divergence draw the two digests with a caret under the first
+ * differing digit.
+ */
+pub fn
+explain_mismatch (expected: &str, actual: &str, algo: Algo) -> Vec<String> {
+    let mut hints = Vec::new();
+    let cleaned: String = expected.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_lowercase();
+
+    if cleaned == actual {
+        hints.push("the digests match once case and whitespace are ignored".to_string());
+        return hints;
+    }
+    if let Some((i, c)) = cleaned.chars().enumerate().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        hints.push(format!("the expected digest has a non-hex character {:?} at digit {}", c, i + 1));
+    }
+
+    if cleaned.len() != actual.len() {
+        let actual_algo = algo.to_string();
+        let algorithm = match cleaned.len() {
+            96 => Some(Algo::Sha3_384),
+            len => algorithm_for_hex_len(len)
+        }.map(with_article);
+
+        hints.push(match algorithm {
+            Some(name) => format!(
+                "the expected digest has {} hex digits, the length of {} digest, but this is {}",
+                cleaned.len(), name, actual_algo
+            ),
+            None if actual.starts_with(&cleaned) => format!(
+                "the expected digest is truncated: its {} digits match the start of the {}-digit digest",
+                cleaned.len(), actual.len()
+            ),
+            None if cleaned.starts_with(actual) => format!(
+                "the expected digest has {} extra digits after a matching {}-digit digest",
+                cleaned.len() - actual.len(), actual.len()
+            ),
+            None => format!("the expected digest has {} hex digits; {} has {}", cleaned.len(), actual_algo, actual.len())
+        });
+        return hints;
+    }
+
+    let differing: Vec<usize> = cleaned.chars().zip(actual.chars()).enumerate()
+        .filter(|(_, (e, a))| e != a)
+        .map(|(i, _)| i)
+        .collect();
+
+    hints.push(if differing.len() <= 2 {
+        format!("only {} of {} digits differ, which looks like a copying mistake rather than different content", differing.len(), actual.len())
+    } else {
+        format!("{} of {} digits differ, so the content is different", differing.len(), actual.len())
+    });
+    hints.push(format!("expected {}", cleaned));
+    hints.push(format!("actual   {}", actual));
+    hints.push(format!("         {}^ digit {}", " ".repeat(differing[0]), differing[0] + 1));
+    hints
+}
+
+/**
+ * Hashes a file in a streaming fashion, returning the digest and the number of bytes read.
+ */
+pub fn
+hash_file (path: &str, algo: Algo) -> io::Result<(Vec<u8>, u64)> {
+    let file = fs::File::open(path)?;
+    let mut hasher = Hasher::new(algo);
+    let bytes = hasher.update_reader(io::BufReader::new(file))?;
+    Ok((hasher.finalize(), bytes))
+}
+
+/**
+ * Verifies a single file against its expected hex digest.
+ */
+pub fn
+check_file (path: &str, expected: &str, algo: Algo) -> Record {
+    let start = Instant::now();
+
+    let (status, actual, bytes) = match hash_file(path, algo) {
+        Ok((digest, bytes)) => {
+            let actual = hex::encode(digest);
+            let status = if actual == expected { Status::Ok } else { Status::Failed };
+            (status, Some(actual), bytes)
+        },
+        Err(_) => (Status::Missing, None, 0)
+    };
+
+    Record {
+        path: path.to_string(),
+        status,
+        expected: expected.to_string(),
+        actual,
+        bytes,
+        duration: start.elapsed()
+    }
+}
+
+/**
+ * Verifies the given entries using up to `jobs` worker threads. Records are passed to `emit` in
+ * the same order as the entries, each as soon as it and all entries before it are complete.
+ */
+pub fn
+check_all<F: FnMut(Record)> (entries: &[Entry], jobs: usize, mut emit: F) {
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel::<(usize, Record)>();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, entries.len().max(1)) {
+            let sender = sender.clone();
+            let next = &next;
+
+            scope.spawn(move || {
+                loop {
+                    let indx = next.fetch_add(1, Ordering::Relaxed);
+                    let entry = match entries.get(indx) {
+                        Some(e) => e,
+                        None => break
+                    };
+
+                    if sender.send((indx, check_file(&entry.path, &entry.expected, entry.algo))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        // Only the workers' senders remain, so the receiver finishes once they all have
+        drop(sender);
+
+        let mut pending: BTreeMap<usize, Record> = BTreeMap::new();
+        let mut next_to_emit = 0;
+
+        for (indx, record) in receiver {
+            pending.insert(indx, record);
+
+            while let Some(record) = pending.remove(&next_to_emit) {
+                emit(record);
+                next_to_emit += 1;
+            }
+        }
+    });
+}
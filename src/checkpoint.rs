@@ -0,0 +1,114 @@
+use std::{fs, io};
+
+use crate::{encoding::hex, Algo, Hasher, State};
+
+const MAGIC: &str = "sha2-checkpoint 1";
+
+/**
+ * The saved state of an interrupted file hash: the input being hashed, how many bytes of it
+ * have been consumed, and the hasher state at that point. Stored as a small text file of
+ * key/value lines.
+ */
+pub struct Checkpoint {
+    pub input: String,
+    pub offset: u64,
+    pub hasher: Hasher
+}
+
+fn
+invalid (msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+impl Checkpoint {
+    /**
+     * Fails for anything but a SHA-224 or SHA-256 hasher, whose state words are the only ones
+     * the format holds.
+     */
+    pub fn to_text (&self) -> io::Result<String> {
+        if !self.hasher.algorithm().has_midstate() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "checkpoints only hold SHA-224 and SHA-256 state"));
+        }
+
+        let stream = self.hasher.narrow();
+        let state = &stream.state;
+        let words = [state.a, state.b, state.c, state.d, state.e, state.f, state.g, state.h];
+        let state_hex: String = words.iter().map(|w| format!("{:08x}", w)).collect();
+
+        Ok(format!(
+            "{}\ninput {}\noffset {}\nalgorithm {}\nlength {}\nstate {}\nbuffer {}\n",
+            MAGIC,
+            self.input,
+            self.offset,
+            state.digest_bits(),
+            self.hasher.bytes_processed(),
+            state_hex,
+            hex::encode(stream.buffered())
+        ))
+    }
+
+    pub fn from_text (text: &str) -> io::Result<Checkpoint> {
+        let mut lines = text.lines();
+        if lines.next() != Some(MAGIC) {
+            return Err(invalid("not a checkpoint file"));
+        }
+
+        let mut fields = std::collections::HashMap::new();
+        for line in lines {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            fields.insert(key, value);
+        }
+
+        let field = |key: &str| fields.get(key).copied().ok_or_else(|| invalid(&format!("checkpoint is missing {}", key)));
+        let number = |key: &str| field(key)?.parse::<u64>().map_err(|_| invalid(&format!("invalid {} in checkpoint", key)));
+
+        let algo = Algo::try_from_bits(number("algorithm")? as usize)
+            .ok()
+            .filter(|algo| algo.has_midstate())
+            .ok_or_else(|| invalid("unsupported algorithm in checkpoint"))?;
+
+        let words = hex::decode(field("state")?).map_err(|_| invalid("invalid state in checkpoint"))?;
+        let buffer = hex::decode(field("buffer")?).map_err(|_| invalid("invalid buffer in checkpoint"))?;
+        if words.len() != 32 || buffer.len() >= 64 {
+            return Err(invalid("invalid state in checkpoint"));
+        }
+
+        let word = |i: usize| u32::from_be_bytes([words[i * 4], words[i * 4 + 1], words[i * 4 + 2], words[i * 4 + 3]]);
+        let mut state = State::new(algo);
+        state.a = word(0);
+        state.b = word(1);
+        state.c = word(2);
+        state.d = word(3);
+        state.e = word(4);
+        state.f = word(5);
+        state.g = word(6);
+        state.h = word(7);
+
+        let len = number("length")?;
+        if len % 64 != buffer.len() as u64 {
+            return Err(invalid("invalid buffer in checkpoint"));
+        }
+        let mut hasher = Hasher::new(algo);
+        let stream = hasher.narrow_mut();
+        stream.state = state;
+        stream.buffer[..buffer.len()].copy_from_slice(&buffer);
+        stream.bit_len = len.wrapping_mul(8);
+
+        Ok(Checkpoint {
+            input: field("input")?.to_string(),
+            offset: number("offset")?,
+            hasher
+        })
+    }
+
+    pub fn save (&self, path: &str) -> io::Result<()> {
+        // Write then rename so a second interrupt can't leave a truncated checkpoint behind
+        let tmp = format!("{}.tmp", path);
+        fs::write(&tmp, self.to_text()?)?;
+        fs::rename(&tmp, path)
+    }
+
+    pub fn load (path: &str) -> io::Result<Checkpoint> {
+        Checkpoint::from_text(&fs::read_to_string(path)?)
+    }
+}
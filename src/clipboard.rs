@@ -0,0 +1,72 @@
+/*
+ * The system clipboard, through whichever helper the platform provides: pbcopy/pbpaste on macOS,
+ * clip and PowerShell on Windows, and wl-clipboard, xclip or xsel elsewhere. Each is tried in
+ * turn until one works, so a Wayland session without wl-clipboard can still fall back to X11.
+ */
+
+use std::{io::{self, Write}, process::{Command, Stdio}};
+
+#[cfg(target_os = "macos")]
+const COPY: &[(&str, &[&str])] = &[("pbcopy", &[])];
+#[cfg(target_os = "macos")]
+const PASTE: &[(&str, &[&str])] = &[("pbpaste", &[])];
+
+#[cfg(windows)]
+const COPY: &[(&str, &[&str])] = &[("clip", &[])];
+#[cfg(windows)]
+const PASTE: &[(&str, &[&str])] = &[("powershell", &["-NoProfile", "-Command", "Get-Clipboard"])];
+
+#[cfg(not(any(target_os = "macos", windows)))]
+const COPY: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"])
+];
+#[cfg(not(any(target_os = "macos", windows)))]
+const PASTE: &[(&str, &[&str])] = &[
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-o"]),
+    ("xsel", &["--clipboard", "--output"])
+];
+
+fn
+no_helper (helpers: &[(&str, &[&str])]) -> io::Error {
+    let names: Vec<&str> = helpers.iter().map(|(program, _)| *program).collect();
+    io::Error::new(io::ErrorKind::NotFound, format!("no working clipboard helper; tried {}", names.join(", ")))
+}
+
+/**
+ * Places `text` on the clipboard.
+ */
+pub fn
+copy (text: &str) -> io::Result<()> {
+    for (program, args) in COPY {
+        let Ok(mut child) = Command::new(program).args(*args)
+            .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null())
+            .spawn() else {
+            continue;
+        };
+
+        let written = child.stdin.take().unwrap().write_all(text.as_bytes());
+        if child.wait()?.success() && written.is_ok() {
+            return Ok(());
+        }
+    }
+    Err(no_helper(COPY))
+}
+
+/**
+ * Reads the clipboard's text.
+ */
+pub fn
+paste () -> io::Result<String> {
+    for (program, args) in PASTE {
+        let Ok(output) = Command::new(program).args(*args).stdin(Stdio::null()).stderr(Stdio::null()).output() else {
+            continue;
+        };
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+        }
+    }
+    Err(no_helper(PASTE))
+}
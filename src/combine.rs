@@ -0,0 +1,154 @@
+/*
+ * Combining the digests of many items into one fingerprint of the whole collection. XOR and
+ * sorted concatenation don't depend on the order the items come in, so two parties holding the
+ * same set, listed differently, compute the same fingerprint; plain concatenation does.
+ *
+ * XOR is cheap and can be updated one item at a time, but an item present twice cancels itself
+ * out, and anyone free to choose items can steer the result. Sorted concatenation rehashes the
+ * whole list, so it has neither weakness but must see every digest again after any change.
+ * SetHasher sits between the two: updated per item like XOR, without the cancelling.
+ */
+
+use alloc::vec::Vec;
+
+use crate::{hash, Algo, Digest, Hasher};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mode {
+    Xor,
+    SortedConcat,
+    Concat
+}
+
+impl Mode {
+    pub fn from_name (name: &str) -> Option<Mode> {
+        match name {
+            "xor" => Some(Mode::Xor),
+            "sorted" => Some(Mode::SortedConcat),
+            "concat" => Some(Mode::Concat),
+            _ => None
+        }
+    }
+}
+
+/**
+ * XORs equal-length digests together. None if the lengths differ, or if there are no digests
+ * and so no length to give the result.
+ */
+pub fn
+xor (digests: &[&[u8]]) -> Option<Vec<u8>> {
+    let mut acc = digests.first()?.to_vec();
+
+    for digest in &digests[1..] {
+        if digest.len() != acc.len() {
+            return None;
+        }
+        for (a, b) in acc.iter_mut().zip(digest.iter()) {
+            *a ^= b;
+        }
+    }
+
+    Some(acc)
+}
+
+/**
+ * Hashes the digests concatenated in the order given.
+ */
+pub fn
+concat (digests: &[&[u8]], algo: Algo) -> Digest {
+    let mut hasher = Hasher::new(algo);
+    for digest in digests {
+        hasher.update(digest);
+    }
+    hasher.finalize_digest()
+}
+
+/**
+ * Hashes the digests concatenated in ascending byte order. Duplicates are kept, so this
+ * fingerprints a multiset; deduplicate first for a set.
+ */
+pub fn
+sorted_concat (digests: &[&[u8]], algo: Algo) -> Digest {
+    let mut sorted = digests.to_vec();
+    sorted.sort_unstable();
+    concat(&sorted, algo)
+}
+
+/**
+ * Combines the digests with the given mode. None only for XOR, as in xor().
+ */
+pub fn
+combine (digests: &[&[u8]], mode: Mode, algo: Algo) -> Option<Vec<u8>> {
+    match mode {
+        Mode::Xor => xor(digests),
+        Mode::SortedConcat => Some(sorted_concat(digests, algo).to_vec()),
+        Mode::Concat => Some(concat(digests, algo).to_vec())
+    }
+}
+
+/**
+ * A running fingerprint of a collection which changes over time, updated per item rather than
+ * recomputed. Each item's digest is hashed again and added into a sum modulo 2^224, 2^256 or 2^512, so
+ * insertions and removals commute, and unlike XOR an item inserted twice counts twice. The
+ * fingerprint covers the number of items as well as the sum.
+ *
+ * Removing an item which was never inserted isn't detected; the fingerprint then matches no
+ * real collection.
+ */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SetHasher {
+    algo: Algo,
+    // Big-endian, in the first digest_len() bytes
+    sum: [u8; 64],
+    count: u64
+}
+
+impl SetHasher {
+    pub fn new (algo: Algo) -> SetHasher {
+        SetHasher { algo, sum: [0u8; 64], count: 0 }
+    }
+
+    pub fn insert (&mut self, digest: &[u8]) {
+        let element = hash(digest, self.algo);
+        let mut carry = 0u16;
+        for (s, e) in self.sum[..element.len()].iter_mut().zip(element.iter()).rev() {
+            let total = *s as u16 + *e as u16 + carry;
+            *s = total as u8;
+            carry = total >> 8;
+        }
+        self.count = self.count.wrapping_add(1);
+    }
+
+    pub fn remove (&mut self, digest: &[u8]) {
+        let element = hash(digest, self.algo);
+        let mut borrow = 0i16;
+        for (s, e) in self.sum[..element.len()].iter_mut().zip(element.iter()).rev() {
+            let total = *s as i16 - *e as i16 - borrow;
+            *s = total as u8;
+            borrow = (total < 0) as i16;
+        }
+        self.count = self.count.wrapping_sub(1);
+    }
+
+    /**
+     * The number of items inserted and not since removed.
+     */
+    pub fn len (&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty (&self) -> bool {
+        self.count == 0
+    }
+
+    /**
+     * The fingerprint of the collection as it stands: the hash of the sum followed by the item
+     * count as a 64-bit big-endian integer.
+     */
+    pub fn current (&self) -> Digest {
+        let mut hasher = Hasher::new(self.algo);
+        hasher.update(&self.sum[..self.algo.digest_len()]);
+        hasher.update(&self.count.to_be_bytes());
+        hasher.finalize_digest()
+    }
+}
@@ -0,0 +1,108 @@
+use std::io::{self, BufRead};
+
+/**
+ * Reads one record of RFC 4180 CSV. Quoted fields may contain the delimiter, doubled quotes and
+ * line breaks, so a record can span several physical lines. Fields are returned as the raw bytes
+ * between the delimiters, so input that isn't UTF-8 passes through unchanged. Returns None at end
+ * of input.
+ */
+pub fn
+read_record<R: BufRead> (reader: &mut R, delimiter: u8) -> io::Result<Option<Vec<Vec<u8>>>> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+
+    let mut fields = Vec::new();
+    let mut field = Vec::new();
+    let mut quoted = false;
+    let mut i = 0;
+
+    loop {
+        if i == line.len() {
+            if quoted {
+                // The line break belongs to a quoted field; continue with the next line
+                let start = line.len();
+                if reader.read_until(b'\n', &mut line)? == 0 {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "unterminated quoted field"));
+                }
+                i = start;
+                continue;
+            }
+            break;
+        }
+
+        let c = line[i];
+        if quoted {
+            if c == b'"' {
+                if line.get(i + 1) == Some(&b'"') {
+                    field.push(b'"');
+                    i += 1;
+                } else {
+                    quoted = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else if c == b'"' && field.is_empty() {
+            quoted = true;
+        } else if c == delimiter {
+            fields.push(std::mem::take(&mut field));
+        } else if c == b'\n' || (c == b'\r' && line.get(i + 1) == Some(&b'\n')) {
+            break;
+        } else {
+            field.push(c);
+        }
+        i += 1;
+    }
+
+    fields.push(field);
+    Ok(Some(fields))
+}
+
+/**
+ * Writes a record, quoting only the fields which contain the delimiter, a quote or a line break.
+ */
+pub fn
+format_record (fields: &[Vec<u8>], delimiter: u8) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    for (i, f) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(delimiter);
+        }
+        if f.iter().any(|&c| c == delimiter || c == b'"' || c == b'\n' || c == b'\r') {
+            out.push(b'"');
+            for &c in f {
+                if c == b'"' {
+                    out.push(b'"');
+                }
+                out.push(c);
+            }
+            out.push(b'"');
+        } else {
+            out.extend_from_slice(f);
+        }
+    }
+    out
+}
+
+/**
+ * Resolves a column selection, given as 1-based indices or, when the input has a header row,
+ * column names, to 0-based indices.
+ */
+pub fn
+resolve_columns (selection: &[String], header: Option<&[Vec<u8>]>) -> Result<Vec<usize>, String> {
+    selection.iter().map(|column| {
+        if let Ok(index) = column.parse::<usize>() {
+            if index == 0 {
+                return Err("column numbers start at 1".to_string());
+            }
+            return Ok(index - 1);
+        }
+
+        header
+            .and_then(|h| h.iter().position(|name| name == column.as_bytes()))
+            .ok_or_else(|| format!("no column named '{}'", column))
+    }).collect()
+}
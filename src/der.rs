@@ -0,0 +1,137 @@
+/*
+ * Minimal ASN.1 DER encoding helpers; just enough to build the small fixed structures that
+ * wrap digests (see ITU-T X.690 # 8 and # 10).
+ */
+
+use crate::Algo;
+
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_SEQUENCE: u8 = 0x30;
+
+// From https://csrc.nist.gov/projects/computer-security-objects-register/algorithm-registration
+// SHA-1's arc is OIW's, from before NIST registered the SHA-2 arcs
+pub const OID_SHA_1: &[u64] = &[1, 3, 14, 3, 2, 26];
+// MD5's is RSA Data Security's, RFC 8017 # A.2.4
+pub const OID_MD5: &[u64] = &[1, 2, 840, 113549, 2, 5];
+pub const OID_SHA_224: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 4];
+pub const OID_SHA_256: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 1];
+pub const OID_SHA_512: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 3];
+pub const OID_SHA3_224: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 7];
+pub const OID_SHA3_256: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 8];
+pub const OID_SHA3_384: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 9];
+pub const OID_SHA3_512: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 10];
+
+/**
+ * Encodes a tag-length-value triple, using the short length form below 128 bytes and the long
+ * form otherwise.
+ */
+pub fn
+tlv (tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+
+    if value.len() < 0x80 {
+        out.push(value.len() as u8);
+    } else {
+        let len_bytes = value.len().to_be_bytes();
+        let skip = len_bytes.iter().take_while(|b| **b == 0).count();
+        out.push(0x80 | (len_bytes.len() - skip) as u8);
+        out.extend_from_slice(&len_bytes[skip..]);
+    }
+
+    out.extend_from_slice(value);
+    out
+}
+
+pub fn
+sequence (items: &[Vec<u8>]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &items.concat())
+}
+
+/**
+ * Encodes a non-negative integer given as big-endian bytes, stripping redundant leading zeros
+ * and adding one back where the high bit would otherwise mark the value as negative.
+ */
+pub fn
+unsigned_integer (value: &[u8]) -> Vec<u8> {
+    let skip = value.iter().take_while(|b| **b == 0).count();
+    let mut content = value[skip..].to_vec();
+
+    if content.is_empty() || content[0] & 0x80 != 0 {
+        content.insert(0, 0x0);
+    }
+
+    tlv(TAG_INTEGER, &content)
+}
+
+pub fn
+boolean (value: bool) -> Vec<u8> {
+    tlv(TAG_BOOLEAN, &[if value { 0xff } else { 0x0 }])
+}
+
+pub fn
+null () -> Vec<u8> {
+    tlv(TAG_NULL, &[])
+}
+
+pub fn
+octet_string (value: &[u8]) -> Vec<u8> {
+    tlv(TAG_OCTET_STRING, value)
+}
+
+/**
+ * Encodes an object identifier; the first two arcs share a byte, and every subsequent arc is
+ * written base-128 with the high bit set on all but its final byte.
+ */
+pub fn
+oid (arcs: &[u64]) -> Vec<u8> {
+    let mut content = vec![(arcs[0] * 40 + arcs[1]) as u8];
+
+    for arc in &arcs[2..] {
+        let mut encoded = vec![(arc & 0x7f) as u8];
+        let mut rest = arc >> 7;
+
+        while rest > 0 {
+            encoded.insert(0, 0x80 | (rest & 0x7f) as u8);
+            rest >>= 7;
+        }
+
+        content.extend_from_slice(&encoded);
+    }
+
+    tlv(TAG_OID, &content)
+}
+
+/**
+ * AlgorithmIdentifier ::= SEQUENCE { algorithm OBJECT IDENTIFIER, parameters NULL } for the
+ * given hash algorithm. Returns None for algorithms without a registered OID.
+ */
+pub fn
+hash_algorithm_identifier (algo: Algo) -> Option<Vec<u8>> {
+    let arcs = match algo {
+        Algo::Sha224 => OID_SHA_224,
+        Algo::Sha256 => OID_SHA_256,
+        Algo::Sha512 => OID_SHA_512,
+        Algo::Sha1 => OID_SHA_1,
+        Algo::Md5 => OID_MD5,
+        Algo::Sha3_224 => OID_SHA3_224,
+        Algo::Sha3_256 => OID_SHA3_256,
+        Algo::Sha3_384 => OID_SHA3_384,
+        Algo::Sha3_512 => OID_SHA3_512
+    };
+
+    Some(sequence(&[oid(arcs), null()]))
+}
+
+/**
+ * DigestInfo ::= SEQUENCE { digestAlgorithm AlgorithmIdentifier, digest OCTET STRING }, the
+ * structure an RSA PKCS#1 v1.5 signature covers (RFC 8017 # 9.2). RFC 3161's MessageImprint has
+ * the same encoding. Returns None for algorithms without a registered OID.
+ */
+pub fn
+digest_info (digest: &[u8], algo: Algo) -> Option<Vec<u8>> {
+    Some(sequence(&[hash_algorithm_identifier(algo)?, octet_string(digest)]))
+}
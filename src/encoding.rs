@@ -0,0 +1,196 @@
+/*
+ * Text encodings for digests. Each encoding maps fixed-width bit groups, most significant bit
+ * first, to characters of an alphabet, so a single streaming encoder serves all of them.
+ */
+
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Encoding {
+    // Lowercase base16
+    Hex,
+    // Douglas Crockford's base32: no I, L, O or U, so it survives being read aloud or retyped
+    Base32Crockford,
+    // Zooko's human-oriented base32, see https://philzimmermann.com/docs/human-oriented-base-32-encoding.txt
+    ZBase32
+}
+
+impl Encoding {
+    pub fn from_name (name: &str) -> Option<Encoding> {
+        match name {
+            "hex" => Some(Encoding::Hex),
+            "base32-crockford" => Some(Encoding::Base32Crockford),
+            "zbase32" => Some(Encoding::ZBase32),
+            _ => None
+        }
+    }
+
+    fn alphabet (&self) -> &'static [u8] {
+        match self {
+            Encoding::Hex => b"0123456789abcdef",
+            Encoding::Base32Crockford => b"0123456789ABCDEFGHJKMNPQRSTVWXYZ",
+            Encoding::ZBase32 => b"ybndrfg8ejkmcpqxot1uwisza345h769"
+        }
+    }
+
+    fn bits (&self) -> u32 {
+        match self {
+            Encoding::Hex => 4,
+            Encoding::Base32Crockford | Encoding::ZBase32 => 5
+        }
+    }
+
+    /**
+     * Encodes bytes in one go. A final partial bit group is padded with zero bits; no padding
+     * characters are added.
+     */
+    pub fn encode<T: AsRef<[u8]>> (&self, bytes: T) -> String {
+        let bytes = bytes.as_ref();
+        let mut groups = Groups { encoding: *self, acc: 0, pending: 0 };
+        let mut out = Vec::with_capacity(bytes.len() * 2);
+
+        groups.push(bytes, &mut out);
+        groups.finish(&mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    /**
+     * Returns an encoder which writes the encoding of everything written to it into `writer`.
+     */
+    #[cfg(feature = "std")]
+    pub fn encoder<W: Write> (&self, writer: W) -> Encoder<W> {
+        Encoder { writer, groups: Groups { encoding: *self, acc: 0, pending: 0 } }
+    }
+}
+
+/**
+ * Splits bytes into the encoding's bit groups. Bits which don't yet make up a whole group are
+ * held back until more input arrives or finish() is called.
+ */
+struct Groups {
+    encoding: Encoding,
+    acc: u32,
+    pending: u32
+}
+
+impl Groups {
+    fn push (&mut self, bytes: &[u8], out: &mut Vec<u8>) {
+        let bits = self.encoding.bits();
+        let alphabet = self.encoding.alphabet();
+
+        for byte in bytes {
+            self.acc = (self.acc << 8) | *byte as u32;
+            self.pending += 8;
+
+            while self.pending >= bits {
+                self.pending -= bits;
+                out.push(alphabet[((self.acc >> self.pending) & ((1 << bits) - 1)) as usize]);
+            }
+            self.acc &= (1 << self.pending) - 1;
+        }
+    }
+
+    /**
+     * Pads a final partial group with zero bits.
+     */
+    fn finish (&mut self, out: &mut Vec<u8>) {
+        let bits = self.encoding.bits();
+        if self.pending > 0 {
+            let index = (self.acc << (bits - self.pending)) & ((1 << bits) - 1);
+            out.push(self.encoding.alphabet()[index as usize]);
+            self.pending = 0;
+        }
+    }
+}
+
+/**
+ * A streaming encoder, writing the encoding of its input through to another writer.
+ */
+#[cfg(feature = "std")]
+pub struct Encoder<W: Write> {
+    writer: W,
+    groups: Groups
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Encoder<W> {
+    /**
+     * Writes out any final partial group and returns the underlying writer.
+     */
+    pub fn finish (mut self) -> io::Result<W> {
+        let mut out = Vec::new();
+        self.groups.finish(&mut out);
+        self.writer.write_all(&out)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Write for Encoder<W> {
+    fn write (&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut out = Vec::with_capacity(buf.len() * 2);
+        self.groups.push(buf, &mut out);
+        self.writer.write_all(&out)?;
+        Ok(buf.len())
+    }
+
+    fn flush (&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/**
+ * Base16, in place of the `hex` crate's functions of the same names.
+ */
+pub mod hex {
+    use alloc::{string::String, vec::Vec};
+    use core::fmt;
+
+    use super::Encoding;
+
+    #[derive(Debug, PartialEq)]
+    pub enum DecodeError {
+        OddLength,
+        InvalidCharacter(char)
+    }
+
+    impl fmt::Display for DecodeError {
+        fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                DecodeError::OddLength => write!(f, "odd number of hex digits"),
+                DecodeError::InvalidCharacter(c) => write!(f, "invalid hex character {:?}", c)
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for DecodeError {}
+
+    pub fn
+    encode<T: AsRef<[u8]>> (bytes: T) -> String {
+        Encoding::Hex.encode(bytes)
+    }
+
+    /**
+     * Decodes base16 in either case.
+     */
+    pub fn
+    decode<T: AsRef<[u8]>> (text: T) -> Result<Vec<u8>, DecodeError> {
+        let text = text.as_ref();
+        if text.len() % 2 != 0 {
+            return Err(DecodeError::OddLength);
+        }
+
+        let nibble = |c: u8| match c {
+            b'0'..=b'9' => Ok(c - b'0'),
+            b'a'..=b'f' => Ok(c - b'a' + 10),
+            b'A'..=b'F' => Ok(c - b'A' + 10),
+            _ => Err(DecodeError::InvalidCharacter(c as char))
+        };
+
+        text.chunks(2).map(|pair| Ok((nibble(pair[0])? << 4) | nibble(pair[1])?)).collect()
+    }
+}
@@ -0,0 +1,416 @@
+/*
+ * The SHA-2 compression function, written once over the word size. SHA-224 and SHA-256 run it
+ * on 32-bit words for 64 rounds; SHA-384, SHA-512 and the SHA-512/t variants on 64-bit words for
+ * 80 rounds. Only the constants and the rotation amounts differ.
+ */
+
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+use core::ops::{BitAnd, BitXor, Not, Shr};
+
+/*
+ * From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 4.2.2 
+ *
+ * SHA-224 and SHA-256 use the same sequence of sixty-four constant 32-bit words,
+ * K{256}_0, K{256}_1, ..., K{256}_63. These words represent the first thirty-two bits of the 
+ * fractional parts of the cube roots of the first sixty-four prime numbers.
+ */
+const K_256: [u32; 64] = [
+   0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+   0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+   0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+   0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+   0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+   0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+   0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+   0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2
+];
+
+/*
+ * From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 4.2.3
+ *
+ * SHA-384, SHA-512, SHA-512/224 and SHA-512/256 use the same sequence of eighty constant 64-bit
+ * words, K{512}_0, ..., K{512}_79. These words represent the first sixty-four bits of the
+ * fractional parts of the cube roots of the first eighty prime numbers.
+ */
+const K_512: [u64; 80] = [
+    0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+    0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+    0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 0xc19bf174cf692694,
+    0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+    0x983e5152ee66dfab, 0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2, 0xd5a79147930aa725, 0x06ca6351e003826f, 0x142929670a0e6e70,
+    0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+    0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b,
+    0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30,
+    0xd192e819d6ef5218, 0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+    0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+    0xca273eceea26619c, 0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+    0x06f067aa72176fba, 0x0a637dc5a2c898a6, 0x113f9804bef90dae, 0x1b710b35131c471b,
+    0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817
+];
+
+/**
+ * The message length in bits, as the padding's length field holds it: a u64 for the SHA-256
+ * family and a u128 for the SHA-512 family, whatever the platform's word size, and like the
+ * field modulo 2^64 or 2^128.
+ */
+pub trait Length: 'static + Copy + Default + PartialEq + core::fmt::Debug {
+    const BYTES: usize;
+
+    // Adds to the count, wrapping
+    fn add_bits (self, bits: u64) -> Self;
+    // The low 64 bits of the count
+    fn low_u64 (self) -> u64;
+    // The count in whole bytes, modulo 2^64
+    fn bytes (self) -> u64;
+    // Writes the count big-endian into the first BYTES bytes of `out`
+    fn write_be_bytes (self, out: &mut [u8]);
+}
+
+impl Length for u64 {
+    const BYTES: usize = 8;
+
+    fn add_bits (self, bits: u64) -> u64 {
+        self.wrapping_add(bits)
+    }
+
+    fn low_u64 (self) -> u64 {
+        self
+    }
+
+    fn bytes (self) -> u64 {
+        self / 8
+    }
+
+    fn write_be_bytes (self, out: &mut [u8]) {
+        out[..<u64 as Length>::BYTES].copy_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl Length for u128 {
+    const BYTES: usize = 16;
+
+    fn add_bits (self, bits: u64) -> u128 {
+        self.wrapping_add(bits as u128)
+    }
+
+    fn low_u64 (self) -> u64 {
+        self as u64
+    }
+
+    fn bytes (self) -> u64 {
+        (self / 8) as u64
+    }
+
+    fn write_be_bytes (self, out: &mut [u8]) {
+        out[..<u128 as Length>::BYTES].copy_from_slice(&self.to_be_bytes());
+    }
+}
+
+/**
+ * A SHA-2 word: u32 for the SHA-256 family and u64 for the SHA-512 family, with the per-size
+ * round constants and the rotation and shift amounts of the functions in FIPS 180-4 # 4.1.
+ */
+pub trait Word: 'static + Copy + Default + BitAnd<Output = Self> + BitXor<Output = Self> + Not<Output = Self> + Shr<u32, Output = Self> {
+    const BYTES: usize;
+    const K: &'static [Self];
+    // The type of the message length, twice the word's width as FIPS 180-4 # 5.1 has it
+    type Length: Length;
+
+    // Σ0 and Σ1: three right rotations each
+    const BIG_SIGMA_0: [u32; 3];
+    const BIG_SIGMA_1: [u32; 3];
+
+    // σ0 and σ1: two right rotations and a right shift each
+    const SMALL_SIGMA_0: [u32; 3];
+    const SMALL_SIGMA_1: [u32; 3];
+
+    fn add (self, other: Self) -> Self;
+    fn rotr (self, n: u32) -> Self;
+    fn from_be_slice (bytes: &[u8]) -> Self;
+    // Writes the word big-endian into the first BYTES bytes of `out`
+    fn write_be_bytes (self, out: &mut [u8]);
+}
+
+impl Word for u32 {
+    const BYTES: usize = 4;
+    const K: &'static [u32] = &K_256;
+    type Length = u64;
+
+    // From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 4.1.2
+    const BIG_SIGMA_0: [u32; 3] = [2, 13, 22];
+    const BIG_SIGMA_1: [u32; 3] = [6, 11, 25];
+    const SMALL_SIGMA_0: [u32; 3] = [7, 18, 3];
+    const SMALL_SIGMA_1: [u32; 3] = [17, 19, 10];
+
+    fn add (self, other: u32) -> u32 {
+        self.wrapping_add(other)
+    }
+
+    fn rotr (self, n: u32) -> u32 {
+        self.rotate_right(n)
+    }
+
+    fn from_be_slice (bytes: &[u8]) -> u32 {
+        u32::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    fn write_be_bytes (self, out: &mut [u8]) {
+        out[..Self::BYTES].copy_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl Word for u64 {
+    const BYTES: usize = 8;
+    const K: &'static [u64] = &K_512;
+    type Length = u128;
+
+    // From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 4.1.3
+    const BIG_SIGMA_0: [u32; 3] = [28, 34, 39];
+    const BIG_SIGMA_1: [u32; 3] = [14, 18, 41];
+    const SMALL_SIGMA_0: [u32; 3] = [1, 8, 7];
+    const SMALL_SIGMA_1: [u32; 3] = [19, 61, 6];
+
+    fn add (self, other: u64) -> u64 {
+        self.wrapping_add(other)
+    }
+
+    fn rotr (self, n: u32) -> u64 {
+        self.rotate_right(n)
+    }
+
+    fn from_be_slice (bytes: &[u8]) -> u64 {
+        u64::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    fn write_be_bytes (self, out: &mut [u8]) {
+        out[..<u64 as Word>::BYTES].copy_from_slice(&self.to_be_bytes());
+    }
+}
+
+/*
+ * Eight working words maintaining the state of the digest during hashing, the digest length in
+ * bits, which decides how much of the state export() returns, and the number of rounds each
+ * block gets, which is the standard 64 or 80 unless reduced with with_rounds().
+ */
+#[derive(Clone)]
+pub struct Engine<W: Word> {
+    pub(crate) a: W,
+    pub(crate) b: W,
+    pub(crate) c: W,
+    pub(crate) d: W,
+    pub(crate) e: W,
+    pub(crate) f: W,
+    pub(crate) g: W,
+    pub(crate) h: W,
+    // Narrow, so that a Hasher around an Engine<u32> stays small for embedded targets
+    pub(crate) n: u16,
+    pub(crate) rounds: u8
+}
+
+impl<W: Word> Engine<W> {
+    /**
+     * Starts from the given initial hash value, for a digest of `n` bits.
+     */
+    pub fn from_words (init: [W; 8], n: usize) -> Engine<W> {
+        Engine {
+            a: init[0],
+            b: init[1],
+            c: init[2],
+            d: init[3],
+            e: init[4],
+            f: init[5],
+            g: init[6],
+            h: init[7],
+            n: n as u16,
+            rounds: W::K.len() as u8
+        }
+    }
+
+    /**
+     * Runs only the first `rounds` rounds of each compression, for studying round-reduced SHA-2.
+     * The message schedule and the final addition of the input state are unchanged. Panics if
+     * `rounds` is more than the standard count.
+     */
+    pub fn with_rounds (mut self, rounds: usize) -> Engine<W> {
+        assert!(rounds <= W::K.len(), "SHA-2 with {}-bit words has at most {} rounds", 8 * W::BYTES, W::K.len());
+        self.rounds = rounds as u8;
+        self
+    }
+
+    pub fn rounds (&self) -> usize {
+        self.rounds as usize
+    }
+
+    /**
+     * The digest length in bits.
+     */
+    pub fn digest_bits (&self) -> usize {
+        self.n as usize
+    }
+
+    pub fn words (&self) -> [W; 8] {
+        [self.a, self.b, self.c, self.d, self.e, self.f, self.g, self.h]
+    }
+
+    /**
+     * The block length in bytes: 64 for 32-bit words, 128 for 64-bit words.
+     */
+    pub fn block_len () -> usize {
+        16 * W::BYTES
+    }
+
+    /**
+     * Rotates state values according to https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf 6.2.2 s
+     * section 3.
+     */
+    fn rotate (&mut self, x: W, y: W) {
+       self.h = self.g;
+       self.g = self.f;
+       self.f = self.e;
+       self.e = self.d.add(x);
+       self.d = self.c;
+       self.c = self.b;
+       self.b = self.a;
+       self.a = x.add(y);
+    }
+
+    fn add (&mut self, v: &[W; 8]) {
+        self.a = self.a.add(v[0]);
+        self.b = self.b.add(v[1]);
+        self.c = self.c.add(v[2]);
+        self.d = self.d.add(v[3]);
+        self.e = self.e.add(v[4]);
+        self.f = self.f.add(v[5]);
+        self.g = self.g.add(v[6]);
+        self.h = self.h.add(v[7]);
+    }
+
+    /**
+     * Returns the big-endian bytes of the state, truncated to the digest length.
+     */
+    #[cfg(feature = "alloc")]
+    pub fn export (&mut self) -> Vec<u8> {
+        let mut bytes = vec![0u8; self.digest_bits() / 8];
+        self.export_into(&mut bytes);
+        bytes
+    }
+
+    /**
+     * Writes the big-endian bytes of the state, truncated to the digest length, to the start of
+     * `out`, without allocating. Returns the number of bytes written, n / 8.
+     */
+    pub fn export_into (&self, out: &mut [u8]) -> usize {
+        let mut bytes = [0u8; 64];
+        for (chunk, word) in bytes.chunks_exact_mut(W::BYTES).zip(self.words()) {
+            word.write_be_bytes(chunk);
+        }
+
+        let len = self.digest_bits() / 8;
+        out[..len].copy_from_slice(&bytes[..len]);
+        len
+    }
+
+    /**
+     * Processes a single message block of 16 words, updating the state.
+     *
+     * See https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 6.2.2 and # 6.4.2
+     */
+    pub fn compress (&mut self, block: &[u8]) {
+        #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+        let mut w: [W; 80] = schedule(block);
+
+        // Stored to add back to the state after the main processing loop
+        #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+        let mut input_values: [W; 8] = self.words();
+
+        for (k, w) in W::K.iter().zip(w.iter()).take(self.rounds()) {
+            /* 
+            * From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 4.1.2
+            *
+            * The functions Σ0, Σ1, Ch(x, y, z) and Maj(x, y, z)
+            */
+            let [r0, r1, r2] = W::BIG_SIGMA_0;
+            let s0 = self.a.rotr(r0) ^ self.a.rotr(r1) ^ self.a.rotr(r2);
+            let [r0, r1, r2] = W::BIG_SIGMA_1;
+            let s1 = self.e.rotr(r0) ^ self.e.rotr(r1) ^ self.e.rotr(r2);
+
+            let ch = (self.e & self.f) ^ ((!self.e) & self.g);
+            let maj = (self.a & self.b) ^ (self.a & self.c) ^ (self.b & self.c);
+
+            // See https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 6.2.2 section 3
+            self.rotate(
+                self.h.add(s1)
+                  .add(ch)
+                  .add(*k)
+                  .add(*w),
+                s0.add(maj)
+            );
+        }
+
+        self.add(&input_values);
+
+        #[cfg(feature = "zeroize")]
+        {
+            crate::wipe::wipe(&mut w);
+            crate::wipe::wipe(&mut input_values);
+        }
+    }
+}
+
+/**
+ * Expands a message block into the message schedule: its 16 words, then each word after from
+ * the four 16, 15, 7 and 2 places before it. The rounds use the first K.len() words, so for u32
+ * the last 16 stay zero.
+ *
+ * See https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 6.2.2 and # 6.4.2, step 1
+ */
+pub(crate) fn
+schedule<W: Word> (block: &[u8]) -> [W; 80] {
+    let rounds = W::K.len();
+    let mut w: [W; 80] = [W::default(); 80];
+    let mut indx = 0;
+
+    // Fill the first 16 elements of w with big-endian words from the block
+    for chunk in block.chunks(W::BYTES) {
+        w[indx] = W::from_be_slice(chunk);
+        indx += 1;
+    }
+
+    while indx < rounds {
+        /* 
+        * From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 4.1.2
+        *
+        * The two functions σ0 and σ1 as defined in the specification.
+        */
+        let [r0, r1, r2] = W::SMALL_SIGMA_0;
+        let s0 = w[indx - 15].rotr(r0) ^ w[indx - 15].rotr(r1) ^ (w[indx - 15] >> r2);
+        let [r0, r1, r2] = W::SMALL_SIGMA_1;
+        let s1 = w[indx - 2].rotr(r0) ^ w[indx - 2].rotr(r1) ^ (w[indx - 2] >> r2);
+
+        // From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 6.2.2            
+        w[indx] = w[indx - 16]
+                    .add(s0)
+                    .add(w[indx - 7])
+                    .add(s1);
+        indx += 1;
+    }
+
+    w
+}
+
+#[cfg(feature = "zeroize")]
+impl<W: Word> Drop for Engine<W> {
+    fn drop (&mut self) {
+        for word in [&mut self.a, &mut self.b, &mut self.c, &mut self.d, &mut self.e, &mut self.f, &mut self.g, &mut self.h] {
+            crate::wipe::wipe(core::slice::from_mut(word));
+        }
+    }
+}
@@ -0,0 +1,119 @@
+/*
+ * Line ending normalization, so a text file checked out with CRLF line endings on Windows and
+ * with LF on Unix hashes the same. Both "\r\n" and a bare "\n" count as a line ending and are
+ * rewritten to the chosen one; a "\r" not followed by "\n" is left alone, as git leaves it.
+ * The conversion is streaming: input can arrive in chunks of any size, including with a "\r\n"
+ * split across two of them.
+ */
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Eol {
+    Lf,
+    Crlf
+}
+
+impl Eol {
+    pub fn from_name (name: &str) -> Option<Eol> {
+        match name {
+            "lf" => Some(Eol::Lf),
+            "crlf" => Some(Eol::Crlf),
+            _ => None
+        }
+    }
+
+    pub fn as_bytes (&self) -> &'static [u8] {
+        match self {
+            Eol::Lf => b"\n",
+            Eol::Crlf => b"\r\n"
+        }
+    }
+}
+
+/**
+ * Rewrites line endings in a stream. Converted output is passed to a callback, typically a
+ * hasher's update, in runs as long as the input allows. A "\r" ending one chunk is held back
+ * until the next shows whether it starts a "\r\n", so finish() must be called after the last
+ * chunk to release it.
+ */
+#[derive(Clone, Debug)]
+pub struct EolNormalizer {
+    eol: Eol,
+    pending_cr: bool
+}
+
+impl EolNormalizer {
+    pub fn new (eol: Eol) -> EolNormalizer {
+        EolNormalizer { eol, pending_cr: false }
+    }
+
+    pub fn update<F: FnMut(&[u8])> (&mut self, data: &[u8], mut out: F) {
+        if data.is_empty() {
+            return;
+        }
+
+        let mut start = 0;
+        if self.pending_cr {
+            self.pending_cr = false;
+            if data[0] == b'\n' {
+                out(self.eol.as_bytes());
+                start = 1;
+            } else {
+                out(b"\r");
+            }
+        }
+
+        let mut i = start;
+        while i < data.len() {
+            match data[i] {
+                b'\n' => {
+                    out(&data[start..i]);
+                    out(self.eol.as_bytes());
+                    start = i + 1;
+                },
+                b'\r' if i + 1 == data.len() => {
+                    out(&data[start..i]);
+                    self.pending_cr = true;
+                    start = i + 1;
+                },
+                b'\r' if data[i + 1] == b'\n' => {
+                    out(&data[start..i]);
+                    out(self.eol.as_bytes());
+                    i += 1;
+                    start = i + 1;
+                },
+                _ => {}
+            }
+            i += 1;
+        }
+
+        if start < data.len() {
+            out(&data[start..]);
+        }
+    }
+
+    /**
+     * Releases a "\r" held back from the end of the last chunk.
+     */
+    pub fn finish<F: FnMut(&[u8])> (&mut self, mut out: F) {
+        if self.pending_cr {
+            self.pending_cr = false;
+            out(b"\r");
+        }
+    }
+}
+
+/**
+ * Rewrites the line endings in a whole buffer.
+ */
+#[cfg(feature = "alloc")]
+pub fn
+normalize (data: &[u8], eol: Eol) -> Vec<u8> {
+    let mut converted = Vec::with_capacity(data.len());
+    let mut normalizer = EolNormalizer::new(eol);
+    normalizer.update(data, |b| converted.extend_from_slice(b));
+    normalizer.finish(|b| converted.extend_from_slice(b));
+    converted
+}
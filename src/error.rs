@@ -0,0 +1,74 @@
+/*
+ * The error type for the library's fallible entry points, in place of the panics and bare
+ * Strings they used to give.
+ */
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+// Variants come and go with the alloc and std features, so matches need a wildcard arm
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ShaError {
+    // A digest length other than 224 or 256 bits
+    UnsupportedAlgorithm(usize),
+    // An algorithm name which isn't SHA-224 or SHA-256
+    UnknownAlgorithm,
+    // A digest of a length, in bytes, which no enabled algorithm produces
+    InvalidDigestLength(usize),
+    // Digest text which is neither hex nor base64
+    InvalidDigestEncoding,
+    // A saved hasher state which no hasher could have been in
+    #[cfg(feature = "alloc")]
+    InvalidState(String),
+    #[cfg(feature = "std")]
+    Io(io::Error)
+}
+
+impl ShaError {
+    /**
+     * The exit status for a command line tool to report this error with: 2 for a bad argument,
+     * as for other usage errors, and 1 otherwise.
+     */
+    pub fn exit_code (&self) -> i32 {
+        let usage = matches!(self,
+            ShaError::UnsupportedAlgorithm(_) | ShaError::UnknownAlgorithm
+                | ShaError::InvalidDigestLength(_) | ShaError::InvalidDigestEncoding);
+        if usage { 2 } else { 1 }
+    }
+}
+
+impl fmt::Display for ShaError {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaError::UnsupportedAlgorithm(n) => write!(f, "unsupported algorithm SHA-{}; use 224 or 256", n),
+            ShaError::UnknownAlgorithm => write!(f, "unknown algorithm; use 224 or 256"),
+            ShaError::InvalidDigestLength(n) => write!(f, "no supported algorithm has a {}-byte digest", n),
+            ShaError::InvalidDigestEncoding => write!(f, "digest is neither hex nor base64"),
+            #[cfg(feature = "alloc")]
+            ShaError::InvalidState(why) => write!(f, "invalid hasher state: {}", why),
+            #[cfg(feature = "std")]
+            ShaError::Io(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ShaError {
+    fn source (&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ShaError::Io(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for ShaError {
+    fn from (e: io::Error) -> ShaError {
+        ShaError::Io(e)
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{encoding::hex, keccak::keccak256};
+
+/**
+ * Derives the Ethereum address for an uncompressed secp256k1 public key, given either as the
+ * 64-byte X || Y coordinates or in SEC1 form with the leading 0x04 byte. The address is the last
+ * 20 bytes of the key's Keccak-256 hash.
+ */
+pub fn
+address (public_key: &[u8]) -> Option<[u8; 20]> {
+    let coordinates = match public_key.len() {
+        64 => public_key,
+        65 if public_key[0] == 0x04 => &public_key[1..],
+        _ => return None
+    };
+
+    let hash = keccak256(coordinates);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    Some(address)
+}
+
+/**
+ * Formats an address with the EIP-55 mixed-case checksum: each hex letter is uppercased when the
+ * corresponding nibble of Keccak-256(lowercase hex address) is 8 or greater.
+ * See https://eips.ethereum.org/EIPS/eip-55
+ */
+pub fn
+checksum_address (address: &[u8; 20]) -> String {
+    let lower = hex::encode(address);
+    let hash = keccak256(lower.as_bytes());
+
+    let checksummed: String = lower.chars().enumerate().map(|(i, c)| {
+        let nibble = if i % 2 == 0 { hash[i / 2] >> 4 } else { hash[i / 2] & 0x0f };
+        if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+    }).collect();
+
+    format!("0x{}", checksummed)
+}
@@ -0,0 +1,99 @@
+/*
+ * Verifying regions of a firmware or disk image against expected digests, the check a release
+ * pipeline makes before shipping a flash image: the bootloader, kernel and root filesystem each
+ * sit at a known offset and must hash to what was signed off, while the gaps between them, and
+ * regions written at provisioning time, don't matter.
+ *
+ * A descriptor lists one region per line: a name, an offset and a length, each in decimal or
+ * 0x-prefixed hex, and the expected digest, separated by whitespace. Blank lines and lines
+ * starting with "#" are skipped:
+ *
+ *     # name      offset    length    sha256
+ *     bootloader  0x0       0x8000    8a3f...
+ *     kernel      0x10000   0x200000  41c2...
+ */
+
+use std::{fs, io::{self, Read, Seek, SeekFrom}, time::Instant};
+
+use crate::{report, Digest, Hasher};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Region {
+    pub name: String,
+    pub offset: u64,
+    pub length: u64,
+    pub expected: Digest
+}
+
+fn
+number (field: &str) -> Option<u64> {
+    match field.strip_prefix("0x").or_else(|| field.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => field.parse().ok()
+    }
+}
+
+/**
+ * Reads the regions out of a descriptor. The digest's length decides the algorithm, so SHA-224
+ * and SHA-256 regions can be mixed.
+ */
+pub fn
+parse (descriptor: &str) -> Result<Vec<Region>, String> {
+    let mut regions = Vec::new();
+
+    for (index, line) in descriptor.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [name, offset, length, digest] = fields[..] else {
+            return Err(format!("line {}: expected a name, offset, length and digest", index + 1));
+        };
+        let offset = number(offset).ok_or_else(|| format!("line {}: invalid offset \"{}\"", index + 1, offset))?;
+        let length = number(length).ok_or_else(|| format!("line {}: invalid length \"{}\"", index + 1, length))?;
+        if offset.checked_add(length).is_none() {
+            return Err(format!("line {}: region runs past the largest possible offset", index + 1));
+        }
+        let expected = digest.parse::<Digest>().map_err(|e| format!("line {}: {}", index + 1, e))?;
+
+        regions.push(Region { name: name.to_string(), offset, length, expected });
+    }
+
+    Ok(regions)
+}
+
+/**
+ * Hashes one region of the image. A region extending past the end of the image is reported as
+ * missing, as is one that can't be read.
+ */
+pub fn
+verify (image: &mut fs::File, image_len: u64, region: &Region) -> report::Record {
+    let start = Instant::now();
+    let mut record = report::Record {
+        path: region.name.clone(),
+        status: report::Status::Missing,
+        expected: region.expected.to_hex(),
+        actual: None,
+        bytes: 0,
+        duration: Default::default()
+    };
+
+    if region.offset + region.length <= image_len {
+        let mut hasher = Hasher::new(region.expected.algo());
+        let read = image.seek(SeekFrom::Start(region.offset))
+            .and_then(|_| hasher.update_reader(io::BufReader::new(Read::by_ref(image).take(region.length))));
+
+        // The image may have shrunk since its length was taken
+        if matches!(read, Ok(bytes) if bytes == region.length) {
+            let actual = hasher.finalize_digest();
+            record.status = if actual == region.expected { report::Status::Ok } else { report::Status::Failed };
+            record.actual = Some(actual.to_hex());
+            record.bytes = region.length;
+        }
+    }
+
+    record.duration = start.elapsed();
+    record
+}
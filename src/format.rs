@@ -0,0 +1,268 @@
+use crate::{der, encoding::{hex, Encoding}, Algo};
+
+/**
+ * Output formats for a computed digest.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Hex,
+    Base64,
+    // RFC 9530 Content-Digest / Repr-Digest structured field value, e.g. sha-256=:<base64>:
+    HttpDigest,
+    // The older RFC 3230 Digest header value, e.g. SHA-256=<base64>
+    HttpDigestLegacy,
+    // Nix's base32 encoding, as used in fetchurl's sha256 attribute
+    NixBase32,
+    // Subresource Integrity, e.g. sha256-<base64>, as used by Nix flakes' hash attribute and npm
+    Sri,
+    // One of the bit-group encodings, such as Crockford's base32 or z-base-32
+    Encoded(Encoding),
+    // Hex of the DER DigestInfo that an RSA PKCS#1 v1.5 signature covers
+    DigestInfoDer,
+    // Windows `certutil -hashfile` style: uppercase hex bytes separated by spaces
+    CertUtil,
+    // A row of PowerShell `Get-FileHash | Export-Csv` output; the digest alone is uppercase hex
+    GetFileHashCsv,
+    // hash://sha256/<hex>, as in https://github.com/hash-uri/hash-uri
+    HashUri,
+    // RFC 6920 Named Information URI, ni:///sha-256;<base64url>
+    Ni
+}
+
+impl Format {
+    pub fn from_name (name: &str) -> Option<Format> {
+        match name {
+            "hex" => Some(Format::Hex),
+            "base64" => Some(Format::Base64),
+            "http-digest" => Some(Format::HttpDigest),
+            "http-digest-legacy" => Some(Format::HttpDigestLegacy),
+            "nix-base32" => Some(Format::NixBase32),
+            "sri" => Some(Format::Sri),
+            "digestinfo-der" => Some(Format::DigestInfoDer),
+            "certutil" => Some(Format::CertUtil),
+            "get-filehash-csv" => Some(Format::GetFileHashCsv),
+            "hash-uri" => Some(Format::HashUri),
+            "ni" => Some(Format::Ni),
+            _ => Encoding::from_name(name).map(Format::Encoded)
+        }
+    }
+}
+
+/**
+ * The algorithm token registered for use in HTTP digest fields, per
+ * https://www.iana.org/assignments/http-digest-hash-alg/http-digest-hash-alg.xhtml.
+ * SHA-224 and SHA-3 have no registered tokens, and SHA-1's and MD5's are marked insecure there.
+ */
+fn
+http_algorithm (algo: Algo) -> Option<&'static str> {
+    match algo {
+        Algo::Sha256 => Some("sha-256"),
+        Algo::Sha512 => Some("sha-512"),
+        Algo::Sha1 => Some("sha"),
+        Algo::Md5 => Some("md5"),
+        Algo::Sha224 | Algo::Sha3_224 | Algo::Sha3_256 | Algo::Sha3_384 | Algo::Sha3_512 => None
+    }
+}
+
+/**
+ * The algorithm name registered for RFC 6920 Named Information, per
+ * https://www.iana.org/assignments/named-information/named-information.xhtml. The registry has
+ * no SHA-224, SHA-1 or MD5, and its sha-384 has no counterpart here.
+ */
+fn
+ni_algorithm (algo: Algo) -> Option<&'static str> {
+    match algo {
+        Algo::Sha256 => Some("sha-256"),
+        Algo::Sha512 => Some("sha-512"),
+        Algo::Sha3_224 => Some("sha3-224"),
+        Algo::Sha3_256 => Some("sha3-256"),
+        Algo::Sha3_384 => Some("sha3-384"),
+        Algo::Sha3_512 => Some("sha3-512"),
+        Algo::Sha224 | Algo::Sha1 | Algo::Md5 => None
+    }
+}
+
+/**
+ * Nix's base32 alphabet omits e, o, u and t.
+ */
+const NIX_BASE32_ALPHABET: &[u8; 32] = b"0123456789abcdfghijklmnpqrsvwxyz";
+
+/**
+ * Encodes bytes the way Nix does (libutil's printHash32): 5-bit groups are taken starting from
+ * the least significant bits of the first byte, and emitted last group first. This is not
+ * RFC 4648 base32, and there's no padding.
+ */
+pub fn
+nix_base32 (bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return String::new();
+    }
+
+    let len = (bytes.len() * 8 - 1) / 5 + 1;
+    let mut out = String::with_capacity(len);
+
+    for group in (0..len).rev() {
+        let bit = group * 5;
+        let i = bit / 8;
+        let j = bit % 8;
+
+        let low = (bytes[i] as u16) >> j;
+        let high = bytes.get(i + 1).map(|b| (*b as u16) << (8 - j)).unwrap_or(0);
+        out.push(NIX_BASE32_ALPHABET[((low | high) & 0x1f) as usize] as char);
+    }
+
+    out
+}
+
+/**
+ * Renders the digest in the requested format. Returns None if the format can't represent
+ * digests of the given algorithm.
+ */
+pub fn
+format_digest (digest: &[u8], algo: Algo, format: Format) -> Option<String> {
+    match format {
+        Format::Hex => Some(hex::encode(digest)),
+        Format::Encoded(encoding) => Some(encoding.encode(digest)),
+        Format::DigestInfoDer => der::digest_info(digest, algo).map(hex::encode),
+        Format::Base64 => Some(base64::encode(digest)),
+        Format::HttpDigest => http_algorithm(algo)
+            .map(|alg| format!("{}=:{}:", alg, base64::encode(digest))),
+        Format::HttpDigestLegacy => http_algorithm(algo)
+            .map(|alg| format!("{}={}", alg.to_uppercase(), base64::encode(digest))),
+        Format::NixBase32 => Some(nix_base32(digest)),
+        // sha256, but sha3-256 keeps its hyphen
+        Format::HashUri => Some(format!("hash://{}/{}", algo.to_string().to_ascii_lowercase().replacen("sha-", "sha", 1), hex::encode(digest))),
+        Format::Ni => ni_algorithm(algo)
+            .map(|alg| format!("ni:///{};{}", alg, base64::encode_config(digest, base64::URL_SAFE_NO_PAD))),
+        Format::GetFileHashCsv => Some(hex::encode(digest).to_ascii_uppercase()),
+        Format::CertUtil => Some(digest.iter().map(|b| format!("{:02X}", b)).collect::<Vec<String>>().join(" ")),
+        // SRI only defines sha256, sha384 and sha512
+        Format::Sri => match algo {
+            Algo::Sha256 => Some(format!("sha256-{}", base64::encode(digest))),
+            Algo::Sha512 => Some(format!("sha512-{}", base64::encode(digest))),
+            _ => None
+        }
+    }
+}
+
+/**
+ * Parses a digest URI, either hash://sha256/<hex> or an RFC 6920 ni:///sha-256;<base64url> with
+ * or without an authority, returning the algorithm and the digest. Query strings and fragments
+ * are ignored. Truncated ni algorithms such as sha-256-128 and ni's sha-384 aren't supported.
+ */
+pub fn
+parse_digest_uri (uri: &str) -> Result<(Algo, Vec<u8>), String> {
+    let uri = uri.trim();
+    let end = uri.find(['?', '#']).unwrap_or(uri.len());
+
+    let (algo, digest) = if let Some(rest) = strip_prefix_ignore_case(&uri[..end], "hash://") {
+        let (algorithm, value) = rest.split_once('/').ok_or("hash URI without a digest")?;
+        let algo = match algorithm.to_ascii_lowercase().as_str() {
+            "md5" => Algo::Md5,
+            "sha1" => Algo::Sha1,
+            "sha224" => Algo::Sha224,
+            "sha256" => Algo::Sha256,
+            "sha512" => Algo::Sha512,
+            "sha3-224" => Algo::Sha3_224,
+            "sha3-256" => Algo::Sha3_256,
+            "sha3-384" => Algo::Sha3_384,
+            "sha3-512" => Algo::Sha3_512,
+            _ => return Err(format!("unsupported algorithm {}", algorithm))
+        };
+        (algo, hex::decode(value.trim_end_matches('/')).map_err(|e| e.to_string())?)
+    } else if let Some(rest) = strip_prefix_ignore_case(&uri[..end], "ni://") {
+        // The authority is optional and says nothing about the digest
+        let (_, path) = rest.split_once('/').ok_or("ni URI without a digest")?;
+        let (algorithm, value) = path.split_once(';').ok_or("ni URI without a digest")?;
+        let algo = [Algo::Sha256, Algo::Sha512, Algo::Sha3_224, Algo::Sha3_256, Algo::Sha3_384, Algo::Sha3_512]
+            .into_iter()
+            .find(|algo| ni_algorithm(*algo).is_some_and(|alg| alg.eq_ignore_ascii_case(algorithm)))
+            .ok_or_else(|| format!("unsupported algorithm {}", algorithm))?;
+        (algo, base64::decode_config(value, base64::URL_SAFE_NO_PAD).map_err(|e| e.to_string())?)
+    } else {
+        return Err("not a hash:// or ni:// URI".to_string());
+    };
+
+    if digest.len() != algo.digest_len() {
+        return Err(format!("a {} digest is {} bytes, not {}", algo, algo.digest_len(), digest.len()));
+    }
+    Ok((algo, digest))
+}
+
+fn
+strip_prefix_ignore_case<'a> (s: &'a str, prefix: &str) -> Option<&'a str> {
+    match s.get(..prefix.len()) {
+        Some(head) if head.eq_ignore_ascii_case(prefix) => Some(&s[prefix.len()..]),
+        _ => None
+    }
+}
+
+/**
+ * The full `certutil -hashfile <path> SHA256` output for a file: a header naming the algorithm
+ * and file, the digest, and certutil's completion line.
+ */
+pub fn
+certutil_report (digest: &[u8], algo: Algo, path: &str) -> String {
+    format!(
+        "{} hash of {}:\n{}\nCertUtil: -hashfile command completed successfully.",
+        algo.tag(),
+        path,
+        format_digest(digest, algo, Format::CertUtil).unwrap()
+    )
+}
+
+/**
+ * The header row of PowerShell `Get-FileHash | Export-Csv -NoTypeInformation` output.
+ */
+pub const GET_FILEHASH_CSV_HEADER: &str = "\"Algorithm\",\"Hash\",\"Path\"";
+
+/**
+ * A row of `Get-FileHash | Export-Csv` output. Export-Csv quotes every field.
+ */
+pub fn
+get_filehash_csv_row (digest: &[u8], algo: Algo, path: &str) -> String {
+    format!(
+        "\"{}\",\"{}\",\"{}\"",
+        algo.tag(),
+        format_digest(digest, algo, Format::GetFileHashCsv).unwrap(),
+        path.replace('"', "\"\"")
+    )
+}
+
+/**
+ * Checks a Content-Digest, Repr-Digest (RFC 9530) or Digest (RFC 3230) header against the given
+ * digest. The header name itself is optional. Returns None if the header has no entry for the
+ * algorithm, otherwise whether the entry matches.
+ */
+pub fn
+verify_http_digest (header: &str, digest: &[u8], algo: Algo) -> Option<bool> {
+    let alg = http_algorithm(algo)?;
+
+    // Drop a leading "Content-Digest:"-style field name, if present
+    let value = match header.split_once(':') {
+        Some((name, rest)) if !name.contains('=') => rest,
+        _ => header
+    };
+
+    for member in value.split(',') {
+        let (key, encoded) = match member.split_once('=') {
+            Some(kv) => kv,
+            None => continue
+        };
+
+        if !key.trim().eq_ignore_ascii_case(alg) {
+            continue;
+        }
+
+        // Structured field byte sequences are wrapped in colons and may carry parameters
+        let encoded = encoded.trim();
+        let encoded = match encoded.strip_prefix(':') {
+            Some(rest) => rest.split(':').next().unwrap_or(""),
+            None => encoded
+        };
+
+        return Some(matches!(base64::decode(encoded), Ok(expected) if expected == digest));
+    }
+
+    None
+}
@@ -0,0 +1,241 @@
+/*
+ * Reading .gitignore rules, so a directory scan can skip what git skips and a manifest of a
+ * source tree lists the files that are actually tracked and deployed rather than build output.
+ * The rules follow gitignore(5): blank lines and "#" comments are skipped, "!" re-includes, a
+ * trailing "/" matches directories only, a pattern with a "/" elsewhere is relative to its
+ * .gitignore and one without matches a name at any depth, and "*", "?", "[...]" and "**" are
+ * wildcards. The global core.excludesFile isn't read.
+ */
+
+use std::{fs, io, path::{Path, PathBuf}};
+
+struct Rule {
+    pattern: String,
+    // The directory of the .gitignore this came from, relative to the repository root, with a
+    // trailing "/", or "" at the root
+    base: String,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool
+}
+
+/**
+ * The rules in effect at one point in a walk. Rules added later take precedence, so the walk
+ * adds each directory's .gitignore on the way in and truncates back to the earlier len() on the
+ * way out.
+ */
+#[derive(Default)]
+pub struct Rules {
+    rules: Vec<Rule>
+}
+
+impl Rules {
+    pub fn new () -> Rules {
+        Rules::default()
+    }
+
+    pub fn len (&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty (&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn truncate (&mut self, len: usize) {
+        self.rules.truncate(len);
+    }
+
+    /**
+     * Adds the rules in the given text, which lives in `base`, a directory relative to the
+     * repository root, "" for the root itself.
+     */
+    pub fn parse (&mut self, text: &str, base: &str) {
+        let base = if base.is_empty() || base.ends_with('/') { base.to_string() } else { format!("{}/", base) };
+
+        for line in text.lines() {
+            let line = trim_trailing_spaces(line);
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line)
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line)
+            };
+            let anchored = line.contains('/');
+            let pattern = line.strip_prefix('/').unwrap_or(line);
+            if pattern.is_empty() {
+                continue;
+            }
+
+            self.rules.push(Rule { pattern: pattern.to_string(), base: base.clone(), negated, dir_only, anchored });
+        }
+    }
+
+    /**
+     * Adds the rules in the given file, if it exists.
+     */
+    pub fn add_file (&mut self, path: &Path, base: &str) -> io::Result<()> {
+        match fs::read(path) {
+            Ok(bytes) => {
+                self.parse(&String::from_utf8_lossy(&bytes), base);
+                Ok(())
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e)
+        }
+    }
+
+    /**
+     * Whether the given path, relative to the repository root with "/" separators, is ignored.
+     * The last rule to match decides.
+     */
+    pub fn is_ignored (&self, path: &str, is_dir: bool) -> bool {
+        for rule in self.rules.iter().rev() {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            let relative = match path.strip_prefix(rule.base.as_str()) {
+                Some(relative) => relative,
+                None => continue
+            };
+            let subject = if rule.anchored { relative } else { relative.rsplit('/').next().unwrap() };
+            if glob(rule.pattern.as_bytes(), subject.as_bytes()) {
+                return !rule.negated;
+            }
+        }
+        false
+    }
+}
+
+/**
+ * Finds the root of the git working tree containing `dir`, the nearest ancestor holding a .git
+ * directory or file, and returns it with the path from it to `dir`.
+ */
+pub fn
+find_repository (dir: &Path) -> io::Result<Option<(PathBuf, String)>> {
+    let dir = fs::canonicalize(dir)?;
+    for ancestor in dir.ancestors() {
+        if ancestor.join(".git").exists() {
+            let relative = dir.strip_prefix(ancestor).unwrap().to_string_lossy().replace('\\', "/");
+            return Ok(Some((ancestor.to_path_buf(), relative)));
+        }
+    }
+    Ok(None)
+}
+
+/**
+ * The rules that apply inside `dir` before its own .gitignore is read: those of the repository's
+ * .git/info/exclude and of the .gitignore in every directory from the root down to the parent of
+ * `dir`. Returns them with the path from the root to `dir`, or with no rules and "" if `dir`
+ * isn't in a repository, in which case only the .gitignore files beneath it count.
+ */
+pub fn
+rules_for (dir: &Path) -> io::Result<(Rules, String)> {
+    let mut rules = Rules::new();
+    let (root, relative) = match find_repository(dir)? {
+        Some(found) => found,
+        None => return Ok((rules, String::new()))
+    };
+
+    rules.add_file(&root.join(".git").join("info").join("exclude"), "")?;
+
+    let mut base = String::new();
+    let mut current = root;
+    for component in relative.split('/').filter(|c| !c.is_empty()) {
+        rules.add_file(&current.join(".gitignore"), &base)?;
+        current.push(component);
+        base.push_str(component);
+        base.push('/');
+    }
+
+    Ok((rules, relative))
+}
+
+/**
+ * Drops unescaped trailing spaces, which gitignore(5) says are insignificant.
+ */
+fn
+trim_trailing_spaces (line: &str) -> &str {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut end = line.len();
+    while end > 0 && line.as_bytes()[end - 1] == b' ' && !(end > 1 && line.as_bytes()[end - 2] == b'\\') {
+        end -= 1;
+    }
+    &line[..end]
+}
+
+/**
+ * Matches a gitignore pattern against a path. "*" and "?" don't match "/"; "**" does, and
+ * "**" followed by "/" matches zero or more whole directories.
+ */
+fn
+glob (pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => {
+            let rest = &pattern[2..];
+            match rest.strip_prefix(b"/") {
+                Some(rest) => glob(rest, text)
+                    || text.iter().enumerate().any(|(i, c)| *c == b'/' && glob(rest, &text[i + 1..])),
+                None => (0..=text.len()).any(|i| glob(rest, &text[i..]))
+            }
+        },
+        Some(b'*') => {
+            for i in 0..=text.len() {
+                if glob(&pattern[1..], &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && text[i] == b'/' {
+                    break;
+                }
+            }
+            false
+        },
+        Some(b'?') => matches!(text.first(), Some(c) if *c != b'/') && glob(&pattern[1..], &text[1..]),
+        Some(b'[') => match class(&pattern[1..], text.first().copied()) {
+            Some((matched, len)) => matched && glob(&pattern[1 + len..], &text[1..]),
+            None => text.first() == Some(&b'[') && glob(&pattern[1..], &text[1..])
+        },
+        Some(b'\\') if pattern.len() > 1 => text.first() == Some(&pattern[1]) && glob(&pattern[2..], &text[1..]),
+        Some(c) => text.first() == Some(c) && glob(&pattern[1..], &text[1..])
+    }
+}
+
+/**
+ * Matches a character class, the pattern just after its "[", against one character. Returns
+ * whether it matched and the length of the class through its "]", or None if the "[" is never
+ * closed and so is literal.
+ */
+fn
+class (pattern: &[u8], c: Option<u8>) -> Option<(bool, usize)> {
+    let (negated, start) = match pattern.first() {
+        Some(b'!') | Some(b'^') => (true, 1),
+        _ => (false, 0)
+    };
+
+    let mut matched = false;
+    let mut i = start;
+    loop {
+        let first = *pattern.get(i)?;
+        // A "]" first in the class is a member, not the end
+        if first == b']' && i > start {
+            break;
+        }
+        if pattern.get(i + 1) == Some(&b'-') && pattern.get(i + 2).is_some_and(|last| *last != b']') {
+            let last = pattern[i + 2];
+            matched |= c.is_some_and(|c| first <= c && c <= last);
+            i += 3;
+        } else {
+            matched |= c == Some(first);
+            i += 1;
+        }
+    }
+
+    Some((c.is_some_and(|c| c != b'/') && matched != negated, i + 1))
+}
@@ -0,0 +1,130 @@
+/*
+ * Git object IDs. Git names every object by the digest of a short header, "<type> <length>\0",
+ * followed by the object's contents, so a file's blob ID can be computed without git and checked
+ * against the IDs `git ls-tree` lists for a commit. That shows whether a checkout, an export or a
+ * release tarball still holds what the commit recorded.
+ *
+ * Repositories use SHA-1 unless they were created with --object-format=sha256. A git SHA-1 ID
+ * is computed with plain SHA-1, not git's collision-detecting variant, so it can't tell a crafted
+ * colliding file from the real one.
+ */
+
+use std::io::{self, Read};
+
+use crate::{encoding::hex, Algo, Digest, Hasher};
+
+/**
+ * The ID of a blob of `len` bytes read from `reader`. Fails if the reader doesn't hold exactly
+ * `len` bytes, since the header would then name the wrong length.
+ */
+pub fn
+blob_id<R: Read> (reader: R, len: u64, algo: Algo) -> io::Result<Digest> {
+    let mut hasher = Hasher::new(algo);
+    hasher.update(format!("blob {}\0", len).as_bytes());
+    if hasher.update_reader(reader.take(len + 1))? != len {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "the file changed while it was being read"));
+    }
+    Ok(hasher.finalize_digest())
+}
+
+/**
+ * The algorithm whose IDs are `len` hex digits long: 40 for SHA-1, 64 for SHA-256.
+ */
+pub fn
+object_format (len: usize) -> Option<Algo> {
+    match len {
+        40 => Some(Algo::Sha1),
+        64 => Some(Algo::Sha256),
+        _ => None
+    }
+}
+
+/**
+ * One line of `git ls-tree` output.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct TreeEntry {
+    // e.g. "100644" for a file, "100755" for an executable and "120000" for a symbolic link
+    pub mode: String,
+    // "blob", "tree" or "commit" (a submodule)
+    pub kind: String,
+    pub id: Vec<u8>,
+    pub path: String
+}
+
+impl TreeEntry {
+    pub fn is_symlink (&self) -> bool {
+        self.mode == "120000"
+    }
+}
+
+/**
+ * Undoes the C-style quoting git applies to paths holding control characters, quotes,
+ * backslashes or, unless core.quotePath is off, bytes outside ASCII. Octal escapes are UTF-8
+ * bytes.
+ */
+fn
+unquote (path: &str) -> Option<String> {
+    let inner = match path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) {
+        Some(inner) => inner.as_bytes(),
+        None => return Some(path.to_string())
+    };
+
+    let mut bytes = Vec::new();
+    let mut i = 0;
+    while i < inner.len() {
+        if inner[i] != b'\\' {
+            bytes.push(inner[i]);
+            i += 1;
+            continue;
+        }
+        let escaped = *inner.get(i + 1)?;
+        let (byte, used) = match escaped {
+            b'0'..=b'7' => (u8::from_str_radix(std::str::from_utf8(inner.get(i + 1..i + 4)?).ok()?, 8).ok()?, 4),
+            b'a' => (0x07, 2),
+            b'b' => (0x08, 2),
+            b't' => (b'\t', 2),
+            b'n' => (b'\n', 2),
+            b'v' => (0x0b, 2),
+            b'f' => (0x0c, 2),
+            b'r' => (b'\r', 2),
+            b'"' | b'\\' => (escaped, 2),
+            _ => return None
+        };
+        bytes.push(byte);
+        i += used;
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+/**
+ * Parses `git ls-tree` output, with or without --long (-l), -r or -z. Returns the entries, or
+ * the 1-based number of the first line that isn't an entry.
+ */
+pub fn
+parse_ls_tree (text: &str) -> Result<Vec<TreeEntry>, usize> {
+    let nul = text.contains('\0');
+    let records = text.split(if nul { '\0' } else { '\n' });
+    let mut entries = Vec::new();
+
+    for (index, record) in records.enumerate() {
+        if record.trim().is_empty() {
+            continue;
+        }
+        let entry = (|| {
+            let (fields, path) = record.split_once('\t')?;
+            let mut fields = fields.split_whitespace();
+            let (mode, kind, id) = (fields.next()?, fields.next()?, fields.next()?);
+            Some(TreeEntry {
+                mode: mode.to_string(),
+                kind: kind.to_string(),
+                id: hex::decode(id).ok().filter(|id| object_format(id.len() * 2).is_some())?,
+                path: if nul { path.to_string() } else { unquote(path)? }
+            })
+        })();
+        entries.push(entry.ok_or(index + 1)?);
+    }
+
+    Ok(entries)
+}
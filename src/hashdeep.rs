@@ -0,0 +1,231 @@
+/*
+ * Reading and writing hashdeep's file format, and its audit semantics.
+ *
+ * A hashdeep file is a two-line header naming the columns, optional "##" comment lines, and then
+ * one comma-separated line per file:
+ *
+ *   %%%% HASHDEEP-1.0
+ *   %%%% size,sha1,sha256,filename
+ *   ## Invoked from: /home/user
+ *   ## $ hashdeep -c sha1,sha256 -r dir
+ *   ##
+ *   5,03de6c570bfe24bfc328ccd7ca46b76eadaf4334,36bbe50e...,dir/a.txt
+ */
+
+use std::{collections::{HashMap, HashSet}, fs, io, io::Read};
+
+use crate::{encoding::hex, sha1::Sha1, Algo, Hasher};
+
+/**
+ * Hash columns this tool can compute, by their hashdeep names.
+ */
+pub const SUPPORTED: [&str; 2] = ["sha1", "sha256"];
+
+/**
+ * A file's size and digests, keyed by hashdeep column name, as listed in or computed for a
+ * hashdeep file.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Entry {
+    pub size: u64,
+    pub hashes: Vec<(String, String)>,
+    pub path: String
+}
+
+impl Entry {
+    fn hash (&self, name: &str) -> Option<&str> {
+        self.hashes.iter().find(|(n, _)| n == name).map(|(_, h)| h.as_str())
+    }
+
+    /**
+     * Whether the two entries describe the same content: equal sizes, at least one hash column
+     * in common, and agreement on every column they share.
+     */
+    pub fn same_content (&self, other: &Entry) -> bool {
+        if self.size != other.size {
+            return false;
+        }
+
+        let mut compared = false;
+        for (name, hash) in &self.hashes {
+            if let Some(other_hash) = other.hash(name) {
+                if !hash.eq_ignore_ascii_case(other_hash) {
+                    return false;
+                }
+                compared = true;
+            }
+        }
+
+        compared
+    }
+}
+
+/**
+ * Builds the file header for the given hash columns.
+ */
+pub fn
+header (columns: &[&str], invoked_from: &str, command: &str) -> String {
+    format!(
+        "%%%% HASHDEEP-1.0\n%%%% size,{},filename\n## Invoked from: {}\n## $ {}\n##\n",
+        columns.join(","),
+        invoked_from,
+        command
+    )
+}
+
+pub fn
+format_entry (entry: &Entry) -> String {
+    let hashes: Vec<&str> = entry.hashes.iter().map(|(_, h)| h.as_str()).collect();
+    format!("{},{},{}", entry.size, hashes.join(","), entry.path)
+}
+
+/**
+ * Parses hashdeep file contents. Columns this tool can't compute are kept, but are ignored when
+ * comparing entries.
+ */
+pub fn
+parse (contents: &str) -> Result<Vec<Entry>, String> {
+    let mut lines = contents.lines();
+
+    if lines.next().map(|l| l.trim()) != Some("%%%% HASHDEEP-1.0") {
+        return Err("missing HASHDEEP-1.0 header".to_string());
+    }
+
+    let columns: Vec<String> = lines.next()
+        .and_then(|l| l.trim().strip_prefix("%%%% "))
+        .ok_or("missing column header")?
+        .split(',')
+        .map(|c| c.trim().to_ascii_lowercase())
+        .collect();
+
+    if columns.first().map(|c| c.as_str()) != Some("size") || columns.last().map(|c| c.as_str()) != Some("filename") {
+        return Err("column header must start with size and end with filename".to_string());
+    }
+
+    let hash_columns = &columns[1..columns.len() - 1];
+    let mut entries = Vec::new();
+
+    for (line_number, line) in lines.enumerate() {
+        if line.starts_with("##") || line.trim().is_empty() {
+            continue;
+        }
+
+        // The filename is the last column and may itself contain commas
+        let fields: Vec<&str> = line.splitn(columns.len(), ',').collect();
+        if fields.len() != columns.len() {
+            return Err(format!("line {}: expected {} columns", line_number + 3, columns.len()));
+        }
+
+        let size = fields[0].parse::<u64>().map_err(|_| format!("line {}: invalid size", line_number + 3))?;
+        let hashes = hash_columns.iter()
+            .zip(fields[1..fields.len() - 1].iter())
+            .map(|(name, hash)| (name.clone(), hash.to_ascii_lowercase()))
+            .collect();
+
+        entries.push(Entry { size, hashes, path: fields[fields.len() - 1].to_string() });
+    }
+
+    Ok(entries)
+}
+
+/**
+ * Hashes a file with each of the requested columns in a single pass over its contents.
+ */
+pub fn
+hash_file (path: &str, columns: &[&str]) -> io::Result<Entry> {
+    let mut file = fs::File::open(path)?;
+    let mut sha1 = Sha1::new();
+    let mut sha256 = Hasher::new(Algo::Sha256);
+    let mut size: u64 = 0;
+    let mut buffer = vec![0u8; 1 << 16];
+
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        };
+
+        if columns.contains(&"sha1") {
+            sha1.update(&buffer[..read]);
+        }
+        if columns.contains(&"sha256") {
+            sha256.update(&buffer[..read]);
+        }
+        size += read as u64;
+    }
+
+    let hashes = columns.iter().map(|c| {
+        let digest = match *c {
+            "sha1" => hex::encode(sha1.clone().finalize()),
+            _ => hex::encode(sha256.clone().finalize())
+        };
+        (c.to_string(), digest)
+    }).collect();
+
+    Ok(Entry { size, hashes, path: path.to_string() })
+}
+
+/**
+ * The audit classification of a scanned or known file, following hashdeep -a.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    // Content and path both match a known entry
+    Matched,
+    // Content matches a known entry recorded under a different path
+    Moved,
+    // Content matches no known entry
+    New,
+    // A known entry which no scanned file matched
+    Missing
+}
+
+pub struct AuditResult<'a> {
+    pub outcome: Outcome,
+    pub entry: &'a Entry,
+    pub known: Option<&'a Entry>
+}
+
+/**
+ * Classifies each scanned file against the known entries, then reports known entries which no
+ * scanned file matched as missing.
+ */
+pub fn
+audit<'a> (known: &'a [Entry], scanned: &'a [Entry]) -> Vec<AuditResult<'a>> {
+    let mut results = Vec::new();
+    let mut used: HashSet<usize> = HashSet::new();
+
+    // Matching content requires equal sizes, so only entries of the same size are compared
+    let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, k) in known.iter().enumerate() {
+        by_size.entry(k.size).or_default().push(i);
+    }
+
+    for entry in scanned {
+        let candidates = by_size.get(&entry.size).map(|c| c.as_slice()).unwrap_or(&[]);
+        let exact = candidates.iter().copied().find(|i| known[*i].path == entry.path && known[*i].same_content(entry));
+        let moved = candidates.iter().copied().find(|i| known[*i].same_content(entry));
+
+        let (outcome, indx) = match (exact, moved) {
+            (Some(i), _) => (Outcome::Matched, Some(i)),
+            (None, Some(i)) => (Outcome::Moved, Some(i)),
+            (None, None) => (Outcome::New, None)
+        };
+
+        if let Some(i) = indx {
+            used.insert(i);
+        }
+
+        results.push(AuditResult { outcome, entry, known: indx.map(|i| &known[i]) });
+    }
+
+    for (i, entry) in known.iter().enumerate() {
+        if !used.contains(&i) {
+            results.push(AuditResult { outcome: Outcome::Missing, entry, known: Some(entry) });
+        }
+    }
+
+    results
+}
@@ -0,0 +1,123 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+use crate::{Algo, Hasher};
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+/**
+ * From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.198-1.pdf # 4
+ *
+ * HMAC(K, text) = H((K0 ⊕ opad) || H((K0 ⊕ ipad) || text))
+ *
+ * Keys longer than the block size are first hashed; shorter keys are padded with zeros to
+ * the block size to form K0. The inner hash is computed incrementally as data arrives, so
+ * arbitrarily large streams can be authenticated without buffering them.
+ */
+#[derive(Clone)]
+pub struct Hmac {
+    inner: Hasher,
+    outer_key: Vec<u8>,
+    algo: Algo
+}
+
+impl Hmac {
+    pub fn new (algo: Algo, key: &[u8]) -> Hmac {
+        // The key is padded to the hash's block size, 64 bytes for SHA-224 and SHA-256 and 128
+        // for SHA-512
+        let block_len = algo.block_len();
+        let mut k0 = if key.len() > block_len {
+            let mut hasher = Hasher::new(algo);
+            hasher.update(key);
+            hasher.finalize()
+        } else {
+            key.to_vec()
+        };
+        k0.resize(block_len, 0x0);
+
+        #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+        let mut inner_key: Vec<u8> = k0.iter().map(|b| b ^ IPAD).collect();
+        let mut inner = Hasher::new(algo);
+        inner.update(&inner_key);
+        let outer_key = k0.iter().map(|b| b ^ OPAD).collect();
+
+        #[cfg(feature = "zeroize")]
+        {
+            crate::wipe::wipe_vec(&mut k0);
+            crate::wipe::wipe_vec(&mut inner_key);
+        }
+
+        Hmac {
+            inner,
+            outer_key,
+            algo
+        }
+    }
+
+    pub fn update (&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    /**
+     * Feeds everything from the given reader into the MAC, returning the number of bytes read.
+     */
+    #[cfg(feature = "std")]
+    pub fn update_reader<R: Read> (&mut self, reader: R) -> io::Result<u64> {
+        self.inner.update_reader(reader)
+    }
+
+    pub fn finalize (mut self) -> Vec<u8> {
+        // Through a reference, since Hmac may implement Drop
+        let inner_digest = self.inner.finalize_reset();
+
+        let mut outer = Hasher::new(self.algo);
+        outer.update(&self.outer_key);
+        outer.update(&inner_digest);
+        outer.finalize()
+    }
+
+    /**
+     * Compares the computed MAC against an expected tag in constant time with respect to the
+     * contents of the tag.
+     */
+    pub fn verify (self, tag: &[u8]) -> bool {
+        let mac = self.finalize();
+
+        if mac.len() != tag.len() {
+            return false;
+        }
+
+        mac.iter().zip(tag.iter()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Hmac {
+    fn drop (&mut self) {
+        crate::wipe::wipe_vec(&mut self.outer_key);
+    }
+}
+
+#[cfg(feature = "std")]
+impl Write for Hmac {
+    fn write (&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush (&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/**
+ * One-shot HMAC over an in-memory message.
+ */
+pub fn
+hmac (key: &[u8], message: &[u8], algo: Algo) -> Vec<u8> {
+    let mut mac = Hmac::new(algo, key);
+    mac.update(message);
+    mac.finalize()
+}
@@ -0,0 +1,25 @@
+use crate::sha1::sha1;
+
+/**
+ * Builds an Apache htpasswd entry using the legacy {SHA} scheme, user:{SHA}base64(SHA-1(password)).
+ * The scheme is unsalted and relies on SHA-1; it exists for interoperability only.
+ */
+pub fn
+sha_entry (user: &str, password: &str) -> String {
+    format!("{}:{{SHA}}{}", user, base64::encode(sha1(password.as_bytes())))
+}
+
+/**
+ * Checks a password against the given user's {SHA} entry in htpasswd file contents. Returns None
+ * if the user has no entry or the entry uses a different scheme.
+ */
+pub fn
+verify (contents: &str, user: &str, password: &str) -> Option<bool> {
+    let hash = contents.lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(name, _)| *name == user)
+        .map(|(_, hash)| hash)?;
+
+    let encoded = hash.strip_prefix("{SHA}")?;
+    Some(base64::decode(encoded).ok()? == sha1(password.as_bytes()))
+}
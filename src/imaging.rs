@@ -0,0 +1,243 @@
+/*
+ * Acquisition logs for forensic disk images. Imaging tools record the MD5, SHA-1 and usually
+ * SHA-256 of the acquired data, and many also hash it in fixed-size chunks so that damage to a
+ * copy can later be narrowed down: ewfacquire ends its log with "MD5 hash calculated over data:"
+ * lines, FTK Imager with "MD5 checksum:", and dcfldd's hashwindow writes "start - end: digest"
+ * per chunk followed by "Total (md5): digest".
+ *
+ * write_log() produces a log in those conventions, and parse_log() reads the digests back out
+ * of this tool's logs and those of the tools above, so an image can be checked against the log
+ * its examiner kept. A digest's length identifies its algorithm. MD5 and SHA-1 are included
+ * only because examiners' logs record them; neither resists deliberate tampering.
+ *
+ * The same three digests are what malware reputation services such as VirusTotal index samples
+ * by, so hash_reader() computes them for any file.
+ */
+
+use std::{io::{self, Read}, ops::Range};
+
+use crate::{encoding::hex, md5::Md5, sha1::Sha1, Algo, Digest, Hasher};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Algorithm {
+    Md5,
+    Sha1,
+    Sha256
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 3] = [Algorithm::Md5, Algorithm::Sha1, Algorithm::Sha256];
+
+    /**
+     * The name imaging logs use, e.g. "SHA1".
+     */
+    pub fn name (&self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Sha1 => "SHA1",
+            Algorithm::Sha256 => "SHA256"
+        }
+    }
+
+    pub fn from_digest_len (len: usize) -> Option<Algorithm> {
+        match len {
+            16 => Some(Algorithm::Md5),
+            20 => Some(Algorithm::Sha1),
+            32 => Some(Algorithm::Sha256),
+            _ => None
+        }
+    }
+}
+
+/**
+ * The three digests of one stretch of the image.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Hashes {
+    pub md5: [u8; 16],
+    pub sha1: [u8; 20],
+    pub sha256: Digest
+}
+
+impl Hashes {
+    pub fn get (&self, algorithm: Algorithm) -> &[u8] {
+        match algorithm {
+            Algorithm::Md5 => &self.md5,
+            Algorithm::Sha1 => &self.sha1,
+            Algorithm::Sha256 => self.sha256.as_bytes()
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Hashers {
+    md5: Md5,
+    sha1: Sha1,
+    sha256: Hasher
+}
+
+impl Hashers {
+    fn new () -> Hashers {
+        Hashers { md5: Md5::new(), sha1: Sha1::new(), sha256: Hasher::new(Algo::Sha256) }
+    }
+
+    fn update (&mut self, data: &[u8]) {
+        self.md5.update(data);
+        self.sha1.update(data);
+        self.sha256.update(data);
+    }
+
+    fn finish (self) -> Hashes {
+        Hashes { md5: self.md5.finalize(), sha1: self.sha1.finalize(), sha256: self.sha256.finalize_digest() }
+    }
+}
+
+/**
+ * Splits an image of `image_len` bytes into chunks of `chunk_size`, the last of which may be
+ * shorter.
+ */
+pub fn
+chunks (image_len: u64, chunk_size: u64) -> Vec<Range<u64>> {
+    (0..image_len.div_ceil(chunk_size)).map(|i| i * chunk_size..((i + 1) * chunk_size).min(image_len)).collect()
+}
+
+/**
+ * Hashes the whole image and each of the given byte ranges of it in a single read, returning
+ * the digests of each range, None for one that runs past the end of the image, then those of
+ * the whole image and its length.
+ */
+pub fn
+hash_ranges<R: Read> (mut reader: R, ranges: &[Range<u64>]) -> io::Result<(Vec<Option<Hashes>>, Hashes, u64)> {
+    let mut whole = Hashers::new();
+    let mut hashers: Vec<Hashers> = ranges.iter().map(|_| Hashers::new()).collect();
+    let mut position: u64 = 0;
+    let mut buffer = vec![0u8; 1 << 16];
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        };
+
+        whole.update(&buffer[..read]);
+
+        let end = position + read as u64;
+        for (range, hasher) in ranges.iter().zip(hashers.iter_mut()) {
+            let start = range.start.max(position);
+            let stop = range.end.min(end);
+            if start < stop {
+                hasher.update(&buffer[(start - position) as usize..(stop - position) as usize]);
+            }
+        }
+        position = end;
+    }
+
+    let hashes = ranges.iter().zip(hashers).map(|(range, hasher)| (range.end <= position).then(|| hasher.finish())).collect();
+    Ok((hashes, whole.finish(), position))
+}
+
+/**
+ * The MD5, SHA-1 and SHA-256 of everything `reader` holds, from a single read, and its length.
+ */
+pub fn
+hash_reader<R: Read> (reader: R) -> io::Result<(Hashes, u64)> {
+    let (_, whole, len) = hash_ranges(reader, &[])?;
+    Ok((whole, len))
+}
+
+/**
+ * Writes an acquisition log: a header, the per-chunk digests in dcfldd's hashwindow form, one
+ * section per algorithm, and the totals as ewfacquire writes them.
+ */
+pub fn
+write_log (image: &str, image_len: u64, chunk_size: u64, chunks: &[(Range<u64>, Hashes)], total: &Hashes) -> String {
+    let mut log = format!(
+        "Image: {}\nSize: {} bytes ({} sectors of 512 bytes)\nChunk size: {} bytes\n",
+        image,
+        image_len,
+        image_len.div_ceil(512),
+        chunk_size
+    );
+
+    for algorithm in Algorithm::ALL {
+        log.push('\n');
+        for (range, hashes) in chunks {
+            log.push_str(&format!("{} - {}: {}\n", range.start, range.end, hex::encode(hashes.get(algorithm))));
+        }
+        log.push_str(&format!("Total ({}): {}\n", algorithm.name().to_lowercase(), hex::encode(total.get(algorithm))));
+    }
+
+    log.push('\n');
+    for algorithm in Algorithm::ALL {
+        // Padded as ewfacquire pads them, so the digests line up
+        let tabs = if algorithm == Algorithm::Sha256 { "\t" } else { "\t\t" };
+        log.push_str(&format!("{} hash calculated over data:{}{}\n", algorithm.name(), tabs, hex::encode(total.get(algorithm))));
+    }
+    log
+}
+
+/**
+ * A digest found in a log, for the whole image or, with a range, for one chunk of it.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Expected {
+    pub algorithm: Algorithm,
+    pub range: Option<Range<u64>>,
+    pub digest: Vec<u8>,
+    // 1-based, for messages
+    pub line: usize
+}
+
+/**
+ * The first whitespace-separated token after `text`'s first ':' that decodes as a digest of a
+ * known length.
+ */
+fn
+digest_after_colon (text: &str) -> Option<(Algorithm, Vec<u8>)> {
+    let (_, value) = text.split_once(':')?;
+    let token = value.split_whitespace().next()?;
+    let digest = hex::decode(token).ok()?;
+    Some((Algorithm::from_digest_len(digest.len())?, digest))
+}
+
+/**
+ * A dcfldd hashwindow line, "start - end: digest", with byte offsets and the end exclusive.
+ */
+fn
+chunk_line (line: &str) -> Option<Expected> {
+    let (range, _) = line.split_once(':')?;
+    let (start, end) = range.split_once('-')?;
+    let range = start.trim().parse::<u64>().ok()?..end.trim().parse::<u64>().ok()?;
+    let (algorithm, digest) = digest_after_colon(line)?;
+    (range.start < range.end).then_some(Expected { algorithm, range: Some(range), digest, line: 0 })
+}
+
+/**
+ * Reads every digest out of an acquisition log. Lines of the form "start - end: digest" are
+ * chunk digests; any other line naming MD5, SHA1 or SHA256 (with or without a hyphen) and
+ * followed by a colon and a digest is a digest of the whole image. Everything else is skipped,
+ * so the rest of an examiner's notes don't get in the way.
+ */
+pub fn
+parse_log (text: &str) -> Vec<Expected> {
+    let mut expected = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        if let Some(chunk) = chunk_line(line) {
+            expected.push(Expected { line: index + 1, ..chunk });
+            continue;
+        }
+
+        let lower = line.to_ascii_lowercase().replace("sha-", "sha");
+        if !["md5", "sha1", "sha256"].iter().any(|name| lower.contains(name)) {
+            continue;
+        }
+        if let Some((algorithm, digest)) = digest_after_colon(line) {
+            expected.push(Expected { algorithm, range: None, digest, line: index + 1 });
+        }
+    }
+
+    expected
+}
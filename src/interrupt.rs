@@ -0,0 +1,88 @@
+/*
+ * SIGINT/SIGTERM handling for long-running hashes. The handler only records which signal arrived;
+ * hashing loops poll received() between reads so they can stop on a block boundary and save
+ * their state before exiting.
+ */
+
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+static RECEIVED: AtomicI32 = AtomicI32::new(0);
+static SNAPSHOT_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+mod sys {
+    pub const SIGINT: i32 = 2;
+    pub const SIGTERM: i32 = 15;
+
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+    pub const SIGUSR1: i32 = 30;
+    #[cfg(not(any(target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd")))]
+    pub const SIGUSR1: i32 = 10;
+
+    extern "C" {
+        pub fn signal (signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn
+handle (signum: i32) {
+    RECEIVED.store(signum, Ordering::SeqCst);
+}
+
+#[cfg(unix)]
+extern "C" fn
+handle_snapshot (_signum: i32) {
+    SNAPSHOT_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/**
+ * Installs handlers for SIGINT and SIGTERM. On platforms without POSIX signals this is a no-op
+ * and interrupts terminate the process as usual.
+ */
+pub fn
+install () {
+    #[cfg(unix)]
+    unsafe {
+        sys::signal(sys::SIGINT, handle);
+        sys::signal(sys::SIGTERM, handle);
+    }
+}
+
+/**
+ * Returns the number of the signal received since install(), if any.
+ */
+pub fn
+received () -> Option<i32> {
+    match RECEIVED.load(Ordering::SeqCst) {
+        0 => None,
+        signum => Some(signum)
+    }
+}
+
+/**
+ * The conventional shell exit status for a process terminated by the given signal.
+ */
+pub fn
+exit_code (signum: i32) -> i32 {
+    128 + signum
+}
+
+/**
+ * Installs a SIGUSR1 handler used to request an on-demand snapshot from long-running loops.
+ */
+pub fn
+install_snapshot () {
+    #[cfg(unix)]
+    unsafe {
+        sys::signal(sys::SIGUSR1, handle_snapshot);
+    }
+}
+
+/**
+ * Returns whether a snapshot was requested since the last call, clearing the request.
+ */
+pub fn
+snapshot_requested () -> bool {
+    SNAPSHOT_REQUESTED.swap(false, Ordering::SeqCst)
+}
@@ -0,0 +1,207 @@
+/*
+ * Read-strategy autotuning. Which buffer size, or whether memory-mapping, gives the best hashing
+ * throughput depends on the filesystem, device and page cache, so rather than guess, a large job
+ * can briefly probe the candidates on its own input and keep the fastest.
+ */
+
+use std::{fs, io::{self, Read, Seek, SeekFrom}, time::{Duration, Instant}};
+
+use crate::{Algo, Hasher};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Strategy {
+    Read(usize),
+    Mmap
+}
+
+impl Strategy {
+    pub fn name (&self) -> String {
+        match self {
+            Strategy::Read(size) if size % (1 << 20) == 0 => format!("read {}MiB", size >> 20),
+            Strategy::Read(size) => format!("read {}KiB", size >> 10),
+            Strategy::Mmap => "mmap".to_string()
+        }
+    }
+}
+
+/**
+ * The read buffer size used when no tuning is done.
+ */
+pub const DEFAULT: Strategy = Strategy::Read(1 << 16);
+
+/**
+ * Files smaller than this aren't worth probing; the probes would be a large share of the work.
+ */
+pub const MIN_TUNE_SIZE: u64 = 256 << 20;
+
+/**
+ * Bytes hashed by each probe.
+ */
+pub const PROBE_LEN: u64 = 8 << 20;
+
+pub fn
+candidates () -> Vec<Strategy> {
+    let mut strategies = vec![
+        Strategy::Read(16 << 10),
+        Strategy::Read(64 << 10),
+        Strategy::Read(256 << 10),
+        Strategy::Read(1 << 20),
+        Strategy::Read(4 << 20)
+    ];
+    if mmap_supported() {
+        strategies.push(Strategy::Mmap);
+    }
+    strategies
+}
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+mod sys {
+    pub const PROT_READ: i32 = 1;
+    pub const MAP_PRIVATE: i32 = 2;
+
+    extern "C" {
+        pub fn mmap (addr: *mut u8, len: usize, prot: i32, flags: i32, fd: i32, offset: i64) -> *mut u8;
+        pub fn munmap (addr: *mut u8, len: usize) -> i32;
+    }
+}
+
+pub fn
+mmap_supported () -> bool {
+    cfg!(all(unix, target_pointer_width = "64"))
+}
+
+/**
+ * A read-only, private mapping of a whole file.
+ */
+#[cfg(all(unix, target_pointer_width = "64"))]
+struct Mapping {
+    ptr: *mut u8,
+    len: usize
+}
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+impl Mapping {
+    fn new (file: &fs::File) -> io::Result<Mapping> {
+        use std::os::unix::io::AsRawFd;
+
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            return Ok(Mapping { ptr: std::ptr::null_mut(), len: 0 });
+        }
+
+        let ptr = unsafe { sys::mmap(std::ptr::null_mut(), len, sys::PROT_READ, sys::MAP_PRIVATE, file.as_raw_fd(), 0) };
+        if ptr as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Mapping { ptr, len })
+    }
+
+    fn bytes (&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // The mapping is valid for len bytes until dropped. The file could be truncated
+        // underneath it, which would fault, the usual caveat for hashing mapped files.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+impl Drop for Mapping {
+    fn drop (&mut self) {
+        if self.len > 0 {
+            unsafe { sys::munmap(self.ptr, self.len); }
+        }
+    }
+}
+
+/**
+ * Feeds up to `limit` bytes of the file, starting at `offset`, to the hasher using the given
+ * strategy. Returns the number of bytes hashed.
+ */
+pub fn
+hash_range (file: &mut fs::File, strategy: Strategy, offset: u64, limit: u64, hasher: &mut Hasher) -> io::Result<u64> {
+    match strategy {
+        Strategy::Read(size) => {
+            file.seek(SeekFrom::Start(offset))?;
+            let mut buffer = vec![0u8; size];
+            let mut total = 0u64;
+
+            while total < limit {
+                let want = (limit - total).min(size as u64) as usize;
+                let read = match file.read(&mut buffer[..want]) {
+                    Ok(0) => break,
+                    Ok(read) => read,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e)
+                };
+                hasher.update(&buffer[..read]);
+                total += read as u64;
+            }
+
+            Ok(total)
+        },
+        #[cfg(all(unix, target_pointer_width = "64"))]
+        Strategy::Mmap => {
+            let mapping = Mapping::new(file)?;
+            let bytes = mapping.bytes();
+            let start = (offset as usize).min(bytes.len());
+            let end = start.saturating_add(limit.min(usize::MAX as u64) as usize).min(bytes.len());
+
+            // Hash in chunks so the kernel's readahead sees a sequential pattern
+            for chunk in bytes[start..end].chunks(1 << 20) {
+                hasher.update(chunk);
+            }
+            Ok((end - start) as u64)
+        },
+        #[cfg(not(all(unix, target_pointer_width = "64")))]
+        Strategy::Mmap => hash_range(file, DEFAULT, offset, limit, hasher)
+    }
+}
+
+pub struct ProbeResult {
+    pub strategy: Strategy,
+    pub bytes: u64,
+    pub elapsed: Duration
+}
+
+impl ProbeResult {
+    pub fn throughput (&self) -> f64 {
+        self.bytes as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+}
+
+/**
+ * Hashes `probe_len` bytes with each candidate strategy. Each probe reads a different region of
+ * the file, so that a later probe doesn't get an unfair advantage from pages an earlier one
+ * brought into the cache.
+ */
+pub fn
+probe (path: &str, strategies: &[Strategy], probe_len: u64, algo: Algo) -> io::Result<Vec<ProbeResult>> {
+    let mut file = fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut results = Vec::new();
+
+    for (i, strategy) in strategies.iter().enumerate() {
+        let offset = if len > probe_len { (i as u64 * probe_len) % (len - probe_len) } else { 0 };
+        let mut hasher = Hasher::new(algo);
+
+        let start = Instant::now();
+        let bytes = hash_range(&mut file, *strategy, offset, probe_len, &mut hasher)?;
+        results.push(ProbeResult { strategy: *strategy, bytes, elapsed: start.elapsed() });
+    }
+
+    Ok(results)
+}
+
+/**
+ * The strategy with the best probed throughput.
+ */
+pub fn
+fastest (results: &[ProbeResult]) -> Strategy {
+    results.iter()
+        .max_by(|a, b| a.throughput().total_cmp(&b.throughput()))
+        .map(|r| r.strategy)
+        .unwrap_or(DEFAULT)
+}
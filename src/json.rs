@@ -0,0 +1,354 @@
+/*
+ * A small JSON reader, per RFC 8259, for the handful of JSON documents this crate consumes.
+ * Numbers are kept as their source text so that large integers such as blob sizes aren't
+ * rounded through f64.
+ */
+
+use alloc::{format, string::{String, ToString}, vec::Vec};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    // Members in document order
+    Object(Vec<(String, Value)>)
+}
+
+impl Value {
+    /**
+     * Looks up a member of an object; None for a missing member or if this isn't an object.
+     */
+    pub fn get (&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None
+        }
+    }
+
+    pub fn as_str (&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None
+        }
+    }
+
+    pub fn as_u64 (&self) -> Option<u64> {
+        match self {
+            Value::Number(n) => n.parse().ok(),
+            _ => None
+        }
+    }
+
+    pub fn as_array (&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None
+        }
+    }
+}
+
+/**
+ * Parses a complete JSON document.
+ */
+pub fn
+parse (text: &str) -> Result<Value, String> {
+    let mut parser = Parser { bytes: text.as_bytes(), pos: 0 };
+    let value = parser.value()?;
+    parser.whitespace();
+
+    if parser.pos != parser.bytes.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize
+}
+
+impl Parser<'_> {
+    fn error (&self, what: &str) -> String {
+        format!("{} at byte {}", what, self.pos)
+    }
+
+    fn whitespace (&mut self) {
+        while matches!(self.bytes.get(self.pos), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect (&mut self, literal: &str) -> Result<(), String> {
+        if self.bytes[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", literal)))
+        }
+    }
+
+    fn value (&mut self) -> Result<Value, String> {
+        self.whitespace();
+
+        match self.bytes.get(self.pos) {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b't') => self.expect("true").map(|_| Value::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| Value::Bool(false)),
+            Some(b'n') => self.expect("null").map(|_| Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input"))
+        }
+    }
+
+    fn object (&mut self) -> Result<Value, String> {
+        let mut members: Vec<(String, Value)> = Vec::new();
+        self.pos += 1;
+        self.whitespace();
+
+        if self.bytes.get(self.pos) == Some(&b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(members));
+        }
+
+        loop {
+            self.whitespace();
+            if self.bytes.get(self.pos) != Some(&b'"') {
+                return Err(self.error("expected member name"));
+            }
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(":")?;
+            members.push((key, self.value()?));
+            self.whitespace();
+
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(members));
+                },
+                _ => return Err(self.error("expected ',' or '}'"))
+            }
+        }
+    }
+
+    fn array (&mut self) -> Result<Value, String> {
+        let mut items: Vec<Value> = Vec::new();
+        self.pos += 1;
+        self.whitespace();
+
+        if self.bytes.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+            self.whitespace();
+
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                },
+                _ => return Err(self.error("expected ',' or ']'"))
+            }
+        }
+    }
+
+    fn number (&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while matches!(self.bytes.get(self.pos), Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+            self.pos += 1;
+        }
+
+        let text = core::str::from_utf8(&self.bytes[start..self.pos]).unwrap();
+        if text.parse::<f64>().is_err() {
+            return Err(self.error("invalid number"));
+        }
+        Ok(Value::Number(text.to_string()))
+    }
+
+    fn hex4 (&mut self) -> Result<u32, String> {
+        let digits = self.bytes.get(self.pos..self.pos + 4).ok_or_else(|| self.error("truncated escape"))?;
+        let code = core::str::from_utf8(digits).ok()
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| self.error("invalid \\u escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn string (&mut self) -> Result<String, String> {
+        let mut out: Vec<u8> = Vec::new();
+        self.pos += 1;
+
+        loop {
+            let Some(&b) = self.bytes.get(self.pos) else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&escape) = self.bytes.get(self.pos) else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.pos += 1;
+
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            // A UTF-16 surrogate pair, per RFC 8259 # 7
+                            if (0xd800..0xdc00).contains(&code) && self.bytes[self.pos..].starts_with(b"\\u") {
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(self.error("unpaired surrogate"));
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(code).ok_or_else(|| self.error("invalid \\u escape"))?
+                        },
+                        _ => return Err(self.error("invalid escape"))
+                    };
+                    out.extend_from_slice(c.encode_utf8(&mut [0u8; 4]).as_bytes());
+                },
+                c if c < 0x20 => return Err(self.error("control character in string")),
+                c => out.push(c)
+            }
+        }
+
+        // The input was a &str, and escapes only ever add whole characters
+        Ok(String::from_utf8(out).unwrap())
+    }
+}
+
+/**
+ * Serializes a parsed document in the JSON Canonicalization Scheme, RFC 8785, so documents
+ * which differ only in whitespace, member order, string escapes or the spelling of numbers
+ * serialize to the same bytes. Fails on duplicate member names and on numbers outside the range
+ * of an IEEE 754 double, neither of which JCS allows.
+ */
+pub fn
+canonicalize (value: &Value) -> Result<String, String> {
+    let mut out = String::new();
+    write_canonical(value, &mut out)?;
+    Ok(out)
+}
+
+fn
+write_canonical (value: &Value, out: &mut String) -> Result<(), String> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&canonical_number(n)?),
+        Value::String(s) => write_canonical_string(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (indx, item) in items.iter().enumerate() {
+                if indx > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out)?;
+            }
+            out.push(']');
+        },
+        Value::Object(members) => {
+            // RFC 8785 # 3.2.3: members are sorted by the UTF-16 code units of their names
+            let mut sorted: Vec<(Vec<u16>, &String, &Value)> = members.iter()
+                .map(|(k, v)| (k.encode_utf16().collect(), k, v))
+                .collect();
+            sorted.sort_by(|a, b| a.0.cmp(&b.0));
+            if let Some(pair) = sorted.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(format!("duplicate member \"{}\"", pair[0].1));
+            }
+
+            out.push('{');
+            for (indx, (_, key, value)) in sorted.iter().enumerate() {
+                if indx > 0 {
+                    out.push(',');
+                }
+                write_canonical_string(key, out);
+                out.push(':');
+                write_canonical(value, out)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+/**
+ * RFC 8785 # 3.2.2.2: only the quote, the backslash and control characters are escaped, using
+ * the two-character escapes where JSON has them.
+ */
+fn
+write_canonical_string (s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+}
+
+/**
+ * RFC 8785 # 3.2.2.3: a number is written as ECMAScript's Number.prototype.toString writes the
+ * nearest double, i.e. the shortest digits which round-trip, in plain notation for decimal
+ * exponents from -6 to 20 and scientific notation outside them.
+ */
+fn
+canonical_number (text: &str) -> Result<String, String> {
+    let value: f64 = text.parse().map_err(|_| format!("invalid number {}", text))?;
+    if !value.is_finite() {
+        return Err(format!("{} is out of range for a double", text));
+    }
+    if value == 0.0 {
+        return Ok("0".to_string());
+    }
+
+    // Rust's {:e} also gives the shortest round-tripping digits, as d.ddde<exponent>
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().unwrap() + 1;
+
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if n > 0 { '+' } else { '-' };
+        format!("{}e{}{}", mantissa, sign, (n - 1).abs())
+    };
+
+    Ok(if value < 0.0 { format!("-{}", body) } else { body })
+}
@@ -0,0 +1,107 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+use crate::{hmac::{hmac, Hmac}, Algo};
+
+/**
+ * From https://www.rfc-editor.org/rfc/rfc5246 # 5
+ *
+ * P_hash(secret, seed) = HMAC_hash(secret, A(1) + seed) +
+ *                        HMAC_hash(secret, A(2) + seed) + ...
+ *
+ * where A(0) = seed and A(i) = HMAC_hash(secret, A(i-1)). Iteration continues until `len`
+ * bytes have been produced; any excess output from the final iteration is discarded.
+ */
+pub fn
+p_hash (secret: &[u8], seed: &[u8], len: usize, algo: Algo) -> Vec<u8> {
+    let mut output: Vec<u8> = Vec::with_capacity(len);
+    let mut a = seed.to_vec();
+
+    while output.len() < len {
+        a = hmac(secret, &a, algo);
+
+        let mut block_input = a.clone();
+        block_input.extend_from_slice(seed);
+        output.extend_from_slice(&hmac(secret, &block_input, algo));
+    }
+
+    output.truncate(len);
+    output
+}
+
+/**
+ * The TLS 1.2 pseudo-random function, PRF(secret, label, seed) = P_<hash>(secret, label + seed).
+ * TLS 1.2 cipher suites use SHA-256 unless they specify otherwise.
+ */
+pub fn
+tls12_prf (secret: &[u8], label: &[u8], seed: &[u8], len: usize, algo: Algo) -> Vec<u8> {
+    let mut label_seed = label.to_vec();
+    label_seed.extend_from_slice(seed);
+    p_hash(secret, &label_seed, len, algo)
+}
+
+/**
+ * From https://www.rfc-editor.org/rfc/rfc8018 # 5.2
+ *
+ * DK = T_1 || T_2 || ... || T_l, where T_i = U_1 ^ U_2 ^ ... ^ U_c,
+ * U_1 = PRF(P, S || INT(i)) and U_j = PRF(P, U_{j-1}).
+ *
+ * The PRF is HMAC with the selected hash; the keyed HMAC is built once and cloned for each
+ * iteration rather than re-deriving the padded key every time.
+ */
+pub fn
+pbkdf2 (password: &[u8], salt: &[u8], iterations: u32, len: usize, algo: Algo) -> Vec<u8> {
+    let prf = Hmac::new(algo, password);
+    let mut output: Vec<u8> = Vec::with_capacity(len);
+    let mut i: u32 = 1;
+
+    while output.len() < len {
+        let mut mac = prf.clone();
+        mac.update(salt);
+        mac.update(&i.to_be_bytes());
+
+        let mut u = mac.finalize();
+        let mut t = u.clone();
+
+        for _ in 1..iterations {
+            let mut mac = prf.clone();
+            mac.update(&u);
+            u = mac.finalize();
+
+            for (t_byte, u_byte) in t.iter_mut().zip(u.iter()) {
+                *t_byte ^= u_byte;
+            }
+        }
+
+        output.extend_from_slice(&t);
+        i += 1;
+    }
+
+    output.truncate(len);
+    output
+}
+
+/**
+ * Estimates the number of PBKDF2 iterations which take roughly `target` to derive a single
+ * block on this machine. The iteration count is doubled until a probe run takes long enough to
+ * time reliably, then scaled linearly to the target duration.
+ */
+#[cfg(feature = "std")]
+pub fn
+calibrate_pbkdf2 (target: Duration, algo: Algo) -> u32 {
+    let mut probe: u32 = 1000;
+
+    loop {
+        let start = Instant::now();
+        pbkdf2(b"password", b"salt", probe, 1, algo);
+        let elapsed = start.elapsed();
+
+        if elapsed >= Duration::from_millis(50) || probe >= u32::MAX / 2 {
+            let scaled = probe as f64 * target.as_secs_f64() / elapsed.as_secs_f64();
+            return scaled.clamp(1.0, u32::MAX as f64) as u32;
+        }
+
+        probe *= 2;
+    }
+}
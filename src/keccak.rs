@@ -0,0 +1,236 @@
+/*
+ * The Keccak sponge construction over the Keccak-f[1600] permutation, per
+ * https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.202.pdf # 3 and # 4.
+ *
+ * The state is a 5x5 array of 64-bit lanes, indexed here as state[x + 5 * y]. Input is XORed into
+ * the first `rate` bytes of the state, one block at a time, with the permutation applied after
+ * each block. The padding's domain separation byte distinguishes original Keccak, as used by
+ * Ethereum, from the later standardized SHA-3 functions.
+ */
+
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::io;
+
+// Round constants for the iota step, FIPS 202 # 3.2.5
+const RC: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008
+];
+
+// Rotation offsets for the rho step, in the lane order visited by the combined rho/pi loop
+const RHO: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44
+];
+
+// Destination lanes for the pi step, following the same visiting order
+const PI: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1
+];
+
+// Domain separation and first padding bit for original Keccak submissions
+pub const KECCAK_PAD: u8 = 0x01;
+
+// The SHAKE domain separation bits 1111 followed by the first padding bit, FIPS 202 # 6.2
+pub const SHAKE_PAD: u8 = 0x1f;
+
+// Rates in bytes for SHAKE128 and SHAKE256, 1600 bits less twice the security strength
+pub const SHAKE128_RATE: usize = 168;
+pub const SHAKE256_RATE: usize = 136;
+
+/**
+ * Keccak-f[1600], FIPS 202 # 3.3: 24 rounds of theta, rho, pi, chi and iota.
+ */
+pub fn
+keccak_f (state: &mut [u64; 25]) {
+    for rc in RC.iter() {
+        // theta
+        let mut c = [0u64; 5];
+        for x in 0..5 {
+            c[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+
+        // rho and pi
+        let mut current = state[1];
+        for i in 0..24 {
+            let next = state[PI[i]];
+            state[PI[i]] = current.rotate_left(RHO[i]);
+            current = next;
+        }
+
+        // chi
+        for y in 0..5 {
+            let row = [state[5 * y], state[5 * y + 1], state[5 * y + 2], state[5 * y + 3], state[5 * y + 4]];
+            for x in 0..5 {
+                state[x + 5 * y] = row[x] ^ ((!row[(x + 1) % 5]) & row[(x + 2) % 5]);
+            }
+        }
+
+        // iota
+        state[0] ^= rc;
+    }
+}
+
+/**
+ * An incremental Keccak sponge with the given rate (block size) in bytes and padding byte.
+ */
+#[derive(Clone)]
+pub struct Keccak {
+    state: [u64; 25],
+    rate: usize,
+    pad: u8,
+    buffer: Vec<u8>
+}
+
+impl Keccak {
+    pub fn new (rate: usize, pad: u8) -> Keccak {
+        Keccak {
+            state: [0; 25],
+            rate,
+            pad,
+            buffer: Vec::with_capacity(rate)
+        }
+    }
+
+    fn absorb_block (&mut self, block: &[u8]) {
+        for (indx, lane) in block.chunks(8).enumerate() {
+            let mut bytes = [0u8; 8];
+            bytes[..lane.len()].copy_from_slice(lane);
+            self.state[indx] ^= u64::from_le_bytes(bytes);
+        }
+
+        keccak_f(&mut self.state);
+    }
+
+    pub fn update (&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+
+        let full = self.buffer.len() - self.buffer.len() % self.rate;
+        let blocks = self.buffer[..full].to_vec();
+        for block in blocks.chunks(self.rate) {
+            self.absorb_block(block);
+        }
+        self.buffer.drain(..full);
+    }
+
+    /**
+     * Pads the final block with the domain separation bits and pad10*1, then squeezes `len` bytes
+     * of output, applying the permutation between each `rate` bytes.
+     */
+    pub fn finalize (self, len: usize) -> Vec<u8> {
+        let mut output = vec![0u8; len];
+        self.finalize_xof().squeeze(&mut output);
+        output
+    }
+
+    /**
+     * Pads and absorbs the final block as finalize() does, returning a reader for however much
+     * output is wanted, so an arbitrarily long output stream never has to be held in memory.
+     */
+    pub fn finalize_xof (mut self) -> Squeeze {
+        let mut block = core::mem::take(&mut self.buffer);
+        let message_len = block.len();
+
+        block.resize(self.rate, 0x0);
+        block[message_len] ^= self.pad;
+        block[self.rate - 1] |= 0x80;
+        self.absorb_block(&block);
+
+        Squeeze { state: self.state, rate: self.rate, offset: 0 }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Keccak {
+    fn drop (&mut self) {
+        crate::wipe::wipe(&mut self.state);
+        crate::wipe::wipe_vec(&mut self.buffer);
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Write for Keccak {
+    fn write (&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush (&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/**
+ * The squeezing phase of a finalized sponge. Successive calls to squeeze() continue the same
+ * output stream, however the requested lengths are split.
+ */
+#[derive(Clone)]
+pub struct Squeeze {
+    state: [u64; 25],
+    rate: usize,
+    // Bytes of the current block already handed out
+    offset: usize
+}
+
+impl Squeeze {
+    pub fn squeeze (&mut self, out: &mut [u8]) {
+        let mut written = 0;
+
+        while written < out.len() {
+            if self.offset == self.rate {
+                keccak_f(&mut self.state);
+                self.offset = 0;
+            }
+
+            let take = (self.rate - self.offset).min(out.len() - written);
+            for (indx, byte) in out[written..written + take].iter_mut().enumerate() {
+                let pos = self.offset + indx;
+                *byte = self.state[pos / 8].to_le_bytes()[pos % 8];
+            }
+            self.offset += take;
+            written += take;
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Squeeze {
+    fn drop (&mut self) {
+        crate::wipe::wipe(&mut self.state);
+    }
+}
+
+/**
+ * Never reaches end of file; use io::Read::take() to bound the output.
+ */
+#[cfg(feature = "std")]
+impl io::Read for Squeeze {
+    fn read (&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.squeeze(buf);
+        Ok(buf.len())
+    }
+}
+
+/**
+ * Keccak-256 as used by Ethereum: a 1088-bit rate with the original Keccak padding. This differs
+ * from the standardized SHA3-256 only in the padding's domain separation bits.
+ */
+pub fn
+keccak256 (data: &[u8]) -> [u8; 32] {
+    let mut sponge = Keccak::new(136, KECCAK_PAD);
+    sponge.update(data);
+
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&sponge.finalize(32));
+    digest
+}
@@ -0,0 +1,304 @@
+/*
+ * Known-file hash sets, such as NIST's National Software Reference Library (NSRL) Reference Data
+ * Set (RDS): digests of files known to come from ordinary software, so that an examiner can set
+ * aside every file on a disk that matches one and look only at the rest.
+ *
+ * A set is read in one of three forms:
+ *
+ *   - A text file of hex digests, one per line and sorted, optionally followed by more fields
+ *     (so sorted sha256sum output will do). It is binary-searched where it lies, so a set of
+ *     hundreds of millions of digests needs no memory and no loading time.
+ *   - The NSRLFile.txt of an RDS 2.x release, a CSV file sorted by its first column, SHA-1. It is
+ *     binary-searched by that column. RDS 3 releases are SQLite databases, which aren't read
+ *     directly; export a column of one to a sorted text file instead.
+ *   - A Bloom filter written by `bloom build --digests`, which answers from memory but may
+ *     mistake an unknown file for a known one at the rate it was built for.
+ *
+ * A Blocklist is the opposite: digests of known-malicious files, as threat intelligence feeds
+ * publish them, where a match is the finding.
+ */
+
+use std::{collections::HashMap, fs, io::{self, BufRead, BufReader, Read, Seek, SeekFrom}};
+
+use crate::{bloom::BloomFilter, encoding::hex, Algo, Hasher};
+
+// The first line of an RDS 2.x NSRLFile.txt starts with its column names
+const NSRL_HEADER: &str = "\"SHA-1\",";
+
+/**
+ * How the digests in a sorted file are laid out on each line.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Layout {
+    // The digest is the first whitespace-separated field
+    Hex,
+    // The digest is the first field of an NSRLFile.txt line, quoted
+    Nsrl
+}
+
+pub enum KnownSet {
+    Sorted {
+        file: BufReader<fs::File>,
+        len: u64,
+        layout: Layout,
+        // Bytes per digest, taken from the first line that has one
+        width: usize
+    },
+    Bloom(BloomFilter)
+}
+
+fn
+invalid (msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/**
+ * The digest a line of a sorted set starts with, or None for a line without one, such as the
+ * NSRL header, a comment or a blank line.
+ */
+fn
+line_digest (line: &[u8], layout: Layout) -> Option<Vec<u8>> {
+    let line = std::str::from_utf8(line).ok()?;
+    let field = match layout {
+        Layout::Hex => line.split_whitespace().next()?,
+        Layout::Nsrl => line.split(',').next()?.trim().trim_matches('"')
+    };
+    hex::decode(field).ok().filter(|d| !d.is_empty())
+}
+
+impl KnownSet {
+    /**
+     * Opens a hash set, telling its form from its first line.
+     */
+    pub fn open (path: &str) -> io::Result<KnownSet> {
+        let mut file = BufReader::new(fs::File::open(path)?);
+        let len = file.get_ref().metadata()?.len();
+
+        let mut first = Vec::new();
+        file.read_until(b'\n', &mut first)?;
+        if first.starts_with(b"sha2-bloom ") {
+            file.rewind()?;
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            return Ok(KnownSet::Bloom(BloomFilter::from_bytes(&bytes)?));
+        }
+
+        let layout = if first.starts_with(NSRL_HEADER.as_bytes()) { Layout::Nsrl } else { Layout::Hex };
+        let mut line = first;
+        let width = loop {
+            if let Some(digest) = line_digest(&line, layout) {
+                break digest.len();
+            }
+            line.clear();
+            if file.read_until(b'\n', &mut line)? == 0 {
+                return Err(invalid("no hex digests found in the hash set"));
+            }
+        };
+
+        Ok(KnownSet::Sorted { file, len, layout, width })
+    }
+
+    /**
+     * The length of the digests in the set, or None for a Bloom filter, which doesn't record it.
+     */
+    pub fn width (&self) -> Option<usize> {
+        match self {
+            KnownSet::Sorted { width, .. } => Some(*width),
+            KnownSet::Bloom(_) => None
+        }
+    }
+
+    /**
+     * Whether the set holds the digest. A Bloom filter may answer true for a digest that was
+     * never added; its false answers, and all of a sorted file's answers, are exact.
+     */
+    pub fn contains (&mut self, digest: &[u8]) -> io::Result<bool> {
+        match self {
+            KnownSet::Sorted { file, len, layout, .. } => search(file, *len, *layout, digest),
+            KnownSet::Bloom(filter) => Ok(filter.contains(digest))
+        }
+    }
+}
+
+/**
+ * The first line starting at or after `pos` that holds a digest: where it starts, where the line
+ * after it starts, and the digest.
+ */
+fn
+next_digest (file: &mut BufReader<fs::File>, pos: u64, layout: Layout) -> io::Result<Option<(u64, u64, Vec<u8>)>> {
+    let mut line = Vec::new();
+    let mut start = pos;
+
+    // Unless pos is the start of the file, the line containing pos - 1 began earlier
+    if pos > 0 {
+        file.seek(SeekFrom::Start(pos - 1))?;
+        start = pos - 1 + file.read_until(b'\n', &mut line)? as u64;
+    } else {
+        file.rewind()?;
+    }
+
+    loop {
+        line.clear();
+        let read = file.read_until(b'\n', &mut line)? as u64;
+        if read == 0 {
+            return Ok(None);
+        }
+        if let Some(digest) = line_digest(&line, layout) {
+            return Ok(Some((start, start + read, digest)));
+        }
+        start += read;
+    }
+}
+
+/**
+ * Looks a digest up in a sorted file by bisecting its byte range, as look(1) does. Lines that
+ * hold no digest are passed over.
+ */
+fn
+search (file: &mut BufReader<fs::File>, len: u64, layout: Layout, digest: &[u8]) -> io::Result<bool> {
+    // The line holding the digest, if there is one, starts in lo..hi
+    let (mut lo, mut hi) = (0, len);
+
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        match next_digest(file, mid, layout)? {
+            Some((start, next, found)) if start < hi => match found.as_slice().cmp(digest) {
+                std::cmp::Ordering::Less => lo = next,
+                std::cmp::Ordering::Equal => return Ok(true),
+                std::cmp::Ordering::Greater => hi = mid
+            },
+            _ => hi = mid
+        }
+    }
+
+    Ok(false)
+}
+
+/**
+ * Whether files can be hashed to match a set of `width`-byte digests: MD5, SHA-1, SHA-224,
+ * SHA-256 and, if enabled, SHA-512 can.
+ */
+pub fn
+supported (width: usize) -> bool {
+    Algo::try_from_bits(width * 8).is_ok()
+}
+
+/**
+ * The name of the algorithm whose digests are `width` bytes long, e.g. "SHA256", or None if that
+ * isn't supported().
+ */
+pub fn
+algorithm_name (width: usize) -> Option<&'static str> {
+    Algo::try_from_bits(width * 8).ok().map(|algo| algo.tag())
+}
+
+/**
+ * Hashes a file with the algorithm whose digests are `width` bytes long. Returns None if that
+ * isn't supported().
+ */
+pub fn
+hash_reader<R: Read> (reader: R, width: usize) -> io::Result<Option<Vec<u8>>> {
+    if !supported(width) {
+        return Ok(None);
+    }
+    Ok(hash_reader_widths(reader, &[width])?.pop())
+}
+
+/**
+ * Hashes a file once with each of the algorithms whose digests are `widths` bytes long, in a
+ * single read. Every width must be supported().
+ */
+pub fn
+hash_reader_widths<R: Read> (mut reader: R, widths: &[usize]) -> io::Result<Vec<Vec<u8>>> {
+    let mut hashers: Vec<Hasher> = widths.iter().map(|width| {
+        Hasher::new(Algo::try_from_bits(width * 8).expect("unsupported digest length"))
+    }).collect();
+
+    let mut buffer = vec![0u8; 1 << 16];
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        };
+        for hasher in hashers.iter_mut() {
+            hasher.update(&buffer[..read]);
+        }
+    }
+
+    Ok(hashers.into_iter().map(|hasher| hasher.finalize_digest().to_vec()).collect())
+}
+
+/**
+ * A blocklist of indicators of compromise: digests of malicious files, each with the rest of its
+ * line, such as a malware family name. Feeds are small, unsorted and often mix MD5, SHA-1 and
+ * SHA-256, so a blocklist is held in memory and files are hashed once with each algorithm it
+ * lists.
+ */
+pub struct Blocklist {
+    entries: HashMap<Vec<u8>, String>,
+    // The digest lengths present, shortest first
+    widths: Vec<usize>
+}
+
+impl Blocklist {
+    /**
+     * Reads a blocklist. Each line starts with a digest, optionally quoted, then whitespace, a
+     * comma or a semicolon and an optional description. Blank lines, comments starting with '#'
+     * and lines that don't start with a digest, such as a CSV header, are skipped. Returns the
+     * blocklist and the numbers of the lines skipped because their digest's length isn't
+     * supported().
+     */
+    pub fn parse (text: &str) -> (Blocklist, Vec<usize>) {
+        let mut entries = HashMap::new();
+        let mut widths = Vec::new();
+        let mut unsupported = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            let (field, label) = line.split_once(|c: char| c.is_whitespace() || c == ',' || c == ';').unwrap_or((line, ""));
+            let digest = match hex::decode(field.trim_matches('"')) {
+                Ok(digest) if !digest.is_empty() => digest,
+                _ => continue
+            };
+            if !supported(digest.len()) {
+                unsupported.push(index + 1);
+                continue;
+            }
+
+            if !widths.contains(&digest.len()) {
+                widths.push(digest.len());
+            }
+            entries.insert(digest, label.trim_matches(|c: char| c.is_whitespace() || c == ',' || c == ';').to_string());
+        }
+
+        widths.sort_unstable();
+        (Blocklist { entries, widths }, unsupported)
+    }
+
+    pub fn len (&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty (&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /**
+     * The lengths of the digests listed, shortest first: the algorithms to hash files with.
+     */
+    pub fn widths (&self) -> &[usize] {
+        &self.widths
+    }
+
+    /**
+     * The description listed with a digest, empty if it had none, or None if it isn't listed.
+     */
+    pub fn lookup (&self, digest: &[u8]) -> Option<&str> {
+        self.entries.get(digest).map(|label| label.as_str())
+    }
+}
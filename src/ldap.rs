@@ -0,0 +1,93 @@
+use crate::{digest, sha1::sha1, Algo};
+
+/**
+ * LDAP userPassword hashing schemes, as produced by slappasswd and the OpenLDAP pw-sha2 module.
+ * The salted schemes store base64(H(password || salt) || salt).
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Scheme {
+    Ssha,
+    Sha256,
+    Ssha256,
+    Ssha512
+}
+
+impl Scheme {
+    pub fn from_name (name: &str) -> Option<Scheme> {
+        match name.to_ascii_uppercase().trim_matches(&['{', '}'][..]) {
+            "SSHA" => Some(Scheme::Ssha),
+            "SHA256" => Some(Scheme::Sha256),
+            "SSHA256" => Some(Scheme::Ssha256),
+            "SSHA512" => Some(Scheme::Ssha512),
+            _ => None
+        }
+    }
+
+    pub fn tag (&self) -> &'static str {
+        match self {
+            Scheme::Ssha => "{SSHA}",
+            Scheme::Sha256 => "{SHA256}",
+            Scheme::Ssha256 => "{SSHA256}",
+            Scheme::Ssha512 => "{SSHA512}"
+        }
+    }
+
+    fn salted (&self) -> bool {
+        *self != Scheme::Sha256
+    }
+
+    fn hash (&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Scheme::Ssha => sha1(data).to_vec(),
+            Scheme::Sha256 | Scheme::Ssha256 => digest(data, Algo::Sha256),
+            Scheme::Ssha512 => digest(data, Algo::Sha512)
+        }
+    }
+
+    fn digest_len (&self) -> usize {
+        match self {
+            Scheme::Ssha => 20,
+            Scheme::Sha256 | Scheme::Ssha256 => 32,
+            Scheme::Ssha512 => 64
+        }
+    }
+}
+
+/**
+ * Builds a userPassword value for the given scheme, e.g. {SSHA256}base64(SHA-256(password || salt) || salt).
+ * The salt is ignored for unsalted schemes.
+ */
+pub fn
+hash_password (scheme: Scheme, password: &[u8], salt: &[u8]) -> String {
+    let salt = if scheme.salted() { salt } else { &[] };
+
+    let mut input = password.to_vec();
+    input.extend_from_slice(salt);
+
+    let mut value = scheme.hash(&input);
+    value.extend_from_slice(salt);
+
+    format!("{}{}", scheme.tag(), base64::encode(value))
+}
+
+/**
+ * Checks a password against a userPassword value. Returns None if the value's scheme isn't
+ * supported or it is malformed.
+ */
+pub fn
+verify (value: &str, password: &[u8]) -> Option<bool> {
+    let end = value.find('}')?;
+    let scheme = Scheme::from_name(&value[..=end])?;
+    let decoded = base64::decode(value[end + 1..].trim()).ok()?;
+
+    if decoded.len() < scheme.digest_len() {
+        return None;
+    }
+
+    let (expected, salt) = decoded.split_at(scheme.digest_len());
+
+    let mut input = password.to_vec();
+    input.extend_from_slice(salt);
+
+    Some(scheme.hash(&input) == expected)
+}
@@ -0,0 +1,1789 @@
+/*!
+ * SHA-224, SHA-256 and SHA-512 per FIPS 180-4 and the SHA-3 family per FIPS 202, plus the formats, checks and constructions built on them
+ * (HMAC, PBKDF2, checksum files, manifests, ...). The `sha-2` binary is a command line front end
+ * to this library. SHA-1 and MD5 are available too, deprecated, for checking digests that legacy
+ * tools still write.
+ *
+ * The simplest entry points hash a complete message:
+ *
+ * ```
+ * assert_eq!(format!("{}", sha_2::hash(b"abc", sha_2::Algo::Sha256)),
+ *     "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+ * ```
+ *
+ * `Hasher` hashes incrementally, and `State`, `pad` and `compress` expose the underlying
+ * block function for constructions that need it.
+ *
+ * With default features off the crate is `#![no_std]` and doesn't allocate: `Hasher`, `Sha256`,
+ * `Sha224`, `hash`, `Digest`, `Midstate`, `Engine` and `compress256` work on fixed-size buffers.
+ * The `alloc` feature adds HMAC, PBKDF2, hex/base32 encoding, padding into a Vec and JSON state
+ * checkpoints, and `std` adds file IO, the checksum formats and other tools. Each algorithm has
+ * a feature of its own, `sha224`, `sha256`, `sha512`, `sha1`, `sha3`, `md5` and `keccak`, all
+ * of which `std` enables.
+ */
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(any(feature = "sha224", feature = "sha256", feature = "sha512", feature = "sha1", feature = "md5", feature = "sha3")))]
+compile_error!("enable at least one of the sha224, sha256, sha512, sha1, md5 and sha3 features");
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::{format, string::{String, ToString}, vec::Vec};
+#[cfg(feature = "std")]
+use std::{io, io::Read, io::Write};
+
+#[cfg(feature = "alloc")]
+use encoding::hex;
+pub use engine::{Engine, Length, Word};
+pub use error::ShaError;
+
+pub mod engine;
+pub mod eol;
+pub mod error;
+pub mod tuple;
+#[cfg(feature = "zeroize")]
+mod wipe;
+
+#[cfg(feature = "alloc")]
+pub mod combine;
+#[cfg(feature = "alloc")]
+pub mod encoding;
+#[cfg(feature = "alloc")]
+pub mod hmac;
+#[cfg(feature = "alloc")]
+pub mod json;
+#[cfg(feature = "alloc")]
+pub mod kdf;
+#[cfg(feature = "alloc")]
+pub mod multi;
+#[cfg(feature = "alloc")]
+pub mod strip;
+#[cfg(feature = "alloc")]
+pub mod unicode;
+#[cfg(feature = "keccak")]
+pub mod keccak;
+#[cfg(feature = "md5")]
+pub mod md5;
+#[cfg(feature = "sha1")]
+pub mod sha1;
+#[cfg(feature = "sha3")]
+pub mod sha3;
+
+#[cfg(feature = "std")]
+pub mod apt;
+#[cfg(feature = "std")]
+pub mod authenticode;
+#[cfg(feature = "std")]
+pub mod bitcoin;
+#[cfg(feature = "std")]
+pub mod blocks;
+#[cfg(feature = "std")]
+pub mod bloom;
+#[cfg(feature = "std")]
+pub mod buildid;
+#[cfg(feature = "std")]
+pub mod cargo;
+#[cfg(feature = "std")]
+pub mod check;
+#[cfg(feature = "std")]
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub mod csv;
+#[cfg(feature = "std")]
+pub mod der;
+#[cfg(feature = "std")]
+pub mod entropy;
+#[cfg(feature = "std")]
+pub mod ethereum;
+#[cfg(feature = "std")]
+pub mod firmware;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+pub mod gitignore;
+#[cfg(feature = "std")]
+pub mod gitobject;
+#[cfg(feature = "std")]
+pub mod hashdeep;
+#[cfg(feature = "std")]
+pub mod htpasswd;
+#[cfg(feature = "std")]
+pub mod imaging;
+#[cfg(feature = "std")]
+pub mod iotune;
+#[cfg(feature = "std")]
+pub mod knownset;
+#[cfg(feature = "std")]
+pub mod ldap;
+#[cfg(feature = "std")]
+pub mod linkfarm;
+#[cfg(feature = "std")]
+pub mod manifest;
+#[cfg(feature = "std")]
+pub mod oci;
+#[cfg(feature = "std")]
+pub mod partition;
+#[cfg(feature = "std")]
+pub mod pgp;
+#[cfg(feature = "std")]
+pub mod pkcs1;
+#[cfg(feature = "std")]
+pub mod random;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod seal;
+#[cfg(feature = "std")]
+pub mod shard;
+#[cfg(feature = "std")]
+pub mod ssri;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod treediff;
+#[cfg(feature = "std")]
+pub mod tsp;
+#[cfg(feature = "std")]
+pub mod uniq;
+
+// From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 5.3.2
+#[cfg(feature = "sha224")]
+const SHA_224_H_INIT: [u32; 8] = [
+    0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7, 0xbefa4fa4
+];
+
+// From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 5.3.3
+#[cfg(feature = "sha256")]
+const SHA_256_H_INIT: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19
+];
+
+// From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 5.3.5
+#[cfg(feature = "sha512")]
+const SHA_512_H_INIT: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179
+];
+
+
+/**
+ * The SHA-224/SHA-256 state: the generic engine over 32-bit words.
+ */
+pub type State = Engine<u32>;
+
+impl Engine<u32> {
+    /**
+     * Panics for SHA-512, which runs on an Engine<u64>.
+     */
+    // Without sha224 and sha256 every arm panics
+    #[allow(unreachable_code, unused_variables)]
+    pub fn new (algo: Algo) -> State {
+
+        // Select the appropriate initialization values based on algorithm 
+        let init: &[u32; 8] = match algo {
+            #[cfg(feature = "sha224")]
+            Algo::Sha224 => &SHA_224_H_INIT,
+            #[cfg(feature = "sha256")]
+            Algo::Sha256 => &SHA_256_H_INIT,
+            #[cfg(feature = "sha512")]
+            Algo::Sha512 => panic!("SHA-512 runs on 64-bit words"),
+            #[cfg(feature = "md5")]
+            Algo::Md5 => panic!("MD5 doesn't run on the SHA-2 engine"),
+            #[cfg(feature = "sha1")]
+            Algo::Sha1 => panic!("SHA-1 doesn't run on the SHA-2 engine"),
+            #[cfg(feature = "sha3")]
+            Algo::Sha3_224 | Algo::Sha3_256 | Algo::Sha3_384 | Algo::Sha3_512 => panic!("SHA-3 doesn't run on the SHA-2 engine")
+        };
+
+        Engine::from_words(*init, algo.bits())
+    }
+}
+
+/**
+ * The SHA-512 state: the generic engine over 64-bit words.
+ */
+#[cfg(feature = "sha512")]
+impl Engine<u64> {
+    /**
+     * Panics for SHA-224 and SHA-256, which run on an Engine<u32>.
+     */
+    pub fn new (algo: Algo) -> Engine<u64> {
+        assert!(algo == Algo::Sha512, "{} runs on 32-bit words", algo);
+        Engine::from_words(SHA_512_H_INIT, algo.bits())
+    }
+}
+
+/**
+ * See https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 5.1.2
+ * 
+ * Suppose the length of the message M, in bits, is  bits. Append the bit “1” to the end of the message, 
+ * followed by k zero bits, where k is the smallest non-negative solution to the equation L + 1 + k = 896 mod 1024. 
+ * Then append the 128-bit block that is equal to the number L expressed using a binary representation. 
+ * For example, the (8-bit ASCII) message “abc” has length 8 x 3 = 24, so the message is padded with a one bit,
+ * then 896 - (24 + 1) = 871 zero bits, and then the message length, to become the 1024-bit padded message.
+ * The length of the padded message should now be a multiple of 1024 bits.
+ */
+#[cfg(feature = "alloc")]
+pub fn
+pad (message: &mut Vec<u8>) {
+    let bit_len = (message.len() as u64).wrapping_mul(8);
+    pad_with_length(message, bit_len);
+}
+
+/**
+ * Pads the trailing, partial block of a message whose total length is `bit_len` bits, a whole
+ * number of bytes, for a 512-bit block. Used by the streaming hashers, where the blocks
+ * preceding `message` have already been compressed and only their bit count was kept.
+ */
+#[cfg(feature = "alloc")]
+pub(crate) fn
+pad_with_length (message: &mut Vec<u8>, bit_len: u64) {
+    append_padding(message, &bit_len.to_be_bytes(), LAYOUT_512);
+}
+
+/**
+ * Block and length-field sizes, in bytes, for the padding in FIPS 180-4 # 5.1. SHA-1, SHA-224
+ * and SHA-256 use 512-bit blocks ending in a 64-bit message length; SHA-384 and SHA-512 use
+ * 1024-bit blocks ending in a 128-bit length.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BlockLayout {
+    pub block_len: usize,
+    pub length_len: usize
+}
+
+pub const LAYOUT_512: BlockLayout = BlockLayout { block_len: 64, length_len: 8 };
+pub const LAYOUT_1024: BlockLayout = BlockLayout { block_len: 128, length_len: 16 };
+
+/**
+ * Pads the trailing, partial block of a message whose total length is `len` bytes: a 1 bit, then
+ * zeros up to the length field, then the length in bits, big-endian, modulo 2^64 or 2^128. Only
+ * `message.len()` modulo the block length matters, so `message` may be the whole message or just
+ * the bytes after its last complete block.
+ */
+#[cfg(feature = "alloc")]
+pub fn
+pad_message (message: &mut Vec<u8>, len: u128, layout: BlockLayout) {
+    append_padding(message, &len.wrapping_mul(8).to_be_bytes(), layout);
+}
+
+/**
+ * Appends the padding for a message whose length in bits is `len_in_bits`, big-endian, of which
+ * the length field keeps the low-order bytes.
+ */
+#[cfg(feature = "alloc")]
+fn
+append_padding (message: &mut Vec<u8>, len_in_bits: &[u8], layout: BlockLayout) {
+    // Appends 1 << 7, ie 1000 0000, we're working in bytes
+    message.push(0x80);
+
+    while message.len() % layout.block_len != layout.block_len - layout.length_len {
+        message.push(0x0);
+    }
+
+    message.extend_from_slice(&len_in_bits[len_in_bits.len() - layout.length_len..]);
+}
+
+/**
+ * Processes a single 512-bit message block, updating the given state.
+ *
+ * See https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 6.2.2
+ */
+pub fn
+compress (state: &mut State, block: &[u8]) {
+    state.compress(block);
+}
+
+/**
+ * The bare SHA-256 compression function: folds one 64-byte block into the eight chaining words.
+ * Nothing is padded or counted, so this is the primitive for tree hashes, custom padding schemes
+ * and other constructions that manage the message schedule themselves. SHA-224 uses the same
+ * function, starting from its own IV and truncating the result.
+ *
+ * ```
+ * // SHA-256("abc") by hand: the IV, then the single padded block
+ * let mut state = sha_2::State::new(sha_2::Algo::Sha256).words();
+ * let mut block = [0u8; 64];
+ * block[..3].copy_from_slice(b"abc");
+ * block[3] = 0x80;
+ * block[63] = 24;
+ * sha_2::compress256(&mut state, &block);
+ * assert_eq!(state[0], 0xba7816bf);
+ * ```
+ *
+ * See https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 6.2.2
+ */
+pub fn
+compress256 (state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut engine = Engine::from_words(*state, 256);
+    engine.compress(block);
+    *state = engine.words();
+}
+
+/**
+ * The SHA-256 message schedule for one 64-byte block: the block's sixteen big-endian words
+ * W[0..16], then W[16..64] expanded from them with σ0 and σ1, exactly as compress256() uses
+ * them. For visualizing a compression step or for cryptanalysis scripts that want the expanded
+ * words without writing σ0 and σ1 again. SHA-224 shares the schedule.
+ *
+ * ```
+ * let mut block = [0u8; 64];
+ * block[..3].copy_from_slice(b"abc");
+ * block[3] = 0x80;
+ * block[63] = 24;
+ * let w = sha_2::expand_schedule(&block);
+ * assert_eq!((w[0], w[15], w[16], w[17], w[63]), (0x61626380, 24, 0x61626380, 0x000f0000, 0x12b1edeb));
+ * ```
+ *
+ * See https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 6.2.2, step 1
+ */
+pub fn
+expand_schedule (block: &[u8; 64]) -> [u32; 64] {
+    let w = engine::schedule::<u32>(block);
+    w[..64].try_into().unwrap()
+}
+
+/**
+ * The hash algorithms implemented by this crate, taken wherever an algorithm is chosen so that an
+ * unsupported one can't be asked for. Parses from "256", "sha256" or "SHA-256", and displays as
+ * the latter; SHA-3 parses from "sha3-256" or "SHA3-256", and MD5 from "md5". Only the variants
+ * whose features are enabled exist.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Algo {
+    // Deprecated like SHA-1, and more thoroughly broken: collisions take seconds
+    #[cfg(feature = "md5")]
+    Md5,
+    // Deprecated: collisions are practical, so it only checks digests legacy tools wrote; see
+    // is_deprecated()
+    #[cfg(feature = "sha1")]
+    Sha1,
+    #[cfg(feature = "sha224")]
+    Sha224,
+    #[cfg(feature = "sha256")]
+    Sha256,
+    #[cfg(feature = "sha512")]
+    Sha512,
+    #[cfg(feature = "sha3")]
+    Sha3_224,
+    #[cfg(feature = "sha3")]
+    Sha3_256,
+    #[cfg(feature = "sha3")]
+    Sha3_384,
+    #[cfg(feature = "sha3")]
+    Sha3_512
+}
+
+impl Algo {
+    /**
+     * Every algorithm compiled in: MD5, SHA-1 and SHA-2 smallest first, then SHA-3.
+     */
+    pub const ALL: &'static [Algo] = &[
+        #[cfg(feature = "md5")]
+        Algo::Md5,
+        #[cfg(feature = "sha1")]
+        Algo::Sha1,
+        #[cfg(feature = "sha224")]
+        Algo::Sha224,
+        #[cfg(feature = "sha256")]
+        Algo::Sha256,
+        #[cfg(feature = "sha512")]
+        Algo::Sha512,
+        #[cfg(feature = "sha3")]
+        Algo::Sha3_224,
+        #[cfg(feature = "sha3")]
+        Algo::Sha3_256,
+        #[cfg(feature = "sha3")]
+        Algo::Sha3_384,
+        #[cfg(feature = "sha3")]
+        Algo::Sha3_512
+    ];
+
+    /**
+     * Maps a digest length in bits, as found in saved states and checksum files, to an Algo.
+     * Lengths SHA-3 shares with SHA-2 map to SHA-2, and SHA3-384 isn't mapped at all, so SHA-3
+     * is only ever chosen by name. Panics on unsupported lengths; see try_from_bits().
+     */
+    pub fn from_bits (n: usize) -> Algo {
+        Algo::try_from_bits(n).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_bits (n: usize) -> Result<Algo, ShaError> {
+        match n {
+            #[cfg(feature = "md5")]
+            128 => Ok(Algo::Md5),
+            #[cfg(feature = "sha1")]
+            160 => Ok(Algo::Sha1),
+            #[cfg(feature = "sha224")]
+            224 => Ok(Algo::Sha224),
+            #[cfg(feature = "sha256")]
+            256 => Ok(Algo::Sha256),
+            #[cfg(feature = "sha512")]
+            512 => Ok(Algo::Sha512),
+            _ => Err(ShaError::UnsupportedAlgorithm(n))
+        }
+    }
+
+    pub fn bits (&self) -> usize {
+        match self {
+            #[cfg(feature = "md5")]
+            Algo::Md5 => 128,
+            #[cfg(feature = "sha1")]
+            Algo::Sha1 => 160,
+            #[cfg(feature = "sha224")]
+            Algo::Sha224 => 224,
+            #[cfg(feature = "sha256")]
+            Algo::Sha256 => 256,
+            #[cfg(feature = "sha512")]
+            Algo::Sha512 => 512,
+            #[cfg(feature = "sha3")]
+            Algo::Sha3_224 => 224,
+            #[cfg(feature = "sha3")]
+            Algo::Sha3_256 => 256,
+            #[cfg(feature = "sha3")]
+            Algo::Sha3_384 => 384,
+            #[cfg(feature = "sha3")]
+            Algo::Sha3_512 => 512
+        }
+    }
+
+    pub fn digest_len (&self) -> usize {
+        self.bits() / 8
+    }
+
+    /**
+     * The block length in bytes: 64 for MD5, SHA-1, SHA-224 and SHA-256, 128 for SHA-512, and the
+     * sponge's rate for SHA-3. HMAC pads its key to this.
+     */
+    pub fn block_len (&self) -> usize {
+        match self {
+            #[cfg(feature = "sha512")]
+            Algo::Sha512 => 128,
+            #[cfg(feature = "sha3")]
+            Algo::Sha3_224 | Algo::Sha3_256 | Algo::Sha3_384 | Algo::Sha3_512 => sha3::rate(self.bits()),
+            #[allow(unreachable_patterns)]
+            _ => 64
+        }
+    }
+
+    /**
+     * Whether the algorithm is broken and only kept for checking digests that older tools and
+     * formats still write. Nothing new should be protected with one.
+     */
+    pub fn is_deprecated (&self) -> bool {
+        #[cfg(feature = "md5")]
+        if *self == Algo::Md5 {
+            return true;
+        }
+        #[cfg(feature = "sha1")]
+        if *self == Algo::Sha1 {
+            return true;
+        }
+        false
+    }
+
+    /**
+     * The SHA-3 function with a `bits`-bit digest, which sha3::Sha3 has already checked.
+     */
+    #[cfg(feature = "sha3")]
+    fn sha3 (bits: usize) -> Algo {
+        match bits {
+            224 => Algo::Sha3_224,
+            256 => Algo::Sha3_256,
+            384 => Algo::Sha3_384,
+            _ => Algo::Sha3_512
+        }
+    }
+
+    /**
+     * Whether this is one of the SHA-3 functions, which run on the Keccak sponge rather than a
+     * Merkle-Damgard engine.
+     */
+    pub fn is_sha3 (&self) -> bool {
+        #[cfg(feature = "sha3")]
+        if matches!(self, Algo::Sha3_224 | Algo::Sha3_256 | Algo::Sha3_384 | Algo::Sha3_512) {
+            return true;
+        }
+        false
+    }
+
+    /**
+     * The name checksum tools write, as in BSD-style "SHA256 (file) = ..." lines and certutil's
+     * and Get-FileHash's output: "MD5", "SHA1", "SHA256", "SHA3-256" and so on.
+     */
+    pub fn tag (&self) -> &'static str {
+        match self {
+            #[cfg(feature = "md5")]
+            Algo::Md5 => "MD5",
+            #[cfg(feature = "sha1")]
+            Algo::Sha1 => "SHA1",
+            #[cfg(feature = "sha224")]
+            Algo::Sha224 => "SHA224",
+            #[cfg(feature = "sha256")]
+            Algo::Sha256 => "SHA256",
+            #[cfg(feature = "sha512")]
+            Algo::Sha512 => "SHA512",
+            #[cfg(feature = "sha3")]
+            Algo::Sha3_224 => "SHA3-224",
+            #[cfg(feature = "sha3")]
+            Algo::Sha3_256 => "SHA3-256",
+            #[cfg(feature = "sha3")]
+            Algo::Sha3_384 => "SHA3-384",
+            #[cfg(feature = "sha3")]
+            Algo::Sha3_512 => "SHA3-512"
+        }
+    }
+
+    /**
+     * Whether the hash state is SHA-256's eight 32-bit words, as Midstate, HasherState and
+     * checkpoints store it: true for SHA-224 and SHA-256.
+     */
+    pub fn has_midstate (&self) -> bool {
+        match self {
+            #[cfg(feature = "sha224")]
+            Algo::Sha224 => true,
+            #[cfg(feature = "sha256")]
+            Algo::Sha256 => true,
+            #[allow(unreachable_patterns)]
+            _ => false
+        }
+    }
+}
+
+impl core::str::FromStr for Algo {
+    type Err = ShaError;
+
+    fn from_str (s: &str) -> Result<Algo, ShaError> {
+        #[cfg(feature = "md5")]
+        if s.eq_ignore_ascii_case("md5") {
+            return Ok(Algo::Md5);
+        }
+        let bits = match s.get(..3) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("sha") => s[3..].trim_start_matches('-'),
+            _ => s
+        };
+        match bits {
+            #[cfg(feature = "sha1")]
+            "1" => Ok(Algo::Sha1),
+            #[cfg(feature = "sha224")]
+            "224" => Ok(Algo::Sha224),
+            #[cfg(feature = "sha256")]
+            "256" => Ok(Algo::Sha256),
+            #[cfg(feature = "sha512")]
+            "512" => Ok(Algo::Sha512),
+            #[cfg(feature = "sha3")]
+            "3-224" | "3_224" => Ok(Algo::Sha3_224),
+            #[cfg(feature = "sha3")]
+            "3-256" | "3_256" => Ok(Algo::Sha3_256),
+            #[cfg(feature = "sha3")]
+            "3-384" | "3_384" => Ok(Algo::Sha3_384),
+            #[cfg(feature = "sha3")]
+            "3-512" | "3_512" => Ok(Algo::Sha3_512),
+            _ => Err(ShaError::UnknownAlgorithm)
+        }
+    }
+}
+
+impl core::fmt::Display for Algo {
+    fn fmt (&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        #[cfg(feature = "md5")]
+        if *self == Algo::Md5 {
+            return f.write_str("MD5");
+        }
+        #[cfg(feature = "sha1")]
+        if *self == Algo::Sha1 {
+            return f.write_str("SHA-1");
+        }
+        if self.is_sha3() {
+            return write!(f, "SHA3-{}", self.bits());
+        }
+        write!(f, "SHA-{}", self.bits())
+    }
+}
+
+// The longest digest compiled in, in bytes
+const DIGEST_MAX: usize = if cfg!(any(feature = "sha512", feature = "sha3")) { 64 } else { 32 };
+
+/**
+ * A computed digest: the 16, 20, 28, 32, 48 or 64 digest bytes, held inline rather than in a Vec or a hex
+ * String so that it can be copied into binary protocols as-is. Derefs to its bytes, and displays
+ * as lowercase hex; `{:X}` gives uppercase hex.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Digest {
+    algo: Algo,
+    bytes: [u8; DIGEST_MAX]
+}
+
+impl Digest {
+    pub(crate) fn from_state<W: Word> (state: &Engine<W>) -> Digest {
+        let mut bytes = [0u8; DIGEST_MAX];
+        state.export_into(&mut bytes);
+        Digest { algo: Algo::from_bits(state.digest_bits()), bytes }
+    }
+
+    #[cfg(feature = "md5")]
+    fn from_md5 (md5: [u8; 16]) -> Digest {
+        let mut bytes = [0u8; DIGEST_MAX];
+        bytes[..16].copy_from_slice(&md5);
+        Digest { algo: Algo::Md5, bytes }
+    }
+
+    #[cfg(feature = "sha1")]
+    fn from_sha1 (sha1: [u8; 20]) -> Digest {
+        let mut bytes = [0u8; DIGEST_MAX];
+        bytes[..20].copy_from_slice(&sha1);
+        Digest { algo: Algo::Sha1, bytes }
+    }
+
+    #[cfg(feature = "sha3")]
+    fn from_sha3 (algo: Algo, sha3: &[u8]) -> Digest {
+        let mut bytes = [0u8; DIGEST_MAX];
+        bytes[..sha3.len()].copy_from_slice(sha3);
+        Digest { algo, bytes }
+    }
+
+    /**
+     * A digest computed with `algo` and stored apart from it. Unlike TryFrom, which has to guess
+     * the algorithm from the length, this keeps SHA-3 digests apart from SHA-2's. Fails if the
+     * length isn't `algo`'s.
+     */
+    pub fn from_slice (algo: Algo, digest: &[u8]) -> Result<Digest, ShaError> {
+        if digest.len() != algo.digest_len() {
+            return Err(ShaError::InvalidDigestLength(digest.len()));
+        }
+        let mut bytes = [0u8; DIGEST_MAX];
+        bytes[..digest.len()].copy_from_slice(digest);
+        Ok(Digest { algo, bytes })
+    }
+
+    pub fn algo (&self) -> Algo {
+        self.algo
+    }
+
+    pub fn as_bytes (&self) -> &[u8] {
+        &self.bytes[..self.algo.digest_len()]
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn to_hex (&self) -> String {
+        hex::encode(self.as_bytes())
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn to_vec (&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    /**
+     * The leading `bits` bits of the digest, as used for key IDs and short content IDs. Panics if
+     * `bits` is 0 or more than the digest's length.
+     */
+    pub fn truncate (&self, bits: usize) -> TruncatedDigest {
+        assert!(bits > 0 && bits <= self.algo.bits(), "can't truncate a {} digest to {} bits", self.algo, bits);
+        let mut bytes = [0u8; DIGEST_MAX];
+        let len = bits.div_ceil(8);
+        bytes[..len].copy_from_slice(&self.bytes[..len]);
+        if !bits.is_multiple_of(8) {
+            bytes[len - 1] &= 0xff << (8 - bits % 8);
+        }
+        TruncatedDigest { bytes, bits }
+    }
+}
+
+/**
+ * The first bits of a Digest. Derefs to whole bytes, with any bits past the end of the
+ * truncation zeroed, and displays as lowercase hex with as many digits as the bits need, so
+ * a 12-bit truncation gives three.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TruncatedDigest {
+    bytes: [u8; DIGEST_MAX],
+    bits: usize
+}
+
+impl TruncatedDigest {
+    pub fn bits (&self) -> usize {
+        self.bits
+    }
+
+    pub fn as_bytes (&self) -> &[u8] {
+        &self.bytes[..self.bits.div_ceil(8)]
+    }
+}
+
+impl core::ops::Deref for TruncatedDigest {
+    type Target = [u8];
+
+    fn deref (&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl core::fmt::Display for TruncatedDigest {
+    fn fmt (&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let digits = self.bits.div_ceil(4);
+        for (i, b) in self.as_bytes().iter().enumerate() {
+            if 2 * i + 1 < digits {
+                write!(f, "{:02x}", b)?;
+            } else {
+                write!(f, "{:x}", b >> 4)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl core::ops::Deref for Digest {
+    type Target = [u8];
+
+    fn deref (&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref (&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<Digest> for Vec<u8> {
+    fn from (digest: Digest) -> Vec<u8> {
+        digest.to_vec()
+    }
+}
+
+impl core::fmt::Display for Digest {
+    fn fmt (&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        core::fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl core::fmt::LowerHex for Digest {
+    fn fmt (&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        self.as_bytes().iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+impl core::fmt::UpperHex for Digest {
+    fn fmt (&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        self.as_bytes().iter().try_for_each(|b| write!(f, "{:02X}", b))
+    }
+}
+
+/**
+ * Takes the algorithm from the length: 16 bytes for MD5, 20 for SHA-1, 28 for SHA-224, 32 for
+ * SHA-256, 64 for SHA-512. SHA-3 digests can't be told from SHA-2's this way.
+ */
+impl TryFrom<&[u8]> for Digest {
+    type Error = ShaError;
+
+    // With only sha3 no length matches
+    #[allow(unreachable_code, unused_variables)]
+    fn try_from (bytes: &[u8]) -> Result<Digest, ShaError> {
+        let algo = match bytes.len() {
+            #[cfg(feature = "md5")]
+            16 => Algo::Md5,
+            #[cfg(feature = "sha1")]
+            20 => Algo::Sha1,
+            #[cfg(feature = "sha224")]
+            28 => Algo::Sha224,
+            #[cfg(feature = "sha256")]
+            32 => Algo::Sha256,
+            #[cfg(feature = "sha512")]
+            64 => Algo::Sha512,
+            n => return Err(ShaError::InvalidDigestLength(n))
+        };
+        let mut digest = Digest { algo, bytes: [0u8; DIGEST_MAX] };
+        digest.bytes[..bytes.len()].copy_from_slice(bytes);
+        Ok(digest)
+    }
+}
+
+/**
+ * Parses hex in either case, optionally prefixed with "0x" as `{:#x}` writes it, or with `std`,
+ * standard base64. The algorithm is taken from the length, as for TryFrom<&[u8]>.
+ */
+impl core::str::FromStr for Digest {
+    type Err = ShaError;
+
+    fn from_str (s: &str) -> Result<Digest, ShaError> {
+        let s = s.trim();
+        let digits = s.strip_prefix("0x").unwrap_or(s).as_bytes();
+
+        if !digits.is_empty() && digits.len().is_multiple_of(2) && digits.iter().all(|c| c.is_ascii_hexdigit()) {
+            if digits.len() > 2 * DIGEST_MAX {
+                return Err(ShaError::InvalidDigestLength(digits.len() / 2));
+            }
+            let nibble = |c: u8| (c as char).to_digit(16).unwrap() as u8;
+            let mut bytes = [0u8; DIGEST_MAX];
+            for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+                *byte = (nibble(pair[0]) << 4) | nibble(pair[1]);
+            }
+            return Digest::try_from(&bytes[..digits.len() / 2]);
+        }
+
+        #[cfg(feature = "std")]
+        if let Ok(bytes) = base64::decode(s) {
+            return Digest::try_from(bytes.as_slice());
+        }
+
+        Err(ShaError::InvalidDigestEncoding)
+    }
+}
+
+/**
+ * Convenience function for passing strings; hashes the string's UTF-8 bytes, returning the
+ * hex-encoded digest.
+ */
+#[cfg(feature = "alloc")]
+pub fn
+hash_string (message: &str, algo: Algo) -> String {
+    hash(message.as_bytes(), algo).to_hex()
+}
+
+/**
+ * Hashes the given message with SHA-224, SHA-256 or SHA-512, SHA-3, or SHA-1 or MD5. The message
+ * is only read: whole blocks are compressed straight from it, and the final partial block is
+ * padded in a scratch buffer. MD5, SHA-1 and SHA-3 copy the message into a buffer instead.
+ */
+pub fn
+hash (message: &[u8], algo: Algo) -> Digest {
+    #[cfg(feature = "md5")]
+    if algo == Algo::Md5 {
+        return Digest::from_md5(md5::md5(message));
+    }
+    #[cfg(feature = "sha1")]
+    if algo == Algo::Sha1 {
+        return Digest::from_sha1(sha1::sha1(message));
+    }
+    #[cfg(feature = "sha3")]
+    if algo.is_sha3() {
+        return Digest::from_sha3(algo, &sha3::sha3(message, algo.bits()));
+    }
+    #[cfg(feature = "sha512")]
+    if algo == Algo::Sha512 {
+        return hash_from(Engine::<u64>::new(algo), message);
+    }
+    hash_from(State::new(algo), message)
+}
+
+/**
+ * Hashes many independent messages, such as the chunks of a deduplicating store, in order. The
+ * initial state is built once and copied for each message, every message goes through the same
+ * allocation-free path as hash(), and the returned Vec, allocated up front, is the only
+ * allocation. Blocks of different messages aren't interleaved for SIMD.
+ */
+#[cfg(feature = "alloc")]
+pub fn
+hash_batch (inputs: &[&[u8]], algo: Algo) -> Vec<Digest> {
+    #[cfg(feature = "sha512")]
+    if algo == Algo::Sha512 {
+        let initial = Engine::<u64>::new(algo);
+        return inputs.iter().map(|message| hash_from(initial.clone(), message)).collect();
+    }
+    #[cfg(feature = "md5")]
+    if algo == Algo::Md5 {
+        return inputs.iter().map(|message| hash(message, algo)).collect();
+    }
+    #[cfg(feature = "sha1")]
+    if algo == Algo::Sha1 {
+        return inputs.iter().map(|message| hash(message, algo)).collect();
+    }
+    #[cfg(feature = "sha3")]
+    if algo.is_sha3() {
+        return inputs.iter().map(|message| hash(message, algo)).collect();
+    }
+
+    let initial = State::new(algo);
+    let mut digests = Vec::with_capacity(inputs.len());
+    for message in inputs {
+        digests.push(hash_from(initial.clone(), message));
+    }
+    digests
+}
+
+fn
+hash_from<W: Word> (mut state: Engine<W>, message: &[u8]) -> Digest {
+    /*
+    * From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 5.2
+    * 
+    * For SHA-1, SHA-224 and SHA-256, the message and its padding are parsed into N 512-bit blocks, M(1), M(2),..., M(N). 
+    * Since the 512 bits of the input block may be expressed as sixteen 32-bit words, the first 32 bits of message 
+    * block i are denoted M0(i), the next 32 bits are M1(i), and so on up to M(i).
+    * 
+    * For SHA-384, SHA-512, SHA-512/224 and SHA-512/256, the message and its padding are parsed into N 1024-bit blocks, 
+    * M(1), M(2),..., M(N). Since the 1024 bits of the input block may be expressed as sixteen 64-bit words, the first 
+    * 64 bits of message block i are denoted M0(i), the next 64 bits are M(i), and so on up to M(i).
+    */
+    let mut blocks = message.chunks_exact(Engine::<W>::block_len());
+    for block in &mut blocks {
+        state.compress(block);
+    }
+
+    compress_final(&mut state, blocks.remainder(), W::Length::default().add_bits((message.len() as u64).wrapping_mul(8)));
+    Digest::from_state(&state)
+}
+
+/**
+ * Pads and compresses the final partial block, `tail`, of a message `bit_len` bits long, in a
+ * scratch buffer on the stack. When `bit_len` isn't a whole number of bytes, the last byte of
+ * `tail` holds the remaining bits in its high-order end, and the padding's 1 bit follows them in
+ * the same byte.
+ */
+fn
+compress_final<W: Word> (state: &mut Engine<W>, tail: &[u8], bit_len: W::Length) {
+    // The padding spills into a second block when there's no room left for it and the length
+    let mut scratch = [0u8; 256];
+    scratch[..tail.len()].copy_from_slice(tail);
+    let block_len = Engine::<W>::block_len();
+
+    let partial = (bit_len.low_u64() % 8) as u32;
+    let used = if partial == 0 {
+        scratch[tail.len()] = 0x80;
+        tail.len() + 1
+    } else {
+        let last = tail.len() - 1;
+        scratch[last] = (scratch[last] & (0xff << (8 - partial))) | (0x80 >> partial);
+        tail.len()
+    };
+
+    let length_len = W::Length::BYTES;
+    let end = if used <= block_len - length_len { block_len } else { 2 * block_len };
+    bit_len.write_be_bytes(&mut scratch[end - length_len..end]);
+
+    for block in scratch[..end].chunks(block_len) {
+        state.compress(block);
+    }
+
+    #[cfg(feature = "zeroize")]
+    wipe::wipe(&mut scratch);
+}
+
+/**
+ * Hashes the given message with any Algo, returning the raw digest bytes.
+ */
+#[cfg(feature = "alloc")]
+pub fn
+digest (message: &[u8], algo: Algo) -> Vec<u8> {
+    hash(message, algo).to_vec()
+}
+
+/**
+ * Where a salt is placed relative to the message before hashing.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SaltPosition {
+    Prefix,
+    Suffix
+}
+
+/**
+ * Combines the given salt with the message ahead of hashing. With SaltPosition::Prefix the digest
+ * is computed over salt || message; with SaltPosition::Suffix it is computed over message || salt.
+ */
+#[cfg(feature = "alloc")]
+pub fn
+apply_salt (message: &mut Vec<u8>, salt: &[u8], position: SaltPosition) {
+    match position {
+        SaltPosition::Prefix => {
+            message.splice(0..0, salt.iter().copied());
+        },
+        SaltPosition::Suffix => {
+            message.extend_from_slice(salt);
+        }
+    }
+}
+
+/**
+ * The chaining value of a partially computed hash: the eight state words after a whole number of
+ * 64-byte blocks, with no buffered input. Handing it over with the number of bytes it covers lets
+ * another process finish the hash, as Bitcoin miners do with the first block of a header and
+ * resumable uploads do across requests.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Midstate(pub [u32; 8]);
+
+impl Midstate {
+    /**
+     * The words in big-endian order, the usual wire form.
+     */
+    pub fn to_bytes (&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(self.0) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes (bytes: &[u8; 32]) -> Midstate {
+        let mut words = [0u32; 8];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+            *word = u32::from_be_bytes(chunk.try_into().unwrap());
+        }
+        Midstate(words)
+    }
+}
+
+/**
+ * Incremental SHA-224/SHA-256/SHA-512 hasher. Input is buffered until a full 512-bit (1024-bit
+ * for SHA-512) block is available, at which point it is compressed into the running state; the
+ * remaining partial block is padded and compressed by finalize().
+ *
+ * The buffer is a fixed array and the padding is done in a scratch block on the stack, so
+ * update() and finalize_digest() never allocate. Without the sha512 feature the hasher fits in
+ * about 110 bytes of RAM on a microcontroller; SHA-512's larger block roughly doubles that.
+ * MD5, SHA-1 and SHA-3 are the exceptions: they run on md5::Md5, sha1::Sha1 and sha3::Sha3, which
+ * buffer in a Vec.
+ *
+ * Cloning is cheap (a few words and at most one block), so a hasher can be forked after a common
+ * prefix and each clone finished with a different suffix, without hashing the prefix again.
+ *
+ * update() never blocks or does IO, so async code can feed it from any runtime's reader between
+ * awaits. With tokio, for example:
+ *
+ * ```ignore
+ * let mut hasher = sha_2::Hasher::new(sha_2::Algo::Sha256);
+ * let mut buffer = vec![0u8; 1 << 16];
+ * loop {
+ *     let read = body.read(&mut buffer).await?;
+ *     if read == 0 {
+ *         break;
+ *     }
+ *     hasher.update(&buffer[..read]);
+ * }
+ * let digest = hasher.finalize();
+ * ```
+ */
+#[derive(Clone)]
+pub struct Hasher {
+    pub(crate) inner: Inner
+}
+
+/**
+ * A Hasher's state for one word size: SHA-224 and SHA-256 on 32-bit words in 64-byte blocks, or
+ * SHA-512 on 64-bit words in 128-byte blocks. Only the first is compiled in without the sha512
+ * feature, so a no_std SHA-256 build doesn't carry the larger buffer. MD5, SHA-1 and SHA-3 keep
+ * their own state, SHA-3's boxed so that its 200-byte sponge doesn't grow every Hasher.
+ */
+#[derive(Clone)]
+pub(crate) enum Inner {
+    Narrow(Stream<u32, 64>),
+    #[cfg(feature = "sha512")]
+    Wide(Stream<u64, 128>),
+    #[cfg(feature = "md5")]
+    Md5(md5::Md5),
+    #[cfg(feature = "sha1")]
+    Sha1(sha1::Sha1),
+    #[cfg(feature = "sha3")]
+    Sha3(alloc::boxed::Box<sha3::Sha3>)
+}
+
+/**
+ * The engine, the current partial block of B bytes, and the message length.
+ */
+#[derive(Clone)]
+pub(crate) struct Stream<W: Word, const B: usize> {
+    pub(crate) state: Engine<W>,
+    // The first bit_len / 8 % B bytes are the current partial block, and after update_bits()
+    // with a partial byte the next byte holds its bit_len % 8 bits
+    pub(crate) buffer: [u8; B],
+    // Message bits consumed so far, the padding's length field
+    pub(crate) bit_len: W::Length
+}
+
+impl<W: Word, const B: usize> Stream<W, B> {
+    pub(crate) fn new (state: Engine<W>) -> Stream<W, B> {
+        Stream { state, buffer: [0u8; B], bit_len: W::Length::default() }
+    }
+
+    /**
+     * The bytes of the current partial block, waiting for the rest of it.
+     */
+    pub(crate) fn buffered (&self) -> &[u8] {
+        &self.buffer[..(self.bit_len.low_u64() / 8 % B as u64) as usize]
+    }
+
+    fn partial_bits (&self) -> u64 {
+        self.bit_len.low_u64() % 8
+    }
+
+    fn update (&mut self, data: &[u8]) {
+        assert!(self.partial_bits() == 0, "no more input can follow a partial byte from update_bits");
+        let mut data = data;
+        let buffered = self.buffered().len();
+        self.bit_len = self.bit_len.add_bits((data.len() as u64).wrapping_mul(8));
+
+        // Top up a previously buffered partial block first
+        if buffered > 0 {
+            let take = core::cmp::min(B - buffered, data.len());
+            self.buffer[buffered..buffered + take].copy_from_slice(&data[..take]);
+            data = &data[take..];
+
+            if buffered + take < B {
+                return;
+            }
+
+            self.state.compress(&self.buffer);
+        }
+
+        let mut blocks = data.chunks_exact(B);
+        for block in &mut blocks {
+            self.state.compress(block);
+        }
+
+        let tail = blocks.remainder();
+        self.buffer[..tail.len()].copy_from_slice(tail);
+    }
+
+    fn update_bits (&mut self, data: &[u8], bit_len: u64) {
+        assert!(bit_len <= data.len() as u64 * 8, "{} bits is more than the {} bytes given", bit_len, data.len());
+        let whole = (bit_len / 8) as usize;
+        self.update(&data[..whole]);
+
+        let partial = bit_len % 8;
+        if partial > 0 {
+            self.buffer[self.buffered().len()] = data[whole] & (0xff << (8 - partial));
+            self.bit_len = self.bit_len.add_bits(partial);
+        }
+    }
+
+    fn finalize_digest (mut self) -> Digest {
+        let tail = self.buffered().len() + (self.partial_bits() > 0) as usize;
+        compress_final(&mut self.state, &self.buffer[..tail], self.bit_len);
+        Digest::from_state(&self.state)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<W: Word, const B: usize> Drop for Stream<W, B> {
+    fn drop (&mut self) {
+        wipe::wipe(&mut self.buffer);
+    }
+}
+
+impl Hasher {
+    pub fn new (algo: Algo) -> Hasher {
+        #[cfg(feature = "sha512")]
+        if algo == Algo::Sha512 {
+            return Hasher { inner: Inner::Wide(Stream::new(Engine::<u64>::new(algo))) };
+        }
+        #[cfg(feature = "md5")]
+        if algo == Algo::Md5 {
+            return Hasher { inner: Inner::Md5(md5::Md5::new()) };
+        }
+        #[cfg(feature = "sha1")]
+        if algo == Algo::Sha1 {
+            return Hasher { inner: Inner::Sha1(sha1::Sha1::new()) };
+        }
+        #[cfg(feature = "sha3")]
+        if algo.is_sha3() {
+            return Hasher { inner: Inner::Sha3(alloc::boxed::Box::new(sha3::Sha3::new(algo.bits()))) };
+        }
+        Hasher { inner: Inner::Narrow(Stream::new(State::new(algo))) }
+    }
+
+    /**
+     * The SHA-224/SHA-256 state, for the features only defined on it. Panics for SHA-512, SHA-3,
+     * SHA-1 and MD5.
+     */
+    pub(crate) fn narrow (&self) -> &Stream<u32, 64> {
+        match &self.inner {
+            Inner::Narrow(stream) => stream,
+            #[cfg(feature = "sha512")]
+            Inner::Wide(_) => panic!("only defined for SHA-224 and SHA-256"),
+            #[cfg(feature = "md5")]
+            Inner::Md5(_) => panic!("only defined for SHA-224 and SHA-256"),
+            #[cfg(feature = "sha1")]
+            Inner::Sha1(_) => panic!("only defined for SHA-224 and SHA-256"),
+            #[cfg(feature = "sha3")]
+            Inner::Sha3(_) => panic!("only defined for SHA-224 and SHA-256")
+        }
+    }
+
+    pub(crate) fn narrow_mut (&mut self) -> &mut Stream<u32, 64> {
+        match &mut self.inner {
+            Inner::Narrow(stream) => stream,
+            #[cfg(feature = "sha512")]
+            Inner::Wide(_) => panic!("only defined for SHA-224 and SHA-256"),
+            #[cfg(feature = "md5")]
+            Inner::Md5(_) => panic!("only defined for SHA-224 and SHA-256"),
+            #[cfg(feature = "sha1")]
+            Inner::Sha1(_) => panic!("only defined for SHA-224 and SHA-256"),
+            #[cfg(feature = "sha3")]
+            Inner::Sha3(_) => panic!("only defined for SHA-224 and SHA-256")
+        }
+    }
+
+    pub fn update (&mut self, data: &[u8]) {
+        match &mut self.inner {
+            Inner::Narrow(stream) => stream.update(data),
+            #[cfg(feature = "sha512")]
+            Inner::Wide(stream) => stream.update(data),
+            #[cfg(feature = "md5")]
+            Inner::Md5(md5) => md5.update(data),
+            #[cfg(feature = "sha1")]
+            Inner::Sha1(sha1) => sha1.update(data),
+            #[cfg(feature = "sha3")]
+            Inner::Sha3(sha3) => sha3.update(data)
+        }
+    }
+
+    /**
+     * Appends the first `bit_len` bits of `data`, most significant bit of each byte first, for
+     * messages which aren't a whole number of bytes, as FIPS 180-4 allows and the CAVP
+     * bit-oriented test vectors use. If `bit_len` isn't a multiple of 8 the message must end
+     * there: any further update panics. Panics if `data` is shorter than `bit_len` bits, and for
+     * MD5, SHA-1 and SHA-3 unless `bit_len` is a whole number of bytes.
+     */
+    pub fn update_bits (&mut self, data: &[u8], bit_len: u64) {
+        match &mut self.inner {
+            Inner::Narrow(stream) => stream.update_bits(data, bit_len),
+            #[cfg(feature = "sha512")]
+            Inner::Wide(stream) => stream.update_bits(data, bit_len),
+            #[cfg(feature = "md5")]
+            Inner::Md5(md5) => {
+                assert!(bit_len.is_multiple_of(8), "MD5 only takes whole bytes");
+                md5.update(&data[..(bit_len / 8) as usize]);
+            },
+            #[cfg(feature = "sha1")]
+            Inner::Sha1(sha1) => {
+                assert!(bit_len.is_multiple_of(8), "SHA-1 only takes whole bytes");
+                sha1.update(&data[..(bit_len / 8) as usize]);
+            },
+            #[cfg(feature = "sha3")]
+            Inner::Sha3(sha3) => {
+                assert!(bit_len.is_multiple_of(8), "SHA-3 only takes whole bytes here");
+                sha3.update(&data[..(bit_len / 8) as usize]);
+            }
+        }
+    }
+
+    /**
+     * Feeds everything from the given reader into the hasher, returning the number of bytes read.
+     */
+    #[cfg(feature = "std")]
+    pub fn update_reader<R: Read> (&mut self, mut reader: R) -> io::Result<u64> {
+        io::copy(&mut reader, self)
+    }
+
+    /**
+     * Resumes a hash from a midstate covering the first `bytes_processed` bytes of the message,
+     * which must be a multiple of the 64-byte block size. Panics for SHA-512, which has no
+     * Midstate.
+     */
+    pub fn from_midstate (algo: Algo, midstate: Midstate, bytes_processed: u64) -> Hasher {
+        assert!(bytes_processed.is_multiple_of(64), "a midstate covers a whole number of 64-byte blocks");
+
+        let mut hasher = Hasher::new(algo);
+        let stream = hasher.narrow_mut();
+        stream.state = Engine::from_words(midstate.0, algo.bits());
+        stream.bit_len = bytes_processed.wrapping_mul(8);
+        hasher
+    }
+
+    /**
+     * The midstate after the input so far. Panics unless the input so far is a whole number of
+     * blocks, since buffered bytes aren't part of the chaining value; see bytes_processed().
+     * Panics for SHA-512.
+     */
+    pub fn midstate (&self) -> Midstate {
+        let stream = self.narrow();
+        assert!(stream.buffered().is_empty() && stream.partial_bits() == 0, "midstate is only defined on a 64-byte block boundary");
+        Midstate(stream.state.words())
+    }
+
+    /**
+     * Whole bytes only; see bits_processed() after update_bits(). Like the bit count, this wraps
+     * past 2^61 bytes, beyond which SHA-256 isn't defined.
+     */
+    pub fn bytes_processed (&self) -> u64 {
+        match &self.inner {
+            Inner::Narrow(stream) => stream.bit_len.bytes(),
+            #[cfg(feature = "sha512")]
+            Inner::Wide(stream) => stream.bit_len.bytes(),
+            #[cfg(feature = "md5")]
+            Inner::Md5(md5) => md5.bit_len / 8,
+            #[cfg(feature = "sha1")]
+            Inner::Sha1(sha1) => sha1.bit_len / 8,
+            #[cfg(feature = "sha3")]
+            Inner::Sha3(sha3) => sha3.bit_len / 8
+        }
+    }
+
+    /**
+     * The message length so far in bits, modulo 2^64: for MD5, SHA-1, SHA-224 and SHA-256, the
+     * value the padding will append.
+     */
+    pub fn bits_processed (&self) -> u64 {
+        match &self.inner {
+            Inner::Narrow(stream) => stream.bit_len,
+            #[cfg(feature = "sha512")]
+            Inner::Wide(stream) => stream.bit_len as u64,
+            #[cfg(feature = "md5")]
+            Inner::Md5(md5) => md5.bit_len,
+            #[cfg(feature = "sha1")]
+            Inner::Sha1(sha1) => sha1.bit_len,
+            #[cfg(feature = "sha3")]
+            Inner::Sha3(sha3) => sha3.bit_len
+        }
+    }
+
+    /**
+     * Panics after update_bits() with a partial byte, which HasherState has no room for, and for
+     * SHA-512, SHA-1 and MD5, whose state words don't fit it.
+     */
+    #[cfg(feature = "alloc")]
+    pub fn save_state (&self) -> HasherState {
+        let stream = self.narrow();
+        assert!(stream.partial_bits() == 0, "can't save the state of a hasher holding a partial byte");
+        HasherState {
+            algorithm: stream.state.digest_bits(),
+            h: stream.state.words(),
+            buffer: stream.buffered().to_vec(),
+            len: self.bytes_processed()
+        }
+    }
+
+    /**
+     * Restores a hasher from a saved state, checking that the state is one a hasher could have
+     * been in: a supported algorithm, less than a block buffered, and a byte count that agrees.
+     */
+    #[cfg(feature = "alloc")]
+    pub fn restore_state (saved: &HasherState) -> Result<Hasher, ShaError> {
+        let algo = Algo::try_from_bits(saved.algorithm)?;
+        if !algo.has_midstate() {
+            return Err(ShaError::InvalidState(format!("{} states can't be saved", algo)));
+        }
+        let mut hasher = Hasher::new(algo);
+        if saved.buffer.len() >= 64 || saved.len % 64 != saved.buffer.len() as u64 {
+            return Err(ShaError::InvalidState("buffered bytes don't match the length".to_string()));
+        }
+
+        let stream = hasher.narrow_mut();
+        stream.state = Engine::from_words(saved.h, saved.algorithm);
+        stream.buffer[..saved.buffer.len()].copy_from_slice(&saved.buffer);
+        stream.bit_len = saved.len.wrapping_mul(8);
+        Ok(hasher)
+    }
+
+    pub fn algorithm (&self) -> Algo {
+        match &self.inner {
+            Inner::Narrow(stream) => Algo::from_bits(stream.state.digest_bits()),
+            #[cfg(feature = "sha512")]
+            Inner::Wide(stream) => Algo::from_bits(stream.state.digest_bits()),
+            #[cfg(feature = "md5")]
+            Inner::Md5(_) => Algo::Md5,
+            #[cfg(feature = "sha1")]
+            Inner::Sha1(_) => Algo::Sha1,
+            #[cfg(feature = "sha3")]
+            Inner::Sha3(sha3) => Algo::sha3(sha3.bits())
+        }
+    }
+
+    /**
+     * Returns the hasher to its initial state, ready for a new message.
+     */
+    pub fn reset (&mut self) {
+        *self = Hasher::new(self.algorithm());
+    }
+
+    /**
+     * Finishes the current message and resets the hasher, so that it can be reused for the next.
+     */
+    #[cfg(feature = "alloc")]
+    pub fn finalize_reset (&mut self) -> Vec<u8> {
+        self.finalize_digest_reset().to_vec()
+    }
+
+    /**
+     * finalize_reset(), returning the digest inline instead of in a Vec.
+     */
+    pub fn finalize_digest_reset (&mut self) -> Digest {
+        let algo = self.algorithm();
+        core::mem::replace(self, Hasher::new(algo)).finalize_digest()
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn finalize (self) -> Vec<u8> {
+        self.finalize_digest().to_vec()
+    }
+
+    /**
+     * finalize(), returning the digest inline instead of in a Vec, so that nothing is allocated.
+     */
+    pub fn finalize_digest (self) -> Digest {
+        match self.inner {
+            Inner::Narrow(stream) => stream.finalize_digest(),
+            #[cfg(feature = "sha512")]
+            Inner::Wide(stream) => stream.finalize_digest(),
+            #[cfg(feature = "md5")]
+            Inner::Md5(md5) => Digest::from_md5(md5.finalize()),
+            #[cfg(feature = "sha1")]
+            Inner::Sha1(sha1) => Digest::from_sha1(sha1.finalize()),
+            #[cfg(feature = "sha3")]
+            Inner::Sha3(sha3) => Digest::from_sha3(Algo::sha3(sha3.bits()), &(*sha3).finalize())
+        }
+    }
+}
+
+/**
+ * Everything a Hasher holds, as plain data, so a long-running job can checkpoint a hash to disk
+ * and pick it up again after a restart: the digest length, the eight H values, the buffered
+ * partial block and the byte count. to_json() and from_json() write and read it as a JSON object
+ * with those four fields.
+ */
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HasherState {
+    pub algorithm: usize,
+    pub h: [u32; 8],
+    pub buffer: Vec<u8>,
+    pub len: u64
+}
+
+#[cfg(feature = "alloc")]
+impl HasherState {
+    pub fn to_json (&self) -> String {
+        let list = |items: Vec<String>| items.join(",");
+        format!(
+            "{{\"algorithm\":{},\"h\":[{}],\"buffer\":[{}],\"len\":{}}}",
+            self.algorithm,
+            list(self.h.iter().map(|w| w.to_string()).collect()),
+            list(self.buffer.iter().map(|b| b.to_string()).collect()),
+            self.len
+        )
+    }
+
+    pub fn from_json (text: &str) -> Result<HasherState, String> {
+        let doc = json::parse(text)?;
+        let number = |v: &json::Value, max: u64| v.as_u64().filter(|n| *n <= max);
+        let field = |name: &str| doc.get(name).ok_or_else(|| format!("missing \"{}\"", name));
+        let numbers = |name: &str, max: u64| -> Result<Vec<u64>, String> {
+            field(name)?.as_array().ok_or_else(|| format!("\"{}\" is not an array", name))?
+                .iter()
+                .map(|v| number(v, max).ok_or_else(|| format!("invalid value in \"{}\"", name)))
+                .collect()
+        };
+
+        let h = numbers("h", u32::MAX as u64)?;
+        Ok(HasherState {
+            algorithm: number(field("algorithm")?, u64::MAX).ok_or("invalid \"algorithm\"")? as usize,
+            h: h.iter().map(|w| *w as u32).collect::<Vec<u32>>().try_into().map_err(|_| "\"h\" must have 8 values")?,
+            buffer: numbers("buffer", u8::MAX as u64)?.iter().map(|b| *b as u8).collect(),
+            len: number(field("len")?, u64::MAX).ok_or("invalid \"len\"")?
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl Write for Hasher {
+    fn write (&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush (&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/**
+ * Incremental SHA-256 with a fixed-size digest; a typed front end to Hasher for callers that
+ * always want SHA-256. The method names follow the RustCrypto `digest::Digest` trait (new,
+ * update, finalize, finalize_reset, reset, output_size, digest), so code written against the
+ * `sha2` crate's inherent API ports over by changing the import. It doesn't implement the
+ * `digest` traits, so generic code built on them can't take it; hmac and kdf cover those uses.
+ */
+#[cfg(feature = "sha256")]
+#[derive(Clone)]
+pub struct Sha256 {
+    hasher: Hasher,
+    // Where reset() starts again from
+    iv: [u32; 8]
+}
+
+#[cfg(feature = "sha256")]
+impl Sha256 {
+    pub fn new () -> Sha256 {
+        Sha256 { hasher: Hasher::new(Algo::Sha256), iv: SHA_256_H_INIT }
+    }
+
+    /**
+     * A research variant of SHA-256, starting from the given eight words instead of the
+     * standard initial hash value and running `rounds` of the 64 rounds per block, e.g. for
+     * following differential trails through round-reduced SHA-256. Padding and output are as
+     * for SHA-256, and with_params(SHA-256's IV, 64) is SHA-256 itself. reset() returns to the
+     * given IV, but midstate() and from_midstate() don't carry the round count. Panics if
+     * `rounds` is more than 64.
+     */
+    pub fn with_params (iv: [u32; 8], rounds: usize) -> Sha256 {
+        let mut hasher = Hasher::new(Algo::Sha256);
+        hasher.narrow_mut().state = Engine::from_words(iv, 256).with_rounds(rounds);
+        Sha256 { hasher, iv }
+    }
+
+    /**
+     * A fresh hasher with the same IV and round count.
+     */
+    fn restart (&self) -> Hasher {
+        let mut hasher = Hasher::new(Algo::Sha256);
+        hasher.narrow_mut().state = Engine::from_words(self.iv, 256).with_rounds(self.hasher.narrow().state.rounds());
+        hasher
+    }
+
+    pub fn update (&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    /**
+     * See Hasher::update_bits.
+     */
+    pub fn update_bits (&mut self, data: &[u8], bit_len: u64) {
+        self.hasher.update_bits(data, bit_len);
+    }
+
+    pub fn finalize (self) -> [u8; 32] {
+        self.hasher.finalize_digest().as_bytes().try_into().unwrap()
+    }
+
+    pub fn finalize_reset (&mut self) -> [u8; 32] {
+        let fresh = self.restart();
+        core::mem::replace(&mut self.hasher, fresh).finalize_digest().as_bytes().try_into().unwrap()
+    }
+
+    pub fn reset (&mut self) {
+        self.hasher = self.restart();
+    }
+
+    /**
+     * See Hasher::from_midstate.
+     */
+    pub fn from_midstate (midstate: Midstate, bytes_processed: u64) -> Sha256 {
+        Sha256 { hasher: Hasher::from_midstate(Algo::Sha256, midstate, bytes_processed), iv: SHA_256_H_INIT }
+    }
+
+    /**
+     * See Hasher::midstate.
+     */
+    pub fn midstate (&self) -> Midstate {
+        self.hasher.midstate()
+    }
+
+    pub fn bytes_processed (&self) -> u64 {
+        self.hasher.bytes_processed()
+    }
+
+    pub fn output_size () -> usize {
+        32
+    }
+
+    /**
+     * Hashes a complete message in one call.
+     */
+    pub fn digest (data: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+#[cfg(feature = "sha256")]
+impl Default for Sha256 {
+    fn default () -> Sha256 {
+        Sha256::new()
+    }
+}
+
+#[cfg(all(feature = "std", feature = "sha256"))]
+impl Write for Sha256 {
+    fn write (&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush (&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/**
+ * Incremental SHA-224 with a fixed-size digest.
+ */
+#[cfg(feature = "sha224")]
+#[derive(Clone)]
+pub struct Sha224 {
+    hasher: Hasher
+}
+
+#[cfg(feature = "sha224")]
+impl Sha224 {
+    pub fn new () -> Sha224 {
+        Sha224 { hasher: Hasher::new(Algo::Sha224) }
+    }
+
+    pub fn update (&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    /**
+     * See Hasher::update_bits.
+     */
+    pub fn update_bits (&mut self, data: &[u8], bit_len: u64) {
+        self.hasher.update_bits(data, bit_len);
+    }
+
+    pub fn finalize (self) -> [u8; 28] {
+        self.hasher.finalize_digest().as_bytes().try_into().unwrap()
+    }
+
+    pub fn finalize_reset (&mut self) -> [u8; 28] {
+        self.hasher.finalize_digest_reset().as_bytes().try_into().unwrap()
+    }
+
+    pub fn reset (&mut self) {
+        self.hasher.reset();
+    }
+
+    pub fn output_size () -> usize {
+        28
+    }
+
+    /**
+     * Hashes a complete message in one call.
+     */
+    pub fn digest (data: &[u8]) -> [u8; 28] {
+        let mut hasher = Sha224::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+#[cfg(feature = "sha224")]
+impl Default for Sha224 {
+    fn default () -> Sha224 {
+        Sha224::new()
+    }
+}
+
+#[cfg(all(feature = "std", feature = "sha224"))]
+impl Write for Sha224 {
+    fn write (&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush (&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/**
+ * SHA-256 behind the standard library's hashing traits, so a HashMap or HashSet can hash its
+ * keys with it: `HashMap::with_hasher(Sha256BuildHasher::random()?)`. Every value is absorbed
+ * after a 32-byte key, and finish() returns the first 8 bytes of the digest as a big-endian u64.
+ * Without a secret key anyone can search for inputs that land in the same buckets, so maps keyed
+ * on untrusted input want random(); the all-zero default key gives reproducible hashes.
+ */
+#[cfg(feature = "sha256")]
+#[derive(Clone)]
+pub struct Sha256Hasher {
+    hasher: Sha256
+}
+
+#[cfg(feature = "sha256")]
+impl Sha256Hasher {
+    pub fn new (key: &[u8; 32]) -> Sha256Hasher {
+        let mut hasher = Sha256::new();
+        hasher.update(key);
+        Sha256Hasher { hasher }
+    }
+
+    /**
+     * The full digest of the key and everything written so far.
+     */
+    pub fn digest (&self) -> [u8; 32] {
+        self.hasher.clone().finalize()
+    }
+}
+
+#[cfg(feature = "sha256")]
+impl core::hash::Hasher for Sha256Hasher {
+    fn write (&mut self, bytes: &[u8]) {
+        self.hasher.update(bytes);
+    }
+
+    fn finish (&self) -> u64 {
+        u64::from_be_bytes(self.digest()[..8].try_into().unwrap())
+    }
+}
+
+/**
+ * Builds a Sha256Hasher for each value hashed, all with the same key.
+ */
+#[cfg(feature = "sha256")]
+#[derive(Clone, Default)]
+pub struct Sha256BuildHasher {
+    key: [u8; 32]
+}
+
+#[cfg(feature = "sha256")]
+impl Sha256BuildHasher {
+    pub fn with_key (key: [u8; 32]) -> Sha256BuildHasher {
+        Sha256BuildHasher { key }
+    }
+
+    /**
+     * A key from the operating system's random number generator.
+     */
+    #[cfg(feature = "std")]
+    pub fn random () -> io::Result<Sha256BuildHasher> {
+        let key = random::random_bytes(32)?;
+        Ok(Sha256BuildHasher { key: key.try_into().unwrap() })
+    }
+}
+
+#[cfg(feature = "sha256")]
+impl core::hash::BuildHasher for Sha256BuildHasher {
+    type Hasher = Sha256Hasher;
+
+    fn build_hasher (&self) -> Sha256Hasher {
+        Sha256Hasher::new(&self.key)
+    }
+}
+
+// The hashers are plain data, so they can be cloned and sent or shared between threads. This stops
+// compiling if a field ever takes that away.
+const _: fn() = || {
+    fn clone_send_sync<T: Clone + Send + Sync> () {}
+    clone_send_sync::<Hasher>();
+    #[cfg(feature = "sha256")]
+    clone_send_sync::<Sha256>();
+    #[cfg(feature = "sha256")]
+    clone_send_sync::<Sha256BuildHasher>();
+    #[cfg(feature = "sha224")]
+    clone_send_sync::<Sha224>();
+    #[cfg(feature = "alloc")]
+    clone_send_sync::<hmac::Hmac>();
+    #[cfg(feature = "md5")]
+    clone_send_sync::<md5::Md5>();
+    #[cfg(feature = "sha1")]
+    clone_send_sync::<sha1::Sha1>();
+    #[cfg(feature = "sha3")]
+    clone_send_sync::<sha3::Sha3>();
+};
+
+// The embedded footprint promised above
+#[cfg(not(feature = "sha512"))]
+const _: () = assert!(core::mem::size_of::<Hasher>() <= 120);
@@ -0,0 +1,81 @@
+/*
+ * A content-addressed view of a directory tree: one link per distinct file content, named by its
+ * hex digest and fanned out into 256 subdirectories on the first byte, as git does for loose
+ * objects, e.g. out/ba/ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.
+ */
+
+use std::{fs, io, path::{Path, PathBuf}};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LinkKind {
+    Symlink,
+    Hardlink
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Outcome {
+    Linked,
+    // A previous run already linked this very file
+    Present,
+    // Another file with the same content is already linked
+    Duplicate
+}
+
+pub fn
+object_path (root: &Path, digest_hex: &str) -> PathBuf {
+    root.join(&digest_hex[..2]).join(digest_hex)
+}
+
+/**
+ * Links `source` into the farm under `root`. Symbolic links point at the canonical path of the
+ * source, so the farm can live anywhere; hard links need `root` on the same filesystem.
+ */
+pub fn
+link (kind: LinkKind, root: &Path, digest_hex: &str, source: &Path) -> io::Result<Outcome> {
+    let dest = object_path(root, digest_hex);
+    if fs::symlink_metadata(&dest).is_ok() {
+        if fs::metadata(&dest).is_ok() {
+            return Ok(if same_file(&dest, source)? { Outcome::Present } else { Outcome::Duplicate });
+        }
+        // A dangling link from an earlier run, whose file has since moved or been deleted
+        fs::remove_file(&dest)?;
+    }
+
+    fs::create_dir_all(dest.parent().unwrap())?;
+    match kind {
+        LinkKind::Symlink => symlink(&fs::canonicalize(source)?, &dest)?,
+        LinkKind::Hardlink => fs::hard_link(source, &dest)?
+    }
+
+    Ok(Outcome::Linked)
+}
+
+#[cfg(unix)]
+fn
+symlink (target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn
+symlink (target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+/**
+ * Whether two paths, following symbolic links, are the same file.
+ */
+#[cfg(unix)]
+fn
+same_file (a: &Path, b: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let (a, b) = (fs::metadata(a)?, fs::metadata(b)?);
+    Ok(a.dev() == b.dev() && a.ino() == b.ino())
+}
+
+#[cfg(windows)]
+fn
+same_file (a: &Path, b: &Path) -> io::Result<bool> {
+    Ok(fs::canonicalize(a)? == fs::canonicalize(b)?)
+}
@@ -0,0 +1,129 @@
+/*
+ * A small structured logger for diagnostics. Events go to stderr, either as human-readable lines
+ * or as one JSON object per line, and are filtered by a global level set once at startup.
+ *
+ * It takes the place of the tracing and tracing-subscriber crates, which the binary doesn't
+ * depend on. Events carry a level, a message and key-value fields as tracing's do, but there are
+ * no spans, no per-module filtering and no subscriber to swap in; --log-json writes each event
+ * as a flat object rather than in tracing-subscriber's JSON layout.
+ */
+
+use std::{
+    io::Write,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+    time::{SystemTime, UNIX_EPOCH}
+};
+
+use sha_2::report::json_string;
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub enum Level {
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5
+}
+
+impl Level {
+    pub fn from_name (name: &str) -> Option<Level> {
+        match name.to_ascii_lowercase().as_str() {
+            "error" => Some(Level::Error),
+            "warn" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None
+        }
+    }
+
+    pub fn name (&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace"
+        }
+    }
+}
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Warn as u8);
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/**
+ * Sets the most verbose level which will be emitted, and whether events are written as JSON.
+ */
+pub fn
+init (level: Level, json: bool) {
+    MAX_LEVEL.store(level as u8, Ordering::Relaxed);
+    JSON.store(json, Ordering::Relaxed);
+}
+
+pub fn
+enabled (level: Level) -> bool {
+    level as u8 <= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+/**
+ * Renders an event as a single line of text or JSON. Field values are written as strings.
+ */
+pub fn
+render (level: Level, message: &str, fields: &[(&str, String)], json: bool, timestamp: f64) -> String {
+    if json {
+        let mut line = format!(
+            "{{\"ts\":{:.3},\"level\":\"{}\",\"message\":{}",
+            timestamp,
+            level.name(),
+            json_string(message)
+        );
+
+        for (key, value) in fields {
+            line.push_str(&format!(",{}:{}", json_string(key), json_string(value)));
+        }
+
+        line.push('}');
+        line
+    } else {
+        let mut line = format!("{}: {}", level.name(), message);
+
+        for (key, value) in fields {
+            line.push_str(&format!(" {}={}", key, value));
+        }
+
+        line
+    }
+}
+
+/**
+ * Emits an event with optional key/value fields, if its level is enabled.
+ */
+pub fn
+event (level: Level, message: &str, fields: &[(&str, String)]) {
+    if !enabled(level) {
+        return;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+
+    let line = render(level, message, fields, JSON.load(Ordering::Relaxed), timestamp);
+    let _ = writeln!(std::io::stderr(), "{}", line);
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => { $crate::logging::event($crate::logging::Level::Error, &format!($($arg)*), &[]) };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => { $crate::logging::event($crate::logging::Level::Warn, &format!($($arg)*), &[]) };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)*) => { $crate::logging::event($crate::logging::Level::Info, &format!($($arg)*), &[]) };
+}
+
+pub(crate) use {log_error, log_info, log_warn};
+
@@ -1,265 +1,3641 @@
-use clap::{arg, Command};
-use std::{fs, io::Read};
-
-const MAX_LEN:usize = 18446744073709551615;
-
-// From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 5.3.2
-const SHA_224_H_INIT: [u32; 8] = [
-    0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7, 0xbefa4fa4
-];
-
-// From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 5.3.3
-const SHA_256_H_INIT: [u32; 8] = [
-    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19
-];
-
-/*
- * From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 4.2.2 
- *
- * SHA-224 and SHA-256 use the same sequence of sixty-four constant 32-bit words,
- * K{256}_0, K{256}_1, ..., K{256}_63. These words represent the first thirty-two bits of the 
- * fractional parts of the cube roots of the first sixty-four prime numbers.
- */
-const K: [u32; 64] = [
-   0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
-   0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
-   0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
-   0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
-   0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
-   0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
-   0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
-   0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2
-];
-
-/*
- * Four 32-bit integer maintaining the state of the digest during hashing.
- */
-struct State {
-    a: u32,
-    b: u32,
-    c: u32,
-    d: u32,
-    e: u32,
-    f: u32,
-    g: u32,
-    h: u32,
-    n: usize
-}
-
-impl State {
-    fn new (n: usize) -> State {       
-
-        // Select the appropriate initialization values based on algorithm 
-        let init: &[u32; 8] = match n {
-            224 => &SHA_224_H_INIT,
-            256 => &SHA_256_H_INIT,
-            _ => panic!("unsupported hash length"),
+use clap::{arg, ArgMatches, Command};
+use std::{fs, hash::{BuildHasher, Hasher as _}, io, io::BufRead, io::Read, io::Write, path::PathBuf};
+
+mod clipboard;
+mod interrupt;
+mod logging;
+
+use sha_2::{
+    apply_salt, apt, authenticode, bitcoin, bloom, blocks, buildid, cargo, check, checkpoint, combine, compress256, csv, der, digest, encoding::{self, hex},
+    entropy, eol, ethereum, expand_schedule, firmware, format, gitignore, gitobject, hash, hash_batch, hash_string, hashdeep, hmac, htpasswd, imaging, iotune, json, kdf, keccak, knownset, ldap, linkfarm, manifest, md5, multi, oci, pad_message, partition, pgp,
+    pkcs1, random, report, seal, sha1, shard, ssri, stream, strip, treediff, tsp, tuple, unicode, uniq, Algo, Digest, Engine, Hasher, HasherState, Midstate, SaltPosition, ShaError, State,
+    Sha224, Sha256, Sha256BuildHasher, LAYOUT_1024, LAYOUT_512
+};
+use logging::{log_error, log_info, log_warn};
+
+fn 
+tests () {
+    assert!(hash_string("", Algo::Sha256).eq("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"));
+    assert!(hash_string("abcde", Algo::Sha256).eq("36bbe50ed96841d10443bcb670d6554f0a34b761be67ec9c4a8ad2c0c44ca42c"));
+    assert!(hash_string("abcdefghijklmnopqrstuvwxyz12345678901234567890", Algo::Sha256)
+      .eq("a8143361b55756a30c4c4369726748e4ae193ca1d31e1f21f47bc7171cd56e9a"));
+    assert!(hash_string("a8143361b55756a30c4c4369726748e4ae193ca1d31e1f21f47bc7171cd56e9a", Algo::Sha256)
+      .eq("fc3b517b3c9ede5c64058615d49ec4ac6eadda73d74f1eade0bdb5d70de93dfb"));
+
+    assert!(hash_string("", Algo::Sha224).eq("d14a028c2a3a2bc9476102bb288234c415a2b01f828ea62ac5b3e42f"));
+    assert!(hash_string("abcde", Algo::Sha224).eq("bdd03d560993e675516ba5a50638b6531ac2ac3d5847c61916cfced6"));
+    assert!(hash_string("abcdefghijklmnopqrstuvwxyz12345678901234567890", Algo::Sha224)
+        .eq("bbf04b42f9aa379d73e39955828523db73f5ddef6f8ca518684fb2b7"));
+    assert!(hash_string("bbf04b42f9aa379d73e39955828523db73f5ddef6f8ca518684fb2b7", Algo::Sha224)
+    .eq("e8cffc71ed2e47380e3ae16a92a6f5cfeb1f393a59f05d2cd05d72af"));
+
+    let mut salted = b"abcde".to_vec();
+    apply_salt(&mut salted, b"salt", SaltPosition::Prefix);
+    assert!(salted.eq(b"saltabcde"));
+    let mut salted = b"abcde".to_vec();
+    apply_salt(&mut salted, b"salt", SaltPosition::Suffix);
+    assert!(salted.eq(b"abcdesalt"));
+
+    assert!(hex::encode(hmac::hmac(b"Jefe", b"what do ya want for nothing?", Algo::Sha256))
+        .eq("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"));
+    assert!(hex::encode(hmac::hmac(b"Jefe", b"what do ya want for nothing?", Algo::Sha224))
+        .eq("a30e01098bc6dbbf45690f3a7e9e6d0f8bbea2a39e6148008fd05e44"));
+    assert!(hex::encode(hmac::hmac(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First", Algo::Sha256))
+        .eq("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"));
+
+    let mut hasher = Hasher::new(Algo::Sha256);
+    hasher.update(b"abcdefghijklmnopqrstuvwxyz");
+    hasher.update(b"");
+    hasher.write_all(b"12345678901234567890").unwrap();
+    assert!(hex::encode(hasher.finalize()).eq("a8143361b55756a30c4c4369726748e4ae193ca1d31e1f21f47bc7171cd56e9a"));
+
+    let long_message = [0x61u8; 1000];
+    let mut hasher = Hasher::new(Algo::Sha224);
+    for chunk in long_message.chunks(7) {
+        hasher.update(chunk);
+    }
+    assert!(hasher.finalize().eq(&digest(&long_message, Algo::Sha224)));
+
+    let mut hasher = Hasher::new(Algo::Sha256);
+    assert!(hasher.update_reader(&long_message[..]).unwrap() == 1000);
+    assert!(hasher.finalize().eq(&digest(&long_message, Algo::Sha256)));
+
+    let mut mac = hmac::Hmac::new(Algo::Sha256, b"Jefe");
+    mac.write_all(b"what do ya ").unwrap();
+    mac.update_reader(&b"want for nothing?"[..]).unwrap();
+    assert!(mac.verify(&hex::decode("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843").unwrap()));
+
+    let mut mac = hmac::Hmac::new(Algo::Sha256, b"Jefe");
+    mac.update(b"what do ya want for nothing!");
+    assert!(!mac.verify(&hex::decode("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843").unwrap()));
+
+    assert!(hex::encode(kdf::pbkdf2(b"password", b"salt", 1, 32, Algo::Sha256))
+        .eq("120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"));
+    assert!(hex::encode(kdf::pbkdf2(b"password", b"salt", 4096, 32, Algo::Sha256))
+        .eq("c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a"));
+    assert!(hex::encode(kdf::pbkdf2(b"passwordPASSWORDpassword", b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 2, 40, Algo::Sha224))
+        .eq("dc1bdb3944d2661875fb796932ae8b06895ebda94bc34b6e6c2d148fafabd58a3e6c7025de3def69"));
+
+    assert!(parse_duration("250ms") == Some(std::time::Duration::from_millis(250)));
+    assert!(parse_duration("1.5s") == Some(std::time::Duration::from_millis(1500)));
+    assert!(parse_duration("fast").is_none());
+
+    let abcde = digest(b"abcde", Algo::Sha256);
+    assert!(format::format_digest(&abcde, Algo::Sha256, format::Format::HttpDigest).unwrap()
+        .eq("sha-256=:NrvlDtloQdEEQ7y2cNZVTwo0t2G+Z+ycSorSwMRMpCw=:"));
+    assert!(format::format_digest(&abcde, Algo::Sha256, format::Format::HttpDigestLegacy).unwrap()
+        .eq("SHA-256=NrvlDtloQdEEQ7y2cNZVTwo0t2G+Z+ycSorSwMRMpCw="));
+    assert!(format::format_digest(&abcde, Algo::Sha224, format::Format::HttpDigest).is_none());
+    // The empty-string value is the one Nix reports for an empty file
+    assert!(format::format_digest(&abcde, Algo::Sha256, format::Format::NixBase32).unwrap()
+        .eq("0b549k2c1lla9affqrxyc6vk82jgapb71dmw8c2d2hb8v47fbfrn"));
+    assert!(format::nix_base32(&digest(&[], Algo::Sha256))
+        .eq("0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73"));
+    assert!(format::format_digest(&abcde, Algo::Sha256, format::Format::Sri).unwrap()
+        .eq("sha256-NrvlDtloQdEEQ7y2cNZVTwo0t2G+Z+ycSorSwMRMpCw="));
+    assert!(format::format_digest(&abcde, Algo::Sha224, format::Format::Sri).is_none());
+    assert!(format::verify_http_digest("Content-Digest: sha-512=:AAAA:, sha-256=:NrvlDtloQdEEQ7y2cNZVTwo0t2G+Z+ycSorSwMRMpCw=:",
+        &abcde, Algo::Sha256) == Some(true));
+    assert!(format::verify_http_digest("SHA-256=NrvlDtloQdEEQ7y2cNZVTwo0t2G+Z+ycSorSwMRMpCw=", &abcde, Algo::Sha256) == Some(true));
+    assert!(format::verify_http_digest("sha-256=:AAAA:", &abcde, Algo::Sha256) == Some(false));
+    assert!(format::verify_http_digest("sha-512=:AAAA:", &abcde, Algo::Sha256).is_none());
+
+    // Matches `openssl ts -query -digest <abcde digest> -sha256 -no_nonce -cert`
+    assert!(hex::encode(tsp::timestamp_request(&abcde, Algo::Sha256, None, true).unwrap())
+        .eq("30390201013031300d06096086480165030402010500042036bbe50ed96841d10443bcb670d6554f0a34b761be67ec9c4a8ad2c0c44ca42c0101ff"));
+    assert!(hex::encode(der::unsigned_integer(&[0x0, 0x0, 0x80])).eq("02020080"));
+    assert!(hex::encode(&der::tlv(der::TAG_OCTET_STRING, &[0x0; 200])[..4]).eq("0481c800"));
+    assert!(random::random_bytes(8).unwrap().len() == 8);
+
+    assert!(hex::encode(sha1::sha1(b"")).eq("da39a3ee5e6b4b0d3255bfef95601890afd80709"));
+    assert!(hex::encode(sha1::sha1(b"abc")).eq("a9993e364706816aba3e25717850c26c9cd0d89d"));
+    assert!(hex::encode(sha1::sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"))
+        .eq("84983e441c3bd26ebaae4aa1f95129e5e54670f1"));
+
+    // SHA-1 through the same Algo as SHA-2, for --algo, check and HMAC
+    assert!(hash(b"abc", Algo::Sha1).to_hex().eq("a9993e364706816aba3e25717850c26c9cd0d89d"));
+    let mut legacy = Hasher::new(Algo::Sha1);
+    legacy.update(b"abcdbcdecdefdefgefghfghighijhijk");
+    legacy.update(b"ijkljklmklmnlmnomnopnopq");
+    assert!(legacy.bytes_processed() == 56 && legacy.algorithm() == Algo::Sha1);
+    assert!(legacy.finalize_digest().as_bytes() == sha1::sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"));
+    assert!("sha1".parse::<Algo>().unwrap() == Algo::Sha1 && "SHA-1".parse::<Algo>().unwrap() == Algo::Sha1);
+    assert!(Algo::Sha1.to_string() == "SHA-1" && Algo::Sha1.tag() == "SHA1" && Algo::Sha1.is_deprecated() && !Algo::Sha1.has_midstate());
+    assert!(!Algo::Sha256.is_deprecated() && Algo::Sha256.has_midstate() && !Algo::Sha512.has_midstate());
+    // RFC 2202 # 3, test case 1, and RFC 6070's first PBKDF2-HMAC-SHA1 vector
+    assert!(hex::encode(hmac::hmac(&[0x0b; 20], b"Hi There", Algo::Sha1)).eq("b617318655057264e28bc0b6fb378c8ef146be00"));
+    assert!(hex::encode(kdf::pbkdf2(b"password", b"salt", 1, 20, Algo::Sha1)).eq("0c60c80f961f0e71f3a9b524af6012062fe037a6"));
+    assert!(hex::encode(&der::digest_info(&sha1::sha1(b"abc"), Algo::Sha1).unwrap()[..15]).eq("3021300906052b0e03021a05000414"));
+    assert!(check::algorithm_for_hex_len(40) == Some(Algo::Sha1) && check::algorithm_for_tag("sha1") == Some(Algo::Sha1));
+    let abc_sha1 = hash(b"abc", Algo::Sha1);
+    assert!(format::format_digest(&abc_sha1, Algo::Sha1, format::Format::HashUri).unwrap() == "hash://sha1/a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert!(format::format_digest(&abc_sha1, Algo::Sha1, format::Format::HttpDigestLegacy).unwrap() == "SHA=qZk+NkcGgWq6PiVxeFDCbJzQ2J0=");
+    assert!(format::format_digest(&abc_sha1, Algo::Sha1, format::Format::Sri).is_none());
+    assert!(format::parse_digest_uri("hash://sha1/a9993e364706816aba3e25717850c26c9cd0d89d").unwrap().0 == Algo::Sha1);
+
+    assert!(hex::encode(md5::md5(b"")).eq("d41d8cd98f00b204e9800998ecf8427e"));
+    assert!(hex::encode(md5::md5(b"abc")).eq("900150983cd24fb0d6963f7d28e17f72"));
+    assert!(hex::encode(md5::md5(b"The quick brown fox jumps over the lazy dog")).eq("9e107d9d372bb6826bd81d3542a419d6"));
+    assert!(hex::encode(md5::md5(&[b'a'; 1000])).eq("cabe45dcc9ae5b66ba86600cca6b8ba8"));
+
+    // MD5 through Algo as well, for checking legacy MD5SUMS files
+    assert!(hash(b"message digest", Algo::Md5).to_hex().eq("f96b697d7cb7938d525a2f31aaf161d0"));
+    let mut legacy = Hasher::new(Algo::Md5);
+    legacy.update(b"The quick brown fox ");
+    legacy.update(b"jumps over the lazy dog");
+    assert!(legacy.bits_processed() == 43 * 8 && legacy.algorithm() == Algo::Md5);
+    assert!(legacy.finalize_digest().to_hex().eq("9e107d9d372bb6826bd81d3542a419d6"));
+    assert!("md5".parse::<Algo>().unwrap() == Algo::Md5 && "MD5".parse::<Algo>().unwrap() == Algo::Md5);
+    assert!(Algo::Md5.to_string() == "MD5" && Algo::Md5.tag() == "MD5" && Algo::Md5.is_deprecated() && !Algo::Md5.has_midstate());
+    assert!(Algo::try_from_bits(128).ok() == Some(Algo::Md5) && Digest::try_from(&md5::md5(b"abc")[..]).unwrap().algo() == Algo::Md5);
+    // RFC 2202 # 2, test case 1
+    assert!(hex::encode(hmac::hmac(&[0x0b; 16], b"Hi There", Algo::Md5)).eq("9294727a3638bb1c13f48ef8158bfc9d"));
+    assert!(hex::encode(&der::digest_info(&md5::md5(b"abc"), Algo::Md5).unwrap()[..18]).eq("3020300c06082a864886f70d020505000410"));
+    assert!(check::algorithm_for_hex_len(32) == Some(Algo::Md5) && check::algorithm_for_tag("md5") == Some(Algo::Md5));
+    assert!(check::parse_line("900150983cd24fb0d6963f7d28e17f72  abc.txt").is_some());
+    assert!(check::parse_pasted_digest("MD5: 90015098 3cd24fb0 d6963f7d 28e17f72").unwrap() == (Some(Algo::Md5), md5::md5(b"abc").to_vec()));
+    assert!(format::format_digest(&md5::md5(b"abc"), Algo::Md5, format::Format::HashUri).unwrap() == "hash://md5/900150983cd24fb0d6963f7d28e17f72");
+    assert!(format::parse_digest_uri("hash://md5/900150983cd24fb0d6963f7d28e17f72").unwrap().0 == Algo::Md5);
+
+    assert!(htpasswd::sha_entry("alice", "password").eq("alice:{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g="));
+    let htpasswd_file = "bob:$apr1$abc$def\nalice:{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=\n";
+    assert!(htpasswd::verify(htpasswd_file, "alice", "password") == Some(true));
+    assert!(htpasswd::verify(htpasswd_file, "alice", "Password") == Some(false));
+    assert!(htpasswd::verify(htpasswd_file, "bob", "password").is_none());
+    assert!(htpasswd::verify(htpasswd_file, "carol", "password").is_none());
+
+    let ssha256 = ldap::hash_password(ldap::Scheme::Ssha256, b"secret", b"saltsalt");
+    assert!(ssha256.eq("{SSHA256}oBmrdHcA6OZEkkCLeXh71YAerbvhXz1qqwjrPsXmEtNzYWx0c2FsdA=="));
+    assert!(ldap::verify(&ssha256, b"secret") == Some(true));
+    assert!(ldap::verify(&ssha256, b"Secret") == Some(false));
+    assert!(ldap::verify("{SSHA}gVK8WC9YyFT1gMsQHTGCgT3sSv5zYWx0", b"secret") == Some(true));
+    assert!(ldap::verify("{SHA256}K7gNU3sdo+OL0wNhqoVWhr3g6s1xYv72ol/pe/Unols=", b"secret") == Some(true));
+    assert!(ldap::verify("{CRYPT}abc", b"secret").is_none());
+    let ssha512 = "{SSHA512}aCu7JRc+kLsuEmFs1zTY+AiP7DSGnjjG+dH28Dp+E5usqoAixeTPihKqZmkWal4mUfp63tqvCAkFV1LKTDFH6XNhbHRzYWx0";
+    assert!(ldap::hash_password(ldap::Scheme::Ssha512, b"secret", b"saltsalt") == ssha512);
+    assert!(ldap::verify(ssha512, b"secret") == Some(true) && ldap::verify(ssha512, b"Secret") == Some(false));
+
+    assert!(check::parse_line("36BBE50ED968 *dir/file name.bin") == Some(("36bbe50ed968".to_string(), "dir/file name.bin".to_string())));
+    assert!(check::parse_line("36bbe50ed968  input.txt") == Some(("36bbe50ed968".to_string(), "input.txt".to_string())));
+    assert!(check::parse_line("not a checksum line").is_none());
+    assert!(check::parse_line("36bbe50ed968").is_none());
+    assert!(report::json_string("a\"b\\c\n\u{1}").eq("\"a\\\"b\\\\c\\n\\u0001\""));
+
+    let record = report::Record {
+        path: "in.txt".to_string(),
+        status: report::Status::Missing,
+        expected: "00".to_string(),
+        actual: None,
+        bytes: 0,
+        duration: std::time::Duration::from_millis(2)
+    };
+    assert!(record.to_json().eq("{\"status\":\"missing\",\"expected\":\"00\",\"actual\":null,\"path\":\"in.txt\",\"bytes\":0,\"duration_ms\":2.000}"));
+
+    assert!(logging::render(logging::Level::Warn, "bad line", &[("line", "3".to_string())], false, 0.0)
+        .eq("warn: bad line line=3"));
+    assert!(logging::render(logging::Level::Warn, "bad line", &[("line", "3".to_string())], true, 1.5)
+        .eq("{\"ts\":1.500,\"level\":\"warn\",\"message\":\"bad line\",\"line\":\"3\"}"));
+    assert!(logging::Level::from_name("DEBUG") == Some(logging::Level::Debug));
+    assert!(logging::Level::Error < logging::Level::Trace);
+
+    let mut hasher = Hasher::new(Algo::Sha224);
+    hasher.update(&long_message[..100]);
+    let saved = checkpoint::Checkpoint { input: "in.bin".to_string(), offset: 100, hasher };
+    let mut restored = checkpoint::Checkpoint::from_text(&saved.to_text().unwrap()).unwrap();
+    assert!(restored.input.eq("in.bin") && restored.offset == 100);
+    restored.hasher.update(&long_message[100..]);
+    assert!(restored.hasher.finalize().eq(&digest(&long_message, Algo::Sha224)));
+    assert!(checkpoint::Checkpoint::from_text("sha2-checkpoint 1\ninput x\n").is_err());
+    assert!(checkpoint::Checkpoint { input: "in.bin".to_string(), offset: 0, hasher: Hasher::new(Algo::Sha512) }.to_text().is_err());
+    assert!(interrupt::exit_code(2) == 130);
+
+    let entries: Vec<check::Entry> = (0..20).map(|i| check::Entry {
+        expected: String::new(),
+        path: format!("/nonexistent/{}", i),
+        algo: Algo::Sha256
+    }).collect();
+    let mut order: Vec<String> = Vec::new();
+    check::check_all(&entries, 4, |record| order.push(record.path));
+    assert!(order == entries.iter().map(|e| e.path.clone()).collect::<Vec<String>>());
+
+    let scan_dir = std::env::temp_dir().join(format!("sha2-selftest-{}", std::process::id()));
+    fs::create_dir_all(scan_dir.join("sub")).unwrap();
+    fs::write(scan_dir.join("b.txt"), b"abcde").unwrap();
+    fs::write(scan_dir.join("sub").join("a.txt"), b"").unwrap();
+    let scan_root = scan_dir.to_string_lossy().into_owned();
+    let files = manifest::walk(&scan_root).unwrap();
+    assert!(files == vec![format!("{}/b.txt", scan_root), format!("{}/sub/a.txt", scan_root)]);
+
+    fs::create_dir_all(scan_dir.join("sub").join("target")).unwrap();
+    fs::write(scan_dir.join("sub").join("target").join("out.o"), b"").unwrap();
+    fs::write(scan_dir.join("sub").join("keep.log"), b"").unwrap();
+    fs::write(scan_dir.join(".gitignore"), b"*.log\n/progress\noci/\n").unwrap();
+    fs::write(scan_dir.join("sub").join(".gitignore"), b"target/\n!keep.log\n").unwrap();
+    let kept = manifest::walk_respecting_gitignore(&scan_root).unwrap();
+    assert!(kept == vec![
+        format!("{}/.gitignore", scan_root), format!("{}/b.txt", scan_root), format!("{}/sub/.gitignore", scan_root),
+        format!("{}/sub/a.txt", scan_root), format!("{}/sub/keep.log", scan_root)
+    ]);
+    for p in [".gitignore", "sub/.gitignore", "sub/keep.log"] {
+        fs::remove_file(scan_dir.join(p)).unwrap();
+    }
+    fs::remove_dir_all(scan_dir.join("sub").join("target")).unwrap();
+    let mut rules = gitignore::Rules::new();
+    rules.parse("# build output\n/build/\ndocs/**/*.html\n*.py[co]\n\\#notes\n!important.pyc\n", "");
+    assert!(rules.is_ignored("build", true) && !rules.is_ignored("src/build", true) && !rules.is_ignored("build", false));
+    assert!(rules.is_ignored("docs/index.html", false) && rules.is_ignored("docs/a/b/index.html", false));
+    assert!(rules.is_ignored("src/x.pyc", false) && rules.is_ignored("x.pyo", false) && !rules.is_ignored("x.py", false));
+    assert!(rules.is_ignored("#notes", false) && !rules.is_ignored("important.pyc", false));
+
+    let other_dir = scan_dir.join("other");
+    fs::create_dir_all(other_dir.join("sub")).unwrap();
+    fs::write(other_dir.join("b.txt"), b"abcdf").unwrap();
+    fs::write(other_dir.join("sub").join("a.txt"), b"").unwrap();
+    fs::write(other_dir.join("extra"), b"x").unwrap();
+    let left = treediff::scan(&format!("{}/sub", scan_root), Algo::Sha256, false).unwrap();
+    assert!(left.keys().collect::<Vec<_>>() == ["a.txt"]);
+    let left = treediff::scan(&scan_root, Algo::Sha256, false).unwrap();
+    let right = treediff::scan(&other_dir.to_string_lossy(), Algo::Sha256, false).unwrap();
+    let comparison = treediff::compare(&left, &right);
+    assert!(comparison.identical == 1);
+    assert!(comparison.changes.iter().map(|c| c.path()).collect::<Vec<_>>() == ["b.txt", "extra", "other/b.txt", "other/extra", "other/sub/a.txt"]);
+    assert!(matches!(&comparison.changes[0], treediff::Change::Differs(_, l, r) if l.bytes == 5 && r.bytes == 5 && l.digest != r.digest));
+    assert!(matches!(comparison.changes[1], treediff::Change::OnlyRight(..)) && matches!(comparison.changes[2], treediff::Change::OnlyLeft(..)));
+    assert!(treediff::compare(&right, &right).is_identical());
+    fs::remove_dir_all(&other_dir).unwrap();
+
+    let mut progress = manifest::ScanProgress::new();
+    progress.completed.insert(files[1].clone(), manifest::manifest_line(&digest(&[], Algo::Sha256), &files[1]));
+    let progress_file = scan_dir.join("progress").to_string_lossy().into_owned();
+    progress.save(&progress_file, &files).unwrap();
+    let restored = manifest::ScanProgress::load(&progress_file).unwrap();
+    assert!(restored.completed.len() == 1 && restored.completed.get(&files[1]) == progress.completed.get(&files[1]));
+
+    // An OCI layout with one image: index -> manifest -> config and one layer
+    let oci_dir = scan_dir.join("oci");
+    let blob = |content: &[u8]| {
+        let digest = format!("sha256:{}", hex::encode(digest(content, Algo::Sha256)));
+        let path = oci::blob_path(&oci_dir, &digest).unwrap();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        (digest, content.len())
+    };
+    let (config, config_len) = blob(b"{}");
+    let (layer, layer_len) = blob(b"layer bytes");
+    let image_manifest = format!("{{\"schemaVersion\":2,\"config\":{{\"mediaType\":\"application/vnd.oci.image.config.v1+json\",\
+        \"digest\":\"{}\",\"size\":{}}},\"layers\":[{{\"mediaType\":\"application/vnd.oci.image.layer.v1.tar\",\
+        \"digest\":\"{}\",\"size\":{}}}]}}", config, config_len, layer, layer_len);
+    let (image, image_len) = blob(image_manifest.as_bytes());
+    let refs = oci::descriptors(&image_manifest).unwrap();
+    assert!(refs.len() == 2 && refs[0].digest == config && refs[1].digest == layer && refs[1].size == 11);
+    let index = format!("{{\"manifests\": [ {{\"mediaType\": \"application/vnd.oci.image.manifest.v1+json\", \
+        \"digest\": \"{}\", \"size\": {}}} ]}}", image, image_len);
+    assert!(oci::descriptors(&index).unwrap()[0].digest == image && oci::is_manifest(&oci::descriptors(&index).unwrap()[0].media_type));
+    assert!(oci::list_blobs(&oci_dir).unwrap().len() == 3);
+    assert!(oci::blob_path(&oci_dir, "sha256:../../etc/passwd").is_none() && oci::blob_path(&oci_dir, "sha512:00").is_none());
+    assert!(verify_oci_blob(&oci::blob_path(&oci_dir, &layer).unwrap(), &layer, Some(11)).status == report::Status::Ok);
+    assert!(verify_oci_blob(&oci::blob_path(&oci_dir, &layer).unwrap(), &layer, Some(12)).status == report::Status::Failed);
+    assert!(verify_oci_blob(&oci::blob_path(&oci_dir, &layer).unwrap(), &config, None).status == report::Status::Failed);
+
+    assert!(json::parse(" [1, -2.5e3, true, null, {\"a\": \"\\u00e9\\ud83d\\ude00\\n\"}] ").unwrap() == json::Value::Array(vec![
+        json::Value::Number("1".to_string()),
+        json::Value::Number("-2.5e3".to_string()),
+        json::Value::Bool(true),
+        json::Value::Null,
+        json::Value::Object(vec![("a".to_string(), json::Value::String("\u{e9}\u{1f600}\n".to_string()))])
+    ]));
+    assert!(json::parse("{\"size\": 18446744073709551615}").unwrap().get("size").unwrap().as_u64() == Some(u64::MAX));
+    for bad in ["", "[1,]", "{\"a\" 1}", "\"abc", "[1] x", "\"\\ud83dx\"", "\"\\ud83d\\u0041\""] {
+        assert!(json::parse(bad).is_err());
+    }
+
+    let farm = scan_dir.join("farm");
+    let abcde_hex = hash_string("abcde", Algo::Sha256);
+    for kind in [linkfarm::LinkKind::Symlink, linkfarm::LinkKind::Hardlink] {
+        let b_txt = scan_dir.join("b.txt");
+        fs::write(scan_dir.join("copy.txt"), b"abcde").unwrap();
+        assert!(linkfarm::link(kind, &farm, &abcde_hex, &b_txt).unwrap() == linkfarm::Outcome::Linked);
+        assert!(linkfarm::link(kind, &farm, &abcde_hex, &b_txt).unwrap() == linkfarm::Outcome::Present);
+        assert!(linkfarm::link(kind, &farm, &abcde_hex, &scan_dir.join("copy.txt")).unwrap() == linkfarm::Outcome::Duplicate);
+        assert!(fs::read(linkfarm::object_path(&farm, &abcde_hex)).unwrap() == b"abcde");
+        fs::remove_dir_all(&farm).unwrap();
+    }
+    assert!(linkfarm::object_path(&farm, &abcde_hex) == farm.join("36").join(&abcde_hex));
+    fs::remove_dir_all(&scan_dir).unwrap();
+
+    assert!(parse_size("4096") == Some(4096));
+    assert!(parse_size("4MiB") == Some(4 << 20));
+    assert!(parse_size("512k") == Some(512 << 10));
+    assert!(parse_size("1GiB") == Some(1 << 30));
+    assert!(parse_size("12 parsecs").is_none());
+
+    let (block_list, whole) = blocks::block_digests(&long_message[..], Algo::Sha256, 300).unwrap();
+    assert!(whole.eq(&digest(&long_message, Algo::Sha256)));
+    assert!(block_list.len() == 4 && block_list[3].offset == 900 && block_list[3].len == 100);
+    assert!(block_list[1].digest.eq(&digest(&long_message[300..600], Algo::Sha256)));
+    assert!(block_list[3].digest.eq(&digest(&long_message[900..], Algo::Sha256)));
+    let mut split_parts: Vec<Vec<u8>> = Vec::new();
+    let (split_list, _) = blocks::block_digests_with(&long_message[..], Algo::Sha256, 300, |index, data| {
+        if split_parts.len() == index {
+            split_parts.push(Vec::new());
+        }
+        split_parts[index].extend_from_slice(data);
+        Ok(())
+    }).unwrap();
+    assert!(split_parts.len() == 4 && split_parts.concat() == long_message);
+    assert!(split_list.iter().zip(&split_parts).all(|(b, part)| b.digest == digest(part, Algo::Sha256)));
+
+    let known = hashdeep::parse("%%%% HASHDEEP-1.0\n%%%% size,md5,sha256,filename\n## Invoked from: /\n##\n\
+        5,ab56b4d92b40713acc5af89985d4b786,36bbe50ed96841d10443bcb670d6554f0a34b761be67ec9c4a8ad2c0c44ca42c,d/a.txt\n\
+        2,0,73cb3858a687a8494ca3323053016282f3dad39d42cf62ca4e79dda2aac7d9ac,d/b,c.txt\n\
+        1,0,3f79bb7b435b05321651daefd374cdc681dc06faa65e374e38337b88ca046dea,d/gone.txt\n").unwrap();
+    assert!(known.len() == 3 && known[1].path.eq("d/b,c.txt"));
+    let scanned_entry = |size: u64, sha256: &str, path: &str| hashdeep::Entry {
+        size,
+        hashes: vec![("sha256".to_string(), sha256.to_string())],
+        path: path.to_string()
+    };
+    let scanned = vec![
+        scanned_entry(5, "36bbe50ed96841d10443bcb670d6554f0a34b761be67ec9c4a8ad2c0c44ca42c", "d/a.txt"),
+        scanned_entry(2, "73cb3858a687a8494ca3323053016282f3dad39d42cf62ca4e79dda2aac7d9ac", "d/moved.txt"),
+        scanned_entry(2, "0000000000000000000000000000000000000000000000000000000000000000", "d/new.txt")
+    ];
+    let outcomes: Vec<hashdeep::Outcome> = hashdeep::audit(&known, &scanned).iter().map(|r| r.outcome).collect();
+    assert!(outcomes == vec![hashdeep::Outcome::Matched, hashdeep::Outcome::Moved, hashdeep::Outcome::New, hashdeep::Outcome::Missing]);
+    assert!(hashdeep::format_entry(&scanned[0]).eq("5,36bbe50ed96841d10443bcb670d6554f0a34b761be67ec9c4a8ad2c0c44ca42c,d/a.txt"));
+    assert!(hashdeep::parse("%%%% HASHDEEP-1.0\n%%%% md5,filename\n").is_err());
+
+    let mut sha1_hasher = sha1::Sha1::new();
+    for chunk in long_message.chunks(13) {
+        sha1_hasher.update(chunk);
+    }
+    assert!(sha1_hasher.finalize() == sha1::sha1(&long_message));
+
+    let genesis = hex::decode("0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c").unwrap();
+    assert!(hex::encode(bitcoin::block_hash(&genesis)).eq("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"));
+    let genesis_header = bitcoin::parse_header(&genesis).unwrap();
+    assert!(genesis_header.nonce == 2083236893 && genesis_header.bits == 0x1d00ffff && genesis_header.time == 1231006505);
+    let genesis_txid = bitcoin::reversed(&genesis_header.merkle_root);
+    assert!(bitcoin::merkle_root(std::slice::from_ref(&genesis_txid)).unwrap() == genesis_txid);
+
+    let block_100000_txids: Vec<Vec<u8>> = [
+        "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+        "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+        "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+        "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d"
+    ].iter().map(|t| hex::decode(t).unwrap()).collect();
+    assert!(hex::encode(bitcoin::merkle_root(&block_100000_txids).unwrap())
+        .eq("f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766"));
+    assert!(hex::encode(bitcoin::merkle_root(&block_100000_txids[..3]).unwrap())
+        .eq("fa435470825de273081dcc706b25514c936fa6dc80ab965ce6970d68ddd0b553"));
+
+    assert!(hex::encode(keccak::keccak256(b"")).eq("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"));
+    assert!(hex::encode(keccak::keccak256(b"abc")).eq("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"));
+    assert!(hex::encode(keccak::keccak256(&[0x61; 200])) == hex::encode({
+        let mut sponge = keccak::Keccak::new(136, keccak::KECCAK_PAD);
+        for chunk in [0x61u8; 200].chunks(17) {
+            sponge.update(chunk);
+        }
+        sponge.finalize(32)
+    }));
+
+    // SHA-3, FIPS 202, with NIST's example values
+    assert!(hash(b"abc", Algo::Sha3_224).to_hex() == "e642824c3f8cf24ad09234ee7d3c766fc9a3a5168d0c94ad73b46fdf");
+    assert!(hash(b"abc", Algo::Sha3_256).to_hex() == "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532");
+    assert!(hash(b"abc", Algo::Sha3_384).to_hex() == "ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b298d88cea927ac7f539f1edf228376d25");
+    assert!(hash(b"abc", Algo::Sha3_512).to_hex() == "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0");
+    assert!(hash(b"", Algo::Sha3_224).to_hex() == "6b4e03423667dbb73b6e15454f0eb1abd4597f9a1b078e3f5b5a6bc7");
+    assert!(hash(b"", Algo::Sha3_256).to_hex() == "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a");
+    assert!(hash(&[0xa3; 200], Algo::Sha3_256).to_hex() == "79f38adec5c20307a98ef76e8324afbfd46cfd81b22e3973c65fa1bd9de31787");
+    let mut sha3 = Hasher::new(Algo::Sha3_512);
+    for chunk in [0x61u8; 1000].chunks(71) {
+        sha3.update(chunk);
+    }
+    assert!(sha3.bytes_processed() == 1000 && sha3.algorithm() == Algo::Sha3_512);
+    assert!(sha3.finalize_digest().to_hex() == "ac7e95cc95aa7f24aaa95e040ca0c79b39cd9cc84a10abb84ddd8dd5e4b45cf96543aaa70d0ef99fbf8d2769639981ee1fd0b0276f4756b9d504d0b7de19b700");
+    assert!("sha3-256".parse::<Algo>().ok() == Some(Algo::Sha3_256) && "SHA3-384".parse::<Algo>().ok() == Some(Algo::Sha3_384) && "sha3".parse::<Algo>().is_err());
+    assert!(Algo::Sha3_224.to_string() == "SHA3-224" && Algo::Sha3_512.tag() == "SHA3-512" && Algo::Sha3_256.block_len() == 136);
+    assert!(Algo::Sha3_384.digest_len() == 48 && Algo::Sha3_256.is_sha3() && !Algo::Sha256.is_sha3() && !Algo::Sha3_256.has_midstate());
+    let sha3_384 = hash(b"abc", Algo::Sha3_384);
+    assert!(Digest::from_slice(Algo::Sha3_384, &sha3_384).unwrap() == sha3_384 && Digest::from_slice(Algo::Sha3_256, &sha3_384).is_err());
+    assert!(sha3_384.truncate(64).to_string() == "ec01498288516fc9");
+    assert!(check::algorithm_for_tag("SHA3-256") == Some(Algo::Sha3_256));
+    assert!(hex::encode(hmac::hmac(b"key", b"abc", Algo::Sha3_256)) == "09b6dbab8d11795ca7c8d82f1cf91682013c7cb980abbb25473be4ae7f7b5683");
+    assert!(format::parse_digest_uri("hash://sha3-256/3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532").unwrap().0 == Algo::Sha3_256);
+    assert!(hex::encode(der::digest_info(&hash(b"abc", Algo::Sha3_256), Algo::Sha3_256).unwrap()).starts_with("3031300d060960864801650304020805000420"));
+
+    // The public key for private key 1, i.e. the secp256k1 generator point
+    let generator = hex::decode("0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+        483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8").unwrap();
+    assert!(ethereum::checksum_address(&ethereum::address(&generator).unwrap()).eq("0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"));
+    assert!(ethereum::address(&generator[1..]) == ethereum::address(&generator));
+    assert!(ethereum::address(&generator[2..]).is_none());
+    let eip55_example: [u8; 20] = hex::decode("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap().try_into().unwrap();
+    assert!(ethereum::checksum_address(&eip55_example).eq("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"));
+
+    let integrity = "sha384-AAAA sha256-NrvlDtloQdEEQ7y2cNZVTwo0t2G+Z+ycSorSwMRMpCw=?foo sha1-A95sVwv+JL/DKMzXyka3bq2vQzQ=";
+    assert!(ssri::parse(integrity).len() == 3);
+    assert!(ssri::verify(integrity, &b"abcde"[..]).unwrap() == ssri::Verdict::Match("sha256"));
+    assert!(ssri::verify(integrity, &b"abcdf"[..]).unwrap() == ssri::Verdict::Mismatch("sha256"));
+    assert!(ssri::verify("sha1-A95sVwv+JL/DKMzXyka3bq2vQzQ=", &b"abcde"[..]).unwrap() == ssri::Verdict::Match("sha1"));
+    assert!(ssri::verify("sha384-AAAA md5-AAAA", &b"abcde"[..]).unwrap() == ssri::Verdict::Unsupported);
+    let npm = "sha512-h4rmWpLobKwBGlcNTDCn6uxEK4XOjsoMKVK148wGKMLnnYia1NXHxiaYbUUt2GN0tv+qfNi2dmW+8iiaXHCwoQ== sha1-A95sVwv+JL/DKMzXyka3bq2vQzQ=";
+    assert!(ssri::verify(npm, &b"abcde"[..]).unwrap() == ssri::Verdict::Match("sha512"));
+
+    let lock = "version = 3\n\n[[package]]\nname = \"hex\"\nversion = \"0.4.3\"\n\
+        source = \"registry+https://github.com/rust-lang/crates.io-index\"\nchecksum = \"7f24\"\n\n\
+        [[package]]\nname = \"sha-2\"\nversion = \"0.1.0\"\ndependencies = [\n \"hex\",\n]\n";
+    let packages = cargo::parse_lock(lock);
+    assert!(packages.len() == 2);
+    assert!(packages[0].crate_file_name() == "hex-0.4.3.crate");
+    assert!(packages[0].checksum.as_deref() == Some("7f24"));
+    assert!(packages[1].checksum.is_none());
+    let v1 = "[[package]]\nname = \"hex\"\nversion = \"0.4.3\"\n\n[metadata]\n\
+        \"checksum hex 0.4.3 (registry+https://github.com/rust-lang/crates.io-index)\" = \"7f24\"\n";
+    assert!(cargo::parse_lock(v1)[0].checksum.as_deref() == Some("7f24"));
+
+    let mut lines = Vec::new();
+    assert!(hash_lines(&b"abcde\r\n\nabc"[..], &mut lines, Algo::Sha256, None, format::Format::Hex, false, 1).unwrap() == 3);
+    assert!(String::from_utf8(lines).unwrap() == format!("{}\t1\n{}\t2\n{}\t3\n",
+        "36bbe50ed96841d10443bcb670d6554f0a34b761be67ec9c4a8ad2c0c44ca42c",
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"));
+    let mut lines = Vec::new();
+    hash_lines(&b"abc\n"[..], &mut lines, Algo::Sha224, None, format::Format::Hex, true, 7).unwrap();
+    assert!(String::from_utf8(lines).unwrap() == "23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7\tabc\n");
+
+    let mut input = &b"id,\"note, with comma\",\"multi\nline \"\"quoted\"\"\"\r\n2,,x\n"[..];
+    let record = csv::read_record(&mut input, b',').unwrap().unwrap();
+    assert!(record == [&b"id"[..], b"note, with comma", b"multi\nline \"quoted\""]);
+    assert!(csv::format_record(&record, b',') == b"id,\"note, with comma\",\"multi\nline \"\"quoted\"\"\"");
+    assert!(csv::read_record(&mut input, b',').unwrap().unwrap() == [&b"2"[..], b"", b"x"]);
+    let latin1 = csv::read_record(&mut &b"caf\xe9,\"a\xff,b\"\n"[..], b',').unwrap().unwrap();
+    assert!(latin1 == [&b"caf\xe9"[..], b"a\xff,b"] && csv::format_record(&latin1, b',') == b"caf\xe9,\"a\xff,b\"");
+    assert!(csv::read_record(&mut input, b',').unwrap().is_none());
+    assert!(csv::resolve_columns(&["3".to_string(), "id".to_string()], Some(&record)).unwrap() == [2, 0]);
+    assert!(csv::resolve_columns(&["email".to_string()], Some(&record)).is_err());
+
+    let mut seen = uniq::SeenSet::new(2);
+    assert!(seen.insert(&digest(b"a", Algo::Sha256)));
+    assert!(seen.insert(&digest(b"b", Algo::Sha256)));
+    assert!(!seen.insert(&digest(b"a", Algo::Sha256)));
+    assert!(seen.insert(&digest(b"c", Algo::Sha256)) && seen.evicted == 1);
+    // "a" fell out of the window
+    assert!(seen.insert(&digest(b"a", Algo::Sha256)));
+
+    let mut filter = bloom::BloomFilter::with_rate(1000, 0.01);
+    // For 1% the optimum is about 9.6 bits per item and 7 hash functions
+    assert!(filter.m == 9586 && filter.k == 7);
+    for i in 0..1000 {
+        filter.insert(&digest(format!("item {}", i).as_bytes(), Algo::Sha256));
+    }
+    let restored = bloom::BloomFilter::from_bytes(&filter.to_bytes()).unwrap();
+    assert!(restored.count == 1000 && restored.bits == filter.bits);
+    assert!((0..1000).all(|i| restored.contains(&digest(format!("item {}", i).as_bytes(), Algo::Sha256))));
+    let false_positives = (0..1000).filter(|i| restored.contains(&digest(format!("other {}", i).as_bytes(), Algo::Sha256))).count();
+    assert!(false_positives < 30);
+    assert!(bloom::BloomFilter::from_bytes(b"sha2-bloom 1 k=7 bits=9586 count=0\n\x00").is_err());
+
+    let keys: Vec<Vec<u8>> = (0..10000).map(|i| digest(format!("key {}", i).as_bytes(), Algo::Sha256)).collect();
+    let mut counts = [0u32; 10];
+    for k in &keys {
+        counts[shard::jump_bucket(k, 10) as usize] += 1;
+    }
+    assert!(counts.iter().all(|c| *c > 900 && *c < 1100));
+    // Adding an eleventh bucket only moves keys into the new bucket
+    assert!(keys.iter().all(|k| {
+        let (before, after) = (shard::jump_bucket(k, 10), shard::jump_bucket(k, 11));
+        before == after || after == 10
+    }));
+    assert!(keys.iter().all(|k| shard::jump_bucket(k, 1) == 0));
+
+    assert!(manifest::pseudonymize_path(b"key", "src/main.rs")
+        == hex::encode(hmac::hmac(b"key", b"src/main.rs", Algo::Sha256)));
+    assert!(manifest::pseudonymize_path(b"key", "src/main.rs") != manifest::pseudonymize_path(b"other", "src/main.rs"));
+
+    let tune_file = std::env::temp_dir().join(format!("sha2-selftest-io-{}", std::process::id()));
+    fs::write(&tune_file, &long_message[..]).unwrap();
+    let tune_path = tune_file.to_string_lossy().into_owned();
+    for strategy in iotune::candidates() {
+        let mut file = fs::File::open(&tune_file).unwrap();
+        let mut hasher = Hasher::new(Algo::Sha256);
+        assert!(iotune::hash_range(&mut file, strategy, 0, u64::MAX, &mut hasher).unwrap() == 1000);
+        assert!(hasher.finalize() == digest(&long_message, Algo::Sha256));
+
+        let mut hasher = Hasher::new(Algo::Sha256);
+        assert!(iotune::hash_range(&mut file, strategy, 300, 300, &mut hasher).unwrap() == 300);
+        assert!(hasher.finalize() == digest(&long_message[300..600], Algo::Sha256));
+    }
+    let probes = iotune::probe(&tune_path, &iotune::candidates(), 100, Algo::Sha256).unwrap();
+    assert!(probes.len() == iotune::candidates().len() && probes.iter().all(|p| p.bytes == 100));
+    assert!(hash_file_autotuned(&tune_path, Algo::Sha256, None) == digest(&long_message, Algo::Sha256));
+    fs::remove_file(&tune_file).unwrap();
+
+    // Padding at each block boundary: (message length, padded length with 512-bit blocks, with 1024-bit blocks)
+    for (len, padded_512, padded_1024) in [
+        (0, 64, 128), (55, 64, 128), (56, 128, 128), (63, 128, 128), (64, 128, 128),
+        (119, 128, 256), (120, 192, 256), (127, 192, 256), (128, 192, 256)
+    ] {
+        for (layout, padded_len) in [(LAYOUT_512, padded_512), (LAYOUT_1024, padded_1024)] {
+            let mut padded = vec![0xffu8; len];
+            pad_message(&mut padded, len as u128, layout);
+            assert!(padded.len() == padded_len);
+            assert!(padded[len] == 0x80);
+            assert!(padded[len + 1..padded_len - layout.length_len].iter().all(|b| *b == 0));
+            let length_field = &padded[padded_len - layout.length_len..];
+            assert!(length_field[..layout.length_len - 8].iter().all(|b| *b == 0));
+            assert!(length_field[layout.length_len - 8..] == ((len as u64) * 8).to_be_bytes());
+        }
+    }
+    // Only the low 128 bits of the bit length are kept
+    let mut padded = Vec::new();
+    pad_message(&mut padded, u128::MAX / 4 + 1, LAYOUT_1024);
+    assert!(padded[112..] == [0u8; 16]);
+
+    let mut sha256 = Sha256::new();
+    let mut sha224 = Sha224::new();
+    for chunk in long_message.chunks(37) {
+        sha256.update(chunk);
+        sha224.update(chunk);
+    }
+    assert!(sha256.finalize().to_vec() == digest(&long_message, Algo::Sha256));
+    assert!(sha224.finalize().to_vec() == digest(&long_message, Algo::Sha224));
+    assert!(hex::encode(Sha256::new().finalize()) == hash_string("", Algo::Sha256));
+    let mut reused = Sha256::new();
+    reused.update(b"abc");
+    assert!(reused.finalize_reset().to_vec() == digest(b"abc", Algo::Sha256));
+    reused.update(b"junk");
+    reused.reset();
+    reused.update(b"abcde");
+    assert!(reused.finalize() == Sha256::digest(b"abcde") && Sha256::output_size() == 32);
+    assert!(Sha224::digest(b"abc").to_vec() == digest(b"abc", Algo::Sha224) && Sha224::output_size() == 28);
+
+    assert!(hex::decode("00fF7a").unwrap() == [0x00, 0xff, 0x7a]);
+    assert!(hex::decode("abc") == Err(hex::DecodeError::OddLength));
+    assert!(hex::decode("0g") == Err(hex::DecodeError::InvalidCharacter('g')));
+    // The example from the z-base-32 specification
+    assert!(encoding::Encoding::ZBase32.encode([0xf0, 0xbf, 0xc7]) == "6n9hq");
+    assert!(format::format_digest(&abcde, Algo::Sha256, format::Format::from_name("zbase32").unwrap()).unwrap()
+        == "g476kds3pby7nbndz158bi1ijhfdjp5bz3u638nktmjcbtncwosy");
+    assert!(format::format_digest(&abcde, Algo::Sha256, format::Format::from_name("base32-crockford").unwrap()).unwrap()
+        == "6TXYA3PSD10X2123QJV71NJN9W539DV1QSKYS72AHB9C1H2CMGP0");
+    assert!(encoding::Encoding::Base32Crockford.encode(b"foobar") == "CSQPYRK1E8");
+    let mut streamed = encoding::Encoding::Base32Crockford.encoder(Vec::new());
+    for chunk in b"foobar".chunks(1) {
+        streamed.write_all(chunk).unwrap();
+    }
+    assert!(streamed.finish().unwrap() == b"CSQPYRK1E8");
+
+    let message = long_message.to_vec();
+    for len in [0, 55, 56, 63, 64, 65, 119, 120, 128] {
+        let mut hasher = Hasher::new(Algo::Sha224);
+        hasher.update(&message[..len]);
+        let d = hash(&message[..len], Algo::Sha224);
+        assert!(d.algo() == Algo::Sha224 && d.len() == 28 && *d == hasher.finalize()[..]);
+    }
+    assert!(message == long_message && message.capacity() == 1000);
+    let abc224 = hash(b"abc", Algo::Sha224);
+    assert!(abc224.to_string() == "23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7");
+    assert!(format!("{:#x}", abc224) == "0x23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7");
+    assert!(format!("{:X}", abc224) == "23097D223405D8228642A477BDA255B32AADBCE4BDA0B3F7E36C9DA7");
+    assert!(abc224.as_ref().len() == 28 && Vec::from(abc224) == abc224.to_vec());
+
+    // The fixed DigestInfo prefix from RFC 8017 # 9.2, note 1
+    assert!(hex::encode(der::digest_info(&abcde, Algo::Sha256).unwrap())
+        == format!("3031300d060960864801650304020105000420{}", hex::encode(&abcde)));
+    assert!(hex::encode(&der::digest_info(&digest(b"abc", Algo::Sha224), Algo::Sha224).unwrap()[..19])
+        == "302d300d06096086480165030402040500041c");
+
+    assert!(format::format_digest(&abcde, Algo::Sha256, format::Format::DigestInfoDer).unwrap()
+        == hex::encode(pkcs1::digest_info(&abcde, Algo::Sha256).unwrap()));
+    // Same layout as the message `openssl pkeyutl -verifyrecover -pkeyopt rsa_padding_mode:none` recovers from a 2048-bit signature
+    let em = pkcs1::emsa_encode(&abcde, Algo::Sha256, 256).unwrap();
+    assert!(em.len() == 256 && em[..2] == [0x00, 0x01] && em[2..204].iter().all(|b| *b == 0xff) && em[204] == 0x00);
+    assert!(em[205..] == pkcs1::digest_info(&abcde, Algo::Sha256).unwrap()[..]);
+    assert!(pkcs1::emsa_encode(&abcde, Algo::Sha256, 61).is_none() && pkcs1::emsa_encode(&abcde, Algo::Sha256, 62).is_some());
+
+    // The same compression loop on 64-bit words computes SHA-512 (FIPS 180-4 # 5.3.5 IV)
+    let mut sha512 = Engine::<u64>::from_words([
+        0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+        0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179
+    ], 512);
+    let mut padded = b"abc".to_vec();
+    pad_message(&mut padded, 3, LAYOUT_1024);
+    for block in padded.chunks(Engine::<u64>::block_len()) {
+        sha512.compress(block);
+    }
+    assert!(hex::encode(sha512.export()) == "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+        2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f");
+    assert!(Engine::<u32>::block_len() == 64 && State::new(Algo::Sha256).words() == Engine::from_words(State::new(Algo::Sha256).words(), 256).words());
+
+    let tag = seal::Tag::compute(b"key", Algo::Sha256, &b"abc"[..], 3).unwrap();
+    assert!(hex::encode(tag.to_bytes())
+        == "5332534c01020000000000000003ae293baca471126b170ec78143ed265b75662c9317984ad5d7fb5c1aa51fe4e1");
+    assert!(seal::Tag::parse(&tag.to_bytes()) == Some(tag.clone()) && tag.encoded_len() == 46);
+    assert!(tag.verify(b"key", &b"abc"[..]).unwrap() && !tag.verify(b"key", &b"abd"[..]).unwrap());
+    assert!(!tag.verify(b"key", &b"ab"[..]).unwrap() && !tag.verify(b"kez", &b"abc"[..]).unwrap());
+    let mut sealed = b"abc".to_vec();
+    sealed.extend(tag.to_bytes());
+    assert!(seal::read_trailer(&mut io::Cursor::new(&sealed)).unwrap() == Some(tag));
+    sealed.insert(0, b'x');
+    assert!(seal::read_trailer(&mut io::Cursor::new(&sealed)).unwrap().is_none());
+    assert!(seal::read_trailer(&mut io::Cursor::new(b"abc")).unwrap().is_none());
+    assert!(seal::algorithm_id(Algo::Sha1).is_none() && seal::algorithm_id(Algo::Sha3_256).is_none() && seal::algorithm_id(Algo::Md5).is_none());
+    let rejected = seal::Tag::compute(b"key", Algo::Sha1, &b"abc"[..], 3).unwrap_err();
+    assert!(rejected.kind() == io::ErrorKind::InvalidInput && rejected.to_string().contains("SHA-224, SHA-256 and SHA-512"));
+
+    // Chaining compress256 by hand over a two-block message matches the padded hash
+    let mut state = State::new(Algo::Sha256).words();
+    let mut padded = long_message[..100].to_vec();
+    pad_message(&mut padded, 100, LAYOUT_512);
+    for block in padded.chunks(64) {
+        compress256(&mut state, block.try_into().unwrap());
+    }
+    assert!(state.iter().flat_map(|w| w.to_be_bytes()).collect::<Vec<u8>>() == digest(&long_message[..100], Algo::Sha256));
+
+    // Hand the first block of an 80-byte Bitcoin-style header over as a midstate, then finish elsewhere
+    let header = &long_message[..80];
+    let mut first = Sha256::new();
+    first.update(&header[..64]);
+    let midstate = first.midstate();
+    let mut chaining = State::new(Algo::Sha256).words();
+    compress256(&mut chaining, header[..64].try_into().unwrap());
+    assert!(midstate == Midstate(chaining) && Midstate::from_bytes(&midstate.to_bytes()) == midstate);
+    let mut resumed = Sha256::from_midstate(midstate, first.bytes_processed());
+    resumed.update(&header[64..]);
+    assert!(resumed.finalize() == Sha256::digest(header));
+    let mut resumed = Hasher::from_midstate(Algo::Sha224, Hasher::new(Algo::Sha224).midstate(), 0);
+    resumed.update(b"abc");
+    assert!(resumed.finalize() == digest(b"abc", Algo::Sha224));
+    // The length field is the bit count modulo 2^64: 2^61 bytes wraps to zero, and just short of
+    // it the field carries the high bits
+    let mut wrapped = Hasher::from_midstate(Algo::Sha256, Hasher::new(Algo::Sha256).midstate(), 1 << 61);
+    wrapped.update(b"abc");
+    assert!(wrapped.bits_processed() == 24 && wrapped.finalize() == digest(b"abc", Algo::Sha256));
+    let mut huge = Hasher::from_midstate(Algo::Sha256, Hasher::new(Algo::Sha256).midstate(), (1 << 61) - 64);
+    huge.update(b"abc");
+    assert!(huge.bits_processed() == u64::MAX - 511 + 24);
+    let mut padded = b"abc".to_vec();
+    pad_message(&mut padded, (1 << 61) - 61, LAYOUT_512);
+    assert!(padded[56..] == [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0x18]);
+    let mut chaining = State::new(Algo::Sha256).words();
+    compress256(&mut chaining, padded[..].try_into().unwrap());
+    assert!(huge.finalize_digest().as_bytes() == chaining.iter().flat_map(|w| w.to_be_bytes()).collect::<Vec<u8>>());
+
+    let mut interrupted = Hasher::new(Algo::Sha224);
+    interrupted.update(&long_message[..100]);
+    let saved = interrupted.save_state();
+    assert!(saved.buffer == long_message[64..100] && saved.len == 100);
+    let restored = HasherState::from_json(&saved.to_json()).unwrap();
+    assert!(restored == saved);
+    let mut resumed = Hasher::restore_state(&restored).unwrap();
+    resumed.update(&long_message[100..]);
+    assert!(resumed.finalize() == digest(&long_message, Algo::Sha224));
+    assert!(Hasher::new(Algo::Sha256).save_state().to_json() == "{\"algorithm\":256,\"h\":[1779033703,3144134277,1013904242,\
+        2773480762,1359893119,2600822924,528734635,1541459225],\"buffer\":[],\"len\":0}");
+    assert!(Hasher::restore_state(&HasherState { len: 101, ..saved.clone() }).is_err());
+    assert!(Hasher::restore_state(&HasherState { algorithm: 512, ..saved.clone() }).is_err());
+    assert!(HasherState::from_json(&saved.to_json().replace("\"h\":[", "\"h\":[1,")).is_err());
+    assert!(HasherState::from_json(&saved.to_json().replace("\"buffer\":[", "\"buffer\":[256,")).is_err());
+
+    // Fork after a common prefix, finishing each clone with its own suffix, on separate threads
+    let mut prefix = Sha256::new();
+    prefix.update(&long_message[..500]);
+    let forks: Vec<std::thread::JoinHandle<bool>> = [600, 777, 1000].into_iter().map(|end| {
+        let mut fork = prefix.clone();
+        std::thread::spawn(move || {
+            fork.update(&long_message[500..end]);
+            fork.finalize() == Sha256::digest(&long_message[..end])
+        })
+    }).collect();
+    assert!(forks.into_iter().all(|f| f.join().unwrap()));
+
+    let in_release = "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA512\n\nOrigin: Debian\nSuite: stable\nMD5Sum:\n \
+        0d1fa2e2b0e0a5a9c1d3b4c7d8e9f0a1 1234 main/binary-amd64/Packages\nSHA256:\n \
+        36bbe50ed96841d10443bcb670d6554f0a34b761be67ec9c4a8ad2c0c44ca42c 5 main/binary-amd64/Packages\n \
+        E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855 0 main/i18n/Translation-en\n\
+        - -dash-escaped: line\n-----BEGIN PGP SIGNATURE-----\n\nwsFzBAEBCgAd\n-----END PGP SIGNATURE-----\n";
+    let (kind, listed) = apt::parse(in_release);
+    assert!(kind == apt::Kind::Release && listed.len() == 2);
+    assert!(listed[0] == apt::FileEntry { path: "main/binary-amd64/Packages".to_string(), size: 5, sha256: hash_string("abcde", Algo::Sha256) });
+    assert!(listed[1].sha256 == hash_string("", Algo::Sha256) && listed[1].path == "main/i18n/Translation-en");
+    assert!(pgp::strip_clearsign(in_release).ends_with("\n-dash-escaped: line"));
+    let (kind, listed) = apt::parse("Package: hello\nVersion: 2.10-3\nFilename: pool/main/h/hello/hello_2.10-3_amd64.deb\n\
+        Size: 5\nSHA256: 36bbe50ed96841d10443bcb670d6554f0a34b761be67ec9c4a8ad2c0c44ca42c\nDescription: greets\n \
+        more description\n\nPackage: nodigest\nFilename: pool/x.deb\nSize: 1\n");
+    assert!(kind == apt::Kind::Packages && listed.len() == 1);
+    assert!(listed[0].path == "pool/main/h/hello/hello_2.10-3_amd64.deb" && listed[0].size == 5);
+
+    // A writer which takes at most 7 bytes per call; only what it accepts is hashed
+    struct Trickle(Vec<u8>);
+    impl Write for Trickle {
+        fn write (&mut self, buf: &[u8]) -> io::Result<usize> {
+            let take = buf.len().min(7);
+            self.0.extend_from_slice(&buf[..take]);
+            Ok(take)
+        }
+        fn flush (&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut writer = stream::HashWriter::new(Trickle(Vec::new()), Algo::Sha224);
+    assert!(writer.write(&long_message[..]).unwrap() == 7 && writer.bytes_written() == 7);
+    writer.write_all(&long_message[7..]).unwrap();
+    let (copied, written_digest) = writer.finish().unwrap();
+    assert!(copied.0 == long_message && written_digest == hash(&long_message, Algo::Sha224));
+
+    assert!(check::parse_tagged_line("SHA256 (Fedora-Server-dvd-x86_64-39-1.5.iso) = 36BBE50ED968")
+        == Some(("SHA256".to_string(), "36bbe50ed968".to_string(), "Fedora-Server-dvd-x86_64-39-1.5.iso".to_string())));
+    assert!(check::parse_tagged_line("SHA224 (a) = b) = 00") == Some(("SHA224".to_string(), "00".to_string(), "a) = b".to_string())));
+    assert!(check::parse_tagged_line("# Fedora-Server-dvd-x86_64-39-1.5.iso: 2612854784 bytes").is_none());
+    assert!(check::parse_tagged_line("SHA256 (x) = not-hex").is_none() && check::parse_tagged_line("36bbe50ed968  x").is_none());
+    assert!(check::parse_tagged_line("36bbe50ed968  photo (1) = 2.jpg").is_none());
+    assert!(check::algorithm_for_tag("sha256") == Some(Algo::Sha256) && check::algorithm_for_tag("SHA384").is_none());
+
+    let mut reader = stream::HashingReader::new(&long_message[..], Algo::Sha256);
+    let mut head = [0u8; 100];
+    reader.read_exact(&mut head).unwrap();
+    assert!(reader.bytes_read() == 100 && reader.digest() == hash(&long_message[..100], Algo::Sha256));
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    let (remaining, read_digest) = reader.finish();
+    assert!(remaining.is_empty() && rest == long_message[100..] && read_digest == hash(&long_message, Algo::Sha256));
+
+    assert!(format::format_digest(&[0x0a, 0xbc, 0xff], Algo::Sha256, format::Format::CertUtil).unwrap() == "0A BC FF");
+    let certutil_output = format::certutil_report(&abcde, Algo::Sha256, "input_file.txt");
+    assert!(certutil_output.starts_with("SHA256 hash of input_file.txt:\n36 BB E5 0E D9 68 41 D1")
+        && certutil_output.ends_with("\nCertUtil: -hashfile command completed successfully."));
+    let parsed = check::parse_certutil(&certutil_output.replace('\n', "\r\n"));
+    assert!(parsed == vec![("SHA256".to_string(), hex::encode(&abcde), "input_file.txt".to_string())]);
+    let parsed = check::parse_certutil(&format!("SHA256 hash of C:\\Users\\me\\Downloads\\input_file.txt:\n{}\n", hex::encode(&abcde)));
+    assert!(parsed.len() == 1 && parsed[0].2 == "input_file.txt");
+    assert!(check::parse_certutil("36bbe50ed968  input_file.txt\n").is_empty());
+
+    let powershell = format!("#TYPE Microsoft.PowerShell.Commands.FileHashInfo\r\n{}\r\n{}\r\n",
+        format::GET_FILEHASH_CSV_HEADER, format::get_filehash_csv_row(&abcde, Algo::Sha256, "C:\\Users\\me\\input_file.txt"));
+    assert!(powershell.contains("\"SHA256\",\"36BBE50ED96841D10443BCB670D6554F0A34B761BE67EC9C4A8AD2C0C44CA42C\","));
+    assert!(check::parse_get_filehash_csv(&powershell) == vec![("SHA256".to_string(), hex::encode(&abcde), "input_file.txt".to_string())]);
+    let reordered = format!("Path,Algorithm,Hash\n\"dir/a \"\"b\"\".txt\",SHA256,{}\n", hex::encode(&abcde));
+    assert!(check::parse_get_filehash_csv(&reordered)[0].2 == "dir/a \"b\".txt");
+    assert!(check::parse_get_filehash_csv("36bbe50ed968  input_file.txt\n").is_empty());
+
+    let pasted = check::parse_pasted_digest(" SHA256: BA7816BF 8F01CFEA 414140DE 5DAE2223 B00361A3 96177A9C B410FF61 F20015AD\n").unwrap();
+    assert!(pasted.0 == Some(Algo::Sha256) && hex::encode(&pasted.1).eq("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"));
+    assert!(check::parse_pasted_digest("ba:78:16:bf:8f:01:cf:ea:41:41:40:de:5d:ae:22:23:b0:03:61:a3:96:17:7a:9c:b4:10:ff:61:f2:00:15:ad") == Some((None, pasted.1.clone())));
+    assert!(check::parse_pasted_digest("sha256-ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=") == Some(pasted.clone()));
+    assert!(check::parse_pasted_digest("SHA-256=ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad") == Some(pasted));
+    let sha3_256 = hash(b"abc", Algo::Sha3_256);
+    assert!(check::parse_pasted_digest(&format!("sha3-256:{}", sha3_256)) == Some((Some(Algo::Sha3_256), sha3_256.to_vec())));
+    let sha3_384 = hash(b"abc", Algo::Sha3_384).to_vec();
+    assert!(check::parse_pasted_digest(&format!("hash://sha3-384/{}", hex::encode(&sha3_384))) == Some((Some(Algo::Sha3_384), sha3_384.clone())));
+    assert!(check::parse_pasted_digest(&hex::encode(&sha3_384)).is_none());
+    assert!(check::with_article(Algo::Md5) == "an MD5" && check::with_article(Algo::Sha3_384) == "a SHA3-384");
+    assert!(check::parse_pasted_digest("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  release.zip\n").is_some());
+    assert!(check::parse_pasted_digest("SHA256 (release.zip) = ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad").is_some());
+    assert!(check::parse_pasted_digest("ba7816bf8f01cfea").is_none());
+    assert!(check::parse_pasted_digest("https://example.com/release.zip").is_none());
+
+    // The fixed-buffer hasher, fed in uneven pieces across block boundaries, agrees with the one-shot hash
+    for split in [0, 1, 55, 56, 63, 64, 65, 127, 999] {
+        let mut hasher = Hasher::new(Algo::Sha256);
+        hasher.update(&long_message[..split]);
+        hasher.update(&long_message[split..]);
+        assert!(hasher.finalize_digest() == hash(&long_message, Algo::Sha256));
+    }
+    let mut reused = Hasher::new(Algo::Sha224);
+    reused.update(b"abc");
+    assert!(reused.finalize_digest_reset().to_hex() == hash_string("abc", Algo::Sha224));
+    assert!(reused.finalize_digest() == hash(b"", Algo::Sha224));
+    assert!(std::mem::size_of::<Hasher>() <= 240);
+
+    let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    assert!(check::explain_mismatch("BA7816BF 8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD", abc, Algo::Sha256)[0].contains("match once case"));
+    assert!(check::explain_mismatch("ba7816bf8f01cfea414140de5dae2223b00361a3", abc, Algo::Sha256)[0].contains("the length of a SHA-1 digest"));
+    assert!(check::explain_mismatch("ba7816bf8f01cfea", abc, Algo::Sha256)[0].contains("truncated: its 16 digits"));
+    let typo = check::explain_mismatch("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb41Off61f20015ad", abc, Algo::Sha256);
+    assert!(typo[0].contains("non-hex character 'o' at digit 52") && typo[1].starts_with("only 1 of 64"));
+    assert!(typo[4] == format!("         {}^ digit 52", " ".repeat(51)));
+    assert!(check::explain_mismatch(&hash_string("abd", Algo::Sha256), abc, Algo::Sha256)[0].contains("so the content is different"));
+    assert!(check::explain_mismatch(abc, &hex::encode(&sha3_384), Algo::Sha3_384)[0].ends_with("but this is SHA3-384"));
+
+    assert!(matches!(Algo::try_from_bits(384), Err(ShaError::UnsupportedAlgorithm(384))));
+    assert!(Algo::try_from_bits(224).unwrap() == Algo::Sha224);
+    assert!(["256", "sha256", "SHA-256", "Sha-256"].iter().all(|s| s.parse::<Algo>().unwrap() == Algo::Sha256));
+    assert!("sha-224".parse::<Algo>().unwrap() == Algo::Sha224 && "384".parse::<Algo>().is_err() && "sha".parse::<Algo>().is_err());
+    assert!(Algo::Sha224.to_string() == "SHA-224" && Hasher::new(Algo::Sha224).algorithm() == Algo::Sha224);
+    assert!(ShaError::UnsupportedAlgorithm(1).to_string().contains("use 224 or 256"));
+    assert!(matches!(Hasher::restore_state(&HasherState { len: 101, ..saved.clone() }), Err(ShaError::InvalidState(_))));
+
+    let ni = format::format_digest(&hash(b"Hello World!", Algo::Sha256), Algo::Sha256, format::Format::Ni).unwrap();
+    assert!(ni == "ni:///sha-256;f4OxZX_x_FO5LcGBSKHWXfwtSx-j1ncoSt3SABJtkGk");
+    assert!(format::parse_digest_uri("ni://example.com/sha-256;f4OxZX_x_FO5LcGBSKHWXfwtSx-j1ncoSt3SABJtkGk?ct=text/plain").unwrap().1 == hash(b"Hello World!", Algo::Sha256).to_vec());
+    let uri = format::format_digest(&abcde, Algo::Sha256, format::Format::HashUri).unwrap();
+    assert!(uri == "hash://sha256/36bbe50ed96841d10443bcb670d6554f0a34b761be67ec9c4a8ad2c0c44ca42c");
+    assert!(format::parse_digest_uri(&format!("{}?type=text/plain", uri)).unwrap() == (Algo::Sha256, abcde.clone()));
+    assert!(format::parse_digest_uri("hash://sha224/36bbe50ed96841d10443bcb670d6554f0a34b761be67ec9c4a8ad2c0c44ca42c").is_err());
+    assert!(format::parse_digest_uri("ni:///sha-256-128;f4OxZX_x-FO5LcGBSKHWXQ").is_err());
+    assert!(format::format_digest(&hash(b"", Algo::Sha224), Algo::Sha224, format::Format::Ni).is_none());
+    let ni = format::format_digest(&hash(b"abc", Algo::Sha512), Algo::Sha512, format::Format::Ni).unwrap();
+    assert!(ni == "ni:///sha-512;3a81oZNherrMQXNJriBBMRLm-k6JqX6iCp7u5ktV05ohkpkqJ0_BqDa6PCOj_uu9RU1EI2Q86A4qmslPpUyknw");
+    assert!(format::parse_digest_uri(&ni).unwrap() == (Algo::Sha512, hash(b"abc", Algo::Sha512).to_vec()));
+    assert!(format::parse_digest_uri("ni:///sha3-256;Ophdp0_iJbIEXBcta9OQvYVfCG4-nVJbRr_iRRFDFTI").unwrap().0 == Algo::Sha3_256);
+    assert!(format::parse_digest_uri("ni:///sha-384;ywB1P0WjXou1oD1pmsZQBycsMqsO3tFjGotgWkP_W-2AhgcroefMI1i67KE0yCWn").is_err());
+    assert!(check::parse_pasted_digest(&uri) == Some((Some(Algo::Sha256), abcde.clone())));
+
+    let mut sponge = keccak::Keccak::new(keccak::SHAKE128_RATE, keccak::SHAKE_PAD);
+    sponge.update(b"");
+    assert!(hex::encode(sponge.finalize(32)) == "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26");
+    let shake256 = keccak::Keccak::new(keccak::SHAKE256_RATE, keccak::SHAKE_PAD);
+    assert!(hex::encode(shake256.clone().finalize(64)) == "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f\
+        d75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be");
+    let mut squeeze = shake256.clone().finalize_xof();
+    let mut pieces = vec![0u8; 500];
+    for chunk in pieces.chunks_mut(7) {
+        squeeze.squeeze(chunk);
+    }
+    assert!(pieces == shake256.finalize(500));
+
+    let records: Vec<&[u8]> = vec![b"", b"abc", &long_message[..64], &long_message[..119], &long_message];
+    let batch = hash_batch(&records, Algo::Sha224);
+    assert!(batch.len() == 5 && records.iter().zip(&batch).all(|(r, d)| *d == hash(r, Algo::Sha224)));
+    assert!(hash_batch(&[], Algo::Sha256).is_empty());
+
+    let (d1, d2, d3) = (hash(b"a", Algo::Sha256), hash(b"b", Algo::Sha256), hash(b"c", Algo::Sha256));
+    assert!(combine::xor(&[&d1, &d2, &d3]) == combine::xor(&[&d3, &d1, &d2]) && combine::xor(&[&d1, &d1]) == Some(vec![0; 32]));
+    assert!(combine::xor(&[&d1, &d2[..28]]).is_none() && combine::xor(&[]).is_none());
+    assert!(combine::sorted_concat(&[&d1, &d2, &d3], Algo::Sha256) == combine::sorted_concat(&[&d2, &d3, &d1], Algo::Sha256));
+    assert!(combine::concat(&[&d1, &d2], Algo::Sha256) == hash(&[d1.to_vec(), d2.to_vec()].concat(), Algo::Sha256));
+    assert!(combine::concat(&[&d1, &d2], Algo::Sha256) != combine::concat(&[&d2, &d1], Algo::Sha256));
+
+    let mut set = combine::SetHasher::new(Algo::Sha256);
+    let empty = set.current();
+    set.insert(&d1);
+    set.insert(&d2);
+    set.insert(&d2);
+    let mut reordered = combine::SetHasher::new(Algo::Sha256);
+    for d in [&d2, &d1, &d3, &d2] {
+        reordered.insert(d);
+    }
+    reordered.remove(&d3);
+    assert!(set.current() == reordered.current() && set.len() == 3 && set.current() != empty);
+    set.remove(&d2);
+    assert!(set.current() != reordered.current());
+    for d in [&d1, &d2] {
+        set.remove(d);
+    }
+    assert!(set.is_empty() && set.current() == empty);
+    let mut carries = combine::SetHasher::new(Algo::Sha224);
+    for i in 0..300u32 {
+        carries.insert(&i.to_be_bytes());
+    }
+    for i in (0..300u32).rev() {
+        carries.remove(&i.to_be_bytes());
+    }
+    assert!(carries == combine::SetHasher::new(Algo::Sha224));
+
+    let mut both = multi::MultiHasher::new(&[Algo::Sha256, Algo::Sha224, Algo::Sha256]);
+    for chunk in long_message.chunks(333) {
+        both.update(chunk);
+    }
+    assert!(both.algorithms() == [Algo::Sha256, Algo::Sha224]);
+    assert!(both.finalize() == [hash(&long_message, Algo::Sha256), hash(&long_message, Algo::Sha224)]);
+
+    assert!(tuple::hash_tuple(&[b"ab", b"c"], Algo::Sha256) != tuple::hash_tuple(&[b"a", b"bc"], Algo::Sha256));
+    assert!(tuple::hash_tuple(&[b""], Algo::Sha256) != tuple::hash_tuple(&[], Algo::Sha256));
+    assert!(tuple::hash_tuple(&[b"abc"], Algo::Sha256) == hash(b"\0\0\0\0\0\0\0\x03abc", Algo::Sha256));
+
+    let jcs = json::parse(r#"{"numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+        "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/", "literals": [null, true, false]}"#).unwrap();
+    assert!(json::canonicalize(&jcs).unwrap()
+        == r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#);
+    let keys = json::parse(r#"{"\u20ac": 1, "\r": 2, "\ufb33": 3, "1": 4, "\ud83d\ude00": 5, "\u0080": 6, "\u00f6": 7}"#).unwrap();
+    assert!(json::canonicalize(&keys).unwrap() == "{\"\\r\":2,\"1\":4,\"\u{80}\":6,\"ö\":7,\"€\":1,\"😀\":5,\"\u{fb33}\":3}");
+    let numbers = json::parse("[-0, 1e21, 1e20, 1e-7, 0.000001, -1.5e-7, 123e-2, 9007199254740993]").unwrap();
+    assert!(json::canonicalize(&numbers).unwrap() == "[0,1e+21,100000000000000000000,1e-7,0.000001,-1.5e-7,1.23,9007199254740992]");
+    assert!(json::canonicalize(&json::parse(r#"{"a": 1, "a": 2}"#).unwrap()).is_err());
+    assert!(json::canonicalize(&json::parse("[1e400]").unwrap()).is_err());
+
+    let build = Sha256BuildHasher::default();
+    let mut h1 = build.build_hasher();
+    h1.write(b"abc");
+    let mut key_then_abc = Vec::from([0u8; 32]);
+    key_then_abc.extend_from_slice(b"abc");
+    assert!(h1.digest() == hash(&key_then_abc, Algo::Sha256).as_bytes());
+    assert!(h1.finish().to_be_bytes() == h1.digest()[..8]);
+    assert!(build.hash_one("key") == build.hash_one("key"));
+    assert!(Sha256BuildHasher::with_key([1u8; 32]).hash_one("key") != build.hash_one("key"));
+    let mut map = std::collections::HashMap::with_hasher(Sha256BuildHasher::random().unwrap());
+    map.insert("a", 1);
+    map.insert("b", 2);
+    assert!(map.get("a") == Some(&1));
+    assert!(map.get("b") == Some(&2));
+
+    assert!(eol::normalize(b"a\r\nb\nc\rd\r\n", eol::Eol::Lf) == b"a\nb\nc\rd\n");
+    assert!(eol::normalize(b"a\r\nb\nc\rd\r", eol::Eol::Crlf) == b"a\r\nb\r\nc\rd\r");
+    let mut split = Vec::new();
+    let mut normalizer = eol::EolNormalizer::new(eol::Eol::Lf);
+    for chunk in [&b"a\r"[..], b"", b"\nb\r", b"c\r"] {
+        normalizer.update(chunk, |b| split.extend_from_slice(b));
+    }
+    normalizer.finish(|b| split.extend_from_slice(b));
+    assert!(split == b"a\nb\rc\r");
+
+    let abcde = hash(b"abcde", Algo::Sha256);
+    assert!(abcde.to_hex().parse::<Digest>().unwrap() == abcde);
+    assert!(format!("{:#X}", abcde).parse::<Digest>().unwrap() == abcde);
+    assert!(base64::encode(abcde).parse::<Digest>().unwrap() == abcde);
+    assert!(Digest::try_from(abcde.as_bytes()).unwrap() == abcde);
+    assert!(Digest::try_from(&abcde[..28]).unwrap().algo() == Algo::Sha224);
+    assert!(Digest::try_from(&abcde[..20]).unwrap().algo() == Algo::Sha1);
+    assert!(matches!(Digest::try_from(&abcde[..24]), Err(ShaError::InvalidDigestLength(24))));
+    assert!(matches!("not a digest".parse::<Digest>(), Err(ShaError::InvalidDigestEncoding)));
+    assert!(matches!(format!("{}00", abcde).parse::<Digest>(), Err(ShaError::InvalidDigestLength(33))));
+
+    assert!(unicode::normalize("e\u{301}", unicode::Form::Nfc) == "\u{e9}");
+    assert!(unicode::normalize("\u{e9}", unicode::Form::Nfd) == "e\u{301}");
+    // Marks are put in canonical order, and a blocked mark stays separate
+    assert!(unicode::normalize("q\u{307}\u{323}", unicode::Form::Nfc) == "q\u{323}\u{307}");
+    assert!(unicode::normalize("\u{1e0b}\u{323}", unicode::Form::Nfc) == "\u{1e0d}\u{307}");
+    // Hangul syllables, and a composition exclusion which NFC leaves decomposed
+    assert!(unicode::normalize("\u{d4db}", unicode::Form::Nfd) == "\u{1111}\u{1171}\u{11b6}");
+    assert!(unicode::normalize("\u{1111}\u{1171}\u{11b6}", unicode::Form::Nfc) == "\u{d4db}");
+    assert!(unicode::normalize("\u{958}", unicode::Form::Nfc) == "\u{915}\u{93c}");
+
+    assert!(abcde.truncate(64).to_string() == "36bbe50ed96841d1");
+    assert!(abcde.truncate(12).to_string() == "36b");
+    assert!(abcde.truncate(12).as_bytes() == [0x36, 0xb0]);
+    assert!(abcde.truncate(1).as_bytes() == [0x00]);
+    assert!(abcde.truncate(256).as_bytes() == abcde.as_bytes());
+
+    let c_source = b"int a; // one\n\n  /* two\n three */ char *s = \"// kept\";\t\r\nint/**/b;\n";
+    assert!(strip::strip_comments(c_source, strip::Language::C) == b"int a;\nchar *s = \"// kept\";\nint b;\n");
+    let rust_source = b"fn f<'a>(s: &'a str) -> char { 'x' } // one\nfn g(x: &'static str) {} // two\nlet q = '\\''; // three\n";
+    assert!(strip::strip_comments(rust_source, strip::Language::C) == b"fn f<'a>(s: &'a str) -> char { 'x' }\nfn g(x: &'static str) {}\nlet q = '\\'';\n");
+    let py_source = b"# header\ndef f():\n    # note\n    return '#' + \"\"\"a\n\n  b\"\"\"  # done\n";
+    assert!(strip::strip_comments(py_source, strip::Language::Python) == b"def f():\n    return '#' + \"\"\"a\n\n  b\"\"\"\n");
+    let sh_source = b"#!/bin/sh\n# setup\n  echo $# \\# '# x' a#b # end\n";
+    assert!(strip::strip_comments(sh_source, strip::Language::Shell) == b"#!/bin/sh\necho $# \\# '# x' a#b\n");
+
+    // Round-reduced and custom-IV SHA-256, checked against an independent implementation
+    let sha256_iv = State::new(Algo::Sha256).words();
+    assert!(Sha256::with_params(sha256_iv, 64).finalize_reset() == Sha256::digest(b""));
+    let mut reduced = Sha256::with_params(sha256_iv, 20);
+    reduced.update(b"abc");
+    assert!(hex::encode(reduced.finalize_reset()) == "4bfbf29ab9df2a44ff6acd43c72a8fd50171d3f8678f01eda3f2bdffdc14efb5");
+    reduced.update(b"abc");
+    assert!(hex::encode(reduced.finalize()) == "4bfbf29ab9df2a44ff6acd43c72a8fd50171d3f8678f01eda3f2bdffdc14efb5");
+    let mut custom = Sha256::with_params([0, 1, 2, 3, 4, 5, 6, 7], 64);
+    custom.update(b"abc");
+    assert!(hex::encode(custom.finalize()) == "c34c60f0862385a9c6ab68858345049a82580ff282f9969f87661dc8c25c7d04");
+    let mut both = Sha256::with_params([0; 8], 24);
+    both.update(&b"abc".repeat(30));
+    both.reset();
+    both.update(&b"abc".repeat(30));
+    assert!(hex::encode(both.finalize()) == "1be1f1980a002df133856d7ad89775325c330c4e8d0d4c53986181219d952a99");
+
+    // Bit-oriented messages: the 5-bit CAVP vector 01101, and partial bytes either side of the
+    // padding spilling into a second block
+    let mut bits = Hasher::new(Algo::Sha256);
+    bits.update_bits(&[0x68], 5);
+    assert!(bits.bits_processed() == 5);
+    assert!(bits.finalize_digest().to_hex() == "d6d3e02a31a84a8caa9718ed6c2057be09db45e7823eb5079ce7a573a3760f95");
+    let mut bits = Sha256::new();
+    bits.update(&(1..=62).collect::<Vec<u8>>());
+    bits.update_bits(&[63, 0xab], 15);
+    assert!(hex::encode(bits.finalize()) == "0040f4f57245f6da4af2a4fb18c7e11b6c58357c80efceb59d6d57484cc07b3c");
+    let mut bits = Hasher::new(Algo::Sha256);
+    let mut message = [0u8; 56];
+    message[55] = 0x80;
+    bits.update_bits(&message, 441);
+    assert!(bits.finalize_digest().to_hex() == "dbe801c6c7c9eac7a12e567aeb54abe8e51138b61a5a46e270ba1a823c7efdf9");
+    let mut bits = Hasher::new(Algo::Sha256);
+    bits.update_bits(b"abcd", 24);
+    assert!(bits.finalize_digest() == hash(b"abc", Algo::Sha256));
+
+    let regions = firmware::parse(&format!("# name offset length digest\n\nboot 0x0 3 {}\nkernel 0X4 2 {}\ntail 4 2 {}\n",
+        hash(b"abc", Algo::Sha256), hash(b"ef", Algo::Sha224), hash(b"eg", Algo::Sha256))).unwrap();
+    assert!(regions.len() == 3 && regions[1].offset == 4 && regions[1].expected.algo() == Algo::Sha224);
+    assert!(firmware::parse("boot 0 3").unwrap_err().starts_with("line 1:"));
+    assert!(firmware::parse("boot 0x 3 00").is_err() && firmware::parse("boot 1 18446744073709551615 00").is_err());
+    let image_file = std::env::temp_dir().join(format!("sha2-selftest-image-{}", std::process::id()));
+    fs::write(&image_file, b"abcdef").unwrap();
+    let mut image = fs::File::open(&image_file).unwrap();
+    let statuses: Vec<report::Status> = regions.iter().map(|r| firmware::verify(&mut image, 6, r).status).collect();
+    assert!(statuses == [report::Status::Ok, report::Status::Ok, report::Status::Failed]);
+    let beyond = firmware::Region { offset: 5, ..regions[1].clone() };
+    assert!(firmware::verify(&mut image, 6, &beyond).status == report::Status::Missing);
+    fs::remove_file(&image_file).unwrap();
+
+    // The message schedule: the block's own 16 words, then the recurrence of FIPS 180-4 # 6.2.2
+    let block: [u8; 64] = long_message[..64].try_into().unwrap();
+    let w = expand_schedule(&block);
+    assert!(w[..16].iter().zip(block.chunks(4)).all(|(w, b)| *w == u32::from_be_bytes(b.try_into().unwrap())));
+    assert!((16..64).all(|i| w[i] == w[i - 16]
+        .wrapping_add(w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3))
+        .wrapping_add(w[i - 7])
+        .wrapping_add(w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10))));
+
+    // A minimal PE32 image: DOS header, PE signature, COFF header, a 224-byte optional header with
+    // 16 data directories, 64 bytes of "section" data, then an 8-byte certificate table
+    let mut pe = vec![0u8; 0x40 + 24 + 224];
+    pe[..2].copy_from_slice(b"MZ");
+    pe[0x3c] = 0x40;
+    pe[0x40..0x44].copy_from_slice(b"PE\0\0");
+    pe[0x40 + 20] = 224;
+    pe[0x58..0x5a].copy_from_slice(&0x10bu16.to_le_bytes());
+    pe[0x58 + 92] = 16;
+    pe.extend((0..64).map(|i| i as u8));
+    let certificate = pe.len() as u32;
+    pe[0x58 + 96 + 32..0x58 + 96 + 40].copy_from_slice(&[certificate.to_le_bytes(), 8u32.to_le_bytes()].concat());
+    pe.extend_from_slice(b"signatur");
+    let layout = authenticode::layout(&mut io::Cursor::new(&pe), pe.len() as u64).unwrap();
+    assert!(layout.checksum == 0x58 + 64 && layout.certificate_entry == 0x58 + 128);
+    assert!(layout.certificate_table == Some(certificate as u64..certificate as u64 + 8));
+    let unsigned = [&pe[..0x98], &pe[0x9c..0xd8], &pe[0xe0..certificate as usize]].concat();
+    let pe_digest = authenticode::digest(io::Cursor::new(&pe), pe.len() as u64, Algo::Sha256).unwrap();
+    assert!(pe_digest == hash(&unsigned, Algo::Sha256));
+    // Signing touches only the skipped fields, so it doesn't change the digest; anything else does
+    let mut signed = pe.clone();
+    signed[0x98..0x9c].copy_from_slice(&[1, 2, 3, 4]);
+    signed[certificate as usize..].copy_from_slice(b"SIGNATUR");
+    assert!(authenticode::digest(io::Cursor::new(&signed), pe.len() as u64, Algo::Sha256).unwrap() == pe_digest);
+    signed[0x150] ^= 1;
+    assert!(authenticode::digest(io::Cursor::new(&signed), pe.len() as u64, Algo::Sha256).unwrap() != pe_digest);
+    assert!(authenticode::layout(&mut io::Cursor::new(&pe), certificate as u64).is_err());
+    assert!(authenticode::digest(io::Cursor::new(&pe[..0x80]), 0x80, Algo::Sha256).is_err());
+    assert!(authenticode::digest(io::Cursor::new(b"ZM"), 2, Algo::Sha256).is_err());
+
+    // A minimal 64-bit ELF: the header, a build-id note, .shstrtab, and three section headers
+    let mut elf = vec![0u8; 312];
+    elf[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+    elf[40] = 120;
+    elf[52] = 64;
+    elf[58] = 64;
+    elf[60] = 3;
+    elf[62] = 2;
+    elf[64..80].copy_from_slice(&[4, 0, 0, 0, 20, 0, 0, 0, 3, 0, 0, 0, b'G', b'N', b'U', 0]);
+    elf[100..117].copy_from_slice(b"\0.note\0.shstrtab\0");
+    for (header, kind, flags, offset, size) in [(184, 7, 2, 64, 36), (248, 3, 0, 100, 17)] {
+        elf[header + 4] = kind;
+        elf[header + 8] = flags;
+        elf[header + 24] = offset;
+        elf[header + 32] = size;
+    }
+    let build_id = buildid::find(&elf).unwrap().unwrap();
+    assert!(build_id.id == [0u8; 20] && build_id.note == (64..100) && build_id.desc == (80..100));
+    let mut header = elf[..64].to_vec();
+    header[32..48].fill(0);
+    let mut shdr1 = elf[184..248].to_vec();
+    shdr1[24] = 0;
+    let mut shdr2 = elf[248..312].to_vec();
+    shdr2[24] = 0;
+    let gnu_ld = sha1::sha1(&[&header[..], &elf[120..184], &shdr1, &[0u8; 36], &shdr2].concat());
+    assert!(buildid::compute(&elf, &build_id, buildid::Style::GnuLd).unwrap().unwrap() == gnu_ld);
+    elf[80..100].copy_from_slice(&gnu_ld);
+    let build_id = buildid::find(&elf).unwrap().unwrap();
+    assert!(buildid::verify(&elf, &build_id).unwrap() == Some(buildid::Style::GnuLd));
+    elf[110] ^= 1;
+    assert!(buildid::verify(&elf, &build_id).unwrap() == Some(buildid::Style::GnuLd));
+    elf[64 + 13] ^= 1;
+    assert!(buildid::find(&elf).unwrap().is_none());
+    elf[64 + 13] ^= 1;
+    elf[80..100].fill(0);
+    let gold = sha1::sha1(&elf);
+    elf[80..100].copy_from_slice(&gold);
+    let build_id = buildid::find(&elf).unwrap().unwrap();
+    assert!(buildid::verify(&elf, &build_id).unwrap() == Some(buildid::Style::Gold));
+    elf[300] ^= 1;
+    assert!(buildid::verify(&elf, &build_id).unwrap().is_none());
+    assert!(buildid::find(b"MZ").is_err());
+    elf[40] = 200;
+    assert!(buildid::find(&elf).is_err());
+
+    // SHA-512 (FIPS 180-4 # 6.4), checked against Python's hashlib: one block, the padding's
+    // 16-byte length field spilling into a second block at 112 bytes, and several blocks
+    let sha512_abc = "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f";
+    assert!(hash_string("abc", Algo::Sha512) == sha512_abc);
+    assert!(hash_string("", Algo::Sha512).starts_with("cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce"));
+    assert!(hash(&[b'a'; 111], Algo::Sha512).to_hex().starts_with("fa9121c7b32b9e01733d034cfc78cbf6"));
+    assert!(hash(&[b'a'; 112], Algo::Sha512).to_hex().starts_with("c01d080efd492776a1c43bd23dd99d0a"));
+    assert!(hash(&[b'a'; 200], Algo::Sha512).to_hex().ends_with("e6f86e4df7ed1ae6cba71f3e98faee9f"));
+    let mut sha512 = Hasher::new(Algo::Sha512);
+    for chunk in [b'a'; 200].chunks(7) {
+        sha512.update(chunk);
+    }
+    assert!(sha512.bytes_processed() == 200 && sha512.algorithm() == Algo::Sha512);
+    assert!(sha512.finalize_digest() == hash(&[b'a'; 200], Algo::Sha512));
+    assert!(hash_batch(&[b"abc", b""], Algo::Sha512)[0].to_hex() == sha512_abc);
+    assert!(sha512_abc.parse::<Digest>().unwrap().algo() == Algo::Sha512 && "512".parse::<Algo>().unwrap() == Algo::Sha512);
+    assert!(check::algorithm_for_hex_len(128) == Some(Algo::Sha512) && check::algorithm_for_tag("SHA512") == Some(Algo::Sha512));
+    // RFC 4231 test case 2
+    assert!(hex::encode(hmac::hmac(b"Jefe", b"what do ya want for nothing?", Algo::Sha512)).starts_with("164b7a7bfcf819e2e395fbe73b56e0a3"));
+
+    // An MBR disk with a primary partition and an extended one holding two logical partitions
+    let mut disk: Vec<u8> = (0..64 * 512).map(|i| (i % 251) as u8).collect();
+    for sector in [0, 16, 24] {
+        disk[sector * 512 + 446..sector * 512 + 510].fill(0);
+        disk[sector * 512 + 510..sector * 512 + 512].copy_from_slice(&[0x55, 0xaa]);
+    }
+    let mut mbr_entry = |sector: usize, slot: usize, kind: u8, start: u32, len: u32| {
+        let at = sector * 512 + 446 + 16 * slot;
+        disk[at + 4] = kind;
+        disk[at + 8..at + 12].copy_from_slice(&start.to_le_bytes());
+        disk[at + 12..at + 16].copy_from_slice(&len.to_le_bytes());
+    };
+    mbr_entry(0, 0, 0x83, 2, 8);
+    mbr_entry(0, 1, 0x05, 16, 32);
+    mbr_entry(16, 0, 0x83, 1, 6);
+    mbr_entry(16, 1, 0x05, 8, 8);
+    mbr_entry(24, 0, 0x07, 1, 4);
+    let table = partition::read_table(&mut io::Cursor::new(&disk)).unwrap().unwrap();
+    let layout: Vec<(usize, &str, u64, u64)> = table.partitions.iter().map(|p| (p.number, p.kind.as_str(), p.offset, p.length)).collect();
+    assert!(table.scheme == partition::Scheme::Mbr);
+    assert!(layout == [(1, "0x83", 1024, 4096), (2, "0x05", 8192, 16384), (5, "0x83", 8704, 3072), (6, "0x07", 12800, 2048)]);
+    let (digests, whole) = partition::digests(&disk[..], Algo::Sha256, &table.partitions).unwrap();
+    assert!(whole == hash(&disk, Algo::Sha256) && digests[2] == Some(hash(&disk[8704..11776], Algo::Sha256)));
+    // A FAT boot sector has the 0x55aa signature too, but no valid entries
+    disk[446..510].fill(0x41);
+    assert!(partition::read_table(&mut io::Cursor::new(&disk)).unwrap().is_none());
+
+    // A GPT disk, with the EFI system partition, an unused entry and a partition past the end
+    let mut disk: Vec<u8> = (0..32 * 512).map(|i| (i % 241) as u8).collect();
+    disk[446..510].fill(0);
+    disk[446 + 4] = 0xee;
+    disk[446 + 8] = 1;
+    disk[446 + 12..446 + 16].copy_from_slice(&u32::MAX.to_le_bytes());
+    disk[510..512].copy_from_slice(&[0x55, 0xaa]);
+    disk[512..512 + 92].fill(0);
+    disk[512..520].copy_from_slice(b"EFI PART");
+    disk[512 + 72] = 2;
+    disk[512 + 80] = 4;
+    disk[512 + 84] = 128;
+    disk[1024..1024 + 4 * 128].fill(0);
+    let esp = hex::decode("28732ac11ff8d211ba4b00a0c93ec93b").unwrap();
+    disk[1024..1040].copy_from_slice(&esp);
+    disk[1024 + 32] = 6;
+    disk[1024 + 40] = 9;
+    disk[1024 + 56..1024 + 62].copy_from_slice(&[b'E', 0, b'F', 0, b'I', 0]);
+    disk[1280..1296].copy_from_slice(&hex::decode("af3dc60f838472478e793d69d8477de4").unwrap());
+    disk[1280 + 32] = 10;
+    disk[1280 + 40] = 39;
+    let table = partition::read_table(&mut io::Cursor::new(&disk)).unwrap().unwrap();
+    assert!(table.scheme == partition::Scheme::Gpt && table.sector_size == 512 && table.partitions.len() == 2);
+    assert!(table.partitions[0].kind == "c12a7328-f81f-11d2-ba4b-00a0c93ec93b" && table.partitions[0].name == "EFI");
+    assert!(table.partitions[1].number == 3 && table.partitions[1].kind == "0fc63daf-8483-4772-8e79-3d69d8477de4");
+    let (digests, _) = partition::digests(&disk[..], Algo::Sha256, &table.partitions).unwrap();
+    assert!(digests == [Some(hash(&disk[3072..5120], Algo::Sha256)), None]);
+    // A hostile header is rejected rather than allocated for
+    let mut evil = disk.clone();
+    evil[512 + 80..512 + 88].copy_from_slice(&[0, 0x40, 0, 0, 0xf8, 0xff, 0xff, 0xff]);
+    assert!(partition::read_table(&mut io::Cursor::new(&evil)).unwrap_err().to_string() == "invalid GPT header");
+    evil[512 + 84..512 + 88].copy_from_slice(&4096u32.to_le_bytes());
+    assert!(partition::read_table(&mut io::Cursor::new(&evil)).unwrap_err().to_string().contains("run past the end"));
+
+    let image: Vec<u8> = (0..2500u32).map(|i| (i % 251) as u8).collect();
+    let ranges = imaging::chunks(image.len() as u64, 1000);
+    assert!(ranges == [0..1000, 1000..2000, 2000..2500]);
+    let (hashes, total, read) = imaging::hash_ranges(&image[..], &ranges).unwrap();
+    assert!(read == 2500 && total.md5 == md5::md5(&image) && total.sha1 == sha1::sha1(&image));
+    assert!(hashes[2].as_ref().unwrap().sha256 == hash(&image[2000..], Algo::Sha256));
+    let chunks: Vec<_> = ranges.iter().cloned().zip(hashes.into_iter().map(Option::unwrap)).collect();
+    let log = imaging::write_log("disk.dd", 2500, 1000, &chunks, &total);
+    assert!(log.contains("Size: 2500 bytes (5 sectors of 512 bytes)\n"));
+    assert!(log.contains(&format!("MD5 hash calculated over data:\t\t{}\n", hex::encode(total.md5))));
+    let expected = imaging::parse_log(&log);
+    assert!(expected.len() == 15 && expected.iter().filter(|e| e.range.is_none()).count() == 6);
+    assert!(expected[3].algorithm == imaging::Algorithm::Md5 && expected[3].range.is_none() && expected[3].digest == total.md5);
+    assert!(expected[4].algorithm == imaging::Algorithm::Sha1 && expected[4].range == Some(0..1000));
+    let (hashes, _, _) = imaging::hash_ranges(&image[..2200], &[0..1000, 2000..2500]).unwrap();
+    assert!(hashes[0].is_some() && hashes[1].is_none());
+    let (bundle, len) = imaging::hash_reader(&b"abc"[..]).unwrap();
+    assert!(len == 3 && bundle.md5 == md5::md5(b"abc") && bundle.sha1 == sha1::sha1(b"abc") && bundle.sha256 == hash(b"abc", Algo::Sha256));
+
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    assert!(entropy::entropy(b"") == 0.0 && entropy::entropy(b"aaaa") == 0.0 && entropy::entropy(&all_bytes) == 8.0);
+    assert!(entropy::entropy(b"abab") == 1.0 && (entropy::entropy(b"aab") - 0.9183).abs() < 1e-4);
+    let mut measured = entropy::Measured::new(&all_bytes[..]);
+    let (bundle, _) = imaging::hash_reader(&mut measured).unwrap();
+    assert!(measured.entropy.len() == 256 && measured.entropy.bits_per_byte() == 8.0 && bundle.sha256 == hash(&all_bytes, Algo::Sha256));
+    let examiner_log = "Case number: 42\n\
+        MD5 checksum: d41d8cd98f00b204e9800998ecf8427e : verified\n\
+        SHA1 checksum: da39a3ee5e6b4b0d3255bfef95601890afd80709\n\
+        SHA-256 hash calculated over data:\te3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\n\
+        Total (md5): notadigest\n\
+        0 - 512: d41d8cd98f00b204e9800998ecf8427e\n";
+    let expected = imaging::parse_log(examiner_log);
+    assert!(expected.len() == 4 && expected[0].line == 2 && expected[0].algorithm == imaging::Algorithm::Md5);
+    assert!(expected[2].algorithm == imaging::Algorithm::Sha256 && expected[3].range == Some(0..512));
+
+    let set_file = std::env::temp_dir().join(format!("sha2-selftest-known-{}", std::process::id()));
+    let mut digests: Vec<String> = (0..500u32).map(|i| hex::encode(hash(&i.to_be_bytes(), Algo::Sha256))).collect();
+    digests.sort();
+    fs::write(&set_file, format!("# known files\n\n{}\n", digests.join("  file\n"))).unwrap();
+    let mut set = knownset::KnownSet::open(set_file.to_str().unwrap()).unwrap();
+    assert!(set.width() == Some(32));
+    assert!((0..500u32).all(|i| set.contains(hash(&i.to_be_bytes(), Algo::Sha256).as_bytes()).unwrap()));
+    assert!(!(500..600u32).any(|i| set.contains(hash(&i.to_be_bytes(), Algo::Sha256).as_bytes()).unwrap()));
+    let nsrl = "\"SHA-1\",\"MD5\",\"CRC32\",\"FileName\",\"FileSize\",\"ProductCode\",\"OpSystemCode\",\"SpecialCode\"\n\
+        \"0000004DA6391F7F5D2F7FCCF36CEBDA60C6EA02\",\"0E53C14A3E48D94FF596A2824307B492\",\"AA6A7B16\",\"00br2026.gif\",2226,228,\"WIN\",\"\"\n\
+        \"A9993E364706816ABA3E25717850C26C9CD0D89D\",\"900150983CD24FB0D6963F7D28E17F72\",\"352441C2\",\"abc.txt\",3,1,\"358\",\"\"\n";
+    fs::write(&set_file, nsrl).unwrap();
+    let mut set = knownset::KnownSet::open(set_file.to_str().unwrap()).unwrap();
+    let abc = knownset::hash_reader(&b"abc"[..], set.width().unwrap()).unwrap().unwrap();
+    assert!(set.contains(&abc).unwrap() && !set.contains(&sha1::sha1(b"abd")).unwrap());
+    assert!(knownset::hash_reader(&b"abc"[..], 16).unwrap().unwrap() == md5::md5(b"abc"));
+    assert!(knownset::supported(28) && !knownset::supported(2));
+    fs::remove_file(&set_file).unwrap();
+
+    let feed = format!(
+        "# sha256_hash,signature\n\"sha256_hash\",\"signature\"\n{},Emotet\n{}\n\n{}; Qakbot\nabcd\n",
+        hex::encode(hash(b"abc", Algo::Sha256)),
+        hex::encode(md5::md5(b"abc")),
+        hex::encode(sha1::sha1(b"abd"))
+    );
+    let (blocklist, unsupported) = knownset::Blocklist::parse(&feed);
+    assert!(blocklist.len() == 3 && blocklist.widths() == [16, 20, 32] && unsupported == [7]);
+    let digests = knownset::hash_reader_widths(&b"abc"[..], blocklist.widths()).unwrap();
+    assert!(digests[0] == md5::md5(b"abc") && digests[1] == sha1::sha1(b"abc") && digests[2] == hash(b"abc", Algo::Sha256).as_bytes());
+    assert!(blocklist.lookup(&digests[2]) == Some("Emotet") && blocklist.lookup(&digests[0]) == Some("") && blocklist.lookup(&digests[1]).is_none());
+    assert!(blocklist.lookup(&sha1::sha1(b"abd")) == Some("Qakbot"));
+    assert!(knownset::algorithm_name(16) == Some("MD5") && knownset::algorithm_name(32) == Some("SHA256") && knownset::algorithm_name(2).is_none());
+
+    assert!(gitobject::blob_id(&b"hello\n"[..], 6, Algo::Sha1).unwrap().to_hex() == "ce013625030ba8dba906f756967f9e9ca394464a");
+    assert!(gitobject::blob_id(&b"hello\n"[..], 6, Algo::Sha256).unwrap().to_hex() == "2cf8d83d9ee29543b34a87727421fdecb7e3f3a183d337639025de576db9ebb4");
+    assert!(gitobject::blob_id(&b"hello\n!"[..], 6, Algo::Sha1).is_err() && gitobject::blob_id(&b"hello"[..], 6, Algo::Sha1).is_err());
+    let tree = gitobject::parse_ls_tree("100644 blob ce013625030ba8dba906f756967f9e9ca394464a\ta.txt\n\
+        120000 blob 8d14cbf983b3fad683171c9418998d9f68340823\tlink\n\
+        100644 blob 45a61541bfc14a021aae8b0cf7081d7c6108d569      4\t\"d/\\303\\251 \\\"q\\\".txt\"\n\
+        040000 tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\tempty\n").unwrap();
+    assert!(tree.len() == 4 && tree[1].is_symlink() && tree[3].kind == "tree");
+    assert!(tree[2].path == "d/\u{e9} \"q\".txt" && hex::encode(&tree[2].id) == "45a61541bfc14a021aae8b0cf7081d7c6108d569");
+    let tree = gitobject::parse_ls_tree("100644 blob 2cf8d83d9ee29543b34a87727421fdecb7e3f3a183d337639025de576db9ebb4\t\"q\".txt\0").unwrap();
+    assert!(tree[0].path == "\"q\".txt" && gitobject::object_format(tree[0].id.len() * 2) == Some(Algo::Sha256));
+    assert!(gitobject::parse_ls_tree("a.txt\n100644 blob ce01\tb.txt\n") == Err(1));
+
+    let prf = kdf::tls12_prf(
+        &hex::decode("9bbe436ba940f017b17652849a71db35").unwrap(),
+        b"test label",
+        &hex::decode("a0ba9f936cda311827a6f796ffd5198c").unwrap(),
+        100,
+        Algo::Sha256
+    );
+    assert!(hex::encode(prf).eq("e3f229ba727be17b8d122620557cd453c2aab21d07c3d495329b52d4e61edb5a\
+        6b301791e90d35c9c9a46b4e14baf9af0fa022f7077def17abfd3797c0564bab4fbc91666e9def9b97fce34f79\
+        6789baa48082d122ee42c5a72e5a5110fff70187347b66"));
+
+    println!("Tests completed successfully!");
+}
+
+/**
+ * Ends the process with a readable message rather than a panic when an operation fails: the
+ * context and the error on stderr, then exit status 1.
+ */
+trait OrExit<T> {
+    fn or_exit (self, context: &str) -> T;
+}
+
+impl<T, E: std::fmt::Display> OrExit<T> for Result<T, E> {
+    fn or_exit (self, context: &str) -> T {
+        self.unwrap_or_else(|e| fail(context, e))
+    }
+}
+
+impl<T> OrExit<T> for Option<T> {
+    fn or_exit (self, context: &str) -> T {
+        self.unwrap_or_else(|| {
+            log_error!("{}", context);
+            std::process::exit(1);
+        })
+    }
+}
+
+fn
+fail (context: &str, e: impl std::fmt::Display) -> ! {
+    log_error!("{}: {}", context, e);
+    std::process::exit(1);
+}
+
+/**
+ * Parses durations such as "250ms", "2s" or "1.5s".
+ */
+fn
+parse_duration (s: &str) -> Option<std::time::Duration> {
+    let (value, scale) = if let Some(v) = s.strip_suffix("ms") {
+        (v, 0.001)
+    } else if let Some(v) = s.strip_suffix('s') {
+        (v, 1.0)
+    } else {
+        (s, 1.0)
+    };
+
+    value.trim().parse::<f64>().ok()
+        .filter(|v| v.is_finite() && *v > 0.0)
+        .map(|v| std::time::Duration::from_secs_f64(v * scale))
+}
+
+/**
+ * Parses byte sizes such as "4096", "512K", "4MiB" or "1GiB". Suffixes are binary multiples.
+ */
+fn
+parse_size (s: &str) -> Option<u64> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return None
+    };
+
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/**
+ * Handles the `kdf` subcommand and its key derivation functions.
+ */
+fn
+run_kdf (matches: &ArgMatches, algo: Algo) {
+    if let Some(("pbkdf2", sub)) = matches.subcommand() {
+        let iterations = match sub.get_one::<String>("calibrate") {
+            Some(d) => {
+                let target = parse_duration(d).or_exit("invalid duration; use e.g. 250ms or 1.5s");
+                let iterations = kdf::calibrate_pbkdf2(target, algo);
+                println!("iterations: {}", iterations);
+                iterations
+            },
+            None => *sub.get_one::<u32>("iterations").or_exit("either --iterations or --calibrate is required")
+        };
+
+        // Calibration on its own only reports the work factor
+        if let Some(salt) = sub.get_one::<String>("salt-hex") {
+            let salt = hex::decode(salt).or_exit("salt must be valid hex");
+            let password = match sub.get_one::<String>("password") {
+                Some(p) => p.clone(),
+                None => prompt_secret("Password: ").or_exit("unable to read password")
+            };
+            let length = *sub.get_one::<usize>("length").unwrap();
+
+            println!("{}", hex::encode(kdf::pbkdf2(password.as_bytes(), &salt, iterations, length, algo)));
+        }
+    }
+
+    if let Some(("tls12-prf", sub)) = matches.subcommand() {
+        let secret = hex::decode(sub.get_one::<String>("secret-hex").unwrap()).or_exit("secret must be valid hex");
+        let seed = hex::decode(sub.get_one::<String>("seed-hex").unwrap()).or_exit("seed must be valid hex");
+        let label = sub.get_one::<String>("label").unwrap();
+        let length = *sub.get_one::<usize>("length").unwrap();
+
+        println!("{}", hex::encode(kdf::tls12_prf(&secret, label.as_bytes(), &seed, length, algo)));
+    }
+}
+
+/**
+ * Compares a digest of `algo` against the expected one the user pasted, from the clipboard or at
+ * the --interactive prompt, printing OK or FAILED. Exits 1 on a mismatch, and 2 if `source`
+ * doesn't hold a digest of the same algorithm. With `explain`, says what looks wrong on stderr.
+ */
+fn
+verify_pasted (pasted: &str, source: &str, digest: &[u8], algo: Algo, explain: bool) {
+    let Some((expected_algo, expected)) = check::parse_pasted_digest(pasted) else {
+        log_error!("{} doesn't hold a recognisable digest", source);
+        if explain {
+            print_hints(&check::explain_mismatch(pasted.trim(), &hex::encode(digest), algo));
+        }
+        std::process::exit(2);
+    };
+
+    // Unlabelled hex only names its algorithm by its length, which SHA-2 and SHA-3 share
+    let expected_algo = match expected_algo {
+        None if expected.len() != digest.len() => check::algorithm_for_hex_len(expected.len() * 2),
+        expected_algo => expected_algo
+    };
+    if let Some(other) = expected_algo.filter(|other| *other != algo) {
+        log_error!("{} holds {} digest; pass --algo {}", source, check::with_article(other), other.to_string().to_ascii_lowercase());
+        std::process::exit(2);
+    }
+    if expected == digest {
+        println!("OK");
+    } else {
+        println!("FAILED");
+        if explain {
+            print_hints(&check::explain_mismatch(&hex::encode(&expected), &hex::encode(digest), algo));
+        }
+        std::process::exit(1);
+    }
+}
+
+fn
+print_hints (hints: &[String]) {
+    for hint in hints {
+        eprintln!("    {}", hint);
+    }
+}
+
+/**
+ * Warns that a digest is being computed with a broken algorithm, which can confirm a file
+ * against a legacy digest but can't rule out one crafted to collide with it.
+ */
+fn
+warn_deprecated (algo: Algo) {
+    if algo.is_deprecated() {
+        log_warn!("{} is deprecated: collisions are practical, so a match doesn't rule out a crafted file", algo);
+    }
+}
+
+/**
+ * Reads a line from the controlling terminal with echo disabled, so secrets typed at the prompt
+ * don't appear on screen. Falls back to a plain read of stdin when there is no terminal.
+ */
+fn
+prompt_secret (prompt: &str) -> io::Result<String> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+
+    let stty = |arg: &str| {
+        fs::File::open("/dev/tty").and_then(|tty| {
+            std::process::Command::new("stty").arg(arg).stdin(tty).status()
+        })
+    };
+
+    let echo_disabled = matches!(stty("-echo"), Ok(status) if status.success());
+
+    let mut line = String::new();
+    let result = match fs::File::open("/dev/tty") {
+        Ok(tty) => io::BufReader::new(tty).read_line(&mut line),
+        Err(_) => io::stdin().read_line(&mut line)
+    };
+
+    if echo_disabled {
+        let _ = stty("echo");
+        eprintln!();
+    }
+
+    result?;
+    Ok(line.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+/**
+ * Resolves the HMAC key from, in order of precedence, --key-hex, --key-file, --key-env, or an
+ * interactive no-echo prompt. Keys read from a file, environment variable or the prompt are used
+ * as raw bytes.
+ */
+fn
+read_key (matches: &ArgMatches) -> Vec<u8> {
+    if let Some(k) = matches.get_one::<String>("key-hex") {
+        return hex::decode(k).or_exit("key must be valid hex");
+    }
+
+    if let Some(f) = matches.get_one::<String>("key-file") {
+        return fs::read(f).or_exit("unable to read key file");
+    }
+
+    if let Some(var) = matches.get_one::<String>("key-env") {
+        return std::env::var_os(var)
+            .or_exit("key environment variable is not set")
+            .into_encoded_bytes();
+    }
+
+    prompt_secret("HMAC key: ").or_exit("unable to read key").into_bytes()
+}
+
+/**
+ * Adds the --key-hex/--key-file/--key-env options read by `read_key`.
+ */
+fn
+key_args (command: Command) -> Command {
+    command
+        .arg(arg!(--"key-hex" <HEX> "The key, hex-encoded").required(false))
+        .arg(arg!(--"key-file" <PATH> "Read the key from a file").required(false))
+        .arg(arg!(--"key-env" <VAR> "Read the key from an environment variable").required(false))
+        .group(clap::ArgGroup::new("key").args(["key-hex", "key-file", "key-env"]).multiple(false))
+}
+
+/**
+ * Handles the `pseudonymize` subcommand: copies CSV from stdin to stdout, replacing the selected
+ * columns with the hex digest of their values, or their HMAC if a key is given. Without a key,
+ * low-entropy values such as email addresses can be recovered by guessing, so keying is
+ * recommended. Empty fields are left empty.
+ */
+fn
+run_pseudonymize (matches: &ArgMatches, algo: Algo) {
+    let delimiter = match matches.get_one::<String>("delimiter").unwrap().as_bytes() {
+        [d] => *d,
+        _ => {
+            log_error!("the delimiter must be a single byte");
+            std::process::exit(2);
+        }
+    };
+    let selection: Vec<String> = matches.get_many::<String>("columns").unwrap()
+        .flat_map(|c| c.split(','))
+        .map(|c| c.trim().to_string())
+        .collect();
+    let key = if matches.contains_id("key") { Some(read_key(matches)) } else { None };
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut input = stdin.lock();
+    let mut out = io::BufWriter::new(stdout.lock());
+
+    let header = if matches.get_flag("header") {
+        let header = csv::read_record(&mut input, delimiter).or_exit("unable to read CSV");
+        if let Some(ref h) = header {
+            out.write_all(&csv::format_record(h, delimiter)).and_then(|_| out.write_all(b"\n")).or_exit("unable to write stdout");
+        }
+        header
+    } else {
+        None
+    };
+
+    let columns = csv::resolve_columns(&selection, header.as_deref()).unwrap_or_else(|e| {
+        log_error!("{}", e);
+        std::process::exit(2);
+    });
+
+    while let Some(mut record) = csv::read_record(&mut input, delimiter).or_exit("unable to read CSV") {
+        for &column in &columns {
+            if let Some(field) = record.get_mut(column).filter(|f| !f.is_empty()) {
+                let digest = match key {
+                    Some(ref k) => hmac::hmac(k, field, algo),
+                    None => digest(field, algo)
+                };
+                *field = hex::encode(digest).into_bytes();
+            }
+        }
+        out.write_all(&csv::format_record(&record, delimiter)).and_then(|_| out.write_all(b"\n")).or_exit("unable to write stdout");
+    }
+}
+
+/**
+ * Handles the `uniq` subcommand: copies stdin to stdout, dropping every line whose digest has
+ * already been seen. Unlike `sort -u`, input order is kept and output starts immediately.
+ */
+fn
+run_uniq (matches: &ArgMatches, algo: Algo) {
+    let memory = parse_size(matches.get_one::<String>("memory").unwrap())
+        .or_exit("invalid memory size; use e.g. 512MiB");
+    let mut seen = uniq::SeenSet::new((memory / uniq::ENTRY_COST) as usize);
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut input = stdin.lock();
+    let mut out = io::BufWriter::new(stdout.lock());
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        if input.read_until(b'\n', &mut line).or_exit("unable to read stdin") == 0 {
+            break;
+        }
+
+        let content = line.strip_suffix(b"\n").unwrap_or(&line);
+        if seen.insert(&digest(content, algo)) {
+            out.write_all(content).or_exit("unable to write stdout");
+            out.write_all(b"\n").or_exit("unable to write stdout");
+        }
+    }
+
+    out.flush().or_exit("unable to write stdout");
+
+    if seen.evicted > 0 {
+        log_warn!("memory limit reached; {} digests were evicted, so some duplicates may remain", seen.evicted);
+    }
+}
+
+/**
+ * The digest a Bloom filter item is keyed by. Lines are hashed with SHA-256 regardless of --algo
+ * so that filters built and checked with different settings agree; with `digests`, each line is
+ * instead expected to start with a hex digest (e.g. sha256sum output), which is used as-is.
+ */
+fn
+bloom_item_digest (line: &str, digests: bool) -> Option<Vec<u8>> {
+    if digests {
+        let token = line.split_whitespace().next()?;
+        hex::decode(token).ok().filter(|d| d.len() >= 16)
+    } else {
+        Some(digest(line.as_bytes(), Algo::Sha256))
+    }
+}
+
+/**
+ * Handles the `bloom` subcommand: `build` creates a filter from lines on stdin, and `check`
+ * queries it, printing "maybe" or "no" for each item and exiting 1 if any item is absent.
+ */
+fn
+run_bloom (matches: &ArgMatches) {
+    match matches.subcommand() {
+        Some(("build", sub)) => {
+            let digests = sub.get_flag("digests");
+            let rate = *sub.get_one::<f64>("fp-rate").unwrap();
+            if !(rate > 0.0 && rate < 1.0) {
+                log_error!("--fp-rate must be between 0 and 1");
+                std::process::exit(2);
+            }
+
+            let mut items: Vec<Vec<u8>> = Vec::new();
+            for (number, line) in io::stdin().lock().lines().enumerate() {
+                let line = line.or_exit("unable to read stdin");
+                match bloom_item_digest(&line, digests) {
+                    Some(d) => items.push(d),
+                    None if line.trim().is_empty() => {},
+                    None => log_warn!("line {}: not a hex digest of at least 128 bits; skipped", number + 1)
+                }
+            }
+
+            let expected = sub.get_one::<u64>("expected").copied().unwrap_or(items.len() as u64);
+            let mut filter = bloom::BloomFilter::with_rate(expected, rate);
+            for item in &items {
+                filter.insert(item);
+            }
+
+            filter.save(sub.get_one::<String>("output").unwrap()).or_exit("unable to write bloom filter");
+            log_info!("{} items, {} bits, {} hash functions", filter.count, filter.m, filter.k);
+        },
+        Some(("check", sub)) => {
+            let filter = bloom::BloomFilter::load(sub.get_one::<String>("filter").unwrap())
+                .or_exit("unable to read bloom filter");
+            let digests = sub.get_flag("digests");
+
+            let queries: Vec<String> = match sub.get_many::<String>("items") {
+                Some(items) => items.cloned().collect(),
+                None => io::stdin().lock().lines().map(|l| l.or_exit("unable to read stdin")).collect()
+            };
+
+            let mut absent = 0;
+            for query in &queries {
+                match bloom_item_digest(query, digests) {
+                    Some(d) if filter.contains(&d) => println!("maybe\t{}", query),
+                    Some(_) => {
+                        println!("no\t{}", query);
+                        absent += 1;
+                    },
+                    None => log_warn!("'{}' is not a hex digest of at least 128 bits; skipped", query)
+                }
+            }
+
+            if absent > 0 {
+                std::process::exit(1);
+            }
+        },
+        _ => unreachable!()
+    }
+}
+
+/**
+ * Handles the `shard` subcommand: assigns each line of stdin (a record key, file name, ...) to a
+ * bucket by its digest, or its HMAC if a key is given, printing "<bucket>\t<line>".
+ */
+fn
+run_shard (matches: &ArgMatches, algo: Algo) {
+    let buckets = *matches.get_one::<u32>("buckets").unwrap();
+    let key = if matches.contains_id("key") { Some(read_key(matches)) } else { None };
+
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+
+    for line in io::stdin().lock().lines() {
+        let line = line.or_exit("unable to read stdin");
+        let digest = match key {
+            Some(ref k) => hmac::hmac(k, line.as_bytes(), algo),
+            None => digest(line.as_bytes(), algo)
+        };
+        writeln!(out, "{}\t{}", shard::jump_bucket(&digest, buckets), line).or_exit("unable to write stdout");
+    }
+}
+
+/**
+ * Handles the `shake` subcommand. Input is absorbed and output squeezed a buffer at a time, so
+ * gigabytes of output can be written without holding them in memory.
+ */
+fn
+run_shake (matches: &ArgMatches) {
+    let rate = match matches.get_one::<String>("bits").unwrap().as_str() {
+        "128" => keccak::SHAKE128_RATE,
+        _ => keccak::SHAKE256_RATE
+    };
+    let len = parse_size(matches.get_one::<String>("output-bytes").unwrap())
+        .or_exit("invalid output length; use e.g. 64 or 1GiB");
+
+    let mut sponge = keccak::Keccak::new(rate, keccak::SHAKE_PAD);
+    match (matches.get_one::<String>("string"), matches.get_one::<String>("path")) {
+        (Some(text), None) => sponge.update(text.as_bytes()),
+        (None, Some(f)) => {
+            let file = fs::File::open(f).or_exit("unable to open file");
+            io::copy(&mut io::BufReader::new(file), &mut sponge).or_exit("unable to read data");
+        },
+        _ => {
+            io::copy(&mut io::stdin().lock(), &mut sponge).or_exit("unable to read stdin");
+        }
+    }
+    let mut output = sponge.finalize_xof().take(len);
+
+    match matches.get_one::<String>("output") {
+        Some(path) => {
+            let mut out = io::BufWriter::new(fs::File::create(path).or_exit("unable to create output file"));
+            io::copy(&mut output, &mut out).or_exit("unable to write output file");
+            out.flush().or_exit("unable to write output file");
+        },
+        None => {
+            let stdout = io::stdout();
+            let mut out = io::BufWriter::new(stdout.lock());
+            let mut buffer = vec![0u8; 1 << 16];
+            loop {
+                let read = output.read(&mut buffer).or_exit("unable to squeeze output");
+                if read == 0 {
+                    break;
+                }
+                out.write_all(hex::encode(&buffer[..read]).as_bytes()).or_exit("unable to write stdout");
+            }
+            writeln!(out).or_exit("unable to write stdout");
+        }
+    }
+}
+
+/**
+ * Handles the `gen` subcommand: writes --size bytes of SHAKE256(seed) to a file, hashing them on
+ * the way out, so the same seed and size give the same file and digest on any machine.
+ */
+fn
+run_gen (matches: &ArgMatches, algo: Algo) {
+    let path = matches.get_one::<String>("file").unwrap();
+    let size = parse_size(matches.get_one::<String>("size").unwrap())
+        .or_exit("invalid size; use e.g. 10GiB");
+
+    let mut sponge = keccak::Keccak::new(keccak::SHAKE256_RATE, keccak::SHAKE_PAD);
+    sponge.update(matches.get_one::<String>("seed").unwrap().as_bytes());
+
+    let file = fs::File::create(path).or_exit("unable to create output file");
+    let mut out = stream::HashWriter::new(io::BufWriter::new(file), algo);
+    io::copy(&mut sponge.finalize_xof().take(size), &mut out).or_exit("unable to write output file");
+    let (_, digest) = out.finish().or_exit("unable to write output file");
+
+    println!("{}  {}", digest, path);
+}
+
+/**
+ * Handles the `combine` subcommand: folds hex digests, given as arguments or one per line on
+ * stdin, into a single fingerprint. Lines of a checksum file work too, since only the first
+ * field of each line is read.
+ */
+fn
+run_combine (matches: &ArgMatches, algo: Algo) {
+    let mode = combine::Mode::from_name(matches.get_one::<String>("mode").unwrap()).unwrap();
+
+    let lines: Vec<String> = match matches.get_many::<String>("digests") {
+        Some(digests) => digests.cloned().collect(),
+        None => io::stdin().lock().lines().collect::<io::Result<Vec<String>>>().or_exit("unable to read stdin")
+    };
+
+    let digests: Vec<Vec<u8>> = lines.iter()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|field| hex::decode(field).or_exit("digests must be valid hex"))
+        .collect();
+    let refs: Vec<&[u8]> = digests.iter().map(|d| d.as_slice()).collect();
+
+    match combine::combine(&refs, mode, algo) {
+        Some(combined) => println!("{}", hex::encode(combined)),
+        None => {
+            log_error!("xor needs at least one digest, and all of the same length");
+            std::process::exit(2);
+        }
+    }
+}
+
+/**
+ * Handles the `multi` subcommand: reads the file once, computing every algorithm needed. With
+ * --expect, checks each published digest, telling its algorithm from its length; otherwise
+ * prints a BSD-style line per algorithm, which `check` reads back.
+ */
+fn
+run_multi (matches: &ArgMatches) {
+    let path = matches.get_one::<String>("file").unwrap();
+
+    let expected: Vec<Digest> = matches.get_many::<String>("expect").unwrap_or_default().map(|text| {
+        text.parse::<Digest>().unwrap_or_else(|e| {
+            log_error!("invalid --expect {}: {}", text, e);
+            std::process::exit(e.exit_code());
+        })
+    }).collect();
+
+    // Deprecated algorithms only when a published digest asks for one
+    let algos: Vec<Algo> = if expected.is_empty() {
+        Algo::ALL.iter().copied().filter(|algo| !algo.is_deprecated()).collect()
+    } else {
+        expected.iter().map(|d| d.algo()).collect()
+    };
+    let mut hasher = multi::MultiHasher::new(&algos);
+    let file = fs::File::open(path).or_exit("unable to open file");
+    hasher.update_reader(io::BufReader::new(file)).or_exit("unable to read file");
+    let computed: Vec<(Algo, Digest)> = hasher.algorithms().into_iter().zip(hasher.finalize()).collect();
+    let digest_for = |algo: Algo| computed.iter().find(|(a, _)| *a == algo).unwrap().1;
+
+    if expected.is_empty() {
+        for (algo, digest) in &computed {
+            println!("{} ({}) = {}", algo.tag(), path, digest);
+        }
+        return;
+    }
+
+    let mut failed = false;
+    for digest in &expected {
+        if digest_for(digest.algo()) == *digest {
+            println!("{}: {}: OK", path, digest.algo());
+        } else {
+            println!("{}: {}: FAILED", path, digest.algo());
+            failed = true;
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/**
+ * Handles the `tuple` subcommand, hashing its arguments as length-prefixed fields.
+ */
+fn
+run_tuple (matches: &ArgMatches, algo: Algo) {
+    let fields: Vec<Vec<u8>> = matches.get_many::<String>("fields").unwrap_or_default().map(|field| {
+        if matches.get_flag("hex") {
+            hex::decode(field).or_exit("fields must be valid hex with --hex")
+        } else {
+            field.as_bytes().to_vec()
+        }
+    }).collect();
+    let refs: Vec<&[u8]> = fields.iter().map(|f| f.as_slice()).collect();
+
+    println!("{}", tuple::hash_tuple(&refs, algo));
+}
+
+/**
+ * Handles the `repro-diff` subcommand: hashes both trees and lists every path whose content
+ * differs or which only one tree has, with sizes and digests. Exits 1 if the trees differ.
+ */
+fn
+run_repro_diff (matches: &ArgMatches, algo: Algo) {
+    let left_root = matches.get_one::<String>("left").unwrap();
+    let right_root = matches.get_one::<String>("right").unwrap();
+    let respect_gitignore = matches.get_flag("respect-gitignore");
+
+    let left = treediff::scan(left_root, algo, respect_gitignore).or_exit("unable to read the first tree");
+    let right = treediff::scan(right_root, algo, respect_gitignore).or_exit("unable to read the second tree");
+    let comparison = treediff::compare(&left, &right);
+
+    let jsonl = matches.get_one::<String>("report").is_some();
+    let info = |i: &treediff::FileInfo| format!("{{\"bytes\":{},\"digest\":\"{}\"}}", i.bytes, i.digest);
+    for change in &comparison.changes {
+        let path = change.path();
+        match (change, jsonl) {
+            (treediff::Change::Differs(_, l, r), true) => println!("{{\"status\":\"differs\",\"path\":{},\"left\":{},\"right\":{}}}",
+                report::json_string(path), info(l), info(r)),
+            (treediff::Change::OnlyLeft(_, l), true) => println!("{{\"status\":\"only-left\",\"path\":{},\"left\":{}}}",
+                report::json_string(path), info(l)),
+            (treediff::Change::OnlyRight(_, r), true) => println!("{{\"status\":\"only-right\",\"path\":{},\"right\":{}}}",
+                report::json_string(path), info(r)),
+            (treediff::Change::Differs(_, l, r), false) => {
+                println!("differs: {}", path);
+                println!("    {}: {} bytes, {}", left_root, l.bytes, l.digest);
+                println!("    {}: {} bytes, {}", right_root, r.bytes, r.digest);
+            },
+            (treediff::Change::OnlyLeft(_, l), false) => println!("only in {}: {} ({} bytes)", left_root, path, l.bytes),
+            (treediff::Change::OnlyRight(_, r), false) => println!("only in {}: {} ({} bytes)", right_root, path, r.bytes)
+        }
+    }
+
+    if !jsonl {
+        let count = |f: fn(&treediff::Change) -> bool| comparison.changes.iter().filter(|c| f(c)).count();
+        println!("{} identical, {} differ, {} only in {}, {} only in {}",
+            comparison.identical,
+            count(|c| matches!(c, treediff::Change::Differs(..))),
+            count(|c| matches!(c, treediff::Change::OnlyLeft(..))), left_root,
+            count(|c| matches!(c, treediff::Change::OnlyRight(..))), right_root);
+    }
+
+    if !comparison.is_identical() {
+        std::process::exit(1);
+    }
+}
+
+/**
+ * Handles the `hmac` subcommand. Files are streamed through the MAC rather than read into memory.
+ */
+fn
+run_hmac (matches: &ArgMatches, algo: Algo) {
+    let key = read_key(matches);
+    let mut mac = hmac::Hmac::new(algo, &key);
+
+    match (matches.get_one::<String>("string"), matches.get_one::<String>("path")) {
+        (Some(text), None) => mac.update(text.as_bytes()),
+        (None, Some(f)) => {
+            let file = fs::File::open(f).or_exit("unable to open file");
+            mac.update_reader(io::BufReader::new(file)).or_exit("unable to read data");
+        },
+        _ => {
+            log_error!("no text provided!");
+            return;
+        }
+    }
+
+    match matches.get_one::<String>("verify") {
+        Some(tag) => {
+            let tag = hex::decode(tag).or_exit("tag must be valid hex");
+            if mac.verify(&tag) {
+                println!("OK");
+            } else {
+                println!("FAILED");
+                std::process::exit(1);
+            }
+        },
+        None => println!("{}", hex::encode(mac.finalize()))
+    }
+}
+
+/**
+ * Handles the `htpasswd` subcommand, printing a {SHA} entry or checking one from an existing file.
+ */
+fn
+run_htpasswd (matches: &ArgMatches) {
+    let user = matches.get_one::<String>("user").unwrap();
+    let password = match matches.get_one::<String>("password") {
+        Some(p) => p.clone(),
+        None => prompt_secret("Password: ").or_exit("unable to read password")
+    };
+
+    match matches.get_one::<String>("verify") {
+        Some(f) => {
+            let contents = fs::read_to_string(f).or_exit("unable to read htpasswd file");
+            match htpasswd::verify(&contents, user, &password) {
+                Some(true) => println!("OK"),
+                Some(false) => {
+                    println!("FAILED");
+                    std::process::exit(1);
+                },
+                None => {
+                    log_error!("no {{SHA}} entry for {}", user);
+                    std::process::exit(1);
+                }
+            }
+        },
+        None => println!("{}", htpasswd::sha_entry(user, &password))
+    }
+}
+
+/**
+ * Handles the `ldap-hash` subcommand, printing a salted userPassword value or verifying one.
+ */
+fn
+run_ldap_hash (matches: &ArgMatches) {
+    let password = match matches.get_one::<String>("password") {
+        Some(p) => p.clone(),
+        None => prompt_secret("Password: ").or_exit("unable to read password")
+    };
+
+    if let Some(value) = matches.get_one::<String>("verify") {
+        match ldap::verify(value, password.as_bytes()) {
+            Some(true) => println!("OK"),
+            Some(false) => {
+                println!("FAILED");
+                std::process::exit(1);
+            },
+            None => {
+                log_error!("unsupported or malformed userPassword value");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let scheme = ldap::Scheme::from_name(matches.get_one::<String>("scheme").unwrap()).unwrap();
+    let salt = match matches.get_one::<String>("salt-hex") {
+        Some(s) => hex::decode(s).or_exit("salt must be valid hex"),
+        None => random::random_bytes(8).or_exit("unable to generate salt")
+    };
+
+    println!("{}", ldap::hash_password(scheme, password.as_bytes(), &salt));
+}
+
+/**
+ * Handles the `check` subcommand, verifying files listed in sha224sum/sha256sum-style checksum
+ * files. Exits with a nonzero status if any file fails verification.
+ */
+fn
+run_check (matches: &ArgMatches) {
+    let jsonl = matches.get_one::<String>("report").map(|r| r == "jsonl").unwrap_or(false);
+    let jobs = *matches.get_one::<u32>("jobs").unwrap() as usize;
+    let mut entries: Vec<check::Entry> = Vec::new();
+
+    let ignore_missing = matches.get_flag("ignore-missing");
+    let mut not_present = 0;
+    let explain = matches.get_flag("explain");
+    // Untagged lines are told apart by their length, which SHA-3 shares with SHA-2, so an explicit
+    // --algo decides for the lines its digests fit, e.g. sha3sum output
+    let untagged = matches.get_one::<String>("algo").and_then(|s| s.parse::<Algo>().ok());
+
+    for checksum_file in matches.get_many::<String>("file").unwrap() {
+        // CHECKSUM files for distribution images are usually clearsigned
+        let contents = pgp::strip_clearsign(&fs::read_to_string(checksum_file).or_exit("unable to read checksum file"));
+
+        // Saved `certutil -hashfile` or `Get-FileHash | Export-Csv` output from Windows
+        let mut windows = check::parse_certutil(&contents);
+        if windows.is_empty() {
+            windows = check::parse_get_filehash_csv(&contents);
+        }
+        if !windows.is_empty() {
+            for (tag, expected, path) in windows {
+                match check::algorithm_for_tag(&tag).filter(|algo| expected.len() == algo.digest_len() * 2) {
+                    Some(algo) => entries.push(check::Entry { expected, path, algo }),
+                    None => log_warn!("skipping {}: unsupported algorithm {}", path, tag)
+                }
+            }
+            continue;
+        }
+
+        for (line_number, line) in contents.lines().enumerate() {
+            if line.starts_with('#') {
+                continue;
+            }
+
+            let parsed = match check::parse_tagged_line(line) {
+                Some((tag, digest, path)) => check::algorithm_for_tag(&tag)
+                    .filter(|algo| digest.len() == algo.digest_len() * 2)
+                    .map(|algo| (digest, path, algo)),
+                None => check::parse_line(line).and_then(|(digest, path)| {
+                    untagged.filter(|algo| digest.len() == algo.digest_len() * 2)
+                        .or_else(|| check::algorithm_for_hex_len(digest.len()))
+                        .map(|algo| (digest, path, algo))
+                })
+            };
+
+            match parsed {
+                // Skip listed files that weren't downloaded, e.g. the other images in a CHECKSUM file
+                Some((_, path, _)) if ignore_missing && !std::path::Path::new(&path).exists() => {
+                    log_info!("{} is not present", path);
+                    not_present += 1;
+                },
+                Some((expected, path, algo)) => entries.push(check::Entry { expected, path, algo }),
+                None => {
+                    if !line.trim().is_empty() {
+                        logging::event(logging::Level::Warn, "improperly formatted checksum line", &[
+                            ("file", checksum_file.clone()),
+                            ("line", (line_number + 1).to_string())
+                        ]);
+                    }
+                }
+            }
+        }
+    }
+
+    // As with sha256sum -c, a file with nothing to check fails rather than passing vacuously
+    if entries.is_empty() && not_present == 0 {
+        log_error!("no properly formatted checksum lines found");
+        std::process::exit(1);
+    }
+
+    for algo in Algo::ALL.iter().filter(|algo| entries.iter().any(|e| e.algo == **algo)) {
+        warn_deprecated(*algo);
+    }
+
+    let mut failures = 0;
+
+    // Records come back in the order of the entries
+    let mut algos = entries.iter().map(|e| e.algo);
+
+    check::check_all(&entries, jobs, |record| {
+        let algo = algos.next().unwrap();
+        logging::event(logging::Level::Debug, "verified file", &[
+            ("path", record.path.clone()),
+            ("status", record.status.name().to_string()),
+            ("bytes", record.bytes.to_string()),
+            ("duration_ms", format!("{:.3}", record.duration.as_secs_f64() * 1000.0))
+        ]);
+        if record.status != report::Status::Ok {
+            failures += 1;
+        }
+
+        if jsonl {
+            println!("{}", record.to_json());
+        } else {
+            println!("{}", record.to_text());
+        }
+        if let (true, Some(actual)) = (explain, &record.actual) {
+            if record.status == report::Status::Failed {
+                print_hints(&check::explain_mismatch(&record.expected, actual, algo));
+            }
+        }
+    });
+
+    if failures > 0 {
+        log_warn!("{} computed checksum(s) did NOT match", failures);
+        std::process::exit(1);
+    }
+}
+
+/**
+ * Handles the `cargo-verify` subcommand: checks downloaded registry crates against the
+ * checksums in a Cargo.lock, or a single .crate file against an expected cksum.
+ */
+fn
+run_cargo_verify (matches: &ArgMatches) {
+    let jsonl = matches.get_one::<String>("report").map(|r| r == "jsonl").unwrap_or(false);
+    let mut entries: Vec<check::Entry> = Vec::new();
+
+    if let Some(path) = matches.get_one::<String>("crate") {
+        let cksum = matches.get_one::<String>("cksum").unwrap();
+        entries.push(check::Entry { expected: cksum.to_ascii_lowercase(), path: path.clone(), algo: Algo::Sha256 });
+    } else {
+        let lockfile = matches.get_one::<String>("lockfile").unwrap();
+        let cache_dir = match matches.get_one::<String>("cache-dir") {
+            Some(d) => PathBuf::from(d),
+            None => cargo::default_cache_dir().or_exit("unable to locate the cargo registry cache")
+        };
+
+        let contents = fs::read_to_string(lockfile).or_exit("unable to read lockfile");
+        let mut not_downloaded = 0;
+
+        for package in cargo::parse_lock(&contents) {
+            let checksum = match package.checksum {
+                Some(ref c) => c.to_ascii_lowercase(),
+                None => continue
+            };
+
+            match cargo::find_cached(&cache_dir, &package.crate_file_name()) {
+                Some(path) => entries.push(check::Entry {
+                    expected: checksum,
+                    path: path.to_string_lossy().into_owned(),
+                    algo: Algo::Sha256
+                }),
+                None => {
+                    log_info!("{} {} is not in the registry cache", package.name, package.version);
+                    not_downloaded += 1;
+                }
+            }
+        }
+
+        if not_downloaded > 0 {
+            log_warn!("{} locked package(s) not downloaded; skipped", not_downloaded);
+        }
+    }
+
+    let mut failures = 0;
+
+    check::check_all(&entries, 1, |record| {
+        if record.status != report::Status::Ok {
+            failures += 1;
+        }
+
+        if jsonl {
+            println!("{}", record.to_json());
+        } else {
+            println!("{}", record.to_text());
+        }
+    });
+
+    if failures > 0 {
+        log_warn!("{} crate checksum(s) did NOT match", failures);
+        std::process::exit(1);
+    }
+}
+
+/**
+ * Lists the files beneath a directory for the recursive subcommands, skipping what git ignores
+ * if asked to.
+ */
+fn
+walk_dir (root: &str, respect_gitignore: bool) -> Vec<String> {
+    let files = if respect_gitignore { manifest::walk_respecting_gitignore(root) } else { manifest::walk(root) };
+    files.or_exit("unable to read directory")
+}
+
+/**
+ * Handles the `manifest` subcommand, hashing every file beneath a directory. With --checkpoint,
+ * progress is saved every --checkpoint-every files and on SIGINT/SIGTERM; --resume reuses the
+ * saved digests instead of rehashing those files.
+ */
+fn
+run_manifest (matches: &ArgMatches, algo: Algo) {
+    let root = matches.get_one::<String>("dir").unwrap();
+    let checkpoint = matches.get_one::<String>("checkpoint");
+    let every = *matches.get_one::<u32>("checkpoint-every").unwrap() as usize;
+    let path_key = if matches.get_flag("hmac-paths") { Some(read_key(matches)) } else { None };
+
+    interrupt::install();
+
+    let files = walk_dir(root, matches.get_flag("respect-gitignore"));
+    let mut progress = match (checkpoint, matches.get_flag("resume")) {
+        (Some(c), true) => {
+            let progress = manifest::ScanProgress::load(c).or_exit("unable to read checkpoint");
+            log_info!("resuming scan with {} files already hashed", progress.completed.len());
+            progress
+        },
+        _ => manifest::ScanProgress::new()
+    };
+
+    let mut output: Box<dyn Write> = match matches.get_one::<String>("output") {
+        Some(f) => Box::new(io::BufWriter::new(fs::File::create(f).or_exit("unable to create output file"))),
+        None => Box::new(io::stdout())
+    };
+
+    let mut hashed_since_save = 0;
+
+    for file in &files {
+        if let Some(signum) = interrupt::received() {
+            if let Some(c) = checkpoint {
+                progress.save(c, &files).or_exit("unable to write checkpoint");
+                log_warn!("interrupted; progress saved to {}", c);
+            }
+            std::process::exit(interrupt::exit_code(signum));
+        }
+
+        let line = match progress.completed.get(file) {
+            Some(line) => line.clone(),
+            None => match check::hash_file(file, algo) {
+                Ok((digest, _)) => {
+                    let line = manifest::manifest_line(&digest, file);
+                    progress.completed.insert(file.clone(), line.clone());
+                    hashed_since_save += 1;
+                    line
+                },
+                Err(e) => {
+                    log_warn!("skipping {}: {}", file, e);
+                    continue;
+                }
+            }
+        };
+
+        // The checkpoint keeps real paths so that --resume can match them against the scan
+        match (&path_key, line.split_once("  ")) {
+            (Some(k), Some((digest, path))) => writeln!(output, "{}  {}", digest, manifest::pseudonymize_path(k, path)),
+            _ => writeln!(output, "{}", line)
+        }.or_exit("unable to write manifest");
+
+        if let Some(c) = checkpoint {
+            if hashed_since_save >= every {
+                progress.save(c, &files).or_exit("unable to write checkpoint");
+                hashed_since_save = 0;
+            }
+        }
+    }
+
+    output.flush().or_exit("unable to write manifest");
+
+    if let Some(c) = checkpoint {
+        let _ = fs::remove_file(c);
+    }
+}
+
+/**
+ * Hashes a file which is still being appended to, like tail -f. Whenever the end of the file is
+ * reached the loop waits for more data. A snapshot of the digest so far, and the number of bytes
+ * it covers, is printed every `interval` and whenever SIGUSR1 is received; SIGINT or SIGTERM
+ * prints a final snapshot and stops.
+ */
+fn
+follow_file (path: &str, algo: Algo, interval: Option<std::time::Duration>, format: format::Format) {
+    interrupt::install();
+    interrupt::install_snapshot();
+
+    let mut file = fs::File::open(path).or_exit("unable to open file");
+    let mut hasher = Hasher::new(algo);
+    let mut offset: u64 = 0;
+    let mut buffer = vec![0u8; 1 << 16];
+    let mut last_snapshot = std::time::Instant::now();
+
+    let snapshot = |hasher: &Hasher, offset: u64| {
+        let digest = hasher.clone().finalize();
+        let rendered = format::format_digest(&digest, algo, format).or_exit("unsupported format for this algorithm");
+        println!("{}  {}", rendered, offset);
+    };
+
+    loop {
+        if interrupt::received().is_some() {
+            snapshot(&hasher, offset);
+            return;
+        }
+
+        let due = interval.map(|i| last_snapshot.elapsed() >= i).unwrap_or(false);
+        if interrupt::snapshot_requested() || due {
+            snapshot(&hasher, offset);
+            last_snapshot = std::time::Instant::now();
+        }
+
+        match file.read(&mut buffer) {
+            Ok(0) => std::thread::sleep(std::time::Duration::from_millis(200)),
+            Ok(read) => {
+                hasher.update(&buffer[..read]);
+                offset += read as u64;
+            },
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => fail("unable to read data", e)
+        }
+    }
+}
+
+/**
+ * Hashes each line of the input independently, writing "<digest>\t<line number>" (or the line
+ * itself with `show_input`) per line. Line terminators, "\n" or "\r\n", aren't part of the hashed
+ * line. Numbering starts at `first_line` so that it can continue across several inputs.
+ * Returns the number of lines read.
+ */
+fn
+hash_lines<R: BufRead, W: Write> (
+    mut reader: R,
+    out: &mut W,
+    algo: Algo,
+    salt: Option<(&[u8], SaltPosition)>,
+    format: format::Format,
+    show_input: bool,
+    first_line: u64
+) -> io::Result<u64> {
+    let mut line = Vec::new();
+    let mut number = first_line;
+
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(number - first_line);
+        }
+
+        if line.last() == Some(&b'\n') {
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+        }
+
+        let mut message = line.clone();
+        if let Some((s, position)) = salt {
+            apply_salt(&mut message, s, position);
+        }
+        let rendered = format::format_digest(&digest(&message, algo), algo, format)
+            .or_exit("unsupported format for this algorithm");
+
+        if show_input {
+            writeln!(out, "{}\t{}", rendered, String::from_utf8_lossy(&line))?;
+        } else {
+            writeln!(out, "{}\t{}", rendered, number)?;
+        }
+        number += 1;
+    }
+}
+
+/**
+ * Copies stdin to stdout unchanged while hashing it. At EOF the formatted digest is written to
+ * stderr, or to `digest_file` if one is given, so the data stream itself is never altered.
+ */
+fn
+tee_stdin (algo: Algo, salt: Option<(&[u8], SaltPosition)>, format: format::Format, digest_file: Option<&str>) {
+    let mut hasher = Hasher::new(algo);
+    if let Some((s, SaltPosition::Prefix)) = salt {
+        hasher.update(s);
+    }
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut input = stdin.lock();
+    let mut output = stdout.lock();
+    let mut buffer = vec![0u8; 1 << 16];
+
+    loop {
+        let read = match input.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => fail("unable to read stdin", e)
+        };
+
+        hasher.update(&buffer[..read]);
+        output.write_all(&buffer[..read]).or_exit("unable to write stdout");
+    }
+
+    output.flush().or_exit("unable to write stdout");
+
+    if let Some((s, SaltPosition::Suffix)) = salt {
+        hasher.update(s);
+    }
+
+    let rendered = format::format_digest(&hasher.finalize(), algo, format).or_exit("unsupported format for this algorithm");
+    match digest_file {
+        Some(f) => fs::write(f, format!("{}\n", rendered)).or_exit("unable to write digest file"),
+        None => eprintln!("{}", rendered)
+    }
+}
+
+/**
+ * Handles the `hashdeep` subcommand: hashes the given files and directories into a hashdeep file,
+ * or with --audit compares them against a known hashdeep file, classifying each file as matched,
+ * moved or new and each unused known entry as missing. Exits nonzero if the audit fails.
+ */
+fn
+run_hashdeep (matches: &ArgMatches) {
+    let known: Option<Vec<hashdeep::Entry>> = matches.get_one::<String>("audit").map(|known_file| {
+        let contents = fs::read_to_string(known_file).or_exit("unable to read known hashes");
+        hashdeep::parse(&contents).unwrap_or_else(|e| {
+            log_error!("{}: {}", known_file, e);
+            std::process::exit(2);
+        })
+    });
+
+    // When auditing without explicit columns, compute whichever supported hashes the known file has
+    let mut columns: Vec<&str> = matches.get_one::<String>("columns").unwrap().split(',').map(|c| c.trim()).collect();
+    if let (Some(entries), Some(clap::parser::ValueSource::DefaultValue)) = (&known, matches.value_source("columns")) {
+        if let Some(first) = entries.first() {
+            columns = hashdeep::SUPPORTED.iter().copied()
+                .filter(|c| first.hashes.iter().any(|(name, _)| name == c))
+                .collect();
+        }
+    }
+
+    if columns.is_empty() {
+        log_error!("the known hashes include none of the supported columns: {}", hashdeep::SUPPORTED.join(","));
+        std::process::exit(2);
+    }
+
+    if let Some(c) = columns.iter().find(|c| !hashdeep::SUPPORTED.contains(c)) {
+        log_error!("unsupported hashdeep column {}; choose from {}", c, hashdeep::SUPPORTED.join(","));
+        std::process::exit(2);
+    }
+
+    let mut files: Vec<String> = Vec::new();
+    for p in matches.get_many::<String>("paths").unwrap() {
+        if std::path::Path::new(p).is_dir() {
+            files.extend(walk_dir(p, matches.get_flag("respect-gitignore")));
+        } else {
+            files.push(p.clone());
+        }
+    }
+
+    let path_key = if matches.get_flag("hmac-paths") { Some(read_key(matches)) } else { None };
+
+    // Pseudonymized paths still audit correctly against a known file written with the same key
+    let scanned: Vec<hashdeep::Entry> = files.iter().filter_map(|f| match hashdeep::hash_file(f, &columns) {
+        Ok(mut entry) => {
+            if let Some(ref k) = path_key {
+                entry.path = manifest::pseudonymize_path(k, &entry.path);
+            }
+            Some(entry)
+        },
+        Err(e) => {
+            log_warn!("skipping {}: {}", f, e);
+            None
+        }
+    }).collect();
+
+    let known = match known {
+        Some(k) => k,
+        None => {
+            let cwd = std::env::current_dir().map(|d| d.to_string_lossy().into_owned()).unwrap_or_default();
+            let command: Vec<String> = std::env::args().collect();
+            // The header would otherwise give away the directory names, and possibly the key
+            let (cwd, command) = match path_key {
+                Some(_) => ("<redacted>".to_string(), "<redacted>".to_string()),
+                None => (cwd, command.join(" "))
+            };
+            print!("{}", hashdeep::header(&columns, &cwd, &command));
+            for entry in &scanned {
+                println!("{}", hashdeep::format_entry(entry));
+            }
+            return;
+        }
+    };
+
+    let jsonl = matches.get_one::<String>("report").map(|r| r == "jsonl").unwrap_or(false);
+    let hashes = |e: &hashdeep::Entry| e.hashes.iter().map(|(_, h)| h.as_str()).collect::<Vec<&str>>().join(",");
+    let mut counts = [0usize; 4];
+
+    for result in hashdeep::audit(&known, &scanned) {
+        let status = match result.outcome {
+            hashdeep::Outcome::Matched => report::Status::Ok,
+            hashdeep::Outcome::Moved => report::Status::Moved,
+            hashdeep::Outcome::New => report::Status::New,
+            hashdeep::Outcome::Missing => report::Status::Missing
+        };
+        counts[result.outcome as usize] += 1;
+
+        let record = report::Record {
+            path: result.entry.path.clone(),
+            status,
+            expected: result.known.map(hashes).unwrap_or_default(),
+            actual: if status == report::Status::Missing { None } else { Some(hashes(result.entry)) },
+            bytes: result.entry.size,
+            duration: std::time::Duration::ZERO
+        };
+
+        match (jsonl, result.outcome) {
+            (true, _) => println!("{}", record.to_json()),
+            (false, hashdeep::Outcome::Matched) => {},
+            (false, hashdeep::Outcome::Moved) => println!("{}: Moved from {}", record.path, result.known.unwrap().path),
+            (false, hashdeep::Outcome::New) => println!("{}", record.to_text()),
+            (false, hashdeep::Outcome::Missing) => println!("{}: Known file not used", record.path)
+        }
+    }
+
+    let passed = counts[0] == scanned.len() && counts[3] == 0;
+    if !jsonl {
+        println!("hashdeep: Audit {}", if passed { "passed" } else { "failed" });
+        println!("          Files matched: {}", counts[0]);
+        println!("            Files moved: {}", counts[1]);
+        println!("        New files found: {}", counts[2]);
+        println!("  Known files not found: {}", counts[3]);
+    }
+
+    if !passed {
+        std::process::exit(1);
+    }
+}
+
+/**
+ * Handles the `bitcoin` subcommand: block header hashing and Merkle root recomputation.
+ */
+fn
+run_bitcoin (matches: &ArgMatches) {
+    let decode_hash = |h: &String| -> Vec<u8> {
+        let bytes = hex::decode(h).or_exit("hashes must be valid hex");
+        if bytes.len() != 32 {
+            log_error!("{} is not a 32-byte hash", h);
+            std::process::exit(2);
+        }
+        bytes
+    };
+
+    match matches.subcommand() {
+        Some(("header", sub)) => {
+            let raw = hex::decode(sub.get_one::<String>("hex").unwrap().trim()).or_exit("header must be valid hex");
+            let header = bitcoin::parse_header(&raw).unwrap_or_else(|| {
+                log_error!("a block header is {} bytes, got {}", bitcoin::HEADER_LEN, raw.len());
+                std::process::exit(2);
+            });
+
+            println!("version:     {}", header.version);
+            println!("prev block:  {}", hex::encode(bitcoin::reversed(&header.prev_block)));
+            println!("merkle root: {}", hex::encode(bitcoin::reversed(&header.merkle_root)));
+            println!("time:        {}", header.time);
+            println!("bits:        {:08x}", header.bits);
+            println!("nonce:       {}", header.nonce);
+            println!("block hash:  {}", hex::encode(bitcoin::block_hash(&raw)));
+
+            if let Some(txids) = sub.get_many::<String>("txids") {
+                let txids: Vec<Vec<u8>> = txids.map(decode_hash).collect();
+                let root = bitcoin::merkle_root(&txids).unwrap();
+
+                if root == bitcoin::reversed(&header.merkle_root) {
+                    println!("merkle root matches {} transaction(s)", txids.len());
+                } else {
+                    println!("merkle root MISMATCH: transactions give {}", hex::encode(root));
+                    std::process::exit(1);
+                }
+            }
+        },
+        Some(("merkle", sub)) => {
+            let txids: Vec<Vec<u8>> = sub.get_many::<String>("txids").unwrap().map(decode_hash).collect();
+            println!("{}", hex::encode(bitcoin::merkle_root(&txids).unwrap()));
+        },
+        _ => {}
+    }
+}
+
+/**
+ * Handles the `eth-address` subcommand, deriving an EIP-55 checksummed address from a public key.
+ */
+fn
+run_eth_address (matches: &ArgMatches) {
+    let key = matches.get_one::<String>("public-key").unwrap();
+    let key = hex::decode(key.trim().trim_start_matches("0x")).or_exit("public key must be valid hex");
+
+    match ethereum::address(&key) {
+        Some(address) => println!("{}", ethereum::checksum_address(&address)),
+        None => {
+            log_error!("expected a 64-byte uncompressed public key, optionally prefixed with 04");
+            std::process::exit(2);
+        }
+    }
+}
+
+/**
+ * Handles the `seal` subcommand, authenticating a file with a tag appended to it or written to
+ * a `.seal` sidecar.
+ */
+fn
+run_seal (matches: &ArgMatches, algo: Algo) {
+    if seal::algorithm_id(algo).is_none() {
+        log_error!("seal supports SHA-224, SHA-256 and SHA-512");
+        std::process::exit(2);
+    }
+    let path = matches.get_one::<String>("file").unwrap();
+    let key = read_key(matches);
+    let sidecar = matches.get_flag("sidecar");
+
+    let mut file = fs::OpenOptions::new().read(true).append(!sidecar).open(path).or_exit("unable to open file");
+    if !sidecar && seal::read_trailer(&mut file).or_exit("unable to read data").is_some() {
+        log_error!("{} is already sealed", path);
+        std::process::exit(2);
+    }
+
+    let len = file.metadata().or_exit("unable to read file metadata").len();
+    let tag = seal::Tag::compute(&key, algo, io::BufReader::new(&file), len).or_exit("unable to read data");
+
+    if sidecar {
+        fs::write(format!("{}.seal", path), tag.to_bytes()).or_exit("unable to write seal file");
+    } else {
+        file.write_all(&tag.to_bytes()).or_exit("unable to write file");
+    }
+}
+
+/**
+ * Handles the `unseal` subcommand: checks a file against its appended or sidecar tag, and with
+ * --strip removes a verified appended tag.
+ */
+fn
+run_unseal (matches: &ArgMatches) {
+    let path = matches.get_one::<String>("file").unwrap();
+    let key = read_key(matches);
+    let sidecar = matches.get_flag("sidecar");
+    let strip = matches.get_flag("strip");
+
+    let mut file = fs::OpenOptions::new().read(true).write(strip).open(path).or_exit("unable to open file");
+    let tag = if sidecar {
+        let bytes = fs::read(format!("{}.seal", path)).or_exit("unable to read seal file");
+        seal::Tag::parse(&bytes)
+    } else {
+        seal::read_trailer(&mut file).or_exit("unable to read data")
+    };
+
+    let Some(tag) = tag else {
+        log_error!("no seal found for {}", path);
+        std::process::exit(2);
+    };
+
+    // A sidecar tag covers the whole file, so anything appended since sealing is tampering too
+    let len = file.metadata().or_exit("unable to read file metadata").len();
+    if sidecar && tag.len != len {
+        log_warn!("{} is {} bytes but was sealed at {}", path, len, tag.len);
+        println!("{}: FAILED", path);
+        std::process::exit(1);
+    }
+
+    if !tag.verify(&key, io::BufReader::new(&file)).or_exit("unable to read data") {
+        println!("{}: FAILED", path);
+        std::process::exit(1);
+    }
+
+    if strip {
+        file.set_len(tag.len).or_exit("unable to truncate file");
+    }
+    println!("{}: OK ({})", path, tag.algo);
+}
+
+/**
+ * Handles the `split` subcommand: digests every --part-size part of a file along with the whole
+ * file, as multipart upload APIs want them up front, optionally writing the parts out as it goes.
+ */
+fn
+run_split (matches: &ArgMatches, algo: Algo) {
+    let path = matches.get_one::<String>("file").unwrap();
+    let part_size = parse_size(matches.get_one::<String>("part-size").unwrap())
+        .filter(|size| *size > 0)
+        .or_exit("invalid part size; use e.g. 8MiB");
+    let format = format::Format::from_name(matches.get_one::<String>("format").unwrap()).unwrap();
+
+    let file = fs::File::open(path).or_exit("unable to open file");
+    let len = file.metadata().or_exit("unable to read file metadata").len();
+
+    // Zero-padded part numbers, so the part files sort in order
+    let width = len.div_ceil(part_size).max(1).to_string().len();
+    let part_path = |dir: &str, index: usize| {
+        let name = PathBuf::from(path).file_name().or_exit("not a file path").to_string_lossy().into_owned();
+        PathBuf::from(dir).join(format!("{}.part{:0width$}", name, index + 1, width = width))
+    };
+
+    let output_dir = matches.get_one::<String>("output-dir");
+    if let Some(dir) = output_dir {
+        fs::create_dir_all(dir).or_exit("unable to create output directory");
+    }
+
+    let mut current: Option<(usize, io::BufWriter<fs::File>)> = None;
+    let (parts, whole) = blocks::block_digests_with(io::BufReader::new(file), algo, part_size, |index, data| {
+        let Some(dir) = output_dir else { return Ok(()) };
+        if current.as_ref().map(|(i, _)| *i) != Some(index) {
+            if let Some((_, mut finished)) = current.take() {
+                finished.flush()?;
+            }
+            current = Some((index, io::BufWriter::new(fs::File::create(part_path(dir, index))?)));
+        }
+        current.as_mut().unwrap().1.write_all(data)
+    }).or_exit("unable to split file");
+    if let Some((_, mut last)) = current {
+        last.flush().or_exit("unable to write part");
+    }
+
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    for (index, part) in parts.iter().enumerate() {
+        let digest = format::format_digest(&part.digest, algo, format).unwrap();
+        match output_dir {
+            Some(dir) => writeln!(out, "{}\t{}\t{}\t{}\t{}", index + 1, part.offset, part.len, digest, part_path(dir, index).display()),
+            None => writeln!(out, "{}\t{}\t{}\t{}", index + 1, part.offset, part.len, digest)
+        }.or_exit("unable to write stdout");
+    }
+    writeln!(out, "whole\t0\t{}\t{}", len, format::format_digest(&whole, algo, format).unwrap()).or_exit("unable to write stdout");
+}
+
+/**
+ * Handles the `link-farm` subcommand, linking every file beneath a directory into a tree named
+ * by content digest. Files whose content is already linked from another file are counted as
+ * duplicates, so the summary shows how much a content-addressed store would save.
+ */
+fn
+run_link_farm (matches: &ArgMatches, algo: Algo) {
+    let root = matches.get_one::<String>("dir").unwrap();
+    let out = PathBuf::from(matches.get_one::<String>("out").unwrap());
+    let kind = if matches.get_flag("hardlink") { linkfarm::LinkKind::Hardlink } else { linkfarm::LinkKind::Symlink };
+
+    // Listed before anything is created, so a farm inside the tree isn't walked into
+    let files = walk_dir(root, matches.get_flag("respect-gitignore"));
+
+    let mut linked: u64 = 0;
+    let mut present: u64 = 0;
+    let mut duplicates: u64 = 0;
+    let mut duplicate_bytes: u64 = 0;
+
+    for file in &files {
+        let (digest, len) = match check::hash_file(file, algo) {
+            Ok(result) => result,
+            Err(e) => {
+                log_warn!("skipping {}: {}", file, e);
+                continue;
+            }
+        };
+
+        match linkfarm::link(kind, &out, &hex::encode(&digest), file.as_ref()) {
+            Ok(linkfarm::Outcome::Linked) => linked += 1,
+            Ok(linkfarm::Outcome::Present) => present += 1,
+            Ok(linkfarm::Outcome::Duplicate) => {
+                log_info!("{} duplicates existing content", file);
+                duplicates += 1;
+                duplicate_bytes += len;
+            },
+            Err(e) => {
+                log_error!("unable to link {}: {}", file, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    println!(
+        "{} files: {} linked, {} already linked, {} duplicates ({} bytes)",
+        linked + present + duplicates, linked, present, duplicates, duplicate_bytes
+    );
+}
+
+/**
+ * Hashes one blob of an OCI layout and compares it with its digest and, if it was referenced by
+ * a descriptor, the size that descriptor gave.
+ */
+fn
+verify_oci_blob (path: &std::path::Path, digest: &str, size: Option<u64>) -> report::Record {
+    let start = std::time::Instant::now();
+    let expected = digest.trim_start_matches("sha256:").to_string();
+
+    let (status, actual, bytes) = match check::hash_file(&path.to_string_lossy(), Algo::Sha256) {
+        Ok((actual, bytes)) => {
+            let actual = hex::encode(actual);
+            let matches = actual == expected && size.is_none_or(|s| s == bytes);
+            if actual == expected && !matches {
+                log_warn!("{} is {} bytes, but its descriptor says {}", path.display(), bytes, size.unwrap());
+            }
+            (if matches { report::Status::Ok } else { report::Status::Failed }, Some(actual), bytes)
+        },
+        Err(_) => (report::Status::Missing, None, 0)
+    };
+
+    report::Record {
+        path: path.to_string_lossy().into_owned(),
+        status,
+        expected,
+        actual,
+        bytes,
+        duration: start.elapsed()
+    }
+}
+
+/**
+ * Handles the `oci-verify` subcommand. Follows index.json through every index and manifest to
+ * the configs and layers, checking that each referenced blob is present with the right size and
+ * digest, then checks any remaining blobs against their file names.
+ */
+fn
+run_oci_verify (matches: &ArgMatches) {
+    let root = std::path::Path::new(matches.get_one::<String>("dir").unwrap());
+    let jsonl = matches.get_one::<String>("report").map(|r| r == "jsonl").unwrap_or(false);
+
+    if !root.join("oci-layout").is_file() {
+        log_warn!("{} has no oci-layout file; checking it as an OCI layout anyway", root.display());
+    }
+    let index = fs::read_to_string(root.join("index.json")).or_exit("unable to read index.json");
+    let mut pending: std::collections::VecDeque<oci::Descriptor> = oci::descriptors(&index).unwrap_or_else(|e| {
+        log_error!("unable to parse index.json: {}", e);
+        std::process::exit(2);
+    }).into();
+
+    let mut verified: std::collections::HashSet<String> = std::collections::HashSet::new();
+    let mut failures = 0;
+    let mut emit = |record: report::Record| {
+        if record.status != report::Status::Ok {
+            failures += 1;
+        }
+        if jsonl {
+            println!("{}", record.to_json());
+        } else {
+            println!("{}", record.to_text());
+        }
+    };
+
+    while let Some(descriptor) = pending.pop_front() {
+        if !verified.insert(descriptor.digest.clone()) {
+            continue;
+        }
+        let Some(path) = oci::blob_path(root, &descriptor.digest) else {
+            log_warn!("skipping blob with unsupported digest {}", descriptor.digest);
+            continue;
+        };
+
+        let record = verify_oci_blob(&path, &descriptor.digest, Some(descriptor.size));
+        if record.status == report::Status::Ok && oci::is_manifest(&descriptor.media_type) {
+            let document = fs::read_to_string(&path).unwrap_or_default();
+            match oci::descriptors(&document) {
+                Ok(children) => pending.extend(children),
+                Err(e) => log_error!("unable to parse {}: {}", path.display(), e)
+            }
+        }
+        emit(record);
+    }
+
+    for (digest, path) in oci::list_blobs(root).or_exit("unable to read blobs directory") {
+        if verified.contains(&digest) {
+            continue;
+        }
+        log_info!("{} is not referenced from index.json", path.display());
+        emit(verify_oci_blob(&path, &digest, None));
+    }
+
+    if failures > 0 {
+        log_warn!("{} blob(s) are missing or corrupt", failures);
+        std::process::exit(1);
+    }
+}
+
+/**
+ * Handles the `firmware-verify` subcommand, hashing each region the descriptor lists and
+ * reporting whether it matches. Exits 1 if any region doesn't match or lies outside the image.
+ */
+fn
+run_firmware_verify (matches: &ArgMatches) {
+    let image_path = matches.get_one::<String>("image").unwrap();
+    let jsonl = matches.get_one::<String>("report").map(|r| r == "jsonl").unwrap_or(false);
+
+    let descriptor = fs::read_to_string(matches.get_one::<String>("descriptor").unwrap()).or_exit("unable to read descriptor");
+    let regions = firmware::parse(&descriptor).unwrap_or_else(|e| {
+        log_error!("unable to parse descriptor: {}", e);
+        std::process::exit(2);
+    });
+    let mut image = fs::File::open(image_path).or_exit("unable to open image");
+    let image_len = image.metadata().or_exit("unable to read image").len();
+
+    let mut failures = 0;
+    for region in &regions {
+        let record = firmware::verify(&mut image, image_len, region);
+        if record.status != report::Status::Ok {
+            failures += 1;
+            if region.offset + region.length > image_len {
+                log_info!("{} ends at {:#x}, past the end of the {:#x}-byte image", region.name, region.offset + region.length, image_len);
+            }
+        }
+
+        if jsonl {
+            println!("{}", record.to_json());
+        } else {
+            println!("{}", record.to_text());
+        }
+    }
+
+    if failures > 0 {
+        log_warn!("{} of {} region(s) did NOT match", failures, regions.len());
+        std::process::exit(1);
+    }
+}
+
+/**
+ * Handles the `imaging-log` subcommand. Writes an acquisition log of the image to stdout, or with
+ * --verify, hashes the image and each chunk the examiner's log lists in one read and reports
+ * every digest in the log.
+ */
+fn
+run_imaging_log (matches: &ArgMatches) {
+    let image_path = matches.get_one::<String>("image").unwrap();
+    let image = fs::File::open(image_path).or_exit("unable to open image");
+
+    let log_path = match matches.get_one::<String>("verify") {
+        Some(log_path) => log_path,
+        None => {
+            let chunk_size = parse_size(matches.get_one::<String>("chunk-size").unwrap())
+                .filter(|size| *size > 0)
+                .or_exit("invalid chunk size; use e.g. 1GiB");
+            let image_len = image.metadata().or_exit("unable to read image").len();
+            let ranges = imaging::chunks(image_len, chunk_size);
+            let (hashes, total, read) = imaging::hash_ranges(io::BufReader::new(image), &ranges).or_exit("unable to read image");
+            if read != image_len {
+                log_error!("{} changed size while it was being read", image_path);
+                std::process::exit(1);
+            }
+
+            let chunks: Vec<_> = ranges.into_iter().zip(hashes.into_iter().map(Option::unwrap)).collect();
+            print!("{}", imaging::write_log(image_path, image_len, chunk_size, &chunks, &total));
+            return;
+        }
+    };
+
+    let jsonl = matches.get_one::<String>("report").map(|r| r == "jsonl").unwrap_or(false);
+    let expected = imaging::parse_log(&fs::read_to_string(log_path).or_exit("unable to read log"));
+    if expected.is_empty() {
+        log_error!("no MD5, SHA-1 or SHA-256 digests found in {}", log_path);
+        std::process::exit(2);
+    }
+
+    let start = std::time::Instant::now();
+    // A log lists each chunk once per algorithm, so hash every distinct range only once
+    let mut ranges: Vec<std::ops::Range<u64>> = Vec::new();
+    let mut index: std::collections::HashMap<std::ops::Range<u64>, usize> = std::collections::HashMap::new();
+    for range in expected.iter().filter_map(|e| e.range.clone()) {
+        index.entry(range.clone()).or_insert_with(|| {
+            ranges.push(range);
+            ranges.len() - 1
+        });
+    }
+    let (hashes, total, read) = imaging::hash_ranges(io::BufReader::new(image), &ranges).or_exit("unable to read image");
+    let duration = start.elapsed();
+
+    let mut failures = 0;
+    for e in &expected {
+        let (name, actual, bytes) = match &e.range {
+            Some(range) => (format!("{} bytes {}-{}", e.algorithm.name(), range.start, range.end), hashes[index[range]].as_ref(), range.end - range.start),
+            None => (format!("{} image", e.algorithm.name()), Some(&total), read)
+        };
+        let actual = actual.map(|hashes| hashes.get(e.algorithm));
+        let status = match actual {
+            Some(actual) if actual == e.digest => report::Status::Ok,
+            Some(_) => report::Status::Failed,
+            None => report::Status::Missing
+        };
+        if status != report::Status::Ok {
+            failures += 1;
+            log_info!("{} line {}: {}", log_path, e.line, if status == report::Status::Missing { "the chunk runs past the end of the image" } else { "digest differs" });
+        }
+
+        let record = report::Record {
+            path: name,
+            status,
+            expected: hex::encode(&e.digest),
+            actual: actual.map(hex::encode),
+            bytes: if status == report::Status::Missing { 0 } else { bytes },
+            duration
         };
+        println!("{}", if jsonl { record.to_json() } else { record.to_text() });
+    }
+
+    if failures > 0 {
+        log_warn!("{} of {} digest(s) did NOT match", failures, expected.len());
+        std::process::exit(1);
+    }
+}
+
+/**
+ * Handles the `apt-verify` subcommand, checking the files listed in an APT Release, InRelease or
+ * Packages file. Files that aren't present are skipped, since mirrors rarely carry every
+ * compression variant of every index.
+ */
+fn
+run_apt_verify (matches: &ArgMatches) {
+    let metadata = matches.get_one::<String>("file").unwrap();
+    let jsonl = matches.get_one::<String>("report").map(|r| r == "jsonl").unwrap_or(false);
+    let jobs = *matches.get_one::<u32>("jobs").unwrap() as usize;
+
+    let contents = fs::read_to_string(metadata).or_exit("unable to read metadata file");
+    let (kind, files) = apt::parse(&contents);
+
+    // Release paths are relative to the Release file, Packages paths to the archive root
+    let root = match (matches.get_one::<String>("root"), kind) {
+        (Some(r), _) => PathBuf::from(r),
+        (None, apt::Kind::Release) => PathBuf::from(metadata).parent().map(|p| p.to_path_buf()).unwrap_or_default(),
+        (None, apt::Kind::Packages) => PathBuf::from(".")
+    };
+
+    let mut entries: Vec<check::Entry> = Vec::new();
+    let mut absent = 0;
+    for file in files {
+        let path = root.join(&file.path);
+        if path.is_file() {
+            entries.push(check::Entry { expected: file.sha256, path: path.to_string_lossy().into_owned(), algo: Algo::Sha256 });
+        } else {
+            log_info!("{} is not present", path.display());
+            absent += 1;
+        }
+    }
+    if absent > 0 {
+        log_warn!("{} listed file(s) not present; skipped", absent);
+    }
+
+    let mut failures = 0;
+
+    check::check_all(&entries, jobs, |record| {
+        if record.status != report::Status::Ok {
+            failures += 1;
+        }
+
+        if jsonl {
+            println!("{}", record.to_json());
+        } else {
+            println!("{}", record.to_text());
+        }
+    });
+
+    if failures > 0 {
+        log_warn!("{} computed checksum(s) did NOT match", failures);
+        std::process::exit(1);
+    }
+}
+
+/**
+ * Handles the `authenticode` subcommand, printing each image's Authenticode digest, or checking
+ * it against --expect.
+ */
+fn
+run_authenticode (matches: &ArgMatches, algo: Algo) {
+    let paths: Vec<&String> = matches.get_many::<String>("file").unwrap().collect();
+    let expected = matches.get_one::<String>("expect").map(|e| e.parse::<Digest>().unwrap_or_else(|e| {
+        log_error!("invalid --expect: {}", e);
+        std::process::exit(2);
+    }));
+    if expected.is_some() && paths.len() > 1 {
+        log_error!("--expect takes a single image");
+        std::process::exit(2);
+    }
+
+    for path in paths {
+        let file = fs::File::open(path).or_exit("unable to open image");
+        let len = file.metadata().or_exit("unable to read image").len();
+        let digest = authenticode::digest(file, len, expected.map(|e| e.algo()).unwrap_or(algo)).unwrap_or_else(|e| {
+            log_error!("{}: {}", path, e);
+            std::process::exit(1);
+        });
+
+        match expected {
+            Some(expected) if expected == digest => println!("{}: OK", path),
+            Some(_) => {
+                println!("{}: FAILED", path);
+                log_warn!("Authenticode digest is {}", digest);
+                std::process::exit(1);
+            },
+            None => println!("{}  {}", digest, path)
+        }
+    }
+}
+
+/**
+ * Handles the `build-id` subcommand: for each binary, recomputes its build-id the way each
+ * supported linker would and reports whether one of them reproduces the embedded note. Exits 1
+ * if any binary's note doesn't match, or it has none.
+ */
+fn
+run_build_id (matches: &ArgMatches) {
+    let mut failures = 0;
+
+    for path in matches.get_many::<String>("file").unwrap() {
+        let elf = fs::read(path).or_exit("unable to read binary");
+        let checked = buildid::find(&elf).and_then(|found| match found {
+            Some(build_id) => buildid::verify(&elf, &build_id).map(|style| Some((build_id, style))),
+            None => Ok(None)
+        });
 
-        State {
-            a: init[0],
-            b: init[1],
-            c: init[2],
-            d: init[3],
-            e: init[4],
-            f: init[5],
-            g: init[6],
-            h: init[7],
-            n: n
+        match checked {
+            Ok(Some((build_id, _))) if build_id.algorithm().is_none() => {
+                println!("{}: {} is not a SHA-1 or SHA-256 build-id; not checked", path, hex::encode(&build_id.id));
+            },
+            Ok(Some((build_id, Some(style)))) => {
+                println!("{}: OK {} ({}, {})", path, hex::encode(&build_id.id), style.name(), build_id.algorithm().unwrap());
+            },
+            Ok(Some((build_id, None))) => {
+                println!("{}: FAILED {}", path, hex::encode(&build_id.id));
+                for style in buildid::Style::ALL {
+                    if let Ok(Some(computed)) = buildid::compute(&elf, &build_id, style) {
+                        log_info!("as {} computes it: {}", style.name(), hex::encode(computed));
+                    }
+                }
+                failures += 1;
+            },
+            Ok(None) => {
+                println!("{}: no build-id", path);
+                failures += 1;
+            },
+            Err(e) => {
+                log_error!("{}: {}", path, e);
+                failures += 1;
+            }
         }
     }
 
-    /**
-     * Rotates state values according to https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf 6.2.2 s
-     * section 3.
-     */
-    fn rotate (&mut self, x: u32, y: u32) {
-       self.h = self.g;
-       self.g = self.f;
-       self.f = self.e;
-       self.e = self.d.wrapping_add(x);
-       self.d = self.c;
-       self.c = self.b;
-       self.b = self.a;
-       self.a = x.wrapping_add(y);
+    if failures > 0 {
+        log_warn!("{} binary(s) without a matching build-id", failures);
+        std::process::exit(1);
     }
+}
 
-    fn add (&mut self, v: &[u32; 8]) {
-        self.a = self.a.wrapping_add(v[0]);
-        self.b = self.b.wrapping_add(v[1]);
-        self.c = self.c.wrapping_add(v[2]);
-        self.d = self.d.wrapping_add(v[3]);
-        self.e = self.e.wrapping_add(v[4]);
-        self.f = self.f.wrapping_add(v[5]);
-        self.g = self.g.wrapping_add(v[6]);
-        self.h = self.h.wrapping_add(v[7]);
+/**
+ * Handles the `git-object` subcommand. Prints each file's blob ID as `git hash-object` does, or
+ * with --ls-tree, checks the blobs a listing names, telling SHA-1 from SHA-256 repositories by the
+ * IDs' length. A symbolic link's blob is its target. Exits 1 if any file is missing or differs.
+ */
+fn
+run_git_object (matches: &ArgMatches) {
+    let blob_id = |path: &str, algo: Algo, symlink: bool| -> io::Result<Digest> {
+        if symlink {
+            let target = fs::read_link(path)?.into_os_string().into_string()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "link target isn't UTF-8"))?;
+            return gitobject::blob_id(target.as_bytes(), target.len() as u64, algo);
+        }
+        let file = fs::File::open(path)?;
+        let len = file.metadata()?.len();
+        gitobject::blob_id(io::BufReader::new(file), len, algo)
+    };
+
+    let listing = match matches.get_one::<String>("ls-tree") {
+        Some(listing) => listing,
+        None => {
+            let algo = if matches.get_one::<String>("object-format").unwrap() == "sha256" { Algo::Sha256 } else { Algo::Sha1 };
+            warn_deprecated(algo);
+            for path in matches.get_many::<String>("file").unwrap() {
+                println!("{}", blob_id(path, algo, false).or_exit("unable to read file"));
+            }
+            return;
+        }
+    };
+
+    let entries = gitobject::parse_ls_tree(&fs::read_to_string(listing).or_exit("unable to read listing"))
+        .unwrap_or_else(|line| {
+            log_error!("{} line {}: not git ls-tree output", listing, line);
+            std::process::exit(2);
+        });
+    let blobs: Vec<&gitobject::TreeEntry> = entries.iter().filter(|e| e.kind == "blob").collect();
+    if blobs.len() < entries.len() {
+        log_info!("skipping {} tree and submodule entries; list with -r to check the files inside trees", entries.len() - blobs.len());
+    }
+    if let Some(first) = blobs.first() {
+        warn_deprecated(gitobject::object_format(first.id.len() * 2).unwrap());
     }
-
-    /**
-     * Returns a byte vector representation of this State's integers
-     */
-    fn export (&mut self) -> Vec<u8> {
-        let mut bytes = Vec::new();
-        bytes.extend_from_slice(&self.a.to_be_bytes());
-        bytes.extend_from_slice(&self.b.to_be_bytes());
-        bytes.extend_from_slice(&self.c.to_be_bytes());
-        bytes.extend_from_slice(&self.d.to_be_bytes());
-        bytes.extend_from_slice(&self.e.to_be_bytes());
-        bytes.extend_from_slice(&self.f.to_be_bytes());
-        bytes.extend_from_slice(&self.g.to_be_bytes());
-
-        if self.n == 256 {
-            bytes.extend_from_slice(&self.h.to_be_bytes());
-        }
-
-        return bytes;
-    }
-}
-
-/**
- * See https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 5.1.2
- * 
- * Suppose the length of the message M, in bits, is  bits. Append the bit “1” to the end of the message, 
- * followed by k zero bits, where k is the smallest non-negative solution to the equation L + 1 + k = 896 mod 1024. 
- * Then append the 128-bit block that is equal to the number L expressed using a binary representation. 
- * For example, the (8-bit ASCII) message “abc” has length 8 x 3 = 24, so the message is padded with a one bit,
- * then 896 - (24 + 1) = 871 zero bits, and then the message length, to become the 1024-bit padded message.
- * The length of the padded message should now be a multiple of 1024 bits.
- */
-fn
-pad (message: &mut Vec<u8>) {
-    let mlen_in_bits = message.len() * 8 % MAX_LEN;
-
-    // Appends 1 << 7, ie 1000 0000, we're working in bytes
-    message.push(0x80);
-
-    // Padding to 448 modulo 512 bits
-    while (message.len() * 8 % MAX_LEN) % 512 != 448 {
-        message.push(0x0);
+
+    let jsonl = matches.get_one::<String>("report").map(|r| r == "jsonl").unwrap_or(false);
+    let mut failures = 0;
+    for entry in blobs {
+        let start = std::time::Instant::now();
+        let algo = gitobject::object_format(entry.id.len() * 2).unwrap();
+        let actual = blob_id(&entry.path, algo, entry.is_symlink());
+
+        let status = match &actual {
+            Ok(id) if id.as_bytes() == entry.id => report::Status::Ok,
+            Ok(_) => report::Status::Failed,
+            Err(_) => report::Status::Missing
+        };
+        if status != report::Status::Ok {
+            failures += 1;
+        }
+
+        let record = report::Record {
+            path: entry.path.clone(),
+            status,
+            expected: hex::encode(&entry.id),
+            actual: actual.as_ref().ok().map(|id| id.to_hex()),
+            bytes: fs::symlink_metadata(&entry.path).map(|m| m.len()).unwrap_or(0),
+            duration: start.elapsed()
+        };
+        println!("{}", if jsonl { record.to_json() } else { record.to_text() });
     }
 
-    let len_in_bytes = mlen_in_bits.to_be_bytes();
-    message.extend_from_slice(&len_in_bytes);
-}
+    if failures > 0 {
+        log_warn!("{} file(s) don't match their blob IDs", failures);
+        std::process::exit(1);
+    }
+}
 
 /**
- * Convenience function for passing strings; converts given string to a Vector of u8 bytes for 
- * the hash() function.
+ * Handles --report bundle: prints a JSON object per file, or for stdin if no --path is given,
+ * with its size and its MD5, SHA-1 and SHA-256, the digests reputation services look samples up
+ * by, and its entropy if asked for. Each file is read once for all of them. Exits 1 if any file
+ * can't be read.
  */
-fn hash_string (message: &str, n: usize) -> String {
-    let mut message_bytes = message.as_bytes().to_vec();
-    return hash (&mut message_bytes, n);
+fn
+report_bundle (paths: &[&String], with_entropy: bool) {
+    let stdin = String::from("-");
+    let sources = if paths.is_empty() { vec![&stdin] } else { paths.to_vec() };
+
+    let mut failures = 0;
+    for path in sources {
+        let reader: Box<dyn Read> = if *path == stdin {
+            Box::new(io::stdin().lock())
+        } else {
+            match fs::File::open(path) {
+                Ok(file) => Box::new(io::BufReader::new(file)),
+                Err(e) => {
+                    log_error!("unable to read {}: {}", path, e);
+                    failures += 1;
+                    continue;
+                }
+            }
+        };
+        let mut reader = entropy::Measured::new(reader);
+        let (hashes, bytes) = match imaging::hash_reader(&mut reader) {
+            Ok(hashed) => hashed,
+            Err(e) => {
+                log_error!("unable to read {}: {}", path, e);
+                failures += 1;
+                continue;
+            }
+        };
+
+        let mut record = format!(
+            "{{\"path\":{},\"bytes\":{},\"md5\":\"{}\",\"sha1\":\"{}\",\"sha256\":\"{}\"",
+            report::json_string(path),
+            bytes,
+            hex::encode(hashes.md5),
+            hex::encode(hashes.sha1),
+            hashes.sha256
+        );
+        if with_entropy {
+            record.push_str(&format!(",\"entropy\":{:.4}", reader.entropy.bits_per_byte()));
+        }
+        record.push('}');
+        println!("{}", record);
+    }
+
+    if failures > 0 {
+        std::process::exit(1);
+    }
 }
 
+/**
+ * Handles the `scan` subcommand: hashes every file beneath the given paths and either marks each
+ * as known or unknown to a known-file set, or reports the files whose digests are on a blocklist.
+ */
 fn
-hash (message: &mut Vec<u8>, n: usize) -> String {
+run_scan (matches: &ArgMatches, algo: Algo) {
+    let mut files: Vec<String> = Vec::new();
+    for p in matches.get_many::<String>("paths").unwrap() {
+        if std::path::Path::new(p).is_dir() {
+            files.extend(walk_dir(p, matches.get_flag("respect-gitignore")));
+        } else {
+            files.push(p.clone());
+        }
+    }
+
+    let jsonl = matches.get_one::<String>("report").map(|r| r == "jsonl").unwrap_or(false);
+    match matches.get_one::<String>("blocklist") {
+        Some(list) => scan_blocklist(list, &files, jsonl),
+        None => scan_known(matches.get_one::<String>("known").unwrap(), &files, algo, matches.get_flag("hide-known"), jsonl)
+    }
+}
 
-    let mut state:State = State::new(n);
+/**
+ * One `scan --report jsonl` line. The timestamp lets a SIEM order events from several scans.
+ */
+fn
+scan_json (status: &str, algorithm: &str, digest: &[u8], path: &str, label: Option<&str>) -> String {
+    let ts = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or(0.0);
+    let mut line = format!(
+        "{{\"ts\":{:.3},\"status\":\"{}\",\"algorithm\":\"{}\",\"digest\":\"{}\",\"path\":{},\"bytes\":{}",
+        ts,
+        status,
+        algorithm,
+        hex::encode(digest),
+        report::json_string(path),
+        fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+    );
+    if let Some(label) = label {
+        line.push_str(&format!(",\"label\":{}", report::json_string(label)));
+    }
+    line.push('}');
+    line
+}
 
-    // Extend to a multiple of 512 bits
-    pad (message);
+/**
+ * Marks each file as known or unknown to a hash set, hashing it with --algo. A sorted set whose
+ * digests are another length names the algorithm by that length instead: MD5, SHA-1 or SHA-2.
+ * A Bloom filter doesn't record a length, so --algo must match the digests it was built from.
+ */
+fn
+scan_known (set_path: &str, files: &[String], algo: Algo, hide_known: bool, jsonl: bool) {
+    let mut set = knownset::KnownSet::open(set_path).or_exit("unable to read hash set");
+    let algo = match set.width() {
+        None => algo,
+        Some(width) if width == algo.digest_len() => algo,
+        Some(width) => Algo::try_from_bits(width * 8).unwrap_or_else(|_| {
+            log_error!("{} holds {}-byte digests, which aren't MD5, SHA-1 or SHA-2", set_path, width);
+            std::process::exit(2);
+        })
+    };
 
-    /*
-    * From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 5.2
-    * 
-    * For SHA-1, SHA-224 and SHA-256, the message and its padding are parsed into N 512-bit blocks, M(1), M(2),..., M(N). 
-    * Since the 512 bits of the input block may be expressed as sixteen 32-bit words, the first 32 bits of message 
-    * block i are denoted M0(i), the next 32 bits are M1(i), and so on up to M(i).
-    * 
-    * For SHA-384, SHA-512, SHA-512/224 and SHA-512/256, the message and its padding are parsed into N 1024-bit blocks, 
-    * M(1), M(2),..., M(N). Since the 1024 bits of the input block may be expressed as sixteen 64-bit words, the first 
-    * 64 bits of message block i are denoted M0(i), the next 64 bits are M(i), and so on up to M(i).
-    */
-    for outer_block in message.chunks(64) {
-        let mut w: [u32; 64] = [0; 64];
-        let mut indx = 0;
+    let (mut known, mut unknown) = (0, 0);
+    for file in files {
+        let digest = match check::hash_file(file, algo) {
+            Ok((digest, _)) => digest,
+            Err(e) => {
+                log_warn!("skipping {}: {}", file, e);
+                continue;
+            }
+        };
 
-        // Fill first 16 elements of w array with 32-bit integer from the 512-bit block
-        // See https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 6.2.2
-        for chunk in outer_block.chunks(4) {
-            // Convert message byte chunks into a big-endian u32 integer and insert into w[indx]
-            let (b1, b2, b3, b4) = (chunk[0] as u32, chunk[1] as u32, chunk[2] as u32, chunk[3] as u32);
-            w[indx] = (b1 << 24) | (b2 << 16) | (b3 << 8) | b4;
-            indx += 1;
+        let status = if set.contains(&digest).or_exit("unable to read hash set") {
+            known += 1;
+            if hide_known {
+                continue;
+            }
+            "known"
+        } else {
+            unknown += 1;
+            "unknown"
+        };
+        if jsonl {
+            println!("{}", scan_json(status, algo.tag(), &digest, file, None));
+        } else {
+            println!("{}\t{}\t{}", status, hex::encode(&digest), file);
         }
+    }
+
+    log_info!("{} known, {} unknown", known, unknown);
+}
+
+/**
+ * Reports each file whose digest is on the blocklist, hashing it once with every algorithm the
+ * list holds digests of. Exits 1 if any file matches.
+ */
+fn
+scan_blocklist (list_path: &str, files: &[String], jsonl: bool) {
+    let (blocklist, unsupported) = knownset::Blocklist::parse(&fs::read_to_string(list_path).or_exit("unable to read blocklist"));
+    for line in &unsupported {
+        log_warn!("{} line {}: skipping a digest that isn't MD5, SHA-1 or SHA-2", list_path, line);
+    }
+    if blocklist.is_empty() {
+        log_error!("{} holds no digests", list_path);
+        std::process::exit(2);
+    }
+    log_info!("{} digests in the blocklist", blocklist.len());
 
-        // 16 .. 63
-        while indx < 64 {
-            /* 
-            * From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 4.1.2
-            *
-            * The two functions σ0 and σ1 as defined in the specification.
-            */
-            let s0 = (w[indx - 15].rotate_right(7)) 
-                        ^ (w[indx - 15].rotate_right(18)) 
-                        ^ (w[indx - 15] >> 3);
-            let s1 = (w[indx - 2].rotate_right(17)) 
-                        ^ (w[indx - 2].rotate_right(19)) 
-                        ^ (w[indx - 2] >> 10);
+    let (mut matched, mut skipped) = (0, 0);
+    for file in files {
+        let digests = match fs::File::open(file).and_then(|f| knownset::hash_reader_widths(io::BufReader::new(f), blocklist.widths())) {
+            Ok(digests) => digests,
+            Err(e) => {
+                log_warn!("skipping {}: {}", file, e);
+                skipped += 1;
+                continue;
+            }
+        };
 
-            // From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 6.2.2            
-            w[indx] = w[indx - 16]
-                        .wrapping_add(s0)
-                        .wrapping_add(w[indx - 7])
-                        .wrapping_add(s1);
-            indx += 1;
+        let found = digests.iter().find_map(|digest| blocklist.lookup(digest).map(|label| (digest, label)));
+        if let Some((digest, label)) = found {
+            matched += 1;
+            if jsonl {
+                println!("{}", scan_json("match", knownset::algorithm_name(digest.len()).unwrap(), digest, file, Some(label)));
+            } else if label.is_empty() {
+                println!("match\t{}\t{}", hex::encode(digest), file);
+            } else {
+                println!("match\t{}\t{}\t{}", hex::encode(digest), file, label);
+            }
         }
+    }
 
-        // Stored to add back to the state after the main processing loop
-        let input_values: [u32; 8] = [state.a, state.b, state.c, state.d, state.e, state.f, state.g, state.h];
-        indx = 0;
+    log_info!("{} file(s) scanned, {} matched, {} skipped", files.len() - skipped, matched, skipped);
+    if matched > 0 {
+        log_warn!("{} file(s) on the blocklist", matched);
+        std::process::exit(1);
+    }
+}
+
+/**
+ * Handles the `ssri` subcommand, checking a tarball against an npm-style integrity string.
+ */
+fn
+run_ssri (matches: &ArgMatches) {
+    let integrity = matches.get_one::<String>("integrity").unwrap();
+    let path = matches.get_one::<String>("file").unwrap();
 
-        // See https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 6.2.2
-        while indx < 64 {
-            /* 
-            * From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 4.1.2
-            *
-            * The functions Σ0, Σ1, Ch(x, y, z) and Maj(x, y, z)
-            */
-            let s0 = state.a.rotate_right(2) ^ state.a.rotate_right(13) ^ state.a.rotate_right(22);
-            let s1 = state.e.rotate_right(6) ^ state.e.rotate_right(11) ^ state.e.rotate_right(25);
+    let skipped: Vec<String> = ssri::parse(integrity).into_iter()
+        .filter(|e| !ssri::supported(&e.algorithm))
+        .map(|e| e.algorithm)
+        .collect();
+    if !skipped.is_empty() {
+        log_warn!("ignoring unsupported integrity algorithm(s): {}", skipped.join(", "));
+    }
 
-            let ch = (state.e & state.f) ^ ((!state.e) & state.g);
-            let maj = (state.a & state.b) ^ (state.a & state.c) ^ (state.b & state.c);
+    let file = fs::File::open(path).or_exit("unable to open file");
+    match ssri::verify(integrity, io::BufReader::new(file)).or_exit("unable to read data") {
+        ssri::Verdict::Match(alg) => println!("{}: OK ({})", path, alg),
+        ssri::Verdict::Mismatch(alg) => {
+            println!("{}: FAILED ({})", path, alg);
+            std::process::exit(1);
+        },
+        ssri::Verdict::Unsupported => {
+            log_error!("no supported algorithm in integrity string");
+            std::process::exit(2);
+        }
+    }
+}
 
-            // See https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 6.2.2 section 3
-            state.rotate(
-                state.h.wrapping_add(s1)
-                  .wrapping_add(ch)
-                  .wrapping_add(K[indx])
-                  .wrapping_add(w[indx]),
-                s0.wrapping_add(maj)
-            );
+/**
+ * Hashes a file with --autotune: large files are first probed to pick the fastest read
+ * strategy, smaller ones just use the default.
+ */
+fn
+hash_file_autotuned (path: &str, algo: Algo, salt: Option<(&[u8], SaltPosition)>) -> Vec<u8> {
+    let mut file = fs::File::open(path).or_exit("unable to open file");
+    let len = file.metadata().or_exit("unable to read file metadata").len();
 
-            indx += 1;
+    let strategy = if len >= iotune::MIN_TUNE_SIZE {
+        let results = iotune::probe(path, &iotune::candidates(), iotune::PROBE_LEN, algo).or_exit("unable to read data");
+        for r in &results {
+            logging::event(logging::Level::Debug, "probed read strategy", &[
+                ("strategy", r.strategy.name()),
+                ("mib_per_s", format!("{:.1}", r.throughput() / (1 << 20) as f64))
+            ]);
         }
+        iotune::fastest(&results)
+    } else {
+        iotune::DEFAULT
+    };
+    log_info!("hashing {} with {}", path, strategy.name());
+
+    let mut hasher = Hasher::new(algo);
+    if let Some((s, SaltPosition::Prefix)) = salt {
+        hasher.update(s);
+    }
+    iotune::hash_range(&mut file, strategy, 0, u64::MAX, &mut hasher).or_exit("unable to read data");
+    if let Some((s, SaltPosition::Suffix)) = salt {
+        hasher.update(s);
+    }
+
+    hasher.finalize()
+}
+
+/**
+ * Handles `bench io`, reporting the hashing throughput of each read strategy on a file.
+ */
+fn
+run_bench (matches: &ArgMatches, algo: Algo) {
+    let Some(("io", sub)) = matches.subcommand() else { unreachable!() };
+    let path = sub.get_one::<String>("file").unwrap();
+    let probe_len = parse_size(sub.get_one::<String>("probe-size").unwrap())
+        .filter(|size| *size > 0)
+        .or_exit("invalid probe size; use e.g. 8MiB");
 
-        state.add(&input_values);
+    let results = iotune::probe(path, &iotune::candidates(), probe_len, algo).or_exit("unable to read file");
+    let best = iotune::fastest(&results);
+
+    for r in &results {
+        println!("{:<12}{:>10.1} MiB/s{}",
+            r.strategy.name(),
+            r.throughput() / (1 << 20) as f64,
+            if r.strategy == best { "  (fastest)" } else { "" });
     }
+}
 
-    // Encode state into base 64
-    return hex::encode(
-        &state.export()
-    ); 
+/**
+ * Renders the leading `bits` bits of a digest for --truncate: hex with as many digits as the
+ * bits need, or base64 of the bytes holding them.
+ */
+fn
+format_truncated (digest: &[u8], algo: Algo, bits: usize, format: format::Format) -> String {
+    let truncated = Digest::from_slice(algo, digest).unwrap().truncate(bits);
+    match format {
+        format::Format::Base64 => base64::encode(truncated.as_bytes()),
+        _ => truncated.to_string()
+    }
 }
 
-fn 
-tests () {
-    assert!(hash_string("", 256).eq("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"));
-    assert!(hash_string("abcde", 256).eq("36bbe50ed96841d10443bcb670d6554f0a34b761be67ec9c4a8ad2c0c44ca42c"));
-    assert!(hash_string("abcdefghijklmnopqrstuvwxyz12345678901234567890", 256)
-      .eq("a8143361b55756a30c4c4369726748e4ae193ca1d31e1f21f47bc7171cd56e9a"));
-    assert!(hash_string("a8143361b55756a30c4c4369726748e4ae193ca1d31e1f21f47bc7171cd56e9a", 256)
-      .eq("fc3b517b3c9ede5c64058615d49ec4ac6eadda73d74f1eade0bdb5d70de93dfb"));
+/**
+ * Prints the number, type, offset, length and digest of each partition in the disk image at
+ * `path`, and its name if it has one, and returns the digest of the whole image. A partition
+ * running past the end of a truncated image is reported instead of hashed.
+ */
+fn
+hash_partitions (path: &str, algo: Algo) -> Vec<u8> {
+    let mut file = fs::File::open(path).or_exit("unable to open file");
+    let table = partition::read_table(&mut file).or_exit("unable to read the partition table").unwrap_or_else(|| {
+        log_warn!("{} has no MBR or GPT partition table; only the whole image is hashed", path);
+        partition::Table { scheme: partition::Scheme::Mbr, sector_size: 512, partitions: Vec::new() }
+    });
 
-    assert!(hash_string("", 224).eq("d14a028c2a3a2bc9476102bb288234c415a2b01f828ea62ac5b3e42f"));
-    assert!(hash_string("abcde", 224).eq("bdd03d560993e675516ba5a50638b6531ac2ac3d5847c61916cfced6"));
-    assert!(hash_string("abcdefghijklmnopqrstuvwxyz12345678901234567890", 224)
-        .eq("bbf04b42f9aa379d73e39955828523db73f5ddef6f8ca518684fb2b7"));
-    assert!(hash_string("bbf04b42f9aa379d73e39955828523db73f5ddef6f8ca518684fb2b7", 224)
-    .eq("e8cffc71ed2e47380e3ae16a92a6f5cfeb1f393a59f05d2cd05d72af"));
+    io::Seek::rewind(&mut file).or_exit("unable to seek");
+    let (digests, whole) = partition::digests(io::BufReader::new(file), algo, &table.partitions)
+        .or_exit("unable to read data");
 
-    println!("Tests completed successfully!");
+    for (p, digest) in table.partitions.iter().zip(digests) {
+        let digest = digest.map(|d| d.to_hex()).unwrap_or_else(|| {
+            log_warn!("partition {} runs past the end of the image", p.number);
+            "truncated".to_string()
+        });
+        let name = if p.name.is_empty() { String::new() } else { format!("\t{}", p.name) };
+        println!("{}{}\t{}\t{}\t{}\t{}{}", table.scheme.name(), p.number, p.kind, p.offset, p.length, digest, name);
+    }
+
+    whole.to_vec()
+}
+
+/**
+ * Streams a file through the hasher in fixed-size reads. If a SIGINT or SIGTERM arrives, hashing
+ * stops after the current read, the partial state is written to `checkpoint` (if one is given),
+ * and the process exits with 128 + the signal number. With `resume`, hashing continues from a
+ * previously written checkpoint; the checkpoint is removed once the digest is complete. With
+ * `eol`, line endings are converted as the file is read; that can't be combined with a
+ * checkpoint, which doesn't record a "\r" held back between reads. With `entropy`, the file's
+ * bytes are also counted into it as they are read.
+ */
+fn
+hash_file_resumable (
+    path: &str,
+    algo: Algo,
+    salt: Option<(&[u8], SaltPosition)>,
+    eol: Option<eol::Eol>,
+    checkpoint: Option<&str>,
+    resume: bool,
+    mut entropy: Option<&mut entropy::Entropy>
+) -> Vec<u8> {
+    interrupt::install();
+
+    let mut file = fs::File::open(path).or_exit("unable to open file");
+
+    let (mut hasher, mut offset) = match (checkpoint, resume) {
+        (Some(c), true) => {
+            let saved = checkpoint::Checkpoint::load(c).or_exit("unable to read checkpoint");
+            if saved.input != path || saved.hasher.algorithm() != algo {
+                log_error!("checkpoint was written for a different input or algorithm");
+                std::process::exit(2);
+            }
+
+            io::Seek::seek(&mut file, io::SeekFrom::Start(saved.offset)).or_exit("unable to seek");
+            log_info!("resuming {} at byte {}", path, saved.offset);
+            (saved.hasher, saved.offset)
+        },
+        _ => {
+            let mut hasher = Hasher::new(algo);
+            if let Some((s, SaltPosition::Prefix)) = salt {
+                hasher.update(s);
+            }
+            (hasher, 0)
+        }
+    };
+
+    let mut buffer = vec![0u8; 1 << 16];
+    let mut normalizer = eol.map(eol::EolNormalizer::new);
+
+    loop {
+        if let Some(signum) = interrupt::received() {
+            if let Some(c) = checkpoint {
+                let saved = checkpoint::Checkpoint { input: path.to_string(), offset, hasher };
+                saved.save(c).or_exit("unable to write checkpoint");
+                log_warn!("interrupted after {} bytes; state saved to {}", offset, c);
+            }
+            std::process::exit(interrupt::exit_code(signum));
+        }
+
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => fail("unable to read data", e)
+        };
+
+        match normalizer.as_mut() {
+            Some(n) => n.update(&buffer[..read], |b| hasher.update(b)),
+            None => hasher.update(&buffer[..read])
+        }
+        if let Some(e) = entropy.as_mut() {
+            e.update(&buffer[..read]);
+        }
+        offset += read as u64;
+    }
+
+    if let Some(n) = normalizer.as_mut() {
+        n.finish(|b| hasher.update(b));
+    }
+
+    if let Some((s, SaltPosition::Suffix)) = salt {
+        hasher.update(s);
+    }
+
+    if let (Some(c), true) = (checkpoint, resume) {
+        let _ = fs::remove_file(c);
+    }
+
+    hasher.finalize()
 }
 
 fn 
@@ -267,50 +3643,811 @@ main () {
     let matches = Command::new("sha2")
     .version("0.1")
     .about("Fun with cryptographic hash functions")
-    .arg(arg!(--path <VALUE>).required(false))
+    .arg(arg!(--path <VALUE>).required(false).num_args(1..).action(clap::ArgAction::Append))
     .arg(arg!(--string <VALUE>).required(false))
-    .arg(arg!(--algo <VALUE>).required(false))
+    .arg(arg!(--concat "Hash all --path files as one continuous stream, in the order given").required(false))
+    .arg(arg!(--"json-canonical" "Parse the input as JSON and hash its RFC 8785 canonical form").required(false))
+    .arg(arg!(--normalize <FORM> "Put --string in this Unicode normalization form before hashing")
+        .required(false)
+        .value_parser(["nfc", "nfd"])
+        .requires("string")
+        .conflicts_with_all(["json-canonical", "lines"]))
+    .arg(arg!(--"strip-comments" <LANGUAGE> "Remove comments and insignificant whitespace before hashing")
+        .required(false)
+        .value_parser(["c", "py", "sh"])
+        .conflicts_with_all(["json-canonical", "normalize", "concat", "checkpoint", "resume", "follow", "autotune", "block-digests", "lines", "tee"]))
+    .arg(arg!(--"normalize-eol" <EOL> "Convert CRLF and LF line endings to this one before hashing")
+        .required(false)
+        .value_parser(["lf", "crlf"])
+        .conflicts_with_all(["json-canonical", "checkpoint", "resume", "follow", "autotune", "block-digests", "lines", "tee"]))
+    .arg(arg!(--algo <VALUE>).required(false).global(true))
     .arg(arg!(--test).required(false))
+    .arg(arg!(--"log-level" <LEVEL> "Most verbose diagnostics to emit on stderr")
+        .required(false)
+        .global(true)
+        .value_parser(["error", "warn", "info", "debug", "trace"])
+        .default_value("warn"))
+    .arg(arg!(--"log-json" "Emit diagnostics as JSON lines").required(false).global(true))
+    .arg(arg!(--"salt-hex" <HEX> "Salt to combine with the input, hex-encoded").required(false))
+    .arg(arg!(--"salt-file" <PATH> "File whose contents are used as the salt").required(false)
+        .conflicts_with("salt-hex"))
+    .arg(arg!(--"salt-position" <POSITION> "Hash salt||message (prefix) or message||salt (suffix)")
+        .required(false)
+        .value_parser(["prefix", "suffix"])
+        .default_value("prefix"))
+    .arg(arg!(--format <FORMAT> "Output format for the digest")
+        .required(false)
+        .value_parser(["hex", "base64", "http-digest", "http-digest-legacy", "nix-base32", "sri", "base32-crockford", "zbase32", "digestinfo-der", "certutil", "get-filehash-csv", "hash-uri", "ni"])
+        .default_value("hex"))
+    .arg(arg!(--truncate <BITS> "Print only the leading BITS bits of the digest, e.g. 64 for a short ID")
+        .required(false)
+        .conflicts_with_all(["verify-header", "paste-expected", "interactive", "prehash", "tsq", "lines", "tee", "follow"]))
+    .arg(arg!(--"verify-header" <VALUE> "Check the digest against a Content-Digest, Repr-Digest or Digest header")
+        .required(false))
+    .arg(arg!(--copy "Also place the digest on the system clipboard")
+        .required(false)
+        .conflicts_with_all(["lines", "tee", "follow"]))
+    .arg(arg!(--"paste-expected" "Compare the digest against one copied to the clipboard, e.g. from a download page")
+        .required(false)
+        .conflicts_with_all(["copy", "verify-header", "lines", "tee", "follow"]))
+    .arg(arg!(--interactive "After printing the digest, prompt for the expected one and compare them")
+        .required(false)
+        .conflicts_with_all(["paste-expected", "verify-header", "lines", "tee", "follow"]))
+    .arg(arg!(--explain "With --paste-expected or --interactive, say where a wrong digest goes wrong").required(false))
+    .arg(arg!(--checkpoint <PATH> "On SIGINT/SIGTERM, save the partial hash of --path here").required(false))
+    .arg(arg!(--resume "Continue hashing --path from the state saved in --checkpoint")
+        .required(false)
+        .requires("checkpoint"))
+    .arg(arg!(--follow "Keep hashing --path as it grows, printing digest-so-far snapshots")
+        .required(false)
+        .requires("path")
+        .conflicts_with_all(["checkpoint", "salt-hex", "salt-file"]))
+    .arg(arg!(--"follow-interval" <DURATION> "With --follow, print a snapshot this often, e.g. 10s")
+        .required(false)
+        .requires("follow"))
+    .arg(arg!(--"block-digests" <SIZE> "Also print offset, length and digest of every SIZE block of --path, e.g. 4MiB")
+        .required(false)
+        .requires("path")
+        .conflicts_with_all(["checkpoint", "follow", "salt-hex", "salt-file"]))
+    .arg(arg!(--partitions "Also print a digest for each partition of the disk image at --path, from its MBR or GPT")
+        .required(false)
+        .requires("path")
+        .conflicts_with_all(["checkpoint", "follow", "block-digests", "salt-hex", "salt-file"]))
+    .arg(arg!(--autotune "For large files, probe read buffer sizes and mmap first and use the fastest")
+        .required(false)
+        .conflicts_with_all(["checkpoint", "follow", "block-digests", "partitions"]))
+    .arg(arg!(--lines "Hash each line of --path, --string or stdin separately, printing digest and line number")
+        .required(false))
+    .arg(arg!(--"show-input" "With --lines, print each line instead of its number")
+        .required(false)
+        .requires("lines"))
+    .arg(arg!(--tee "Copy stdin to stdout unchanged, printing its digest to stderr at EOF")
+        .required(false)
+        .conflicts_with_all(["path", "string"]))
+    .arg(arg!(--"tee-digest-file" <PATH> "With --tee, write the digest to this file instead of stderr")
+        .required(false)
+        .requires("tee"))
+    .arg(arg!(--prehash <PATH> "Write the digest as a DER DigestInfo, ready for signing elsewhere, to this file")
+        .required(false))
+    .arg(arg!(--tsq <PATH> "Write an RFC 3161 time-stamp request for the digest to this file").required(false))
+    .arg(arg!(--entropy "Also print the Shannon entropy of each --path in bits per byte, and of each block with --block-digests")
+        .required(false)
+        .conflicts_with_all(["string", "concat", "json-canonical", "strip-comments", "lines", "tee", "follow", "autotune", "partitions",
+            "checkpoint", "verify-header", "paste-expected", "interactive"]))
+    .arg(arg!(--report <FORMAT> "Emit a JSON record of each --path's size, MD5, SHA-1 and SHA-256, read once")
+        .required(false)
+        .value_parser(["bundle"])
+        .conflicts_with_all(["string", "concat", "json-canonical", "strip-comments", "normalize-eol", "lines", "tee", "follow", "autotune",
+            "block-digests", "partitions", "checkpoint", "truncate", "verify-header", "copy", "paste-expected", "interactive",
+            "prehash", "tsq", "salt-hex", "salt-file"]))
+    .arg(arg!(--"tsq-no-nonce" "Omit the random nonce from the time-stamp request").required(false))
+    .arg(arg!(--"tsq-cert-req" "Ask the TSA to include its certificate in the response").required(false))
+    .subcommand(Command::new("kdf")
+        .about("Key derivation functions")
+        .subcommand_required(true)
+        .subcommand(Command::new("pbkdf2")
+            .about("PBKDF2 with HMAC, per RFC 8018 # 5.2")
+            .arg(arg!(--password <PASSWORD> "The password; prompted for if omitted").required(false))
+            .arg(arg!(--"salt-hex" <HEX> "The salt, hex-encoded").required(false))
+            .arg(arg!(--iterations <COUNT> "Iteration count").required(false)
+                .value_parser(clap::value_parser!(u32).range(1..)))
+            .arg(arg!(--length <BYTES> "Number of output bytes").required(false)
+                .value_parser(clap::value_parser!(usize))
+                .default_value("32"))
+            .arg(arg!(--calibrate <DURATION> "Pick the iteration count taking about this long, e.g. 250ms")
+                .required(false)
+                .conflicts_with("iterations")))
+        .subcommand(Command::new("tls12-prf")
+            .about("TLS 1.2 PRF, P_hash(secret, label + seed), per RFC 5246 # 5")
+            .arg(arg!(--"secret-hex" <HEX> "The secret, hex-encoded").required(true))
+            .arg(arg!(--label <LABEL> "ASCII label, e.g. \"key expansion\"").required(true))
+            .arg(arg!(--"seed-hex" <HEX> "The seed, hex-encoded").required(true))
+            .arg(arg!(--length <BYTES> "Number of output bytes").required(true).value_parser(clap::value_parser!(usize)))))
+    .subcommand(key_args(Command::new("hmac")
+        .about("Keyed-hash message authentication code, per FIPS 198-1"))
+        .arg(arg!(--path <VALUE>).required(false))
+        .arg(arg!(--string <VALUE>).required(false))
+        .arg(arg!(--verify <HEX> "Compare against an expected tag instead of printing the MAC").required(false)))
+    .subcommand(Command::new("shake")
+        .about("SHAKE128 or SHAKE256 extendable output of a string, file or stdin, per FIPS 202")
+        .arg(arg!(--path <VALUE>).required(false))
+        .arg(arg!(--string <VALUE>).required(false).conflicts_with("path"))
+        .arg(arg!(--bits <BITS> "Security strength")
+            .required(false)
+            .value_parser(["128", "256"])
+            .default_value("256"))
+        .arg(arg!(--"output-bytes" <SIZE> "Length of output to produce, e.g. 64 or 1GiB")
+            .required(false)
+            .default_value("64"))
+        .arg(arg!(--output <PATH> "Write the raw output here instead of hex to stdout").required(false)))
+    .subcommand(Command::new("multi")
+        .about("Hash a file with several algorithms in one read, or check it against several published digests")
+        .arg(arg!(<file> "The file to hash"))
+        .arg(arg!(--expect <HEX> "A published digest; its algorithm is told from its length. May be repeated")
+            .required(false)
+            .action(clap::ArgAction::Append)))
+    .subcommand(Command::new("tuple")
+        .about("Hash several values as an unambiguous tuple, each prefixed with its length")
+        .arg(arg!([fields] ... "The values, in order"))
+        .arg(arg!(--hex "The values are hex-encoded bytes rather than text").required(false)))
+    .subcommand(Command::new("repro-diff")
+        .about("Compare two build output trees file by file, for reproducible-build checks")
+        .arg(arg!(<left> "The first tree"))
+        .arg(arg!(<right> "The second tree"))
+        .arg(arg!(--"respect-gitignore" "Skip files git ignores, and the .git directory").required(false))
+        .arg(arg!(--report <FORMAT> "Emit a JSON record per difference instead of text")
+            .required(false)
+            .value_parser(["jsonl"])))
+    .subcommand(Command::new("combine")
+        .about("Combine many digests into one fingerprint of the set, e.g. of a directory's files")
+        .arg(arg!([digests] ... "Hex digests; read one per line from stdin if omitted"))
+        .arg(arg!(--mode <MODE> "xor and sorted ignore order; concat doesn't. All but xor rehash with --algo")
+            .required(false)
+            .value_parser(["xor", "sorted", "concat"])
+            .default_value("sorted")))
+    .subcommand(Command::new("gen")
+        .about("Write a reproducible pseudo-random file derived from a seed, and print its digest")
+        .arg(arg!(<file> "The file to write"))
+        .arg(arg!(--seed <SEED> "Any string; the same seed and size always give the same file").required(true))
+        .arg(arg!(--size <SIZE> "Length of the file, e.g. 4096 or 10GiB").required(true)))
+    .subcommand(key_args(Command::new("pseudonymize")
+        .about("Replace selected CSV columns from stdin with their digests, or HMACs if a key is given"))
+        .arg(arg!(-c --columns <COLUMNS> ... "Columns to replace, by 1-based number or header name; comma-separated")
+            .required(true))
+        .arg(arg!(--header "Treat the first row as a header, passing it through unchanged").required(false))
+        .arg(arg!(-d --delimiter <CHAR> "Field delimiter").required(false).default_value(",")))
+    .subcommand(key_args(Command::new("seal")
+        .about("Append an HMAC tag to a file, or write one to FILE.seal, for later tamper checks"))
+        .arg(arg!(<file> "The file to seal"))
+        .arg(arg!(--sidecar "Write the tag to FILE.seal instead of appending it").required(false)))
+    .subcommand(key_args(Command::new("unseal")
+        .about("Verify a file sealed with `seal`"))
+        .arg(arg!(<file> "The sealed file"))
+        .arg(arg!(--sidecar "Read the tag from FILE.seal").required(false))
+        .arg(arg!(--strip "After verifying, remove the appended tag from the file")
+            .required(false)
+            .conflicts_with("sidecar")))
+    .subcommand(Command::new("uniq")
+        .about("Copy stdin to stdout, dropping lines that have already been seen")
+        .arg(arg!(--memory <SIZE> "Upper bound on memory used to remember digests, e.g. 512MiB")
+            .required(false)
+            .default_value("256MiB")))
+    .subcommand(Command::new("bloom")
+        .about("Build and query Bloom filters of lines or digests")
+        .subcommand_required(true)
+        .subcommand(Command::new("build")
+            .about("Build a Bloom filter from the lines on stdin")
+            .arg(arg!(-o --output <PATH> "Where to write the filter").required(true))
+            .arg(arg!(--expected <N> "Number of items to size for; defaults to the number read")
+                .required(false)
+                .value_parser(clap::value_parser!(u64)))
+            .arg(arg!(--"fp-rate" <RATE> "Target false-positive rate")
+                .required(false)
+                .value_parser(clap::value_parser!(f64))
+                .default_value("0.01"))
+            .arg(arg!(--digests "Each line starts with a hex digest to add, e.g. sha256sum output")
+                .required(false)))
+        .subcommand(Command::new("check")
+            .about("Check whether items may be in a Bloom filter")
+            .arg(arg!(<filter> "The filter file"))
+            .arg(arg!([items] ... "Items to look up; read from stdin if omitted"))
+            .arg(arg!(--digests "Items are hex digests rather than lines").required(false))))
+    .subcommand(key_args(Command::new("shard")
+        .about("Assign each line of stdin to a bucket by its digest, or HMAC if a key is given"))
+        .arg(arg!(--buckets <N> "Number of buckets")
+            .required(true)
+            .value_parser(clap::value_parser!(u32).range(1..))))
+    .subcommand(Command::new("split")
+        .about("Digest each fixed-size part of a file and the whole file, e.g. for multipart uploads")
+        .arg(arg!(<file> "The file to split"))
+        .arg(arg!(--"part-size" <SIZE> "Part size, e.g. 8MiB; the last part may be shorter").required(true))
+        .arg(arg!(--"output-dir" <DIR> "Also write the parts here as FILE.partN; otherwise only offsets are printed")
+            .required(false))
+        .arg(arg!(--format <FORMAT> "Output format for the digests")
+            .required(false)
+            .value_parser(["hex", "base64"])
+            .default_value("hex")))
+    .subcommand(Command::new("bench")
+        .about("Measure hashing throughput")
+        .subcommand_required(true)
+        .subcommand(Command::new("io")
+            .about("Compare read buffer sizes and mmap on a file")
+            .arg(arg!(<file> "The file to read; use one larger than the probe size times six"))
+            .arg(arg!(--"probe-size" <SIZE> "Bytes hashed per strategy")
+                .required(false)
+                .default_value("8MiB"))))
+    .subcommand(Command::new("htpasswd")
+        .about("Generate or verify Apache htpasswd {SHA} entries (legacy, unsalted SHA-1)")
+        .arg(arg!(<user> "The user name"))
+        .arg(arg!(--password <PASSWORD> "The password; prompted for if omitted").required(false))
+        .arg(arg!(--verify <FILE> "Check the password against the user's entry in this htpasswd file").required(false)))
+    .subcommand(Command::new("ldap-hash")
+        .about("Generate or verify LDAP userPassword values such as {SSHA256}")
+        .arg(arg!(--scheme <SCHEME> "Hashing scheme").required(false)
+            .value_parser(["ssha", "sha256", "ssha256", "ssha512"])
+            .default_value("ssha256"))
+        .arg(arg!(--password <PASSWORD> "The password; prompted for if omitted").required(false))
+        .arg(arg!(--"salt-hex" <HEX> "Use this salt instead of 8 random bytes").required(false))
+        .arg(arg!(--verify <VALUE> "Check the password against this userPassword value").required(false)))
+    .subcommand(Command::new("check")
+        .about("Verify files against md5sum/sha256sum-style or BSD-tagged checksum files")
+        .arg(arg!(<file> ... "Checksum files to read"))
+        .arg(arg!(--"ignore-missing" "Skip listed files which don't exist instead of failing them").required(false))
+        .arg(arg!(--explain "For each failure, say where the digests diverge and what might be wrong").required(false))
+        .arg(arg!(--report <FORMAT> "Emit a machine-readable record per file instead of status lines")
+            .required(false)
+            .value_parser(["jsonl"]))
+        .arg(arg!(-j --jobs <N> "Number of files to hash concurrently")
+            .required(false)
+            .value_parser(clap::value_parser!(u32).range(1..))
+            .default_value("1")))
+    .subcommand(Command::new("cargo-verify")
+        .about("Verify downloaded crates against the checksums in a Cargo.lock")
+        .arg(arg!(--lockfile <PATH> "The Cargo.lock to read")
+            .required(false)
+            .default_value("Cargo.lock"))
+        .arg(arg!(--"cache-dir" <DIR> "Registry cache directory; defaults to $CARGO_HOME/registry/cache")
+            .required(false))
+        .arg(arg!(--crate <FILE> "Verify a single .crate file instead of a lockfile")
+            .required(false)
+            .requires("cksum"))
+        .arg(arg!(--cksum <HEX> "The expected SHA-256 of the --crate file")
+            .required(false)
+            .requires("crate"))
+        .arg(arg!(--report <FORMAT> "Emit a machine-readable record per crate instead of status lines")
+            .required(false)
+            .value_parser(["jsonl"])))
+    .subcommand(Command::new("oci-verify")
+        .about("Verify every blob of an OCI image layout against its digest")
+        .arg(arg!(<dir> "The image layout directory, containing index.json and blobs/"))
+        .arg(arg!(--report <FORMAT> "Emit a machine-readable record per blob instead of status lines")
+            .required(false)
+            .value_parser(["jsonl"])))
+    .subcommand(Command::new("firmware-verify")
+        .about("Verify regions of a firmware or disk image against a descriptor of expected digests")
+        .arg(arg!(<image> "The image file"))
+        .arg(arg!(<descriptor> "Lines of: name, offset, length, expected digest"))
+        .arg(arg!(--report <FORMAT> "Emit a machine-readable record per region instead of status lines")
+            .required(false)
+            .value_parser(["jsonl"])))
+    .subcommand(Command::new("imaging-log")
+        .about("Write a forensic acquisition log of an image's MD5, SHA-1 and SHA-256, per chunk and in total, or check it against one")
+        .arg(arg!(<image> "The acquired image"))
+        .arg(arg!(--"chunk-size" <SIZE> "Hash each SIZE chunk of the image as well as the whole, e.g. 1GiB")
+            .required(false)
+            .default_value("1GiB"))
+        .arg(arg!(--verify <LOG> "Check the image against the digests in an examiner's log instead of writing one")
+            .required(false))
+        .arg(arg!(--report <FORMAT> "With --verify, emit a machine-readable record per digest instead of status lines")
+            .required(false)
+            .requires("verify")
+            .value_parser(["jsonl"])))
+    .subcommand(Command::new("apt-verify")
+        .about("Verify the files listed in an APT Release, InRelease or Packages file")
+        .arg(arg!(<file> "The Release, InRelease or Packages file"))
+        .arg(arg!(--root <DIR> "Where listed paths are relative to; defaults to the Release file's directory, \
+            or the current directory for Packages")
+            .required(false))
+        .arg(arg!(--report <FORMAT> "Emit a machine-readable record per file instead of status lines")
+            .required(false)
+            .value_parser(["jsonl"]))
+        .arg(arg!(-j --jobs <N> "Number of files to hash concurrently")
+            .required(false)
+            .value_parser(clap::value_parser!(u32).range(1..))
+            .default_value("1")))
+    .subcommand(key_args(Command::new("manifest")
+        .about("Hash every file beneath a directory, producing a sha256sum-style manifest"))
+        .arg(arg!(--"hmac-paths" "Replace each path with its HMAC under the --key-* key").required(false))
+        .arg(arg!(<dir> "Directory to scan"))
+        .arg(arg!(--"respect-gitignore" "Skip files git ignores, and the .git directory").required(false))
+        .arg(arg!(--output <PATH> "Write the manifest here instead of stdout").required(false))
+        .arg(arg!(--checkpoint <PATH> "Periodically save scan progress here").required(false))
+        .arg(arg!(--"checkpoint-every" <FILES> "Save progress after this many newly hashed files")
+            .required(false)
+            .value_parser(clap::value_parser!(u32).range(1..))
+            .default_value("100"))
+        .arg(arg!(--resume "Skip files already recorded in --checkpoint").required(false).requires("checkpoint")))
+    .subcommand(key_args(Command::new("hashdeep")
+        .about("Write hashdeep-format hash lists, or audit files against one"))
+        .arg(arg!(--"hmac-paths" "Replace each path with its HMAC under the --key-* key").required(false))
+        .arg(arg!(<paths> ... "Files and directories to hash"))
+        .arg(arg!(--"respect-gitignore" "Skip files git ignores in directories, and the .git directory").required(false))
+        .arg(arg!(-c --columns <LIST> "Comma-separated hash columns: sha1, sha256")
+            .required(false)
+            .default_value("sha256"))
+        .arg(arg!(-a --audit <KNOWN> "Audit against this hashdeep file").required(false))
+        .arg(arg!(--report <FORMAT> "With --audit, emit a JSON record per file")
+            .required(false)
+            .value_parser(["jsonl"])
+            .requires("audit")))
+    .subcommand(Command::new("link-farm")
+        .about("Build a directory of links to every file beneath DIR, named by content digest")
+        .arg(arg!(<dir> "Directory to scan"))
+        .arg(arg!(<out> "Where to create the links, as OUT/ab/abcd..."))
+        .arg(arg!(--"respect-gitignore" "Skip files git ignores, and the .git directory").required(false))
+        .arg(arg!(--hardlink "Create hard links instead of symbolic links").required(false)))
+    .subcommand(Command::new("bitcoin")
+        .about("Bitcoin block header hashing (SHA-256d) and Merkle roots")
+        .subcommand_required(true)
+        .subcommand(Command::new("header")
+            .about("Parse an 80-byte block header and print its block hash")
+            .arg(arg!(<hex> "The serialized header, hex-encoded"))
+            .arg(arg!(--txids <TXID> ... "Check the header's Merkle root against these transaction ids").required(false)))
+        .subcommand(Command::new("merkle")
+            .about("Compute the Merkle root of transaction ids, given in display order")
+            .arg(arg!(<txids> ... "Transaction ids, hex-encoded"))))
+    .subcommand(Command::new("eth-address")
+        .about("Derive an Ethereum address (EIP-55 checksummed) from an uncompressed secp256k1 public key")
+        .arg(arg!(<"public-key"> "The public key as hex, X || Y with an optional 04 prefix")))
+    .subcommand(Command::new("ssri")
+        .about("Verify a file against an npm/SSRI integrity string such as sha512-<base64>")
+        .arg(arg!(<integrity> "The integrity value, e.g. from package-lock.json"))
+        .arg(arg!(<file> "The tarball to verify")))
+    .subcommand(Command::new("authenticode")
+        .about("Compute the Authenticode digest of PE images (.exe, .dll, .sys, .efi), as signtool reports it")
+        .arg(arg!(<file> ... "The images to hash"))
+        .arg(arg!(--expect <DIGEST> "Check a single image's digest against this value, e.g. from signtool verify /v")
+            .required(false)))
+    .subcommand(Command::new("build-id")
+        .about("Check that ELF binaries' GNU build-id notes match their contents, as the linker computed them")
+        .arg(arg!(<file> ... "The binaries to check")))
+    .subcommand(Command::new("git-object")
+        .about("Compute the git blob IDs of files, or check files against the IDs git ls-tree lists")
+        .arg(arg!([file] ... "Files to hash").required_unless_present("ls-tree"))
+        .arg(arg!(--"object-format" <FORMAT> "The repository's object format; sha1, git's default, is deprecated")
+            .required(false)
+            .value_parser(["sha1", "sha256"])
+            .default_value("sha1"))
+        .arg(arg!(--"ls-tree" <LISTING> "Check the files named in saved `git ls-tree -r` output, relative to the current directory")
+            .required(false)
+            .conflicts_with("file"))
+        .arg(arg!(--report <FORMAT> "With --ls-tree, emit a machine-readable record per file instead of status lines")
+            .required(false)
+            .requires("ls-tree")
+            .value_parser(["jsonl"])))
+    .subcommand(Command::new("scan")
+        .about("Hash every file beneath the given paths and mark each as known or unknown to a hash set, e.g. the NSRL RDS, \
+            or report those on a blocklist")
+        .arg(arg!(<paths> ... "Files and directories to scan"))
+        .arg(arg!(--known <SET> "Sorted hex digests, an RDS 2.x NSRLFile.txt, or a Bloom filter from bloom build --digests")
+            .required(false))
+        .arg(arg!(--blocklist <LIST> "Digests of malicious files, one per line, each optionally followed by a description")
+            .required(false))
+        .group(clap::ArgGroup::new("set").args(["known", "blocklist"]).required(true))
+        .arg(arg!(--"respect-gitignore" "Skip files git ignores in directories, and the .git directory").required(false))
+        .arg(arg!(--"hide-known" "Print only the unknown files").required(false).conflicts_with("blocklist"))
+        .arg(arg!(--report <FORMAT> "Emit a machine-readable record per file instead of text lines").required(false)
+            .value_parser(["jsonl"])))
     .get_matches();
 
+    logging::init(
+        logging::Level::from_name(matches.get_one::<String>("log-level").unwrap()).unwrap(),
+        matches.get_flag("log-json")
+    );
+
     let string = matches.get_one::<String>("string");
-    let path = matches.get_one::<String>("path");
-    let algo = matches.get_one::<String>("algo");
+    let paths: Vec<&String> = matches.get_many::<String>("path").map(|p| p.collect()).unwrap_or_default();
+    let path = paths.first().copied();
     let test = matches.get_one::<bool>("test");
 
-    let n = match algo.as_deref() {
+    let algo = match matches.get_one::<String>("algo") {
         None => {
-            if test.is_none() {
-                println!("no algorithim specified; assuming SHA-256");
+            if !test.copied().unwrap_or(false) {
+                log_info!("no algorithm specified; assuming SHA-256");
             }
-            256
-        },
-        Some(s) => match s.as_str() {
-            "224" => 224,
-            "256" => 256,
-            _ => panic!("unsupported algorithm; provide either '224' or '256'"),
+            Algo::Sha256
         },
+        Some(s) => s.parse::<Algo>().unwrap_or_else(|e| {
+            log_error!("invalid --algo {}: {}", s, e);
+            std::process::exit(e.exit_code());
+        })
+    };
+    warn_deprecated(algo);
+
+    let salt: Option<Vec<u8>> = match (matches.get_one::<String>("salt-hex"), matches.get_one::<String>("salt-file")) {
+        (Some(s), None) => Some(hex::decode(s).or_exit("salt must be valid hex")),
+        (None, Some(f)) => Some(fs::read(f).or_exit("unable to read salt file")),
+        _ => None
+    };
+
+    let salt_position = match matches.get_one::<String>("salt-position").map(|s| s.as_str()) {
+        Some("suffix") => SaltPosition::Suffix,
+        _ => SaltPosition::Prefix
     };
 
-    match (string, path, test) {
-        (Some(&ref text), None, Some(false)) => {
-            let digest = hash_string(&text, n);
-            println!("{}", digest);
+    let eol = matches.get_one::<String>("normalize-eol").and_then(|e| eol::Eol::from_name(e));
+
+    match matches.subcommand() {
+        Some(("bitcoin", sub)) => return run_bitcoin(sub),
+        Some(("check", sub)) => return run_check(sub),
+        Some(("kdf", sub)) => return run_kdf(sub, algo),
+        Some(("hashdeep", sub)) => return run_hashdeep(sub),
+        Some(("eth-address", sub)) => return run_eth_address(sub),
+        Some(("hmac", sub)) => return run_hmac(sub, algo),
+        Some(("shake", sub)) => return run_shake(sub),
+        Some(("gen", sub)) => return run_gen(sub, algo),
+        Some(("tuple", sub)) => return run_tuple(sub, algo),
+        Some(("multi", sub)) => return run_multi(sub),
+        Some(("combine", sub)) => return run_combine(sub, algo),
+        Some(("repro-diff", sub)) => return run_repro_diff(sub, algo),
+        Some(("htpasswd", sub)) => return run_htpasswd(sub),
+        Some(("ldap-hash", sub)) => return run_ldap_hash(sub),
+        Some(("manifest", sub)) => return run_manifest(sub, algo),
+        Some(("link-farm", sub)) => return run_link_farm(sub, algo),
+        Some(("ssri", sub)) => return run_ssri(sub),
+        Some(("authenticode", sub)) => return run_authenticode(sub, algo),
+        Some(("build-id", sub)) => return run_build_id(sub),
+        Some(("scan", sub)) => return run_scan(sub, algo),
+        Some(("git-object", sub)) => return run_git_object(sub),
+        Some(("bench", sub)) => return run_bench(sub, algo),
+        Some(("shard", sub)) => return run_shard(sub, algo),
+        Some(("split", sub)) => return run_split(sub, algo),
+        Some(("seal", sub)) => return run_seal(sub, algo),
+        Some(("unseal", sub)) => return run_unseal(sub),
+        Some(("bloom", sub)) => return run_bloom(sub),
+        Some(("uniq", sub)) => return run_uniq(sub, algo),
+        Some(("pseudonymize", sub)) => return run_pseudonymize(sub, algo),
+        Some(("cargo-verify", sub)) => return run_cargo_verify(sub),
+        Some(("firmware-verify", sub)) => return run_firmware_verify(sub),
+        Some(("imaging-log", sub)) => return run_imaging_log(sub),
+        Some(("apt-verify", sub)) => return run_apt_verify(sub),
+        Some(("oci-verify", sub)) => return run_oci_verify(sub),
+        _ => {}
+    }
+
+    if matches.contains_id("report") {
+        return report_bundle(&paths, matches.get_flag("entropy"));
+    }
+    // Measured from the file as stored, before any --normalize-eol or salt
+    let mut file_entropy = matches.get_flag("entropy").then(entropy::Entropy::new);
+    if file_entropy.is_some() && matches!(matches.get_one::<String>("format").unwrap().as_str(), "certutil" | "get-filehash-csv") {
+        log_error!("--entropy can't be added to certutil or Get-FileHash output");
+        std::process::exit(2);
+    }
+
+    let truncate = matches.get_one::<String>("truncate").map(|t| {
+        if !matches!(matches.get_one::<String>("format").unwrap().as_str(), "hex" | "base64") {
+            log_error!("--truncate takes --format hex or base64");
+            std::process::exit(2);
+        }
+        t.parse::<usize>().ok().filter(|bits| *bits > 0 && *bits <= algo.bits()).unwrap_or_else(|| {
+            log_error!("invalid --truncate {}; use 1 to {} bits", t, algo.bits());
+            std::process::exit(2);
+        })
+    });
+
+    if matches.get_flag("tee") {
+        let format = format::Format::from_name(matches.get_one::<String>("format").unwrap()).unwrap();
+        return tee_stdin(
+            algo,
+            salt.as_deref().map(|s| (s, salt_position)),
+            format,
+            matches.get_one::<String>("tee-digest-file").map(|f| f.as_str())
+        );
+    }
+
+    if matches.get_flag("lines") {
+        let format = format::Format::from_name(matches.get_one::<String>("format").unwrap()).unwrap();
+        let salt = salt.as_deref().map(|s| (s, salt_position));
+        let show_input = matches.get_flag("show-input");
+        let stdout = io::stdout();
+        let mut out = io::BufWriter::new(stdout.lock());
+
+        if let Some(text) = string {
+            hash_lines(text.as_bytes(), &mut out, algo, salt, format, show_input, 1).or_exit("unable to write stdout");
+        } else if paths.is_empty() {
+            hash_lines(io::stdin().lock(), &mut out, algo, salt, format, show_input, 1).or_exit("unable to read stdin");
+        } else {
+            // Line numbers continue across files, as if they were concatenated
+            let mut next = 1;
+            for f in &paths {
+                let file = fs::File::open(f).or_exit("unable to open file");
+                next += hash_lines(io::BufReader::new(file), &mut out, algo, salt, format, show_input, next)
+                    .or_exit("unable to read data");
+            }
+        }
+        return;
+    }
+
+    let single_file_only = matches.get_flag("follow")
+        || matches.get_flag("autotune")
+        || matches.get_flag("copy")
+        || matches.get_flag("paste-expected")
+        || matches.get_flag("interactive")
+        || matches.contains_id("checkpoint")
+        || matches.contains_id("block-digests")
+        || matches.get_flag("partitions");
+    if paths.len() > 1 && single_file_only {
+        log_error!("--follow, --autotune, --block-digests, --partitions, --checkpoint, --copy, --paste-expected and --interactive take a single --path");
+        std::process::exit(2);
+    }
+    if matches.contains_id("checkpoint") && !algo.has_midstate() {
+        log_error!("--checkpoint only saves SHA-224 and SHA-256 states");
+        std::process::exit(2);
+    }
+
+    let digest: Vec<u8> = match (string, path, test) {
+        (_, _, Some(false)) if matches.get_flag("json-canonical") => {
+            let source = match (string, path) {
+                (Some(text), None) => text.clone(),
+                (None, Some(f)) if paths.len() == 1 => fs::read_to_string(f).or_exit("unable to read file"),
+                _ => {
+                    log_error!("--json-canonical takes --string or a single --path");
+                    std::process::exit(2);
+                }
+            };
+            let document = json::parse(&source).or_exit("invalid JSON");
+            let mut message = json::canonicalize(&document).or_exit("unable to canonicalize JSON").into_bytes();
+            if let Some(s) = salt {
+                apply_salt(&mut message, &s, salt_position);
+            }
+            digest(&message, algo)
         },
-        (None, Some(f), Some(false)) => {
-            let mut file_data: Vec<u8> = Vec::new();
-            let mut file = fs::File::open(f).expect("unable to open file");
+        (_, _, Some(false)) if matches.contains_id("strip-comments") => {
+            let language = strip::Language::from_name(matches.get_one::<String>("strip-comments").unwrap()).unwrap();
+            let stripped = |source: &[u8]| {
+                let mut message = strip::strip_comments(source, language);
+                if let Some(s) = &salt {
+                    apply_salt(&mut message, s, salt_position);
+                }
+                digest(&message, algo)
+            };
+
+            match (string, paths.len()) {
+                (Some(text), _) => stripped(text.as_bytes()),
+                (None, 1) => stripped(&fs::read(paths[0]).or_exit("unable to read file")),
+                (None, 0) => {
+                    log_error!("--strip-comments takes --string or --path");
+                    std::process::exit(2);
+                },
+                (None, _) => {
+                    let format = format::Format::from_name(matches.get_one::<String>("format").unwrap()).unwrap();
+                    for f in &paths {
+                        let digest = stripped(&fs::read(f).or_exit("unable to read file"));
+                        let output = match truncate {
+                            Some(bits) => Some(format_truncated(&digest, algo, bits, format)),
+                            None => format::format_digest(&digest, algo, format)
+                        };
+                        match output {
+                            Some(output) => println!("{}  {}", output, f),
+                            None => {
+                                log_error!("{} digests can't be rendered in this format", algo);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    return;
+                }
+            }
+        },
+        (Some(text), None, Some(false)) => {
+            let text = match matches.get_one::<String>("normalize").and_then(|n| unicode::Form::from_name(n)) {
+                Some(form) => unicode::normalize(text, form),
+                None => text.clone()
+            };
+            let mut message = match eol {
+                Some(eol) => eol::normalize(text.as_bytes(), eol),
+                None => text.into_bytes()
+            };
+            if let Some(s) = salt {
+                apply_salt(&mut message, &s, salt_position);
+            }
+            digest(&message, algo)
+        },
+        (None, Some(_), Some(false)) if matches.get_flag("concat") => {
+            let mut hasher = Hasher::new(algo);
+            if let (Some(s), SaltPosition::Prefix) = (&salt, salt_position) {
+                hasher.update(s);
+            }
+
+            // Line endings are normalized across file boundaries, as if the files were one
+            let mut normalizer = eol.map(eol::EolNormalizer::new);
+            for f in &paths {
+                let mut file = fs::File::open(f).or_exit("unable to open file");
+                match normalizer.as_mut() {
+                    Some(n) => {
+                        let mut buffer = vec![0u8; 1 << 16];
+                        loop {
+                            let read = file.read(&mut buffer).or_exit("unable to read data");
+                            if read == 0 {
+                                break;
+                            }
+                            n.update(&buffer[..read], |b| hasher.update(b));
+                        }
+                    },
+                    None => {
+                        hasher.update_reader(io::BufReader::new(file)).or_exit("unable to read data");
+                    }
+                }
+            }
+            if let Some(n) = normalizer.as_mut() {
+                n.finish(|b| hasher.update(b));
+            }
 
-            file.read_to_end(&mut file_data).expect("unable to read data");
+            if let (Some(s), SaltPosition::Suffix) = (&salt, salt_position) {
+                hasher.update(s);
+            }
+            hasher.finalize()
+        },
+        (None, Some(_), Some(false)) if paths.len() > 1 => {
+            let format = format::Format::from_name(matches.get_one::<String>("format").unwrap()).unwrap();
+
+            if format == format::Format::GetFileHashCsv {
+                println!("{}", format::GET_FILEHASH_CSV_HEADER);
+            }
+            for f in &paths {
+                let mut measured = file_entropy.is_some().then(entropy::Entropy::new);
+                let digest = hash_file_resumable(f, algo, salt.as_deref().map(|s| (s, salt_position)), eol, None, false, measured.as_mut());
+                let output = match truncate {
+                    Some(bits) => Some(format_truncated(&digest, algo, bits, format)),
+                    None => format::format_digest(&digest, algo, format)
+                };
+                match output {
+                    Some(_) if format == format::Format::CertUtil => println!("{}", format::certutil_report(&digest, algo, f)),
+                    Some(_) if format == format::Format::GetFileHashCsv => println!("{}", format::get_filehash_csv_row(&digest, algo, f)),
+                    Some(output) => match measured {
+                        Some(measured) => println!("{}  {:.4}  {}", output, measured.bits_per_byte(), f),
+                        None => println!("{}  {}", output, f)
+                    },
+                    None => {
+                        log_error!("{} digests can't be rendered in this format", algo);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            return;
+        },
+        (None, Some(f), Some(false)) if matches.get_flag("follow") => {
+            let interval = matches.get_one::<String>("follow-interval")
+                .map(|i| parse_duration(i).or_exit("invalid duration; use e.g. 250ms or 10s"));
+            let format = format::Format::from_name(matches.get_one::<String>("format").unwrap()).unwrap();
+            return follow_file(f, algo, interval, format);
+        },
+        (None, Some(f), Some(false)) if matches.get_flag("autotune") => {
+            hash_file_autotuned(f, algo, salt.as_deref().map(|s| (s, salt_position)))
+        },
+        (None, Some(f), Some(false)) if matches.contains_id("block-digests") => {
+            let block_size = parse_size(matches.get_one::<String>("block-digests").unwrap())
+                .filter(|size| *size > 0)
+                .or_exit("invalid block size; use e.g. 4MiB");
+            let file = fs::File::open(f).or_exit("unable to open file");
+            let mut block_entropy: Vec<entropy::Entropy> = Vec::new();
+            let (blocks, whole) = blocks::block_digests_with(io::BufReader::new(file), algo, block_size, |index, data| {
+                if let Some(whole) = file_entropy.as_mut() {
+                    whole.update(data);
+                    if index == block_entropy.len() {
+                        block_entropy.push(entropy::Entropy::new());
+                    }
+                    block_entropy[index].update(data);
+                }
+                Ok(())
+            }).or_exit("unable to read data");
 
-            let digest = hash(&mut file_data, n);
-            println!("{}", digest);
+            for (index, block) in blocks.into_iter().enumerate() {
+                match block_entropy.get(index) {
+                    Some(measured) => println!("{}\t{}\t{}\t{:.4}", block.offset, block.len, hex::encode(block.digest), measured.bits_per_byte()),
+                    None => println!("{}\t{}\t{}", block.offset, block.len, hex::encode(block.digest))
+                }
+            }
+            whole
+        },
+        (None, Some(f), Some(false)) if matches.get_flag("partitions") => hash_partitions(f, algo),
+        (None, Some(f), Some(false)) => {
+            hash_file_resumable(
+                f,
+                algo,
+                salt.as_deref().map(|s| (s, salt_position)),
+                eol,
+                matches.get_one::<String>("checkpoint").map(|c| c.as_str()),
+                matches.get_flag("resume"),
+                file_entropy.as_mut()
+            )
         },
         (None, None, Some(true)) => {
             tests();
+            return;
         }
         _ => {
-            println!("no text provided!");
+            log_error!("no text provided!");
+            return;
+        }
+    };
+
+    if matches.get_flag("paste-expected") {
+        let pasted = clipboard::paste().unwrap_or_else(|e| {
+            log_error!("unable to read the clipboard: {}", e);
+            std::process::exit(2);
+        });
+        return verify_pasted(&pasted, "the clipboard", &digest, algo, matches.get_flag("explain"));
+    }
+
+    if let Some(header) = matches.get_one::<String>("verify-header") {
+        match format::verify_http_digest(header, &digest, algo) {
+            Some(true) => println!("OK"),
+            Some(false) => {
+                println!("FAILED");
+                std::process::exit(1);
+            },
+            None => {
+                log_error!("no matching {} entry in header", algo);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(tsq_path) = matches.get_one::<String>("tsq") {
+        let nonce = if matches.get_flag("tsq-no-nonce") {
+            None
+        } else {
+            Some(random::random_bytes(8).or_exit("unable to generate nonce"))
+        };
+
+        let request = tsp::timestamp_request(&digest, algo, nonce.as_deref(), matches.get_flag("tsq-cert-req"))
+            .or_exit("unsupported algorithm for time-stamp requests");
+        fs::write(tsq_path, request).or_exit("unable to write time-stamp request");
+    }
+
+    if let Some(prehash_path) = matches.get_one::<String>("prehash") {
+        let digest_info = der::digest_info(&digest, algo).or_exit("unsupported algorithm for DigestInfo");
+        fs::write(prehash_path, digest_info).or_exit("unable to write prehash");
+    }
+
+    let format = format::Format::from_name(matches.get_one::<String>("format").unwrap()).unwrap();
+    let output = match truncate {
+        Some(bits) => Some(format_truncated(&digest, algo, bits, format)),
+        None => format::format_digest(&digest, algo, format)
+    };
+    match output {
+        Some(_) if format == format::Format::CertUtil && path.is_some() => {
+            println!("{}", format::certutil_report(&digest, algo, path.unwrap()));
+        },
+        // Get-FileHash -InputStream leaves the Path column empty
+        Some(_) if format == format::Format::GetFileHashCsv => {
+            println!("{}\n{}", format::GET_FILEHASH_CSV_HEADER, format::get_filehash_csv_row(&digest, algo, path.map(|p| p.as_str()).unwrap_or("")));
+        },
+        Some(output) => {
+            match &file_entropy {
+                Some(measured) => println!("{}  {:.4}", output, measured.bits_per_byte()),
+                None => println!("{}", output)
+            }
+            if matches.get_flag("copy") {
+                if let Err(e) = clipboard::copy(&output) {
+                    log_error!("unable to copy the digest to the clipboard: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        },
+        None => {
+            log_error!("{} digests can't be rendered in this format", algo);
+            std::process::exit(1);
         }
     }
-}
\ No newline at end of file
+
+    if matches.get_flag("interactive") {
+        eprint!("Paste the expected digest: ");
+        let mut line = String::new();
+        io::stdin().read_line(&mut line).or_exit("unable to read stdin");
+        verify_pasted(&line, "the pasted text", &digest, algo, matches.get_flag("explain"));
+    }
+}
@@ -0,0 +1,145 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use crate::{encoding::hex, gitignore};
+
+/**
+ * Recursively lists the regular files beneath `root`, in sorted order so that manifests of the
+ * same tree are reproducible. Symbolic links are not followed.
+ */
+pub fn
+walk (root: &str) -> io::Result<Vec<String>> {
+    let mut files: Vec<String> = Vec::new();
+    walk_into(Path::new(root), &mut files)?;
+    Ok(files)
+}
+
+fn
+walk_into (dir: &Path, files: &mut Vec<String>) -> io::Result<()> {
+    let mut entries: Vec<fs::DirEntry> = fs::read_dir(dir)?.collect::<io::Result<Vec<fs::DirEntry>>>()?;
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+
+        if file_type.is_dir() {
+            walk_into(&path, files)?;
+        } else if file_type.is_file() {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Like walk(), but skips what git would ignore: anything matched by the .gitignore files of
+ * the repository containing `root`, and by those beneath it, or by .git/info/exclude, along
+ * with the .git directory itself. Ignored directories aren't entered.
+ */
+pub fn
+walk_respecting_gitignore (root: &str) -> io::Result<Vec<String>> {
+    let (mut rules, relative) = gitignore::rules_for(Path::new(root))?;
+    let mut files: Vec<String> = Vec::new();
+    walk_ignoring(Path::new(root), &relative, &mut rules, &mut files)?;
+    Ok(files)
+}
+
+fn
+walk_ignoring (dir: &Path, relative: &str, rules: &mut gitignore::Rules, files: &mut Vec<String>) -> io::Result<()> {
+    let before = rules.len();
+    rules.add_file(&dir.join(".gitignore"), relative)?;
+
+    let mut entries: Vec<fs::DirEntry> = fs::read_dir(dir)?.collect::<io::Result<Vec<fs::DirEntry>>>()?;
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let entry_relative = if relative.is_empty() { name.clone() } else { format!("{}/{}", relative.trim_end_matches('/'), name) };
+
+        if name == ".git" || rules.is_ignored(&entry_relative, file_type.is_dir()) {
+            continue;
+        }
+
+        if file_type.is_dir() {
+            walk_ignoring(&path, &entry_relative, rules, files)?;
+        } else if file_type.is_file() {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+
+    rules.truncate(before);
+    Ok(())
+}
+
+/**
+ * Formats a manifest line in the format read by `check` and sha256sum -c.
+ */
+pub fn
+manifest_line (digest: &[u8], path: &str) -> String {
+    format!("{}  {}", hex::encode(digest), path)
+}
+
+/**
+ * Replaces a path with the hex HMAC-SHA-256 of it under `key`, so that integrity data can be
+ * shared without revealing file names or directory structure. The key holder can still map
+ * entries back by recomputing the pseudonyms of their own paths. SHA-256 is used regardless of
+ * --algo so that the same key always gives the same pseudonyms.
+ */
+pub fn
+pseudonymize_path (key: &[u8], path: &str) -> String {
+    hex::encode(crate::hmac::hmac(key, path.as_bytes(), crate::Algo::Sha256))
+}
+
+/**
+ * Progress of a manifest scan: the manifest lines completed so far, keyed by path. Persisted as a
+ * partial manifest so that an interrupted scan can skip files it has already hashed.
+ */
+pub struct ScanProgress {
+    pub completed: HashMap<String, String>
+}
+
+impl Default for ScanProgress {
+    fn default () -> ScanProgress {
+        ScanProgress::new()
+    }
+}
+
+impl ScanProgress {
+    pub fn new () -> ScanProgress {
+        ScanProgress { completed: HashMap::new() }
+    }
+
+    pub fn load (path: &str) -> io::Result<ScanProgress> {
+        let mut completed = HashMap::new();
+
+        for line in fs::read_to_string(path)?.lines() {
+            if let Some((_, file)) = line.split_once("  ") {
+                completed.insert(file.to_string(), line.to_string());
+            }
+        }
+
+        Ok(ScanProgress { completed })
+    }
+
+    /**
+     * Writes the completed lines, in walk order, to `path` via a temporary file so an interrupted
+     * save never leaves a truncated checkpoint.
+     */
+    pub fn save (&self, path: &str, order: &[String]) -> io::Result<()> {
+        let mut contents = String::new();
+
+        for file in order {
+            if let Some(line) = self.completed.get(file) {
+                contents.push_str(line);
+                contents.push('\n');
+            }
+        }
+
+        let tmp = format!("{}.tmp", path);
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, path)
+    }
+}
@@ -0,0 +1,140 @@
+/*
+ * MD5, per https://www.rfc-editor.org/rfc/rfc1321
+ *
+ * MD5 is broken: collisions take seconds to find, so it says nothing about whether data was
+ * tampered with. It is provided only to match the digests recorded by older tools, such as
+ * forensic acquisition logs and MD5SUMS files, and must not be used for new integrity checks.
+ */
+
+use alloc::vec::Vec;
+
+// From RFC 1321 # 3.3
+const MD5_H_INIT: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+// From RFC 1321 # 3.4: the integer part of 2^32 * abs(sin(i + 1)), for i in 0..64
+const K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391
+];
+
+// Left rotation amounts, four per round
+const S: [[u32; 4]; 4] = [[7, 12, 17, 22], [5, 9, 14, 20], [4, 11, 16, 23], [6, 10, 15, 21]];
+
+/**
+ * Processes a single 512-bit message block, per RFC 1321 # 3.4. Unlike SHA-1 and SHA-2, MD5
+ * reads its words little-endian.
+ */
+fn
+compress (h: &mut [u32; 4], block: &[u8]) {
+    let mut m: [u32; 16] = [0; 16];
+    for (word, chunk) in m.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+
+    let (mut a, mut b, mut c, mut d) = (h[0], h[1], h[2], h[3]);
+
+    for (indx, k) in K.iter().enumerate() {
+        // The round functions F, G, H and I, and which message word each step takes
+        let (f, g) = match indx / 16 {
+            0 => ((b & c) | (!b & d), indx),
+            1 => ((d & b) | (!d & c), (5 * indx + 1) % 16),
+            2 => (b ^ c ^ d, (3 * indx + 5) % 16),
+            _ => (c ^ (b | !d), (7 * indx) % 16)
+        };
+
+        let t = a.wrapping_add(f).wrapping_add(*k).wrapping_add(m[g]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(t.rotate_left(S[indx / 16][indx % 4]));
+    }
+
+    h[0] = h[0].wrapping_add(a);
+    h[1] = h[1].wrapping_add(b);
+    h[2] = h[2].wrapping_add(c);
+    h[3] = h[3].wrapping_add(d);
+}
+
+/**
+ * Incremental MD5 hasher, buffering input until a full 512-bit block is available.
+ */
+#[derive(Clone)]
+pub struct Md5 {
+    h: [u32; 4],
+    buffer: Vec<u8>,
+    // Message bits consumed so far, modulo 2^64 as the padding's length field holds them
+    pub(crate) bit_len: u64
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Md5 {
+    fn drop (&mut self) {
+        crate::wipe::wipe(&mut self.h);
+        crate::wipe::wipe_vec(&mut self.buffer);
+    }
+}
+
+impl Default for Md5 {
+    fn default () -> Md5 {
+        Md5::new()
+    }
+}
+
+impl Md5 {
+    pub fn new () -> Md5 {
+        Md5 {
+            h: MD5_H_INIT,
+            buffer: Vec::with_capacity(128),
+            bit_len: 0
+        }
+    }
+
+    pub fn update (&mut self, data: &[u8]) {
+        self.bit_len = self.bit_len.wrapping_add((data.len() as u64).wrapping_mul(8));
+        self.buffer.extend_from_slice(data);
+
+        let full = self.buffer.len() - self.buffer.len() % 64;
+        for block in self.buffer[..full].chunks(64) {
+            compress(&mut self.h, block);
+        }
+        self.buffer.drain(..full);
+    }
+
+    /**
+     * The padding is SHA-256's, except that the length field is little-endian (RFC 1321 # 3.2).
+     */
+    pub fn finalize (mut self) -> [u8; 16] {
+        self.buffer.push(0x80);
+        while self.buffer.len() % 64 != 56 {
+            self.buffer.push(0x0);
+        }
+        self.buffer.extend_from_slice(&self.bit_len.to_le_bytes());
+
+        for block in self.buffer.chunks(64) {
+            compress(&mut self.h, block);
+        }
+
+        let mut digest = [0u8; 16];
+        for (indx, word) in self.h.iter().enumerate() {
+            digest[indx * 4..indx * 4 + 4].copy_from_slice(&word.to_le_bytes());
+        }
+
+        digest
+    }
+}
+
+/**
+ * Computes the MD5 digest of the given message.
+ */
+pub fn
+md5 (message: &[u8]) -> [u8; 16] {
+    let mut hasher = Md5::new();
+    hasher.update(message);
+    hasher.finalize()
+}
@@ -0,0 +1,73 @@
+/*
+ * Several digests of the same input in one pass, for checking a large download against every
+ * checksum its publisher lists without reading it once per algorithm.
+ */
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+use crate::{Algo, Digest, Hasher};
+
+/**
+ * Feeds the same input to one Hasher per algorithm. Each block of input is handed to every
+ * hasher while it is still in cache, so the cost over a single algorithm is the extra
+ * compression work, not extra reads.
+ */
+#[derive(Clone)]
+pub struct MultiHasher {
+    hashers: Vec<Hasher>
+}
+
+impl MultiHasher {
+    /**
+     * Repeated algorithms are only computed once.
+     */
+    pub fn new (algos: &[Algo]) -> MultiHasher {
+        let mut hashers: Vec<Hasher> = Vec::with_capacity(algos.len());
+        for algo in algos {
+            if !hashers.iter().any(|h| h.algorithm() == *algo) {
+                hashers.push(Hasher::new(*algo));
+            }
+        }
+        MultiHasher { hashers }
+    }
+
+    pub fn algorithms (&self) -> Vec<Algo> {
+        self.hashers.iter().map(|h| h.algorithm()).collect()
+    }
+
+    pub fn update (&mut self, data: &[u8]) {
+        for hasher in self.hashers.iter_mut() {
+            hasher.update(data);
+        }
+    }
+
+    /**
+     * Feeds everything from the given reader into every hasher, returning the number of bytes
+     * read.
+     */
+    #[cfg(feature = "std")]
+    pub fn update_reader<R: Read> (&mut self, mut reader: R) -> io::Result<u64> {
+        io::copy(&mut reader, self)
+    }
+
+    /**
+     * The digests, in the order the algorithms were first given.
+     */
+    pub fn finalize (self) -> Vec<Digest> {
+        self.hashers.into_iter().map(|h| h.finalize_digest()).collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Write for MultiHasher {
+    fn write (&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush (&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
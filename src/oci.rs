@@ -0,0 +1,89 @@
+/*
+ * OCI image layouts, as written by `skopeo copy oci:...`, `docker buildx --output type=oci` and
+ * similar: an `oci-layout` marker, an `index.json` listing the images, and every manifest,
+ * config and layer stored as blobs/<algorithm>/<hex digest>. See
+ * https://github.com/opencontainers/image-spec/blob/main/image-layout.md
+ */
+
+use std::{fs, io, path::{Path, PathBuf}};
+
+use crate::json;
+
+/**
+ * A reference from an index or manifest to a blob, per the OCI content descriptor.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Descriptor {
+    pub media_type: String,
+    pub digest: String,
+    pub size: u64
+}
+
+/**
+ * Whether blobs of this media type are themselves indexes or manifests, referring to more blobs.
+ */
+pub fn
+is_manifest (media_type: &str) -> bool {
+    matches!(media_type,
+        "application/vnd.oci.image.index.v1+json"
+        | "application/vnd.oci.image.manifest.v1+json"
+        | "application/vnd.docker.distribution.manifest.list.v2+json"
+        | "application/vnd.docker.distribution.manifest.v2+json")
+}
+
+/**
+ * The descriptors an index or manifest refers to: its manifests, or its config and layers.
+ */
+pub fn
+descriptors (document: &str) -> Result<Vec<Descriptor>, String> {
+    let doc = json::parse(document)?;
+    let mut refs: Vec<&json::Value> = Vec::new();
+
+    for list in ["manifests", "layers"] {
+        if let Some(items) = doc.get(list).and_then(|v| v.as_array()) {
+            refs.extend(items);
+        }
+    }
+    if let Some(config) = doc.get("config") {
+        refs.insert(0, config);
+    }
+
+    refs.into_iter().map(|d| {
+        let field = |name: &str| d.get(name).ok_or_else(|| format!("descriptor without \"{}\"", name));
+        Ok(Descriptor {
+            media_type: field("mediaType")?.as_str().ok_or("mediaType is not a string")?.to_string(),
+            digest: field("digest")?.as_str().ok_or("digest is not a string")?.to_string(),
+            size: field("size")?.as_u64().ok_or("size is not a non-negative integer")?
+        })
+    }).collect()
+}
+
+/**
+ * Where a sha256 blob lives in the layout. None for other algorithms, and for anything which
+ * isn't a well-formed digest, so a hostile index can't point outside the blobs directory.
+ */
+pub fn
+blob_path (root: &Path, digest: &str) -> Option<PathBuf> {
+    let hex = digest.strip_prefix("sha256:")?;
+    if hex.len() != 64 || !hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+    Some(root.join("blobs").join("sha256").join(hex))
+}
+
+/**
+ * Every file under blobs/sha256, as (digest, path) pairs with the digest taken from the file name.
+ */
+pub fn
+list_blobs (root: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let dir = root.join("blobs").join("sha256");
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut blobs: Vec<(String, PathBuf)> = fs::read_dir(&dir)?
+        .map(|entry| entry.map(|e| (format!("sha256:{}", e.file_name().to_string_lossy()), e.path())))
+        .collect::<io::Result<Vec<(String, PathBuf)>>>()?;
+    blobs.sort();
+    Ok(blobs)
+}
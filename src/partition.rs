@@ -0,0 +1,302 @@
+/*
+ * Reading the partition table of a disk image, so that each partition can be hashed on its own
+ * as well as the image as a whole: after imaging a disk, a filesystem can then be checked
+ * against a digest taken from the source partition, or from another image of the same disk with
+ * a different layout around it.
+ *
+ * Both MBR and GPT tables are read. An MBR holding a protective 0xee entry is taken to front a
+ * GPT, which is looked for in the sector after it, with 512- and then 4096-byte sectors. Logical
+ * partitions inside an MBR extended partition are followed and numbered from 5, as Linux numbers
+ * them. GPT header and entry array checksums aren't verified, and the backup GPT at the end of
+ * the disk isn't read.
+ */
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::{encoding::hex, Algo, Digest, Hasher};
+
+const MBR_SECTOR: u64 = 512;
+const MBR_ENTRIES: usize = 446;
+const MBR_PROTECTIVE: u8 = 0xee;
+// CHS, LBA and Windows extended partition types
+const MBR_EXTENDED: [u8; 3] = [0x05, 0x0f, 0x85];
+// An extended partition chain longer than this is assumed to loop
+const MAX_LOGICAL: usize = 128;
+
+const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+const GPT_SECTOR_SIZES: [u64; 2] = [512, 4096];
+// Far beyond the 128 entries every partitioning tool writes, but bounded so that a corrupt
+// header can't ask for gigabytes
+const GPT_MAX_ENTRIES: u64 = 16384;
+// Entries are 128 bytes in every GPT written so far; the bound keeps the array within 64MiB
+const GPT_MAX_ENTRY_SIZE: u64 = 4096;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Scheme {
+    Mbr,
+    Gpt
+}
+
+impl Scheme {
+    pub fn name (&self) -> &'static str {
+        match self {
+            Scheme::Mbr => "MBR",
+            Scheme::Gpt => "GPT"
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Partition {
+    // As the kernel numbers them: 1-4 for MBR primaries, 5 on for logicals, and the entry's
+    // position in the array, from 1, for GPT
+    pub number: usize,
+    // The MBR type byte in hex, e.g. "0x83", or the GPT partition type GUID
+    pub kind: String,
+    // The GPT partition name; MBR partitions have none
+    pub name: String,
+    // Byte offsets within the image
+    pub offset: u64,
+    pub length: u64
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Table {
+    pub scheme: Scheme,
+    pub sector_size: u64,
+    pub partitions: Vec<Partition>
+}
+
+fn
+invalid (msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/**
+ * Reads `buf.len()` bytes at `offset`, returning false if the image ends first.
+ */
+fn
+read_at<R: Read + Seek> (reader: &mut R, offset: u64, buf: &mut [u8]) -> io::Result<bool> {
+    reader.seek(SeekFrom::Start(offset))?;
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e)
+    }
+}
+
+fn
+le32 (bytes: &[u8]) -> u64 {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap()) as u64
+}
+
+fn
+le64 (bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+/**
+ * Formats a GUID as it is conventionally written: the first three fields are stored
+ * little-endian and the last two big-endian.
+ */
+pub fn
+format_guid (guid: &[u8; 16]) -> String {
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{}",
+        u32::from_le_bytes(guid[..4].try_into().unwrap()),
+        u16::from_le_bytes([guid[4], guid[5]]),
+        u16::from_le_bytes([guid[6], guid[7]]),
+        u16::from_be_bytes([guid[8], guid[9]]),
+        hex::encode(&guid[10..])
+    )
+}
+
+/**
+ * One 16-byte MBR partition entry: its type, and its first sector and sector count.
+ */
+struct MbrEntry {
+    status: u8,
+    kind: u8,
+    start: u64,
+    sectors: u64
+}
+
+fn
+mbr_entries (sector: &[u8]) -> [MbrEntry; 4] {
+    core::array::from_fn(|i| {
+        let entry = &sector[MBR_ENTRIES + 16 * i..MBR_ENTRIES + 16 * (i + 1)];
+        MbrEntry { status: entry[0], kind: entry[4], start: le32(&entry[8..]), sectors: le32(&entry[12..]) }
+    })
+}
+
+/**
+ * Reads the partition table at the start of an image. Returns None if there isn't one, which
+ * includes a filesystem written straight to the device: a FAT boot sector carries the same
+ * 0x55aa signature as an MBR, so entries with an invalid boot flag or no extent mean no table.
+ */
+pub fn
+read_table<R: Read + Seek> (reader: &mut R) -> io::Result<Option<Table>> {
+    let mut mbr = [0u8; 512];
+    if !read_at(reader, 0, &mut mbr)? || mbr[510..] != [0x55, 0xaa] {
+        return Ok(None);
+    }
+
+    let entries = mbr_entries(&mbr);
+    let used: Vec<(usize, &MbrEntry)> = entries.iter().enumerate().filter(|(_, e)| e.kind != 0).collect();
+    if used.is_empty() || used.iter().any(|(_, e)| !matches!(e.status, 0x00 | 0x80) || e.start == 0 || e.sectors == 0) {
+        return Ok(None);
+    }
+
+    if used.iter().any(|(_, e)| e.kind == MBR_PROTECTIVE) {
+        for sector_size in GPT_SECTOR_SIZES {
+            if let Some(table) = read_gpt(reader, sector_size)? {
+                return Ok(Some(table));
+            }
+        }
+        return Err(invalid("protective MBR without a GPT header"));
+    }
+
+    let mut partitions = Vec::new();
+    for (i, entry) in &used {
+        partitions.push(Partition {
+            number: i + 1,
+            kind: format!("{:#04x}", entry.kind),
+            name: String::new(),
+            offset: entry.start * MBR_SECTOR,
+            length: entry.sectors * MBR_SECTOR
+        });
+    }
+    if let Some((_, extended)) = used.iter().find(|(_, e)| MBR_EXTENDED.contains(&e.kind)) {
+        read_logical(reader, extended.start, &mut partitions)?;
+    }
+
+    Ok(Some(Table { scheme: Scheme::Mbr, sector_size: MBR_SECTOR, partitions }))
+}
+
+/**
+ * Follows the chain of extended boot records in an extended partition starting at sector
+ * `base`. Each holds a logical partition, relative to itself, and a link to the next record,
+ * relative to `base`.
+ */
+fn
+read_logical<R: Read + Seek> (reader: &mut R, base: u64, partitions: &mut Vec<Partition>) -> io::Result<()> {
+    let mut ebr = base;
+    for number in 5..5 + MAX_LOGICAL {
+        let mut sector = [0u8; 512];
+        if !read_at(reader, ebr * MBR_SECTOR, &mut sector)? || sector[510..] != [0x55, 0xaa] {
+            return Err(invalid("invalid extended boot record"));
+        }
+
+        let [logical, next, ..] = mbr_entries(&sector);
+        if logical.kind != 0 && logical.sectors != 0 {
+            partitions.push(Partition {
+                number,
+                kind: format!("{:#04x}", logical.kind),
+                name: String::new(),
+                offset: (ebr + logical.start) * MBR_SECTOR,
+                length: logical.sectors * MBR_SECTOR
+            });
+        }
+
+        if next.kind == 0 || next.start == 0 {
+            return Ok(());
+        }
+        ebr = base + next.start;
+    }
+
+    Err(invalid("too many logical partitions; the extended partition chain may loop"))
+}
+
+fn
+read_gpt<R: Read + Seek> (reader: &mut R, sector_size: u64) -> io::Result<Option<Table>> {
+    let mut header = [0u8; 92];
+    if !read_at(reader, sector_size, &mut header)? || &header[..8] != GPT_SIGNATURE {
+        return Ok(None);
+    }
+
+    let entries_lba = le64(&header[72..]);
+    let count = le32(&header[80..]);
+    let entry_size = le32(&header[84..]);
+    if count > GPT_MAX_ENTRIES || !(128..=GPT_MAX_ENTRY_SIZE).contains(&entry_size) || !entry_size.is_multiple_of(8) {
+        return Err(invalid("invalid GPT header"));
+    }
+
+    // Checked against the image before anything is allocated for it
+    let image_len = reader.seek(SeekFrom::End(0))?;
+    let array_offset = entries_lba.checked_mul(sector_size).ok_or_else(|| invalid("invalid GPT header"))?;
+    if array_offset.checked_add(count * entry_size).is_none_or(|end| end > image_len) {
+        return Err(invalid("the GPT partition entries run past the end of the image"));
+    }
+    let mut array = vec![0u8; (count * entry_size) as usize];
+    if !read_at(reader, array_offset, &mut array)? {
+        return Err(invalid("the GPT partition entries run past the end of the image"));
+    }
+
+    let mut partitions = Vec::new();
+    for (i, entry) in array.chunks_exact(entry_size as usize).enumerate() {
+        let kind: [u8; 16] = entry[..16].try_into().unwrap();
+        if kind == [0u8; 16] {
+            continue;
+        }
+
+        let first = le64(&entry[32..]);
+        let last = le64(&entry[40..]);
+        if last < first {
+            return Err(invalid("GPT partition ends before it starts"));
+        }
+        let name: Vec<u16> = entry[56..128].chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).take_while(|c| *c != 0).collect();
+
+        partitions.push(Partition {
+            number: i + 1,
+            kind: format_guid(&kind),
+            name: String::from_utf16_lossy(&name),
+            offset: first.checked_mul(sector_size).ok_or_else(|| invalid("invalid GPT partition"))?,
+            length: (last - first + 1).checked_mul(sector_size).ok_or_else(|| invalid("invalid GPT partition"))?
+        });
+    }
+
+    Ok(Some(Table { scheme: Scheme::Gpt, sector_size, partitions }))
+}
+
+/**
+ * Hashes the whole image and each of the given partitions in a single read of the image.
+ * Returns one digest per partition, None for a partition that runs past the end of the image,
+ * and the digest of the whole image.
+ */
+pub fn
+digests<R: Read> (mut reader: R, algo: Algo, partitions: &[Partition]) -> io::Result<(Vec<Option<Digest>>, Digest)> {
+    let mut whole = Hasher::new(algo);
+    let mut hashers: Vec<Hasher> = partitions.iter().map(|_| Hasher::new(algo)).collect();
+    let mut position: u64 = 0;
+    let mut buffer = vec![0u8; 1 << 16];
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        };
+
+        whole.update(&buffer[..read]);
+
+        let end = position + read as u64;
+        for (partition, hasher) in partitions.iter().zip(hashers.iter_mut()) {
+            let start = partition.offset.max(position);
+            let stop = partition.offset.saturating_add(partition.length).min(end);
+            if start < stop {
+                hasher.update(&buffer[(start - position) as usize..(stop - position) as usize]);
+            }
+        }
+        position = end;
+    }
+
+    let digests = partitions.iter().zip(hashers).map(|(partition, hasher)| {
+        match partition.offset.checked_add(partition.length) {
+            Some(end) if end <= position => Some(hasher.finalize_digest()),
+            _ => None
+        }
+    }).collect();
+
+    Ok((digests, whole.finalize_digest()))
+}
@@ -0,0 +1,24 @@
+/*
+ * OpenPGP cleartext signatures, as wrapped around APT InRelease files and the CHECKSUM files
+ * published with Linux distribution images.
+ */
+
+/**
+ * Returns the signed text of a clearsigned message, per RFC 4880 # 7, or the input unchanged if
+ * it isn't clearsigned. The signature itself isn't checked; that's a job for gpgv.
+ */
+pub fn
+strip_clearsign (text: &str) -> String {
+    let Some((_, signed)) = text.split_once("-----BEGIN PGP SIGNED MESSAGE-----") else {
+        return text.to_string();
+    };
+
+    // Armor headers such as "Hash: SHA512" end at the first blank line
+    let body = signed.split_once("\n\n").or_else(|| signed.split_once("\r\n\r\n")).map(|(_, b)| b).unwrap_or("");
+    let body = body.split("-----BEGIN PGP SIGNATURE-----").next().unwrap_or("");
+
+    body.lines()
+        .map(|line| line.strip_prefix("- ").unwrap_or(line))
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
@@ -0,0 +1,36 @@
+/*
+ * PKCS#1 v1.5 signature encoding, per RFC 8017 # 9.2. Only the encoding is implemented; the RSA
+ * operation itself is left to openssl or another library.
+ */
+
+use crate::{der, Algo};
+
+/**
+ * The DigestInfo which a PKCS#1 v1.5 signature covers. See der::digest_info.
+ */
+pub fn
+digest_info (digest: &[u8], algo: Algo) -> Option<Vec<u8>> {
+    der::digest_info(digest, algo)
+}
+
+/**
+ * EMSA-PKCS1-v1_5 encoding: EM = 0x00 || 0x01 || PS || 0x00 || DigestInfo, where PS is at least
+ * eight 0xff bytes filling EM out to `em_len`, the RSA modulus length in bytes. Returns None if
+ * the algorithm has no OID or `em_len` is too short ("intended encoded message length too
+ * short" in the RFC).
+ */
+pub fn
+emsa_encode (digest: &[u8], algo: Algo, em_len: usize) -> Option<Vec<u8>> {
+    let t = digest_info(digest, algo)?;
+    if em_len < t.len() + 11 {
+        return None;
+    }
+
+    let mut em = Vec::with_capacity(em_len);
+    em.extend_from_slice(&[0x00, 0x01]);
+    em.resize(em_len - t.len() - 1, 0xff);
+    em.push(0x00);
+    em.extend_from_slice(&t);
+
+    Some(em)
+}
@@ -0,0 +1,11 @@
+use std::{fs, io, io::Read};
+
+/**
+ * Reads `len` bytes from the operating system's random number generator.
+ */
+pub fn
+random_bytes (len: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0u8; len];
+    fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
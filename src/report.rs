@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+/**
+ * Outcome of verifying a single file.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Status {
+    Ok,
+    Failed,
+    // The file couldn't be opened or read, or a known file wasn't found during an audit
+    Missing,
+    // An audited file whose content is known under a different path
+    Moved,
+    // An audited file whose content isn't known at all
+    New
+}
+
+impl Status {
+    pub fn name (&self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Failed => "failed",
+            Status::Missing => "missing",
+            Status::Moved => "moved",
+            Status::New => "new"
+        }
+    }
+}
+
+/**
+ * A per-file verification result, rendered either as a coreutils-style status line or as a
+ * JSON object for the JSON-Lines report.
+ */
+pub struct Record {
+    pub path: String,
+    pub status: Status,
+    pub expected: String,
+    pub actual: Option<String>,
+    pub bytes: u64,
+    pub duration: Duration
+}
+
+impl Record {
+    pub fn to_text (&self) -> String {
+        match self.status {
+            Status::Ok => format!("{}: OK", self.path),
+            Status::Failed => format!("{}: FAILED", self.path),
+            Status::Missing => format!("{}: FAILED open or read", self.path),
+            Status::Moved => format!("{}: Moved", self.path),
+            Status::New => format!("{}: No match", self.path)
+        }
+    }
+
+    pub fn to_json (&self) -> String {
+        format!(
+            "{{\"status\":\"{}\",\"expected\":{},\"actual\":{},\"path\":{},\"bytes\":{},\"duration_ms\":{:.3}}}",
+            self.status.name(),
+            json_string(&self.expected),
+            self.actual.as_deref().map(json_string).unwrap_or_else(|| "null".to_string()),
+            json_string(&self.path),
+            self.bytes,
+            self.duration.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/**
+ * Quotes and escapes a string for inclusion in JSON output, per RFC 8259 # 7.
+ */
+pub fn
+json_string (s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c)
+        }
+    }
+
+    out.push('"');
+    out
+}
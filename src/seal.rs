@@ -0,0 +1,153 @@
+/*
+ * Single-file authentication tags, written by `sha2 seal` either after the file's contents or
+ * to a sidecar file:
+ *
+ *   "S2SL" || version (1 byte) || algorithm id (1 byte) || content length (8 bytes, big-endian) || HMAC
+ *
+ * The HMAC is taken over the 14-byte header followed by the content, so the algorithm and
+ * length are authenticated along with the data: a tag can't be downgraded to a weaker
+ * algorithm, and truncating or extending the content is detected even before the MAC is checked.
+ */
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::{hmac::Hmac, Algo};
+
+pub const MAGIC: [u8; 4] = *b"S2SL";
+pub const VERSION: u8 = 1;
+pub const HEADER_LEN: usize = 14;
+
+/**
+ * Algorithms with an id, largest first.
+ */
+const ALGORITHMS: [(u8, Algo); 3] = [(3, Algo::Sha512), (2, Algo::Sha256), (1, Algo::Sha224)];
+
+/**
+ * The id a tag records the algorithm by, or None for one seal doesn't support: only SHA-224,
+ * SHA-256 and SHA-512 have ids.
+ */
+pub fn
+algorithm_id (algo: Algo) -> Option<u8> {
+    ALGORITHMS.iter().find(|(_, a)| *a == algo).map(|(id, _)| *id)
+}
+
+fn
+algorithm (id: u8) -> Option<Algo> {
+    ALGORITHMS.iter().find(|(i, _)| *i == id).map(|(_, a)| *a)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Tag {
+    pub algo: Algo,
+    pub len: u64,
+    pub mac: Vec<u8>
+}
+
+impl Tag {
+    fn header (id: u8, len: u64) -> [u8; HEADER_LEN] {
+        let mut header = [0u8; HEADER_LEN];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4] = VERSION;
+        header[5] = id;
+        header[6..].copy_from_slice(&len.to_be_bytes());
+        header
+    }
+
+    /**
+     * Authenticates exactly `len` bytes from the reader, failing with UnexpectedEof if it
+     * holds fewer and with InvalidInput if `algo` has no algorithm_id().
+     */
+    pub fn compute<R: Read> (key: &[u8], algo: Algo, reader: R, len: u64) -> io::Result<Tag> {
+        Ok(Tag { algo, len, mac: authenticate(key, algo, reader, len)?.finalize() })
+    }
+
+    pub fn encoded_len (&self) -> usize {
+        HEADER_LEN + self.mac.len()
+    }
+
+    /**
+     * Panics if `algo` has no algorithm_id(), which a tag from compute() or parse() always has.
+     */
+    pub fn to_bytes (&self) -> Vec<u8> {
+        let id = algorithm_id(self.algo).expect("seal doesn't support this algorithm");
+        let mut bytes = Tag::header(id, self.len).to_vec();
+        bytes.extend_from_slice(&self.mac);
+        bytes
+    }
+
+    /**
+     * Parses a complete tag, returning None if the bytes are anything else.
+     */
+    pub fn parse (bytes: &[u8]) -> Option<Tag> {
+        if bytes.len() < HEADER_LEN || bytes[..4] != MAGIC || bytes[4] != VERSION {
+            return None;
+        }
+
+        let algo = algorithm(bytes[5])?;
+        if bytes.len() != HEADER_LEN + algo.digest_len() {
+            return None;
+        }
+
+        Some(Tag {
+            algo,
+            len: u64::from_be_bytes(bytes[6..HEADER_LEN].try_into().unwrap()),
+            mac: bytes[HEADER_LEN..].to_vec()
+        })
+    }
+
+    /**
+     * Checks the sealed length's worth of bytes from the reader against the tag. Comparison of the MAC is constant-time.
+     */
+    pub fn verify<R: Read> (&self, key: &[u8], reader: R) -> io::Result<bool> {
+        match authenticate(key, self.algo, reader, self.len) {
+            Ok(mac) => Ok(mac.verify(&self.mac)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(e)
+        }
+    }
+}
+
+fn
+authenticate<R: Read> (key: &[u8], algo: Algo, reader: R, len: u64) -> io::Result<Hmac> {
+    let id = algorithm_id(algo)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seal supports SHA-224, SHA-256 and SHA-512"))?;
+    let mut mac = Hmac::new(algo, key);
+    mac.update(&Tag::header(id, len));
+
+    if mac.update_reader(reader.take(len))? != len {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "content is shorter than the sealed length"));
+    }
+
+    Ok(mac)
+}
+
+/**
+ * Looks for a tag at the end of the stream. A trailer only counts if its length field accounts
+ * for everything before it, so a file which merely ends in the magic bytes isn't mistaken for a
+ * sealed one. Leaves the stream positioned at the start.
+ */
+pub fn
+read_trailer<R: Read + Seek> (reader: &mut R) -> io::Result<Option<Tag>> {
+    let total = reader.seek(SeekFrom::End(0))?;
+    let longest = (HEADER_LEN + ALGORITHMS[0].1.digest_len()) as u64;
+    let tail_len = longest.min(total);
+
+    let mut tail = vec![0u8; tail_len as usize];
+    reader.seek(SeekFrom::Start(total - tail_len))?;
+    reader.read_exact(&mut tail)?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    for (_, algo) in ALGORITHMS {
+        let tag_len = HEADER_LEN + algo.digest_len();
+        if tag_len > tail.len() {
+            continue;
+        }
+        if let Some(tag) = Tag::parse(&tail[tail.len() - tag_len..]) {
+            if tag.len + tag_len as u64 == total {
+                return Ok(Some(tag));
+            }
+        }
+    }
+
+    Ok(None)
+}
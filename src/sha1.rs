@@ -0,0 +1,135 @@
+/*
+ * SHA-1, per https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 6.1
+ *
+ * SHA-1 is deprecated and practical collisions are known; it is provided only to interoperate
+ * with legacy formats which still use it and must not be used for new integrity checks.
+ */
+
+use alloc::vec::Vec;
+
+use crate::pad_with_length;
+
+// From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 5.3.1
+const SHA_1_H_INIT: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+// From https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf # 4.2.1
+const K: [u32; 4] = [0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xca62c1d6];
+
+/**
+ * Processes a single 512-bit message block, per FIPS 180-4 # 6.1.2
+ */
+fn
+compress (h: &mut [u32; 5], block: &[u8]) {
+    let mut w: [u32; 80] = [0; 80];
+
+    for (indx, chunk) in block.chunks(4).enumerate() {
+        w[indx] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+
+    for indx in 16..80 {
+        w[indx] = (w[indx - 3] ^ w[indx - 8] ^ w[indx - 14] ^ w[indx - 16]).rotate_left(1);
+    }
+
+    let (mut a, mut b, mut c, mut d, mut e) = (h[0], h[1], h[2], h[3], h[4]);
+
+    for (indx, word) in w.iter().enumerate() {
+        // From FIPS 180-4 # 4.1.1: Ch, Parity, Maj, Parity
+        let f = match indx / 20 {
+            0 => (b & c) ^ ((!b) & d),
+            2 => (b & c) ^ (b & d) ^ (c & d),
+            _ => b ^ c ^ d
+        };
+
+        let t = a.rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(K[indx / 20])
+            .wrapping_add(*word);
+
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = t;
+    }
+
+    h[0] = h[0].wrapping_add(a);
+    h[1] = h[1].wrapping_add(b);
+    h[2] = h[2].wrapping_add(c);
+    h[3] = h[3].wrapping_add(d);
+    h[4] = h[4].wrapping_add(e);
+}
+
+/**
+ * Incremental SHA-1 hasher, buffering input until a full 512-bit block is available.
+ */
+#[derive(Clone)]
+pub struct Sha1 {
+    h: [u32; 5],
+    buffer: Vec<u8>,
+    // Message bits consumed so far, modulo 2^64 as the padding's length field holds them
+    pub(crate) bit_len: u64
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Sha1 {
+    fn drop (&mut self) {
+        crate::wipe::wipe(&mut self.h);
+        crate::wipe::wipe_vec(&mut self.buffer);
+    }
+}
+
+impl Default for Sha1 {
+    fn default () -> Sha1 {
+        Sha1::new()
+    }
+}
+
+impl Sha1 {
+    pub fn new () -> Sha1 {
+        Sha1 {
+            h: SHA_1_H_INIT,
+            buffer: Vec::with_capacity(128),
+            bit_len: 0
+        }
+    }
+
+    pub fn update (&mut self, data: &[u8]) {
+        self.bit_len = self.bit_len.wrapping_add((data.len() as u64).wrapping_mul(8));
+        self.buffer.extend_from_slice(data);
+
+        let full = self.buffer.len() - self.buffer.len() % 64;
+        for block in self.buffer[..full].chunks(64) {
+            compress(&mut self.h, block);
+        }
+        self.buffer.drain(..full);
+    }
+
+    /**
+     * SHA-1 shares SHA-256's padding scheme.
+     */
+    pub fn finalize (mut self) -> [u8; 20] {
+        pad_with_length(&mut self.buffer, self.bit_len);
+
+        for block in self.buffer.chunks(64) {
+            compress(&mut self.h, block);
+        }
+
+        let mut digest = [0u8; 20];
+        for (indx, word) in self.h.iter().enumerate() {
+            digest[indx * 4..indx * 4 + 4].copy_from_slice(&word.to_be_bytes());
+        }
+
+        digest
+    }
+}
+
+/**
+ * Computes the SHA-1 digest of the given message.
+ */
+pub fn
+sha1 (message: &[u8]) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+    hasher.update(message);
+    hasher.finalize()
+}
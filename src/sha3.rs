@@ -0,0 +1,69 @@
+/*
+ * SHA3-224, SHA3-256, SHA3-384 and SHA3-512, per
+ * https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.202.pdf # 6.1: the Keccak sponge with a
+ * capacity of twice the digest length, so a rate of 1600 bits less that, and the domain
+ * separation bits 01 ahead of the padding.
+ *
+ * The digest lengths are SHA-2's, but the construction shares nothing with it, so a weakness
+ * found in one says nothing about the other.
+ */
+
+use alloc::vec::Vec;
+
+use crate::keccak::Keccak;
+
+// The SHA-3 domain separation bits 01 followed by the first padding bit, FIPS 202 # 6.1
+pub const SHA3_PAD: u8 = 0x06;
+
+/**
+ * The rate in bytes for a digest of `bits` bits: 200 bytes of state less the capacity.
+ */
+pub fn
+rate (bits: usize) -> usize {
+    200 - bits / 4
+}
+
+/**
+ * Incremental SHA-3 hasher of one of the four standard lengths.
+ */
+#[derive(Clone)]
+pub struct Sha3 {
+    sponge: Keccak,
+    bits: usize,
+    // Message bits consumed so far; SHA-3 doesn't need it, but Hasher reports it
+    pub(crate) bit_len: u64
+}
+
+impl Sha3 {
+    /**
+     * Panics unless `bits` is 224, 256, 384 or 512.
+     */
+    pub fn new (bits: usize) -> Sha3 {
+        assert!(matches!(bits, 224 | 256 | 384 | 512), "SHA-3 has no {}-bit variant", bits);
+        Sha3 { sponge: Keccak::new(rate(bits), SHA3_PAD), bits, bit_len: 0 }
+    }
+
+    pub fn bits (&self) -> usize {
+        self.bits
+    }
+
+    pub fn update (&mut self, data: &[u8]) {
+        self.bit_len = self.bit_len.wrapping_add((data.len() as u64).wrapping_mul(8));
+        self.sponge.update(data);
+    }
+
+    pub fn finalize (self) -> Vec<u8> {
+        let bits = self.bits;
+        self.sponge.finalize(bits / 8)
+    }
+}
+
+/**
+ * Computes the `bits`-bit SHA-3 digest of the given message.
+ */
+pub fn
+sha3 (message: &[u8], bits: usize) -> Vec<u8> {
+    let mut hasher = Sha3::new(bits);
+    hasher.update(message);
+    hasher.finalize()
+}
@@ -0,0 +1,20 @@
+/**
+ * Maps a key's digest to one of `buckets` buckets using jump consistent hashing (Lamping and
+ * Veach, "A Fast, Minimal Memory, Consistent Hash Algorithm"), seeded with the digest's first 8
+ * bytes. Growing from N to N + 1 buckets moves only about 1/(N + 1) of the keys, all of them into
+ * the new bucket, whereas a plain modulus would reshuffle nearly everything.
+ */
+pub fn
+jump_bucket (digest: &[u8], buckets: u32) -> u32 {
+    let mut key = u64::from_be_bytes(digest[0..8].try_into().unwrap());
+    let mut b: i64 = -1;
+    let mut j: i64 = 0;
+
+    while j < buckets as i64 {
+        b = j;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        j = ((b + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+
+    b as u32
+}
@@ -0,0 +1,85 @@
+/*
+ * Subresource Integrity strings as used by npm's package-lock.json ("integrity") and the ssri
+ * package: one or more whitespace-separated "<algorithm>-<base64 digest>[?options]" entries, e.g.
+ * "sha512-9/u6bgY... sha1-8a4d8f...". See https://www.w3.org/TR/SRI/#the-integrity-attribute
+ */
+
+use std::io::{self, Read};
+
+use crate::{Algo, Hasher};
+
+/**
+ * Algorithms in order of preference; verification uses the strongest one which is both present
+ * in the integrity string and supported here.
+ */
+const PREFERENCE: [&str; 4] = ["sha512", "sha384", "sha256", "sha1"];
+
+pub fn
+supported (algorithm: &str) -> bool {
+    matches!(algorithm, "sha512" | "sha256" | "sha1")
+}
+
+pub struct IntegrityEntry {
+    pub algorithm: String,
+    pub digest: Vec<u8>
+}
+
+/**
+ * Parses an integrity string, skipping malformed entries as the SRI specification requires.
+ */
+pub fn
+parse (integrity: &str) -> Vec<IntegrityEntry> {
+    integrity.split_whitespace().filter_map(|token| {
+        let token = token.split('?').next().unwrap_or("");
+        let (algorithm, encoded) = token.split_once('-')?;
+        let digest = base64::decode(encoded).ok()?;
+        Some(IntegrityEntry { algorithm: algorithm.to_ascii_lowercase(), digest })
+    }).collect()
+}
+
+/**
+ * Picks the strongest supported algorithm among the entries.
+ */
+pub fn
+pick_algorithm (entries: &[IntegrityEntry]) -> Option<&'static str> {
+    PREFERENCE.iter().copied().find(|alg| supported(alg) && entries.iter().any(|e| e.algorithm == *alg))
+}
+
+/**
+ * Hashes the reader with the given supported algorithm.
+ */
+pub fn
+hash_reader<R: Read> (algorithm: &str, reader: R) -> io::Result<Vec<u8>> {
+    let mut hasher = Hasher::new(algorithm.parse::<Algo>().unwrap());
+    hasher.update_reader(reader)?;
+    Ok(hasher.finalize())
+}
+
+/**
+ * The result of checking data against an integrity string.
+ */
+#[derive(PartialEq, Debug)]
+pub enum Verdict {
+    Match(&'static str),
+    Mismatch(&'static str),
+    // No entry uses an algorithm supported here
+    Unsupported
+}
+
+/**
+ * Verifies the reader's contents against the integrity string. Any entry of the selected
+ * algorithm may match, since an integrity string can list several digests per algorithm.
+ */
+pub fn
+verify<R: Read> (integrity: &str, reader: R) -> io::Result<Verdict> {
+    let entries = parse(integrity);
+    let algorithm = match pick_algorithm(&entries) {
+        Some(a) => a,
+        None => return Ok(Verdict::Unsupported)
+    };
+
+    let actual = hash_reader(algorithm, reader)?;
+    let matched = entries.iter().any(|e| e.algorithm == algorithm && e.digest == actual);
+
+    Ok(if matched { Verdict::Match(algorithm) } else { Verdict::Mismatch(algorithm) })
+}
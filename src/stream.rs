@@ -0,0 +1,111 @@
+/*
+ * Adapters which hash data as it passes through an ordinary reader or writer, so a copy, upload
+ * or download can be checksummed in the same pass rather than a second one.
+ */
+
+use std::io::{self, Read, Write};
+
+use crate::{Algo, Digest, Hasher};
+
+/**
+ * Forwards everything written to the inner writer, hashing exactly the bytes the inner writer
+ * accepted, so a short write doesn't leave the digest ahead of the data.
+ */
+pub struct HashWriter<W: Write> {
+    inner: W,
+    hasher: Hasher
+}
+
+impl<W: Write> HashWriter<W> {
+    pub fn new (inner: W, algo: Algo) -> HashWriter<W> {
+        HashWriter { inner, hasher: Hasher::new(algo) }
+    }
+
+    pub fn get_ref (&self) -> &W {
+        &self.inner
+    }
+
+    /**
+     * Writing to the inner writer directly bypasses the hash.
+     */
+    pub fn get_mut (&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    pub fn bytes_written (&self) -> u64 {
+        self.hasher.bytes_processed()
+    }
+
+    /**
+     * Flushes the inner writer and returns it along with the digest of everything written.
+     */
+    pub fn finish (mut self) -> io::Result<(W, Digest)> {
+        self.inner.flush()?;
+        Ok((self.inner, self.hasher.finalize_digest()))
+    }
+}
+
+impl<W: Write> Write for HashWriter<W> {
+    fn write (&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush (&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/**
+ * Passes reads through from the inner reader, hashing every byte handed out, so a download can
+ * be verified as it is consumed instead of being buffered and hashed afterwards.
+ */
+pub struct HashingReader<R: Read> {
+    inner: R,
+    hasher: Hasher
+}
+
+impl<R: Read> HashingReader<R> {
+    pub fn new (inner: R, algo: Algo) -> HashingReader<R> {
+        HashingReader { inner, hasher: Hasher::new(algo) }
+    }
+
+    pub fn get_ref (&self) -> &R {
+        &self.inner
+    }
+
+    /**
+     * Reading from the inner reader directly bypasses the hash.
+     */
+    pub fn get_mut (&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn bytes_read (&self) -> u64 {
+        self.hasher.bytes_processed()
+    }
+
+    /**
+     * The digest of everything read so far; reading can carry on afterwards.
+     */
+    pub fn digest (&self) -> Digest {
+        self.hasher.clone().finalize_digest()
+    }
+
+    /**
+     * Returns the inner reader along with the digest of everything read from it.
+     */
+    pub fn finish (self) -> (R, Digest) {
+        let digest = self.digest();
+        (self.inner, digest)
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read (&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
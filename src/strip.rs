@@ -0,0 +1,190 @@
+/*
+ * Removing comments and insignificant whitespace from source files before hashing, so the digest
+ * changes when the code does but not when only a comment, the indentation or the blank lines
+ * do. This is a lexer, not a parser: it knows each language's comment and string syntax, so a
+ * "#" or "//" inside a string literal is kept, and nothing more.
+ *
+ * After comments are removed, trailing whitespace and blank lines are dropped from every
+ * language, and leading whitespace from C and shell, where indentation means nothing. Python's
+ * indentation is kept. Whitespace inside string literals, including strings spanning several
+ * lines, is always kept.
+ */
+
+use alloc::vec::Vec;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Language {
+    // C, C++, Java, JavaScript, Rust and the like: // and /* */ comments. A quote followed by a
+    // name and no closing quote is a Rust lifetime or label, such as 'a or 'static, not a char.
+    C,
+    // # comments and triple-quoted strings
+    Python,
+    // POSIX shell: # comments at the start of a word. Here-documents aren't recognized, so a
+    // "#" word inside one is taken for a comment.
+    Shell
+}
+
+impl Language {
+    pub fn from_name (name: &str) -> Option<Language> {
+        match name {
+            "c" => Some(Language::C),
+            "py" => Some(Language::Python),
+            "sh" => Some(Language::Shell),
+            _ => None
+        }
+    }
+}
+
+/**
+ * Returns the source with comments and insignificant whitespace removed, each remaining line
+ * ending in "\n". A C block comment is replaced by a space, so that it still separates the
+ * tokens either side of it. A shell script's "#!" line is kept.
+ */
+pub fn
+strip_comments (source: &[u8], language: Language) -> Vec<u8> {
+    let mut out = Vec::with_capacity(source.len());
+    // The current line, which continues across newlines inside strings and block comments
+    let mut line: Vec<u8> = Vec::new();
+    let mut i = 0;
+
+    if language == Language::Shell && source.starts_with(b"#!") {
+        i = line_end(source, 0, language);
+        line.extend_from_slice(&source[..i]);
+    }
+
+    while i < source.len() {
+        let next = source.get(i + 1).copied();
+        match source[i] {
+            b'\n' => {
+                finish_line(&mut out, &line, language);
+                line.clear();
+                i += 1;
+            },
+            b'\'' if language == Language::C && lifetime_len(&source[i + 1..]) > 0 => {
+                let end = i + 1 + lifetime_len(&source[i + 1..]);
+                line.extend_from_slice(&source[i..end]);
+                i = end;
+            },
+            b'"' | b'\'' => {
+                let end = string_end(source, i, language);
+                line.extend_from_slice(&source[i..end]);
+                i = end;
+            },
+            b'#' if language == Language::Python => i = line_end(source, i, language),
+            b'#' if language == Language::Shell && starts_word(&line) => i = line_end(source, i, language),
+            b'/' if language == Language::C && next == Some(b'/') => i = line_end(source, i, language),
+            b'/' if language == Language::C && next == Some(b'*') => {
+                i = source[i + 2..].windows(2).position(|w| w == b"*/").map(|p| i + 2 + p + 2).unwrap_or(source.len());
+                line.push(b' ');
+            },
+            b'\\' if language == Language::Shell => {
+                let end = (i + 2).min(source.len());
+                line.extend_from_slice(&source[i..end]);
+                i = end;
+            },
+            c => {
+                line.push(c);
+                i += 1;
+            }
+        }
+    }
+    finish_line(&mut out, &line, language);
+
+    out
+}
+
+fn
+is_space (c: u8) -> bool {
+    matches!(c, b' ' | b'\t' | b'\r' | b'\x0b' | b'\x0c')
+}
+
+fn
+finish_line (out: &mut Vec<u8>, line: &[u8], language: Language) {
+    let end = line.iter().rposition(|c| !is_space(*c)).map(|p| p + 1).unwrap_or(0);
+    let start = match language {
+        Language::Python => 0,
+        _ => line[..end].iter().position(|c| !is_space(*c)).unwrap_or(end)
+    };
+    if start < end {
+        out.extend_from_slice(&line[start..end]);
+        out.push(b'\n');
+    }
+}
+
+/**
+ * Whether a "#" following the given line so far starts a shell comment: it must begin a word.
+ */
+fn
+starts_word (line: &[u8]) -> bool {
+    match line.last() {
+        None => true,
+        Some(c) => is_space(*c) || matches!(c, b';' | b'&' | b'|' | b'(' | b')')
+    }
+}
+
+/**
+ * The index of the newline ending the line comment starting at `start`, or the end of the
+ * source. In C, a backslash before the newline continues the comment onto the next line.
+ */
+fn
+line_end (source: &[u8], start: usize, language: Language) -> usize {
+    let mut i = start;
+    while i < source.len() {
+        match source[i] {
+            b'\\' if language == Language::C => i += 2,
+            b'\n' => return i,
+            _ => i += 1
+        }
+    }
+    source.len()
+}
+
+/**
+ * The length of the lifetime name following a quote in `rest`, or 0 if the quote starts a char
+ * literal instead: 'a' and 'ab' are chars, 'a and 'static are lifetimes.
+ */
+fn
+lifetime_len (rest: &[u8]) -> usize {
+    if !rest.first().is_some_and(|c| c.is_ascii_alphabetic() || *c == b'_') {
+        return 0;
+    }
+    let len = rest.iter().position(|c| !c.is_ascii_alphanumeric() && *c != b'_').unwrap_or(rest.len());
+    if rest.get(len) == Some(&b'\'') { 0 } else { len }
+}
+
+/**
+ * The index just past the string literal starting with the quote at `start`, or the end of the
+ * source if it is never closed. An unterminated single-line string in C or Python ends at the
+ * newline, so one stray quote doesn't swallow the rest of the file.
+ */
+fn
+string_end (source: &[u8], start: usize, language: Language) -> usize {
+    let quote = source[start];
+
+    if language == Language::Python && source[start..].starts_with(&[quote; 3]) {
+        let mut i = start + 3;
+        while i < source.len() {
+            if source[i] == b'\\' {
+                i += 2;
+            } else if source[i..].starts_with(&[quote; 3]) {
+                return i + 3;
+            } else {
+                i += 1;
+            }
+        }
+        return source.len();
+    }
+
+    let escapes = !(language == Language::Shell && quote == b'\'');
+    let multiline = language == Language::Shell;
+    let mut i = start + 1;
+    while i < source.len() {
+        match source[i] {
+            b'\\' if escapes => i += 2,
+            c if c == quote => return i + 1,
+            b'\n' if !multiline => return i,
+            _ => i += 1
+        }
+    }
+    source.len()
+}
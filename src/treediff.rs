@@ -0,0 +1,86 @@
+/*
+ * Comparing two directory trees by content, for checking that a build is reproducible: build
+ * twice, or on two machines, and every output file should be byte-for-byte the same. Files are
+ * matched by their path relative to each root, and compared by size and digest.
+ */
+
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use crate::{manifest, Algo, Digest, Hasher};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FileInfo {
+    pub bytes: u64,
+    pub digest: Digest
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Change {
+    OnlyLeft(String, FileInfo),
+    OnlyRight(String, FileInfo),
+    Differs(String, FileInfo, FileInfo)
+}
+
+impl Change {
+    pub fn path (&self) -> &str {
+        match self {
+            Change::OnlyLeft(path, _) | Change::OnlyRight(path, _) | Change::Differs(path, _, _) => path
+        }
+    }
+}
+
+/**
+ * The result of comparing two trees: how many files matched, and every difference, in path
+ * order.
+ */
+#[derive(Default, Debug)]
+pub struct Comparison {
+    pub identical: usize,
+    pub changes: Vec<Change>
+}
+
+impl Comparison {
+    pub fn is_identical (&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/**
+ * Hashes every regular file beneath `root`, keyed by its path relative to `root` with "/"
+ * separators. Symbolic links aren't followed, as in manifest::walk.
+ */
+pub fn
+scan (root: &str, algo: Algo, respect_gitignore: bool) -> io::Result<BTreeMap<String, FileInfo>> {
+    let files = if respect_gitignore { manifest::walk_respecting_gitignore(root)? } else { manifest::walk(root)? };
+    let mut tree = BTreeMap::new();
+
+    for file in files {
+        let mut hasher = Hasher::new(algo);
+        let bytes = hasher.update_reader(io::BufReader::new(fs::File::open(&file)?))?;
+        let relative = Path::new(&file).strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+        tree.insert(relative, FileInfo { bytes, digest: hasher.finalize_digest() });
+    }
+
+    Ok(tree)
+}
+
+pub fn
+compare (left: &BTreeMap<String, FileInfo>, right: &BTreeMap<String, FileInfo>) -> Comparison {
+    let mut comparison = Comparison::default();
+
+    for (path, l) in left {
+        match right.get(path) {
+            Some(r) if r == l => comparison.identical += 1,
+            Some(r) => comparison.changes.push(Change::Differs(path.clone(), *l, *r)),
+            None => comparison.changes.push(Change::OnlyLeft(path.clone(), *l))
+        }
+    }
+    for (path, r) in right {
+        if !left.contains_key(path) {
+            comparison.changes.push(Change::OnlyRight(path.clone(), *r));
+        }
+    }
+
+    comparison.changes.sort_by(|a, b| a.path().cmp(b.path()));
+    comparison
+}
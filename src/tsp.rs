@@ -0,0 +1,37 @@
+use crate::{der, Algo};
+
+/**
+ * Builds a DER-encoded RFC 3161 time-stamp request over the given digest, ready to POST to a
+ * TSA with Content-Type application/timestamp-query.
+ *
+ * From https://www.rfc-editor.org/rfc/rfc3161 # 2.4.1
+ *
+ * TimeStampReq ::= SEQUENCE  {
+ *    version                  INTEGER  { v1(1) },
+ *    messageImprint           MessageImprint,
+ *    reqPolicy                TSAPolicyId              OPTIONAL,
+ *    nonce                    INTEGER                  OPTIONAL,
+ *    certReq                  BOOLEAN                  DEFAULT FALSE,
+ *    extensions               [0] IMPLICIT Extensions  OPTIONAL  }
+ *
+ * MessageImprint ::= SEQUENCE  {
+ *    hashAlgorithm            AlgorithmIdentifier,
+ *    hashedMessage            OCTET STRING  }
+ */
+pub fn
+timestamp_request (digest: &[u8], algo: Algo, nonce: Option<&[u8]>, cert_req: bool) -> Option<Vec<u8>> {
+    let message_imprint = der::digest_info(digest, algo)?;
+
+    let mut fields = vec![der::unsigned_integer(&[1]), message_imprint];
+
+    if let Some(nonce) = nonce {
+        fields.push(der::unsigned_integer(nonce));
+    }
+
+    // DER omits fields equal to their DEFAULT value
+    if cert_req {
+        fields.push(der::boolean(true));
+    }
+
+    Some(der::sequence(&fields))
+}
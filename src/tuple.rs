@@ -0,0 +1,43 @@
+/*
+ * Hashing a tuple of fields unambiguously. Plain concatenation maps ("ab", "c") and ("a", "bc")
+ * to the same input, so an ID derived from several values that way can collide by construction.
+ * Here each field is preceded by its length as a 64-bit big-endian integer, which makes the
+ * encoding of any tuple, of any number of fields, decodable back into exactly that tuple.
+ */
+
+use crate::{Algo, Digest, Hasher};
+
+#[derive(Clone)]
+pub struct TupleHasher {
+    hasher: Hasher
+}
+
+impl TupleHasher {
+    pub fn new (algo: Algo) -> TupleHasher {
+        TupleHasher { hasher: Hasher::new(algo) }
+    }
+
+    /**
+     * Appends one field. Fields are whole values, since the length goes in ahead of the data.
+     */
+    pub fn field (&mut self, data: &[u8]) {
+        self.hasher.update(&(data.len() as u64).to_be_bytes());
+        self.hasher.update(data);
+    }
+
+    pub fn finalize (self) -> Digest {
+        self.hasher.finalize_digest()
+    }
+}
+
+/**
+ * Hashes the given fields as a tuple, in order.
+ */
+pub fn
+hash_tuple (fields: &[&[u8]], algo: Algo) -> Digest {
+    let mut hasher = TupleHasher::new(algo);
+    for field in fields {
+        hasher.field(field);
+    }
+    hasher.finalize()
+}
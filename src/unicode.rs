@@ -0,0 +1,168 @@
+/*
+ * Unicode canonical normalization (UAX #15), so text which looks the same hashes the same. "é"
+ * can be typed as the single character U+00E9 or as "e" followed by the combining acute accent
+ * U+0301, and macOS file names, for one, use the second. NFD takes every character apart into
+ * its canonical decomposition; NFC does the same and then puts back together every pair that
+ * has a precomposed form. Compatibility forms (NFKC and NFKD), which also fold e.g. "ﬁ" into
+ * "fi", aren't supported.
+ *
+ * The tables in tables.rs are generated by scripts/gen-unicode-tables.py.
+ */
+
+use alloc::{string::String, vec::Vec};
+
+mod tables;
+
+use tables::{COMBINING_CLASSES, COMPOSITIONS, DECOMPOSITIONS};
+
+// From https://www.unicode.org/versions/Unicode14.0.0/ch03.pdf # 3.12
+const S_BASE: u32 = 0xac00;
+const L_BASE: u32 = 0x1100;
+const V_BASE: u32 = 0x1161;
+const T_BASE: u32 = 0x11a7;
+const L_COUNT: u32 = 19;
+const V_COUNT: u32 = 21;
+const T_COUNT: u32 = 28;
+const N_COUNT: u32 = V_COUNT * T_COUNT;
+const S_COUNT: u32 = L_COUNT * N_COUNT;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Form {
+    Nfc,
+    Nfd
+}
+
+impl Form {
+    pub fn from_name (name: &str) -> Option<Form> {
+        match name {
+            "nfc" => Some(Form::Nfc),
+            "nfd" => Some(Form::Nfd),
+            _ => None
+        }
+    }
+}
+
+/**
+ * Returns the text in the given normalization form.
+ */
+pub fn
+normalize (text: &str, form: Form) -> String {
+    let mut chars = decompose(text);
+    if form == Form::Nfc {
+        compose(&mut chars);
+    }
+    chars.into_iter().map(|c| char::from_u32(c).unwrap()).collect()
+}
+
+fn
+combining_class (c: u32) -> u8 {
+    match COMBINING_CLASSES.binary_search_by(|&(first, last, _)| {
+        if last < c { core::cmp::Ordering::Less } else if first > c { core::cmp::Ordering::Greater } else { core::cmp::Ordering::Equal }
+    }) {
+        Ok(i) => COMBINING_CLASSES[i].2,
+        Err(_) => 0
+    }
+}
+
+fn
+decompose_char (c: u32, out: &mut Vec<u32>) {
+    if (S_BASE..S_BASE + S_COUNT).contains(&c) {
+        let index = c - S_BASE;
+        out.push(L_BASE + index / N_COUNT);
+        out.push(V_BASE + (index % N_COUNT) / T_COUNT);
+        if !index.is_multiple_of(T_COUNT) {
+            out.push(T_BASE + index % T_COUNT);
+        }
+        return;
+    }
+
+    match DECOMPOSITIONS.binary_search_by_key(&c, |&(cp, _, _)| cp) {
+        Ok(i) => {
+            let (_, first, second) = DECOMPOSITIONS[i];
+            decompose_char(first, out);
+            if second != 0 {
+                decompose_char(second, out);
+            }
+        },
+        Err(_) => out.push(c)
+    }
+}
+
+/**
+ * The full canonical decomposition, with each run of combining marks put in canonical order:
+ * sorted by combining class, keeping the original order among marks of the same class.
+ */
+fn
+decompose (text: &str) -> Vec<u32> {
+    let mut out = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        let start = out.len();
+        decompose_char(c as u32, &mut out);
+
+        // Insertion sort, since runs of marks are short and the sort must be stable
+        for i in start..out.len() {
+            let class = combining_class(out[i]);
+            if class == 0 {
+                continue;
+            }
+            let mut j = i;
+            while j > 0 && combining_class(out[j - 1]) > class {
+                out.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+    }
+    out
+}
+
+fn
+compose_pair (first: u32, second: u32) -> Option<u32> {
+    if (L_BASE..L_BASE + L_COUNT).contains(&first) && (V_BASE..V_BASE + V_COUNT).contains(&second) {
+        return Some(S_BASE + ((first - L_BASE) * V_COUNT + (second - V_BASE)) * T_COUNT);
+    }
+    if (S_BASE..S_BASE + S_COUNT).contains(&first) && (first - S_BASE).is_multiple_of(T_COUNT)
+        && (T_BASE + 1..T_BASE + T_COUNT).contains(&second) {
+        return Some(first + (second - T_BASE));
+    }
+
+    COMPOSITIONS.binary_search_by_key(&(first, second), |&(a, b, _)| (a, b)).ok().map(|i| COMPOSITIONS[i].2)
+}
+
+/**
+ * Canonical composition of decomposed text, in place. Each character combines with the last
+ * starter (a character of class 0) unless something between them blocks it: another starter,
+ * or a mark of the same or a higher class.
+ */
+fn
+compose (chars: &mut Vec<u32>) {
+    let mut starter: Option<usize> = None;
+    // The class of the last character kept since the starter, None if there isn't one
+    let mut last_class: Option<u8> = None;
+    let mut kept = 0;
+
+    for i in 0..chars.len() {
+        let c = chars[i];
+        let class = combining_class(c);
+
+        if let Some(s) = starter {
+            let blocked = matches!(last_class, Some(last) if last >= class);
+            if !blocked {
+                if let Some(composite) = compose_pair(chars[s], c) {
+                    chars[s] = composite;
+                    continue;
+                }
+            }
+        }
+
+        if class == 0 {
+            starter = Some(kept);
+            last_class = None;
+        } else {
+            last_class = Some(class);
+        }
+        chars[kept] = c;
+        kept += 1;
+    }
+
+    chars.truncate(kept);
+}
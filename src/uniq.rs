@@ -0,0 +1,55 @@
+use std::collections::{HashSet, VecDeque};
+
+/**
+ * Bytes of each digest kept in the set. 128 bits still makes an accidental collision, which would
+ * drop a distinct line, vanishingly unlikely at any practical stream size.
+ */
+pub const KEY_LEN: usize = 16;
+
+/**
+ * Rough per-entry cost: the key in both the set and the eviction queue, plus hash table overhead.
+ */
+pub const ENTRY_COST: u64 = 3 * KEY_LEN as u64;
+
+/**
+ * A set of digest prefixes holding at most `capacity` entries. Once full, the oldest entry is
+ * evicted for each new one, so duplicates further apart than the window are no longer caught.
+ */
+pub struct SeenSet {
+    set: HashSet<[u8; KEY_LEN]>,
+    order: VecDeque<[u8; KEY_LEN]>,
+    capacity: usize,
+    pub evicted: u64
+}
+
+impl SeenSet {
+    pub fn new (capacity: usize) -> SeenSet {
+        SeenSet {
+            set: HashSet::new(),
+            order: VecDeque::new(),
+            capacity: capacity.max(1),
+            evicted: 0
+        }
+    }
+
+    /**
+     * Records the digest, returning true if it wasn't already present.
+     */
+    pub fn insert (&mut self, digest: &[u8]) -> bool {
+        let mut key = [0u8; KEY_LEN];
+        key.copy_from_slice(&digest[..KEY_LEN]);
+
+        if !self.set.insert(key) {
+            return false;
+        }
+
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            let oldest = self.order.pop_front().unwrap();
+            self.set.remove(&oldest);
+            self.evicted += 1;
+        }
+
+        true
+    }
+}
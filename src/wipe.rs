@@ -0,0 +1,28 @@
+/*
+ * Overwriting intermediate values with zeros before their memory is given up, for the `zeroize`
+ * feature. Plain assignments to memory that is about to be freed are dead stores the optimizer
+ * may remove; volatile writes can't be removed, and the fence keeps them from being moved past
+ * the free.
+ */
+
+use core::sync::atomic::{compiler_fence, Ordering};
+
+pub(crate) fn
+wipe<T: Copy + Default> (items: &mut [T]) {
+    for item in items.iter_mut() {
+        // Safety: the pointer comes from a live &mut, so it is valid and aligned
+        unsafe { core::ptr::write_volatile(item, T::default()) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+/**
+ * Wipes the whole allocation, including capacity beyond the current length, which may still
+ * hold bytes drained or truncated away earlier.
+ */
+#[cfg(feature = "alloc")]
+pub(crate) fn
+wipe_vec (bytes: &mut alloc::vec::Vec<u8>) {
+    bytes.resize(bytes.capacity(), 0);
+    wipe(bytes);
+}
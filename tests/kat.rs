@@ -0,0 +1,66 @@
+/*
+ * Known-answer tests against vectors produced by openssl. The fixture is generated on demand by
+ * scripts/gen-kat.sh rather than checked in; without it, these tests pass vacuously with a
+ * note. KAT_FIXTURE overrides the fixture path.
+ */
+
+use std::{fs, path::PathBuf};
+
+use sha_2::{digest, encoding::hex, Algo, Sha224, Sha256};
+
+struct Vector {
+    message: Vec<u8>,
+    sha224: String,
+    sha256: String
+}
+
+fn
+load () -> Option<Vec<Vector>> {
+    let path = std::env::var_os("KAT_FIXTURE").map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/kat.txt"));
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(_) => {
+            eprintln!("{} not found; run scripts/gen-kat.sh to generate known-answer vectors", path.display());
+            return None;
+        }
+    };
+
+    Some(contents.lines().enumerate().map(|(i, line)| {
+        let fields: Vec<&str> = line.split(' ').collect();
+        assert!(fields.len() == 4, "line {}: expected 4 fields", i + 1);
+
+        let message = if fields[1] == "-" { Vec::new() } else { hex::decode(fields[1]).unwrap() };
+        assert!(message.len() == fields[0].parse::<usize>().unwrap(), "line {}: length mismatch", i + 1);
+
+        Vector { message, sha224: fields[2].to_string(), sha256: fields[3].to_string() }
+    }).collect())
+}
+
+#[test]
+fn
+one_shot_matches_openssl () {
+    for v in load().unwrap_or_default() {
+        assert_eq!(hex::encode(digest(&v.message, Algo::Sha224)), v.sha224, "len {}", v.message.len());
+        assert_eq!(hex::encode(digest(&v.message, Algo::Sha256)), v.sha256, "len {}", v.message.len());
+    }
+}
+
+#[test]
+fn
+streaming_matches_openssl () {
+    for (i, v) in load().unwrap_or_default().iter().enumerate() {
+        // Vary the chunk size so that updates straddle block boundaries in different ways
+        let chunk = 1 + i % 97;
+        let mut sha224 = Sha224::new();
+        let mut sha256 = Sha256::new();
+        for part in v.message.chunks(chunk) {
+            sha224.update(part);
+            sha256.update(part);
+        }
+
+        assert_eq!(hex::encode(sha224.finalize()), v.sha224, "len {}", v.message.len());
+        assert_eq!(hex::encode(sha256.finalize()), v.sha256, "len {}", v.message.len());
+    }
+}